@@ -0,0 +1,75 @@
+//! Baselines for the CAR read path: `cargo bench --features test-support`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::path::PathBuf;
+use tile_documents_lib::car::{parse_tile, walk_index};
+use tile_documents_lib::testing;
+
+const SIZES: [usize; 3] = [10, 1_000, 10_000];
+
+fn fixture(resources: usize) -> PathBuf {
+    fixture_of(resources, 1024)
+}
+
+fn fixture_of(resources: usize, size: usize) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("tile-bench-{resources}x{size}.tile"));
+    testing::write_synthetic_tile(&path, resources, size).unwrap();
+    path
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_tile");
+    for n in SIZES {
+        let path = fixture(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &path, |b, p| {
+            b.iter(|| parse_tile(p).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_walk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("walk_index");
+    group.sample_size(10);
+    // Many small blocks, where the walk is most CPU-bound, and fewer large.
+    for (resources, size) in [(90_000, 64), (2_000, 64 << 10)] {
+        let path = fixture_of(resources, size);
+        let id = BenchmarkId::from_parameter(format!("{resources}x{size}"));
+        group.bench_with_input(id, &path, |b, p| b.iter(|| walk_index(p).unwrap()));
+    }
+    group.finish();
+}
+
+fn bench_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for n in SIZES {
+        let tile = parse_tile(&fixture(n)).unwrap();
+        let hit = format!("/r/{}.html", n / 2);
+        group.bench_with_input(BenchmarkId::new("hit", n), &hit, |b, p| {
+            b.iter(|| tile.resolve(p).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("root", n), "/", |b, p| {
+            b.iter(|| tile.resolve(p).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("miss", n), "/nope", |b, p| {
+            b.iter(|| tile.resolve(p))
+        });
+    }
+    group.finish();
+}
+
+fn bench_serve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("handle_tile_protocol");
+    let app = testing::mock_app();
+    for n in SIZES {
+        let authority = testing::open(&app, &fixture(n)).unwrap();
+        let uri = format!("tile://{authority}/r/{}.html", n / 2);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &uri, |b, uri| {
+            b.iter(|| assert_eq!(testing::get(&app, uri).status(), 200))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_walk, bench_resolve, bench_serve);
+criterion_main!(benches);
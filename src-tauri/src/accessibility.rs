@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// ── Accessibility preferences ────────────────────────────────────────────────
+//
+// The OS's reduced-motion, high-contrast and text-size settings, passed to
+// tiles without any network access. Every tile can fetch them from reserved
+// paths that no MASL resource can take:
+//
+//   /.well-known/tile/accessibility.json   the values below, as JSON
+//   /.well-known/tile/accessibility.css    the same as custom properties on
+//                                          `:root`, for a stylesheet link
+//
+// The CSS sets `--tile-reduced-motion` and `--tile-high-contrast` (`0` or
+// `1`) and `--tile-font-scale` (a factor, `1` by default). The settings are
+// read from the desktop's own tools (`gsettings` on Linux, `defaults` on
+// macOS, the registry on Windows) and kept for a few seconds, so a change
+// made while a tile is open shows at its next fetch. Anything that can't be
+// read is left at its default.
+
+/// Prefix of the paths served by the app rather than the tile.
+pub const RESERVED_PREFIX: &str = "/.well-known/tile/";
+
+/// How long a reading of the OS settings is reused.
+const FRESH_FOR: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AccessibilityPrefs {
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    /// Preferred text size relative to the default.
+    pub font_scale: f64,
+}
+
+impl Default for AccessibilityPrefs {
+    fn default() -> Self {
+        AccessibilityPrefs { reduced_motion: false, high_contrast: false, font_scale: 1.0 }
+    }
+}
+
+impl AccessibilityPrefs {
+    fn css(&self) -> String {
+        format!(
+            ":root {{\n  --tile-reduced-motion: {};\n  --tile-high-contrast: {};\n  \
+             --tile-font-scale: {};\n}}\n",
+            u8::from(self.reduced_motion),
+            u8::from(self.high_contrast),
+            self.font_scale
+        )
+    }
+}
+
+static LAST: Mutex<Option<(Instant, AccessibilityPrefs)>> = Mutex::new(None);
+
+/// The OS settings, read again once the last reading is stale.
+pub fn current() -> AccessibilityPrefs {
+    let mut last = LAST.lock().unwrap();
+    match *last {
+        Some((read, prefs)) if read.elapsed() < FRESH_FOR => prefs,
+        _ => {
+            let prefs = detect();
+            *last = Some((Instant::now(), prefs));
+            prefs
+        }
+    }
+}
+
+/// The body and content type for a reserved `path`, if it is one.
+pub fn serve(path: &str) -> Option<(Vec<u8>, &'static str)> {
+    match path.strip_prefix(RESERVED_PREFIX)? {
+        "accessibility.json" => Some((serde_json::to_vec(&current()).ok()?, "application/json")),
+        "accessibility.css" => Some((current().css().into_bytes(), "text/css")),
+        _ => None,
+    }
+}
+
+/// Trimmed stdout of `program args`, if it ran and succeeded.
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let out = std::process::Command::new(program).args(args).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> AccessibilityPrefs {
+    let get = |schema: &str, key: &str| output("gsettings", &["get", schema, key]);
+    let defaults = AccessibilityPrefs::default();
+    let animations = get("org.gnome.desktop.interface", "enable-animations");
+    let contrast = get("org.gnome.desktop.a11y.interface", "high-contrast");
+    let scale = get("org.gnome.desktop.interface", "text-scaling-factor");
+    AccessibilityPrefs {
+        reduced_motion: animations.as_deref() == Some("false"),
+        high_contrast: contrast.as_deref() == Some("true"),
+        font_scale: scale.and_then(|s| s.parse().ok()).unwrap_or(defaults.font_scale),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> AccessibilityPrefs {
+    let get = |key: &str| output("defaults", &["read", "com.apple.universalaccess", key]);
+    AccessibilityPrefs {
+        reduced_motion: get("reduceMotion").as_deref() == Some("1"),
+        high_contrast: get("increaseContrast").as_deref() == Some("1"),
+        // macOS has no system-wide text size that applies to web content.
+        ..Default::default()
+    }
+}
+
+#[cfg(windows)]
+fn detect() -> AccessibilityPrefs {
+    // `reg query` prints `    <name>    <type>    <value>`.
+    let get = |key: &str, name: &str| {
+        let out = output("reg", &["query", key, "/v", name])?;
+        let line = out.lines().find(|l| l.trim_start().starts_with(name))?;
+        line.split_whitespace().nth(2).map(str::to_string)
+    };
+    let number = |value: String| match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    };
+    let defaults = AccessibilityPrefs::default();
+    let animate = get(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate");
+    let contrast = get(r"HKCU\Control Panel\Accessibility\HighContrast", "Flags");
+    let scale = get(r"HKCU\Software\Microsoft\Accessibility", "TextScaleFactor");
+    AccessibilityPrefs {
+        reduced_motion: animate.as_deref() == Some("0"),
+        // HCF_HIGHCONTRASTON is bit 0.
+        high_contrast: contrast.and_then(number).is_some_and(|flags| flags & 1 != 0),
+        font_scale: scale
+            .and_then(number)
+            .map_or(defaults.font_scale, |percent| f64::from(percent) / 100.0),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect() -> AccessibilityPrefs {
+    AccessibilityPrefs::default()
+}
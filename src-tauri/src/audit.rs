@@ -0,0 +1,101 @@
+use crate::feed::rfc3339;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+// ── Access audit log ─────────────────────────────────────────────────────────
+//
+// What each open tile did this session: every resource it was served and
+// every permission or bridge API it asked for. Kept in memory, per authority,
+// so someone reviewing an untrusted document can see exactly what it touched.
+
+/// Entries kept per tile; the oldest are dropped first.
+const MAX_ENTRIES: usize = 2000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditKind {
+    /// A `tile:` protocol request.
+    Resource,
+    /// A permission prompt or check.
+    Permission,
+    /// A bridge API call relayed by the shell.
+    Bridge,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// RFC 3339 time of the access.
+    pub time: String,
+    pub kind: AuditKind,
+    /// Resource path, permission name, or bridge message type.
+    pub target: String,
+    /// HTTP status, or what became of the request (`granted`, `denied`, …).
+    pub outcome: String,
+}
+
+#[derive(Default)]
+pub struct AuditLog(Mutex<HashMap<String, VecDeque<AuditEntry>>>);
+
+impl AuditLog {
+    fn entries(&self, authority: &str) -> Vec<AuditEntry> {
+        let log = self.0.lock().unwrap();
+        log.get(authority).map(|e| e.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
+/// Append an entry to `authority`'s log.
+pub fn record<R: Runtime>(
+    app: &AppHandle<R>,
+    authority: &str,
+    kind: AuditKind,
+    target: &str,
+    outcome: impl Into<String>,
+) {
+    let Some(log) = app.try_state::<AuditLog>() else { return };
+    let mut log = log.0.lock().unwrap();
+    let entries = log.entry(authority.to_string()).or_default();
+    if entries.len() == MAX_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(AuditEntry {
+        time: rfc3339(SystemTime::now()),
+        kind,
+        target: target.to_string(),
+        outcome: outcome.into(),
+    });
+}
+
+/// Drop `authority`'s log (see `private.rs`).
+pub fn forget<R: Runtime>(app: &AppHandle<R>, authority: &str) {
+    if let Some(log) = app.try_state::<AuditLog>() {
+        log.0.lock().unwrap().remove(authority);
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Everything the tile at `authority` has accessed this session, oldest first.
+#[tauri::command]
+pub fn get_tile_audit_log(authority: String, log: State<'_, AuditLog>) -> Vec<AuditEntry> {
+    log.entries(&authority)
+}
+
+/// Write `authority`'s audit log to `dest` as JSON.
+#[tauri::command]
+pub fn export_tile_audit_log(
+    authority: String,
+    dest: String,
+    log: State<'_, AuditLog>,
+) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(&log.entries(&authority)).map_err(|e| e.to_string())?;
+    std::fs::write(dest, json).map_err(|e| e.to_string())
+}
+
+/// Called by the shell's bridge for each message a tile sends it.
+#[tauri::command]
+pub fn record_bridge_call(authority: String, action: String, outcome: String, app: AppHandle) {
+    record(&app, &authority, AuditKind::Bridge, &action, outcome);
+}
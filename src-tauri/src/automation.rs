@@ -0,0 +1,315 @@
+use crate::car::{authority_from_path, TileContent};
+use crate::{i18n, load_tile, open_when_ready, OpenOptions, PendingOpens, TileStore};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Emitter, Manager, Url, WebviewUrl, WebviewWindowBuilder};
+
+// ── Automation ───────────────────────────────────────────────────────────────
+//
+// Scripts and workflow tools can drive the app through four actions: open a
+// tile file, navigate an open tile to a path, export a tile page to PDF, and
+// close a tile. Tiles are named by the authority `open` returns.
+//
+// On Linux the actions are methods of `ing.dasl.Tile1` at `/ing/dasl/tile`,
+// under the name `ing.dasl.tile` on the session bus:
+//
+//   gdbus call --session --dest ing.dasl.tile --object-path /ing/dasl/tile \
+//     --method ing.dasl.Tile1.Open /path/to/doc.tile
+//
+// On macOS they are `tile-documents:` URLs, which AppleScript reaches with
+// `open location`:
+//
+//   open location "tile-documents://open?path=/path/to/doc.tile"
+//   open location "tile-documents://navigate?authority=…&path=/notes.html"
+//   open location "tile-documents://export-pdf?authority=…&path=/&dest=…/Downloads/doc.pdf"
+//   open location "tile-documents://close?authority=…"
+//
+// Any web page can fire such a URL, so the user is asked before one is acted
+// on, and the scheme is only registered where it is handled (macOS, in
+// `tauri.macos.conf.json`). A URL has nowhere to send a result, so there
+// failures are only logged and the authority to use is the one a file
+// always opens under (see `authority_from_path`).
+//
+// A PDF is only exported to a plain absolute path in the downloads folder,
+// whichever way the export is asked for.
+//
+// Exporting loads the page into a hidden window of its own, so the tab being
+// read is left alone. WebKitGTK prints it straight to the file. Elsewhere the
+// webview has no way to do that, so the window is shown with the system
+// print dialog, where the page can be saved as PDF by hand.
+
+/// One thing a script can ask for.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Action {
+    Open { path: PathBuf },
+    Navigate { authority: String, path: String },
+    ExportPdf { authority: String, path: String, dest: PathBuf },
+    Close { authority: String },
+}
+
+/// A tab the frontend is asked to change, emitted as `tile:navigate` (with
+/// `path`) or `tile:close-requested`.
+#[derive(Debug, Clone, Serialize)]
+struct TabRequest<'a> {
+    authority: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+}
+
+/// How long an export waits for the page to load and print.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(60);
+
+impl Action {
+    /// The action a `tile-documents:` URL stands for.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub fn from_url(url: &Url) -> Result<Self> {
+        let query: serde_json::Map<String, serde_json::Value> = url
+            .query_pairs()
+            .map(|(name, value)| (name.into_owned(), value.into_owned().into()))
+            .chain([("action".to_string(), url.host_str().unwrap_or_default().into())])
+            .collect();
+        serde_json::from_value(query.into()).map_err(|e| anyhow!("{url}: {e}"))
+    }
+
+    /// What the action does, as the question about a link puts it.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn describe(&self, app: &AppHandle) -> String {
+        let name = |authority: &str| match app.state::<TileStore>().get(authority) {
+            Some(tile) => tile.masl.name.clone(),
+            None => authority.to_string(),
+        };
+        match self {
+            Action::Open { path } => {
+                i18n::tf("link.open", &[("path", &path.display().to_string())])
+            }
+            Action::Navigate { authority, path } => {
+                i18n::tf("link.navigate", &[("path", path), ("name", &name(authority))])
+            }
+            Action::ExportPdf { authority, path, dest } => {
+                let dest = dest.display().to_string();
+                let named = [("path", path.as_str()), ("name", &name(authority)), ("dest", &dest)];
+                i18n::tf("link.export-pdf", &named)
+            }
+            Action::Close { authority } => i18n::tf("link.close", &[("name", &name(authority))]),
+        }
+    }
+}
+
+/// Carry out `action`, which a `tile-documents:` URL asked for, if the user
+/// allows it. Blocks until done, so call it off the main thread.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn perform_link(app: &AppHandle, action: Action) -> Result<()> {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+    let message = i18n::tf("link.prompt", &[("action", &action.describe(app))]);
+    let (allow, deny) = (i18n::t("link.allow"), i18n::t("link.deny"));
+    let allowed = app
+        .dialog()
+        .message(message)
+        .title(i18n::t("link.title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(allow, deny))
+        .blocking_show();
+    if !allowed {
+        tracing::info!(?action, "automation link declined");
+        return Ok(());
+    }
+    perform(app, action).map(drop)
+}
+
+/// Carry out `action`, returning the tile's authority. Blocks until done,
+/// so call it off the main thread.
+pub fn perform(app: &AppHandle, action: Action) -> Result<String> {
+    tracing::info!(?action, "automation");
+    match action {
+        Action::Open { path } => open(app, &path),
+        Action::Navigate { authority, path } => {
+            let tile = loaded(app, &authority)?;
+            if tile.resolve(&path).is_none() {
+                bail!("{authority} has no resource at {path}");
+            }
+            app.emit("tile:navigate", TabRequest { authority: &authority, path: Some(&path) })?;
+            Ok(authority)
+        }
+        Action::ExportPdf { authority, path, dest } => {
+            let dest = export_dest(app, &dest)?;
+            loaded(app, &authority)?;
+            export_pdf(app, &authority, &path, &dest)?;
+            Ok(authority)
+        }
+        Action::Close { authority } => {
+            loaded(app, &authority)?;
+            app.emit("tile:close-requested", TabRequest { authority: &authority, path: None })?;
+            Ok(authority)
+        }
+    }
+}
+
+fn loaded(app: &AppHandle, authority: &str) -> Result<Arc<TileContent>> {
+    let tile = app.state::<TileStore>().get(authority);
+    tile.ok_or_else(|| anyhow!(i18n::t("error.tile-not-loaded")))
+}
+
+/// Open `path` now, or queue it like a file passed at launch if the
+/// frontend isn't listening yet.
+fn open(app: &AppHandle, path: &Path) -> Result<String> {
+    if !path.is_file() {
+        bail!("{} is not a tile file", path.display());
+    }
+    if app.state::<PendingOpens>().0.lock().unwrap().is_some() {
+        open_when_ready(app, vec![path.to_path_buf()]);
+        return Ok(authority_from_path(path));
+    }
+    let payload = load_tile(path, OpenOptions::default(), &app.state(), app)?;
+    Ok(payload.authority)
+}
+
+/// `dest` if an export may write there: a plain absolute path, without `.`
+/// or `..`, to a file in the downloads folder that isn't a symlink.
+fn export_dest(app: &AppHandle, dest: &Path) -> Result<PathBuf> {
+    let plain = dest.is_absolute()
+        && !dest.components().any(|c| matches!(c, Component::ParentDir | Component::CurDir));
+    let (Some(parent), Some(name), true) = (dest.parent(), dest.file_name(), plain) else {
+        bail!("{} is not a plain absolute file path", dest.display());
+    };
+    let downloads = std::fs::canonicalize(app.path().download_dir()?)?;
+    let parent = std::fs::canonicalize(parent)
+        .map_err(|e| anyhow!("{}: {e}", parent.display()))?;
+    if !parent.starts_with(&downloads) {
+        bail!("PDFs are only exported into {}", downloads.display());
+    }
+    let dest = parent.join(name);
+    if dest.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+        bail!("{} is a symlink", dest.display());
+    }
+    Ok(dest)
+}
+
+/// Load `path` of the tile `authority` into a hidden window and print it
+/// to `dest`, which [`export_dest`] has checked.
+fn export_pdf(app: &AppHandle, authority: &str, path: &str, dest: &Path) -> Result<()> {
+    static EXPORTS: AtomicUsize = AtomicUsize::new(0);
+    let dest = dest.to_path_buf();
+    let url: Url = format!("tile://{authority}{path}").parse()?;
+    let label = format!("export-{}", EXPORTS.fetch_add(1, Ordering::Relaxed));
+    let (sender, done) = mpsc::channel::<Result<()>>();
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::CustomProtocol(url))
+        .visible(false)
+        .on_page_load(move |window, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                print(&window, dest.clone(), sender.clone());
+            }
+        })
+        .build()?;
+    let result = done.recv_timeout(EXPORT_TIMEOUT).unwrap_or_else(|_| Err(anyhow!("timed out")));
+    if cfg!(target_os = "linux") || result.is_err() {
+        window.destroy()?;
+    }
+    result.map_err(|e| anyhow!("exporting {authority}{path} to PDF: {e:#}"))
+}
+
+#[cfg(target_os = "linux")]
+fn print(window: &tauri::WebviewWindow, dest: PathBuf, sender: mpsc::Sender<Result<()>>) {
+    let started = window.with_webview(move |webview| {
+        use webkit2gtk::{PrintOperation, PrintOperationExt};
+        let uri = match Url::from_file_path(&dest) {
+            Ok(uri) => uri,
+            Err(()) => {
+                let _ = sender.send(Err(anyhow!("{} is not a file path", dest.display())));
+                return;
+            }
+        };
+        let settings = gtk::PrintSettings::new();
+        settings.set_printer("Print to File");
+        settings.set(gtk::PRINT_SETTINGS_OUTPUT_FILE_FORMAT, Some("pdf"));
+        settings.set(gtk::PRINT_SETTINGS_OUTPUT_URI, Some(uri.as_str()));
+        let operation = PrintOperation::new(&webview.inner());
+        operation.set_print_settings(&settings);
+        let finished = sender.clone();
+        operation.connect_finished(move |_| {
+            let _ = finished.send(Ok(()));
+        });
+        operation.connect_failed(move |_, e| {
+            let _ = sender.send(Err(anyhow!("{e}")));
+        });
+        operation.print();
+    });
+    if let Err(e) = started {
+        tracing::warn!("not printing: {e}");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn print(window: &tauri::WebviewWindow, _dest: PathBuf, sender: mpsc::Sender<Result<()>>) {
+    let shown = window.show().and_then(|()| window.print());
+    let _ = sender.send(shown.map_err(anyhow::Error::from));
+}
+
+/// Start listening for scripts on the session bus.
+#[cfg(target_os = "linux")]
+pub fn spawn_dbus(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let served = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name("ing.dasl.tile"))
+            .and_then(|builder| builder.serve_at("/ing/dasl/tile", Automation(app)));
+        let connection = match served {
+            Ok(builder) => builder.build().await,
+            Err(e) => Err(e),
+        };
+        match connection {
+            // The object server lives as long as the connection does.
+            Ok(connection) => {
+                std::future::pending::<()>().await;
+                drop(connection);
+            }
+            Err(e) => tracing::warn!("automation not on the session bus: {e}"),
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+struct Automation(AppHandle);
+
+#[cfg(target_os = "linux")]
+impl Automation {
+    async fn run(&self, action: Action) -> zbus::fdo::Result<String> {
+        let app = self.0.clone();
+        tauri::async_runtime::spawn_blocking(move || perform(&app, action))
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?
+            .map_err(|e| zbus::fdo::Error::Failed(format!("{e:#}")))
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[zbus::interface(name = "ing.dasl.Tile1")]
+impl Automation {
+    /// Open a tile file, returning its authority.
+    async fn open(&self, path: String) -> zbus::fdo::Result<String> {
+        self.run(Action::Open { path: path.into() }).await
+    }
+
+    async fn navigate(&self, authority: String, path: String) -> zbus::fdo::Result<()> {
+        self.run(Action::Navigate { authority, path }).await.map(drop)
+    }
+
+    /// Write the page at `path` of an open tile to the PDF file `dest`, in
+    /// the downloads folder.
+    async fn export_pdf(
+        &self,
+        authority: String,
+        path: String,
+        dest: String,
+    ) -> zbus::fdo::Result<()> {
+        self.run(Action::ExportPdf { authority, path, dest: dest.into() }).await.map(drop)
+    }
+
+    async fn close(&self, authority: String) -> zbus::fdo::Result<()> {
+        self.run(Action::Close { authority }).await.map(drop)
+    }
+}
@@ -0,0 +1,5 @@
+// The `tile` command line; see `tile_documents_lib::cli`.
+
+fn main() {
+    std::process::exit(tile_documents_lib::cli::main())
+}
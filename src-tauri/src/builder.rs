@@ -0,0 +1,713 @@
+use crate::car::{
+    default_text, is_src_key, Author, ErrorPages, Icon, Localized, Masl, Redirect, Resource,
+    Terms, TileContent, ENCODED_SRCS, REDIRECT_STATUSES,
+};
+use crate::dagcbor::{self, RawCbor};
+use crate::dagpb::{self, DAG_PB};
+use crate::hashing::{BLAKE3, SHA2_256};
+use crate::revisions;
+use crate::signing::Signer;
+use crate::validate;
+use anyhow::{bail, Result};
+use ciborium::value::Value as CborValue;
+use cid::multihash::Multihash;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+// ── Tile builder ─────────────────────────────────────────────────────────────
+//
+// The writing counterpart to `car::parse_tile`: collect resources and MASL
+// metadata, then `finish` hashes each body into a raw-codec CIDv1 block,
+// encodes the MASL as the CAR header, and writes the CARv1 file, returning
+// the header's CID: the tile's root, as its revisions name it. Identical
+// bodies under the same `CidOptions`, which pick a different hash function
+// or codec for the whole tile or per resource, are stored once; a resource
+// given options of its own gets a CID made with them, even for a body
+// another resource stores another way. The header is written as canonical
+// DAG-CBOR (see `dagcbor.rs`). The file is written next to its destination
+// and renamed into place, so readers never see a half-written tile.
+//
+// A resource may also be copied from a tile already open
+// (`copy_resource`): its entry keeps its CIDs, and its blocks, every block
+// of a chunked file included, are copied frame by frame out of that tile's
+// file as the new one is written, never held in memory whole.
+
+/// MASL fields besides `resources` and `icons`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaslFields {
+    #[serde(default)]
+    pub name: String,
+    /// The name by language, written in place of `name` when not empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub names: Localized,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The description by language, written in place of `description`
+    /// when not empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: Localized,
+    #[serde(default)]
+    pub short_name: Option<String>,
+    #[serde(default)]
+    pub theme_color: Option<String>,
+    #[serde(default)]
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub update_url: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Author>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub published: Option<String>,
+    #[serde(default)]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dc: Terms,
+    /// Pages shown for error statuses, by status; each must be a resource.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_pages: ErrorPages,
+    /// Other header fields, written as they are (see `Masl::extra`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, RawCbor>,
+}
+
+/// Multihash function for a block's CID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HashFunction {
+    #[default]
+    #[serde(rename = "sha2-256")]
+    Sha2_256,
+    #[serde(rename = "blake3")]
+    Blake3,
+}
+
+/// Codec of a block: the body as is, or wrapped as a UnixFS file node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Codec {
+    #[default]
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "dag-pb")]
+    DagPb,
+}
+
+/// How a resource's block and CID are produced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CidOptions {
+    #[serde(default)]
+    pub hash: HashFunction,
+    #[serde(default)]
+    pub codec: Codec,
+}
+
+impl CidOptions {
+    /// The block stored for `body`, with its CID.
+    pub fn block<'a>(&self, body: &'a [u8]) -> Result<(Cid, Cow<'a, [u8]>)> {
+        let block = match self.codec {
+            Codec::Raw => Cow::Borrowed(body),
+            Codec::DagPb => Cow::Owned(dagpb::encode_file(body)),
+        };
+        let (code, digest) = match self.hash {
+            HashFunction::Sha2_256 => (SHA2_256, Sha256::digest(&block).to_vec()),
+            HashFunction::Blake3 => (BLAKE3, blake3::hash(&block).as_bytes().to_vec()),
+        };
+        let codec = match self.codec {
+            Codec::Raw => RAW,
+            Codec::DagPb => DAG_PB,
+        };
+        Ok((Cid::new_v1(codec, Multihash::wrap(code, &digest)?), block))
+    }
+}
+
+/// A resource to be written: its body and the headers stored beside `src`.
+#[derive(Debug, Clone)]
+struct PendingResource {
+    bytes: Vec<u8>,
+    headers: BTreeMap<String, String>,
+    /// Overrides the builder's [`CidOptions`] for this resource.
+    cid: Option<CidOptions>,
+    /// Precompressed copies of `bytes`, by the resource key linking to them
+    /// (`src-br`, `src-gzip`).
+    variants: BTreeMap<String, Vec<u8>>,
+    /// Alternatives to `bytes` in other content types, by content type.
+    typed: BTreeMap<String, Vec<u8>>,
+}
+
+/// A resource copied from an open tile, with the blocks it links to there.
+#[derive(Debug, Clone)]
+struct CopiedResource {
+    /// The entry as the tile has it: headers, and CIDs under its `src` keys.
+    entry: Resource,
+    /// Its variants by content type, as the tile lists them.
+    typed: Vec<Resource>,
+    from: Arc<TileContent>,
+}
+
+/// A resource's links: the keys it has, `src` first, and their CIDs, then
+/// the CID of each of its variants by content type.
+struct Links<'a> {
+    keys: Vec<(&'a str, Cid)>,
+    typed: Vec<(&'a str, Cid)>,
+}
+/// Blocks to write, by CID bytes.
+type Blocks<'a> = BTreeMap<Vec<u8>, Cow<'a, [u8]>>;
+
+/// Assembles a tile in memory and writes it with [`TileBuilder::finish`].
+#[derive(Debug, Clone, Default)]
+pub struct TileBuilder {
+    metadata: MaslFields,
+    icons: Vec<Icon>,
+    resources: BTreeMap<String, PendingResource>,
+    copied: BTreeMap<String, CopiedResource>,
+    redirects: BTreeMap<String, Redirect>,
+    cid: CidOptions,
+    signer: Option<Signer>,
+}
+
+impl TileBuilder {
+    pub fn new() -> Self {
+        TileBuilder::default()
+    }
+
+    /// Serve `bytes` at `path` (absolute, e.g. `/index.html`) with the given
+    /// content type, replacing anything already added there.
+    pub fn add_resource(
+        &mut self,
+        path: &str,
+        bytes: impl Into<Vec<u8>>,
+        content_type: &str,
+    ) -> &mut Self {
+        let headers = BTreeMap::from([("content-type".to_string(), content_type.to_string())]);
+        let resource = PendingResource {
+            bytes: bytes.into(),
+            headers,
+            cid: None,
+            variants: BTreeMap::new(),
+            typed: BTreeMap::new(),
+        };
+        self.resources.insert(path.to_string(), resource);
+        self.copied.remove(path);
+        self.redirects.remove(path);
+        self
+    }
+
+    /// Serve at `path` the resource `from` serves there, variants and all,
+    /// under the same CIDs, replacing anything already added there. Its
+    /// blocks are copied out of `from`'s file by [`finish`](Self::finish).
+    pub(crate) fn copy_resource(
+        &mut self,
+        path: &str,
+        from: &Arc<TileContent>,
+    ) -> Result<&mut Self> {
+        let Some(entry) = from.masl.resources.get(path) else {
+            bail!("no resource at {path}");
+        };
+        let typed = from.masl.variants.get(path).cloned().unwrap_or_default();
+        let copied = CopiedResource { entry: entry.clone(), typed, from: from.clone() };
+        self.copied.insert(path.to_string(), copied);
+        self.resources.remove(path);
+        self.redirects.remove(path);
+        Ok(self)
+    }
+
+    /// Redirect `path` to `location`, a path in the tile, with `status`
+    /// (one of [`REDIRECT_STATUSES`]), replacing anything already added there.
+    pub fn add_redirect(&mut self, path: &str, location: &str, status: u16) -> Result<&mut Self> {
+        if !REDIRECT_STATUSES.contains(&status) {
+            bail!("{status} is not a redirect status");
+        }
+        let redirect = Redirect { location: location.to_string(), status };
+        self.redirects.insert(path.to_string(), redirect);
+        self.resources.remove(path);
+        self.copied.remove(path);
+        Ok(self)
+    }
+
+    /// Hash function and codec for every resource without its own.
+    pub fn set_cid_options(&mut self, options: CidOptions) -> &mut Self {
+        self.cid = options;
+        self
+    }
+
+    /// Hash function and codec for one resource already added.
+    pub fn set_resource_cid_options(
+        &mut self,
+        path: &str,
+        options: CidOptions,
+    ) -> Result<&mut Self> {
+        let Some(resource) = self.resources.get_mut(path) else {
+            bail!("no resource at {path}");
+        };
+        resource.cid = Some(options);
+        Ok(self)
+    }
+
+    /// Set an extra header on a resource already added. Which headers are
+    /// actually sent is up to the viewer (see `headers.rs`).
+    pub fn set_header(&mut self, path: &str, name: &str, value: &str) -> Result<&mut Self> {
+        let Some(resource) = self.resources.get_mut(path) else {
+            bail!("no resource at {path}");
+        };
+        resource.headers.insert(name.to_ascii_lowercase(), value.to_string());
+        Ok(self)
+    }
+
+    /// Replace the body of a resource already added, keeping its headers.
+    /// Precompressed and content-type variants of the old body are dropped.
+    pub fn set_body(&mut self, path: &str, bytes: impl Into<Vec<u8>>) -> Result<&mut Self> {
+        let Some(resource) = self.resources.get_mut(path) else {
+            bail!("no resource at {path}");
+        };
+        resource.bytes = bytes.into();
+        resource.variants.clear();
+        resource.typed.clear();
+        Ok(self)
+    }
+
+    /// Store `bytes` as the body of a resource already added, compressed
+    /// with `coding` (one of [`ENCODED_SRCS`]), for clients that accept it.
+    pub fn add_variant(
+        &mut self,
+        path: &str,
+        coding: &str,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Result<&mut Self> {
+        let Some((_, key)) = ENCODED_SRCS.iter().find(|(c, _)| *c == coding) else {
+            bail!("no precompressed variant for content coding {coding}");
+        };
+        let Some(resource) = self.resources.get_mut(path) else {
+            bail!("no resource at {path}");
+        };
+        resource.variants.insert(key.to_string(), bytes.into());
+        Ok(self)
+    }
+
+    /// Offer `bytes` as the body of a resource already added in another
+    /// content type, served to clients whose `Accept` names that type (see
+    /// `lib.rs`). Replaces an earlier variant of the same type.
+    pub fn add_type_variant(
+        &mut self,
+        path: &str,
+        content_type: &str,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Result<&mut Self> {
+        let Some(resource) = self.resources.get_mut(path) else {
+            bail!("no resource at {path}");
+        };
+        resource.typed.insert(content_type.to_string(), bytes.into());
+        Ok(self)
+    }
+
+    pub fn body(&self, path: &str) -> Option<&[u8]> {
+        self.resources.get(path).map(|r| r.bytes.as_slice())
+    }
+
+    pub fn resource_header(&self, path: &str, name: &str) -> Option<&str> {
+        self.resources.get(path)?.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Drop the resource or redirect at `path`, if any.
+    pub fn remove_resource(&mut self, path: &str) -> &mut Self {
+        self.resources.remove(path);
+        self.copied.remove(path);
+        self.redirects.remove(path);
+        self
+    }
+
+    pub fn set_metadata(&mut self, metadata: MaslFields) -> &mut Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sign the tile with `signer` when it is written, or don't for `None`.
+    pub(crate) fn set_signer(&mut self, signer: Option<Signer>) -> &mut Self {
+        self.signer = signer;
+        self
+    }
+
+    pub fn set_icons(&mut self, icons: Vec<Icon>) -> &mut Self {
+        self.icons = icons;
+        self
+    }
+
+    /// Resources whose bodies are byte-identical, in groups of two or more
+    /// paths. [`finish`](Self::finish) stores each such body once for each
+    /// [`CidOptions`] it is stored under.
+    pub fn identical_bodies(&self) -> Vec<Vec<String>> {
+        let mut by_digest: BTreeMap<Vec<u8>, Vec<String>> = BTreeMap::new();
+        for (path, resource) in &self.resources {
+            let digest = Sha256::digest(&resource.bytes).to_vec();
+            by_digest.entry(digest).or_default().push(path.clone());
+        }
+        by_digest.into_values().filter(|paths| paths.len() > 1).collect()
+    }
+
+    /// Resource paths added so far with their bodies, in order: redirects
+    /// and copied resources aside.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.resources.keys().map(String::as_str)
+    }
+
+    /// The MASL [`finish`](Self::finish) would write, as `parse_tile` would
+    /// read it back.
+    pub fn masl(&self) -> Result<Masl> {
+        self.validate()?;
+        let (links, _) = self.blocks()?;
+        let mut resources = HashMap::new();
+        let mut variants = HashMap::new();
+        for ((resource_path, resource), links) in self.resources.iter().zip(links) {
+            let mut entry: Resource = resource.headers.clone().into_iter().collect();
+            let typed: Vec<Resource> = links
+                .typed
+                .into_iter()
+                .map(|(content_type, cid)| {
+                    let mut variant = entry.clone();
+                    variant.insert("content-type".into(), content_type.into());
+                    variant.insert("src".into(), cid.to_string());
+                    variant
+                })
+                .collect();
+            if !typed.is_empty() {
+                variants.insert(resource_path.clone(), typed);
+            }
+            let keys = links.keys.into_iter();
+            entry.extend(keys.map(|(key, cid)| (key.to_string(), cid.to_string())));
+            resources.insert(resource_path.clone(), entry);
+        }
+        for (resource_path, copied) in &self.copied {
+            resources.insert(resource_path.clone(), copied.entry.clone());
+            if !copied.typed.is_empty() {
+                variants.insert(resource_path.clone(), copied.typed.clone());
+            }
+        }
+        let m = self.metadata.clone();
+        let default = |by_language: &Localized| default_text(by_language).map(|(_, t)| t.into());
+        Ok(Masl {
+            name: default(&m.names).unwrap_or(m.name),
+            names: m.names.clone(),
+            resources,
+            variants,
+            redirects: self.redirects.clone().into_iter().collect(),
+            icons: self.icons.clone(),
+            description: default(&m.descriptions).or(m.description),
+            descriptions: m.descriptions.clone(),
+            short_name: m.short_name,
+            theme_color: m.theme_color,
+            background_color: m.background_color,
+            update_url: m.update_url,
+            permissions: m.permissions,
+            signature: None,
+            author_did: None,
+            author_proof: None,
+            authors: m.authors,
+            license: m.license,
+            published: m.published,
+            modified: m.modified,
+            dc: m.dc,
+            error_pages: m.error_pages,
+            extra: m.extra,
+        })
+    }
+
+    /// Encode the tile and write it to `path`, unless the result would fail
+    /// `validate::check`. Returns the CID of the header written, which is
+    /// the MASL as a DAG-CBOR block.
+    pub fn finish(&self, path: &Path) -> Result<Cid> {
+        self.validate()?;
+
+        let (links, blocks) = self.blocks()?;
+        let mut entries = Vec::new();
+        let link = |cid: Cid| {
+            let mut link = vec![0x00];
+            link.extend(cid.to_bytes());
+            CborValue::Tag(42, Box::new(CborValue::Bytes(link)))
+        };
+        for ((resource_path, resource), links) in self.resources.iter().zip(links) {
+            let mut entry = Vec::new();
+            for (key, cid) in links.keys {
+                entry.push((text(key), link(cid)));
+            }
+            entry.extend(resource.headers.iter().map(|(k, v)| (text(k), text(v))));
+            if !links.typed.is_empty() {
+                let typed = links.typed.into_iter().map(|(content_type, cid)| {
+                    let src = (text("src"), link(cid));
+                    CborValue::Map(vec![src, (text("content-type"), text(content_type))])
+                });
+                entry.push((text("variants"), CborValue::Array(typed.collect())));
+            }
+            entries.push((text(resource_path), CborValue::Map(entry)));
+        }
+        let mut copied_blocks: BTreeMap<Vec<u8>, (&Arc<TileContent>, String)> = BTreeMap::new();
+        for (resource_path, copied) in &self.copied {
+            let mut entry = Vec::new();
+            for (key, value) in &copied.entry {
+                let value = if is_src_key(key) {
+                    link(Cid::try_from(value.as_str())?)
+                } else {
+                    text(value)
+                };
+                entry.push((text(key), value));
+            }
+            let mut typed = Vec::new();
+            for variant in &copied.typed {
+                let content_type = variant.get("content-type");
+                let (Some(src), Some(content_type)) = (variant.get("src"), content_type) else {
+                    continue;
+                };
+                let src = (text("src"), link(Cid::try_from(src.as_str())?));
+                typed.push(CborValue::Map(vec![src, (text("content-type"), text(content_type))]));
+            }
+            if !typed.is_empty() {
+                entry.push((text("variants"), CborValue::Array(typed)));
+            }
+            entries.push((text(resource_path), CborValue::Map(entry)));
+            let srcs = copied.entry.iter().filter(|(key, _)| is_src_key(key)).map(|(_, v)| v);
+            let srcs = srcs.chain(copied.typed.iter().filter_map(|variant| variant.get("src")));
+            for src in srcs {
+                for cid in copied.from.content_blocks(src)? {
+                    let bytes = Cid::try_from(cid.as_str())?.to_bytes();
+                    if !blocks.contains_key(&bytes) {
+                        copied_blocks.entry(bytes).or_insert((&copied.from, cid));
+                    }
+                }
+            }
+        }
+        for (redirect_path, redirect) in &self.redirects {
+            let status = CborValue::Integer(redirect.status.into());
+            let location = (text("location"), text(&redirect.location));
+            let entry = vec![location, (text("status"), status)];
+            entries.push((text(redirect_path), CborValue::Map(entry)));
+        }
+
+        let mut header = self.header(entries);
+        if let Some(signer) = &self.signer {
+            signer.sign_header(&mut header)?;
+        }
+        let cids = blocks.keys().chain(copied_blocks.keys());
+        let cids = cids.filter_map(|c| Cid::try_from(c.as_slice()).ok()).collect();
+        validate::ensure(&header, &cids)?;
+        let header_bytes = dagcbor::encode(&CborValue::Map(header))?;
+
+        let tmp = path.with_extension("tile.partial");
+        let written = (|| -> Result<()> {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            write_uvarint(&mut out, header_bytes.len() as u64)?;
+            out.write_all(&header_bytes)?;
+            for (cid_bytes, data) in &blocks {
+                write_uvarint(&mut out, (cid_bytes.len() + data.len()) as u64)?;
+                out.write_all(cid_bytes)?;
+                out.write_all(data)?;
+            }
+            for (cid_bytes, (from, cid)) in &copied_blocks {
+                write_uvarint(&mut out, cid_bytes.len() as u64 + from.stored_len(cid)?)?;
+                out.write_all(cid_bytes)?;
+                from.copy_block(cid, &mut out)?;
+            }
+            out.into_inner()?.sync_all()?;
+            Ok(())
+        })();
+        if let Err(e) = written.and_then(|()| Ok(std::fs::rename(&tmp, path)?)) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+        revisions::header_cid(&header_bytes)
+    }
+
+    /// The CID each resource links to under each of its keys, `src` first,
+    /// and for each of its variants by content type, in resource order, and
+    /// the blocks to write by CID. Bytes already stored, as another
+    /// resource's body or variant, get the CID they were stored under.
+    fn blocks<'a>(&'a self) -> Result<(Vec<Links<'a>>, Blocks<'a>)> {
+        let mut blocks = BTreeMap::new();
+        let mut stored: HashMap<(CidOptions, &[u8]), Cid> = HashMap::new();
+        let mut links = Vec::new();
+        for resource in self.resources.values() {
+            let options = resource.cid.unwrap_or(self.cid);
+            let mut store = |bytes: &'a Vec<u8>| -> Result<Cid> {
+                if let Some(cid) = stored.get(&(options, bytes.as_slice())) {
+                    return Ok(*cid);
+                }
+                let (cid, block) = options.block(bytes)?;
+                blocks.entry(cid.to_bytes()).or_insert(block);
+                stored.insert((options, bytes), cid);
+                Ok(cid)
+            };
+            let bodies = std::iter::once(("src", &resource.bytes))
+                .chain(resource.variants.iter().map(|(key, bytes)| (key.as_str(), bytes)));
+            let keys = bodies.map(|(key, bytes)| Ok((key, store(bytes)?)));
+            let keys = keys.collect::<Result<_>>()?;
+            let typed = resource.typed.iter().map(|(ty, bytes)| Ok((ty.as_str(), store(bytes)?)));
+            let typed = typed.collect::<Result<_>>()?;
+            links.push(Links { keys, typed });
+        }
+        Ok((links, blocks))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let names = &self.metadata.names;
+        let name = default_text(names).map_or(self.metadata.name.as_str(), |(_, name)| name);
+        if name.trim().is_empty() {
+            bail!("tile needs a name");
+        }
+        if self.resources.is_empty() && self.copied.is_empty() {
+            bail!("tile has no resources");
+        }
+        let served: BTreeSet<&String> = self.resources.keys().chain(self.copied.keys()).collect();
+        for icon in &self.icons {
+            if !served.contains(&icon.src) {
+                bail!("icon {} is not one of the tile's resources", icon.src);
+            }
+        }
+        for (status, page) in &self.metadata.error_pages {
+            if !served.contains(page) {
+                bail!("error page {page} for {status} is not one of the tile's resources");
+            }
+        }
+        Ok(())
+    }
+
+    /// The CAR header: `version`, the MASL fields, `resources`, and the
+    /// extra fields whose keys those don't take.
+    fn header(&self, resources: Vec<(CborValue, CborValue)>) -> Vec<(CborValue, CborValue)> {
+        let m = &self.metadata;
+        let mut header = vec![
+            (text("version"), CborValue::Integer(1.into())),
+            (text("name"), language_map(&m.names).unwrap_or_else(|| text(&m.name))),
+        ];
+        if let Some(descriptions) = language_map(&m.descriptions) {
+            header.push((text("description"), descriptions));
+        }
+        let description = m.description.as_ref().filter(|_| m.descriptions.is_empty());
+        let optional = [
+            ("description", description),
+            ("short_name", m.short_name.as_ref()),
+            ("theme_color", m.theme_color.as_ref()),
+            ("background_color", m.background_color.as_ref()),
+            ("update_url", m.update_url.as_ref()),
+            ("license", m.license.as_ref()),
+            ("published", m.published.as_ref()),
+            ("modified", m.modified.as_ref()),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                header.push((text(key), text(value)));
+            }
+        }
+        if !m.permissions.is_empty() {
+            let permissions = m.permissions.iter().map(|p| text(p)).collect();
+            header.push((text("permissions"), CborValue::Array(permissions)));
+        }
+        if let Some(authors) = authors_value(&m.authors) {
+            header.push((text("author"), authors));
+        }
+        if !m.dc.is_empty() {
+            header.push((text("dc"), terms_value(&m.dc)));
+        }
+        if !m.error_pages.is_empty() {
+            let pages = m.error_pages.iter().map(|(status, page)| {
+                (text(&status.to_string()), text(page))
+            });
+            header.push((text("error_pages"), CborValue::Map(pages.collect())));
+        }
+        if !self.icons.is_empty() {
+            header.push((text("icons"), icons_value(&self.icons)));
+        }
+        header.push((text("resources"), CborValue::Map(resources)));
+        for (key, RawCbor(value)) in &m.extra {
+            if !header.iter().any(|(k, _)| k.as_text() == Some(key)) {
+                header.push((text(key), value.clone()));
+            }
+        }
+        header
+    }
+}
+
+pub(crate) fn text(s: &str) -> CborValue {
+    CborValue::Text(s.to_string())
+}
+
+/// `by_language` as a MASL language map, unless it is empty.
+pub(crate) fn language_map(by_language: &Localized) -> Option<CborValue> {
+    let entries = by_language.iter().map(|(tag, value)| (text(tag), text(value)));
+    (!by_language.is_empty()).then(|| CborValue::Map(entries.collect()))
+}
+
+/// The MASL `author`: the name alone for one author known only by name,
+/// else a map per author, in an array if there are several.
+pub(crate) fn authors_value(authors: &[Author]) -> Option<CborValue> {
+    let value = |author: &Author| {
+        let mut fields = vec![(text("name"), text(&author.name))];
+        for (key, value) in [("url", &author.url), ("email", &author.email)] {
+            if let Some(value) = value {
+                fields.push((text(key), text(value)));
+            }
+        }
+        CborValue::Map(fields)
+    };
+    match authors {
+        [] => None,
+        [Author { name, url: None, email: None }] => Some(text(name)),
+        [author] => Some(value(author)),
+        authors => Some(CborValue::Array(authors.iter().map(value).collect())),
+    }
+}
+
+/// The MASL `dc` map: a term with one value maps to it, one with several
+/// to an array.
+pub(crate) fn terms_value(terms: &Terms) -> CborValue {
+    let terms = terms.iter().map(|(term, values)| {
+        let value = match values.as_slice() {
+            [value] => text(value),
+            values => CborValue::Array(values.iter().map(|v| text(v)).collect()),
+        };
+        (text(term), value)
+    });
+    CborValue::Map(terms.collect())
+}
+
+/// The MASL `icons` array.
+pub(crate) fn icons_value(icons: &[Icon]) -> CborValue {
+    let icons = icons
+        .iter()
+        .map(|icon| {
+            let mut fields = vec![(text("src"), text(&icon.src))];
+            if !icon.sizes.is_empty() {
+                fields.push((text("sizes"), text(&icon.sizes)));
+            }
+            if !icon.purpose.is_empty() {
+                fields.push((text("purpose"), text(&icon.purpose)));
+            }
+            CborValue::Map(fields)
+        })
+        .collect();
+    CborValue::Array(icons)
+}
+
+/// Multicodec code of raw blocks.
+const RAW: u64 = 0x55;
+
+/// Raw-codec CIDv1 with a SHA-256 multihash, the default for new blocks.
+pub(crate) fn raw_cid(data: &[u8]) -> Result<Cid> {
+    Ok(Cid::new_v1(RAW, Multihash::wrap(SHA2_256, &Sha256::digest(data))?))
+}
+
+pub(crate) fn write_uvarint(out: &mut impl Write, mut value: u64) -> std::io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
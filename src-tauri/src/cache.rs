@@ -0,0 +1,131 @@
+use crate::car::cid_matches;
+use anyhow::Result;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// ── Block cache ──────────────────────────────────────────────────────────────
+//
+// A byte-budgeted LRU in front of `TileContent::read_block`, shared by every
+// open tile. A block whose bytes hash to its CID is cached under the CID
+// alone, so frameworks and fonts that several tiles bundle are read and held
+// once. Blocks that can't be checked (unsupported hash, or a mismatch) are
+// keyed by (tile file, CID) instead: the CID alone would let one tile's
+// unverified bytes be served for another tile that references the same CID.
+//
+// Blocks are held as `Bytes`, so a hit hands out a reference-counted view of
+// the cached buffer rather than a copy.
+
+/// `(None, cid)` for verified shared blocks, `(Some(file), cid)` otherwise.
+type Key = (Option<PathBuf>, String);
+
+pub struct BlockCache(Mutex<Lru>);
+
+struct Lru {
+    budget: u64,
+    used: u64,
+    tick: u64,
+    entries: HashMap<Key, Entry>,
+    /// Last-use tick → key; the first entry is the least recently used.
+    order: BTreeMap<u64, Key>,
+}
+
+struct Entry {
+    data: Bytes,
+    tick: u64,
+}
+
+impl BlockCache {
+    pub fn new(budget_bytes: u64) -> Self {
+        BlockCache(Mutex::new(Lru {
+            budget: budget_bytes,
+            used: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }))
+    }
+
+    /// Return the cached block, or `load` it and cache the result. The lock
+    /// is not held while loading.
+    pub fn get_or_load(
+        &self,
+        path: &Path,
+        cid: &str,
+        load: impl FnOnce() -> Result<Vec<u8>>,
+    ) -> Result<Bytes> {
+        let shared = (None, cid.to_string());
+        let own = (Some(path.to_path_buf()), cid.to_string());
+        {
+            let mut lru = self.0.lock().unwrap();
+            if let Some(data) = lru.get(&shared).or_else(|| lru.get(&own)) {
+                return Ok(data);
+            }
+        }
+        let data = Bytes::from(load()?);
+        let key = if cid_matches(cid, &data) == Some(true) { shared } else { own };
+        self.0.lock().unwrap().insert(key, data.clone());
+        Ok(data)
+    }
+
+    /// Change the byte budget, evicting as needed.
+    pub fn set_budget(&self, budget_bytes: u64) {
+        let mut lru = self.0.lock().unwrap();
+        lru.budget = budget_bytes;
+        lru.evict();
+    }
+
+    /// Drop every unverified block cached for the tile at `path`, e.g. after
+    /// it was rewritten on disk. Shared blocks stay valid: their bytes are
+    /// fixed by their CID.
+    pub fn evict_path(&self, path: &Path) {
+        let mut lru = self.0.lock().unwrap();
+        let keys: Vec<Key> =
+            lru.entries.keys().filter(|(p, _)| p.as_deref() == Some(path)).cloned().collect();
+        for key in keys {
+            lru.remove(&key);
+        }
+    }
+}
+
+impl Lru {
+    fn get(&mut self, key: &Key) -> Option<Bytes> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.tick);
+        entry.tick = tick;
+        self.order.insert(tick, key.clone());
+        Some(entry.data.clone())
+    }
+
+    fn insert(&mut self, key: Key, data: Bytes) {
+        let size = data.len() as u64;
+        if size > self.budget {
+            return;
+        }
+        self.remove(&key);
+        self.tick += 1;
+        self.used += size;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, Entry { data, tick: self.tick });
+        self.evict();
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+            self.used -= entry.data.len() as u64;
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.used > self.budget {
+            let Some((_, key)) = self.order.pop_first() else { break };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.used -= entry.data.len() as u64;
+            }
+        }
+    }
+}
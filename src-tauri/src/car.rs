@@ -1,34 +1,211 @@
+use crate::dagcbor::RawCbor;
+use crate::dagpb::{self, FileNode, DAG_PB};
+use crate::error::TileError;
+use crate::hashing::{file_sha256, hex, multihash_digest, MultiHasher, IDENTITY};
+use crate::mmap::Mapping;
+use crate::revisions;
+use crate::routes::RouteTrie;
+use crate::sidecar;
 use anyhow::{anyhow, bail, Result};
+use cid::multihash::Multihash;
 use cid::Cid;
 use ciborium::value::Value as CborValue;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
 
 // ── MASL types ───────────────────────────────────────────────────────────────
 //
 // Resources are stored as flat maps: "src" → CID string, other keys → HTTP
 // header values.  This mirrors the MASL structure directly (headers are
 // siblings of `src`, not nested under a "headers" key).
+//
+// Blocks are usually raw-codec and SHA-256 hashed. SHA-512 and BLAKE3
+// digests are read as well, and so are dag-pb blocks heading a UnixFS file
+// (see `dagpb.rs`), whose content is reassembled from every block the DAG
+// links to; ranges of one read only the blocks they overlap. A CID with an
+// identity multihash carries its block inline and has no frame in the CAR:
+// its bytes are taken from the CID itself, for tiles that inline tiny
+// resources that way. Reads trust the block index unless verification of
+// reads is on for the tile, in which case a block whose bytes don't hash to
+// its CID fails with [`TileError::CorruptBlock`].
+//
+// A resource may also carry precompressed copies of its body as `src-br` and
+// `src-gzip` links; the protocol handler picks one based on
+// `Accept-Encoding`. Its entry may list alternatives in other content types
+// as `variants`, an array of maps each with its own `src` and
+// `content-type`, of which the handler picks one based on `Accept`: AVIF for
+// webviews that ask for it and PNG to the others, say.
+//
+// An entry with a `location` and no `src` is a redirect rather than a
+// resource: the handler answers it with `status` (an integer, 302 unless
+// given) pointing at `location`, a path in the tile. Redirects are kept in
+// `Masl::redirects`, apart from the resources, and routed like them.
+//
+// `error_pages` maps error statuses, as text, to resource paths: a tile with
+// `{ "404": "/not-found.html" }` has that page served, with its status, in
+// place of the handler's plain-text 404. The path is resolved and the page
+// negotiated as a request for it would be.
 
 pub type Resource = HashMap<String, String>;
 
+/// Resource paths mapped to the variants their entries list: alternatives to
+/// the resource's body in other content types.
+pub type Variants = HashMap<String, Vec<Resource>>;
+
+/// Error statuses mapped to the resource paths of the pages shown for them.
+pub type ErrorPages = BTreeMap<u16, String>;
+
+/// Statuses a redirect entry may give, and the one it has without.
+pub const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+pub const DEFAULT_REDIRECT_STATUS: u16 = 302;
+
+/// A resource entry with a `location` and no `src`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redirect {
+    pub location: String,
+    pub status: u16,
+}
+
+impl Redirect {
+    /// Whether `location` is a path in the tile, the only place the handler
+    /// redirects to: absolute, and not a `//host` (or `/\host`) reference.
+    pub fn stays_in_tile(&self) -> bool {
+        self.location.starts_with('/')
+            && !self.location.starts_with("//")
+            && !self.location.contains('\\')
+    }
+}
+
+/// Text given once per language, by BCP 47 tag, as `name` and `description`
+/// may be: `{ "en": "Report", "fr": "Rapport" }`.
+pub type Localized = BTreeMap<String, String>;
+
+/// Dublin Core–style terms (`subject`, `publisher`, `rights`, …), each with
+/// one value or several.
+pub type Terms = BTreeMap<String, Vec<String>>;
+
+/// Content codings a resource can carry precompressed variants for, in order
+/// of preference, with the resource key holding each variant's CID.
+pub const ENCODED_SRCS: [(&str, &str); 2] = [("br", "src-br"), ("gzip", "src-gzip")];
+
+/// Whether a resource key holds a block link rather than a header value.
+pub fn is_src_key(key: &str) -> bool {
+    key == "src" || ENCODED_SRCS.iter().any(|(_, k)| *k == key)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Masl {
+    /// The name, or for a name given by language its [`default_text`].
     pub name: String,
+    /// The name by language, if the MASL gives it as a language map.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub names: Localized,
     pub resources: HashMap<String, Resource>,
+    /// The `variants` array of each resource entry that has one. A variant
+    /// is a resource of its own, with its own `src` and `content-type`, and
+    /// the entry's headers where it doesn't set them.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variants: Variants,
+    /// Resource entries that are redirects, by path.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub redirects: HashMap<String, Redirect>,
     #[serde(default)]
     pub icons: Vec<Icon>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// The description by language, as for `names`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptions: Localized,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub short_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub theme_color: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub background_color: Option<String>,
+    /// Where newer revisions of this tile can be downloaded from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_url: Option<String>,
+    /// Capabilities the tile asks for beyond the offline sandbox, e.g.
+    /// `"network"`. Nothing is granted until the user approves it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+    /// Publisher signature embedded in the header (see [`TileSignature`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<TileSignature>,
+    /// DID the author claims; checked against the signing key by `did.rs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_did: Option<String>,
+    /// Hex Ed25519 signature by one of the DID's keys over the signing key,
+    /// for tiles signed with a key the DID document doesn't list itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_proof: Option<String>,
+    /// Who made the tile, as `author` names them: a name, a map of `name`,
+    /// `url` and `email`, or an array of either.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Author>,
+    /// SPDX expression or URL of the terms the tile is under.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// RFC 3339 dates of first publication and of the last change, as the
+    /// author gives them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// The `dc` map of further terms.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dc: Terms,
+    /// The `error_pages` map.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_pages: ErrorPages,
+    /// Header fields this app doesn't read, by key, kept so the MASL can be
+    /// written back whole. `version`, `roots` and the revision links
+    /// (see `revisions.rs`) aren't among them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, RawCbor>,
+}
+
+impl Masl {
+    /// Every resource and every content-type variant, with the path each is
+    /// served at.
+    pub fn served(&self) -> impl Iterator<Item = (&String, &Resource)> {
+        let variants = self.variants.iter();
+        let variants = variants.flat_map(|(path, v)| v.iter().map(move |variant| (path, variant)));
+        self.resources.iter().chain(variants)
+    }
+}
+
+/// An Ed25519 signature over a tile's root hash, embedded in the MASL as
+/// `signature: { alg: "ed25519", key: bytes, sig: bytes, name?: text }` or
+/// shipped next to the file as `<file>.sig` JSON with hex `key`/`sig`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileSignature {
+    pub alg: String,
+    /// Hex public key.
+    pub key: String,
+    /// Hex signature.
+    pub sig: String,
+    /// Publisher name as claimed by the signer; not itself verified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// One of the people or organisations a MASL's `author` names.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,88 +219,1418 @@ pub struct Icon {
 
 // ── Tile content ─────────────────────────────────────────────────────────────
 
+/// Upper bound on the encoded size of a block's CID prefix.
+const MAX_CID_LEN: usize = 256;
+
+// Caps on what a tile may make the parser hold, so a pathological file fails
+// to open instead of exhausting memory.
+
+/// Largest CAR header (MASL) accepted.
+pub(crate) const MAX_HEADER_LEN: u64 = 16 << 20;
+/// Most resources a MASL may declare.
+const MAX_RESOURCES: usize = 100_000;
+/// Most block frames indexed.
+const MAX_BLOCKS: usize = 1_000_000;
+/// Rough budget for the block index: each entry's CID string plus its
+/// offset/length and map overhead.
+const MAX_INDEX_BYTES: usize = 256 << 20;
+const INDEX_ENTRY_OVERHEAD: usize = 48;
+
+/// CID (canonical string form) → (byte offset of block data, byte length)
+pub type BlockIndex = HashMap<String, (u64, u64)>;
+
 /// Parsed tile: keeps the file path + MASL + a CID→(offset, len) index so
 /// individual blocks can be served by seeking into the file on demand.
+///
+/// The block index may be built lazily (see [`parse_tile_header`]): it is
+/// filled by the first caller of [`TileContent::index`] or by a background
+/// indexer, and concurrent callers wait for that single build.
 #[derive(Debug)]
 pub struct TileContent {
     pub path: PathBuf,
     pub masl: Masl,
-    /// CID (canonical string form) → (byte offset of block data, byte length)
-    pub index: HashMap<String, (u64, u64)>,
+    /// `masl` serialised once at open, shared by every payload that carries it.
+    pub masl_json: Arc<RawValue>,
+    /// Hex SHA-256 of the CAR header re-encoded without its `signature`
+    /// entry: what publisher signatures are made over. The header pins every
+    /// resource's CID, so this covers the whole tile.
+    pub root_hash: String,
+    /// Path lookup structure over `masl.resources`.
+    routes: RouteTrie,
+    /// Byte offset of the first block frame, just past the CAR header.
+    data_start: u64,
+    index: Arc<OnceLock<Result<BlockIndex, TileError>>>,
+    /// Handle reused for positioned reads, with the size/mtime it was opened
+    /// against. Dropped and reopened if the file changes or a read fails.
+    handle: RwLock<Option<(Arc<File>, FileStamp)>>,
+    /// Read blocks out of a memory mapping of the file (see `mmap.rs`).
+    memory_mapped: bool,
+    /// That mapping, with the size/mtime it was made against, kept like
+    /// `handle`.
+    mapping: RwLock<Option<(Arc<Mapping>, FileStamp)>>,
+    /// Hash blocks against their CIDs as they are read.
+    verify_reads: AtomicBool,
+    /// Blocks read since and found to match, so each is hashed only once.
+    verified: Mutex<HashSet<String>>,
+    /// Size/mtime the file was last checked at, and whether it still held
+    /// this content then, `None` while that is being hashed: taken when
+    /// parsed, and moved on to each changed stamp as it is checked, so a
+    /// stamp is hashed at most once.
+    stamp: Arc<Mutex<Option<CheckedStamp>>>,
+    /// SHA-256 of the whole file as parsed, once [`TileContent::spawn_hasher`]
+    /// has computed it.
+    file_hash: Arc<OnceLock<String>>,
+    backing: Backing,
+    /// CID of the earlier header this content was read from, when it shows
+    /// the tile as of a past revision (see `revisions.rs`).
+    pub revision: Option<String>,
+    /// The CAR header's `roots`.
+    pub roots: Vec<String>,
+    /// The root block `masl` was read from, or `None` if it is the header.
+    pub root: Option<String>,
+    /// Whether `root` is another root than the file's own MASL, as for a
+    /// view made by [`TileContent::at_root`].
+    other_root: bool,
+}
+
+/// Where a tile's blocks are read from.
+#[derive(Debug)]
+enum Backing {
+    /// Block frames in the CAR file at `path`.
+    Car,
+    /// A folder registered as a virtual tile: CID → the file holding that
+    /// block, with the size/mtime it had when registered. `path` is the
+    /// folder.
+    Directory(HashMap<String, (PathBuf, FileStamp)>),
+    /// Block frames in a decompressed copy of the zstd-compressed file at
+    /// `path` (see [`decompress`]).
+    Compressed(Arc<Decompressed>),
+}
+
+/// A stamp the file was checked at, and whether it still held the parsed
+/// content then (`None` while being hashed).
+type CheckedStamp = (FileStamp, Option<bool>);
+
+/// Cheap identity check for the file behind a handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Inode number, where the platform has one: a file renamed over the
+    /// path has a new one.
+    #[serde(default)]
+    inode: Option<u64>,
+}
+
+impl FileStamp {
+    pub(crate) fn of(path: &Path) -> std::io::Result<Self> {
+        let meta = std::fs::metadata(path)?;
+        #[cfg(unix)]
+        let inode = Some(std::os::unix::fs::MetadataExt::ino(&meta));
+        #[cfg(not(unix))]
+        let inode = None;
+        Ok(FileStamp { len: meta.len(), modified: meta.modified().ok(), inode })
+    }
+
+    /// Whether `other` may be the same bytes, touched: the same size, in
+    /// the same file.
+    fn may_be_same(&self, other: &FileStamp) -> bool {
+        self.len == other.len && self.inode == other.inode
+    }
+}
+
+/// Everything parsing derives from a CAR, in a form that can be stored and
+/// turned back into a [`TileContent`] without reading the file again.
+/// Stored by the parse cache: bump its `SCHEMA` on any change here.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParsedParts {
+    pub masl: Masl,
+    pub root_hash: String,
+    pub data_start: u64,
+    pub index: BlockIndex,
+    #[serde(default)]
+    pub roots: Vec<String>,
+    #[serde(default)]
+    pub root: Option<String>,
 }
 
 impl TileContent {
-    /// Read the raw bytes of the block identified by `cid_str`.
+    fn new(
+        path: &Path,
+        masl: Masl,
+        root_hash: String,
+        data_start: u64,
+        index: OnceLock<Result<BlockIndex, TileError>>,
+    ) -> Result<Self> {
+        let masl_json = Arc::from(serde_json::value::to_raw_value(&masl)?);
+        let routes = RouteTrie::new(masl.resources.keys().chain(masl.redirects.keys()));
+        Ok(TileContent {
+            path: path.to_path_buf(),
+            masl,
+            masl_json,
+            root_hash,
+            routes,
+            data_start,
+            index: Arc::new(index),
+            handle: RwLock::new(None),
+            memory_mapped: false,
+            mapping: RwLock::new(None),
+            verify_reads: AtomicBool::new(false),
+            verified: Mutex::new(HashSet::new()),
+            stamp: Arc::new(Mutex::new(FileStamp::of(path).ok().map(|stamp| (stamp, Some(true))))),
+            file_hash: Arc::new(OnceLock::new()),
+            backing: Backing::Car,
+            revision: None,
+            roots: Vec::new(),
+            root: None,
+            other_root: false,
+        })
+    }
+
+    /// A virtual tile over the folder `dir`, whose blocks are whole files:
+    /// `files` maps each CID in `masl` to the file with those bytes.
+    pub fn from_directory(
+        dir: &Path,
+        masl: Masl,
+        root_hash: String,
+        files: HashMap<String, PathBuf>,
+    ) -> Result<Self> {
+        let mut index = BlockIndex::new();
+        let mut stamped = HashMap::new();
+        for (cid, file) in files {
+            let stamp = FileStamp::of(&file)?;
+            index.insert(cid.clone(), (0, stamp.len));
+            stamped.insert(cid, (file, stamp));
+        }
+        let mut content = TileContent::new(dir, masl, root_hash, 0, OnceLock::from(Ok(index)))?;
+        content.backing = Backing::Directory(stamped);
+        Ok(content)
+    }
+
+    /// Read blocks out of a memory mapping of the file rather than with a
+    /// read call each, where the platform maps files and the file is a
+    /// decompressed copy only this app writes.
+    pub fn set_memory_mapped(&mut self, on: bool) {
+        self.memory_mapped = on;
+    }
+
+    /// Hash each block against its CID the first time it is read, failing
+    /// reads of one that doesn't match with [`TileError::CorruptBlock`].
+    pub fn set_verify_reads(&self, on: bool) {
+        self.verify_reads.store(on, Ordering::Relaxed);
+    }
+
+    /// Whether blocks are hashed as they are read (see [`Self::set_verify_reads`]).
+    pub fn verifies_reads(&self) -> bool {
+        self.verify_reads.load(Ordering::Relaxed)
+    }
+
+    /// Whether this is a virtual tile over a folder rather than a CAR.
+    pub fn is_directory(&self) -> bool {
+        matches!(self.backing, Backing::Directory(_))
+    }
+
+    /// Whether the file is a zstd-compressed CAR, read through a
+    /// decompressed copy.
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.backing, Backing::Compressed(_))
+    }
+
+    /// The CAR block frames are read from: the file, or for a compressed
+    /// tile its decompressed copy.
+    pub(crate) fn car_path(&self) -> &Path {
+        match &self.backing {
+            Backing::Compressed(copy) => &copy.0,
+            _ => &self.path,
+        }
+    }
+
+    /// The tile as of the earlier header stored in block `cid_str`: its MASL
+    /// over this file's blocks. Shares this content's block index.
+    pub fn as_of(&self, cid_str: &str) -> Result<TileContent> {
+        if self.is_directory() {
+            bail!("a directory tile has no revisions");
+        }
+        let header = self.read_block(cid_str)?;
+        if cid_matches(cid_str, &header) != Some(true) {
+            bail!("revision {cid_str} does not match its CID");
+        }
+        let (masl, root_hash) = parse_masl(&header)?;
+        let mut content =
+            TileContent::new(&self.path, masl, root_hash, self.data_start, OnceLock::new())?;
+        content.index = self.index.clone();
+        content.backing = self.shared_backing();
+        content.memory_mapped = self.memory_mapped;
+        content.set_verify_reads(self.verifies_reads());
+        content.revision = Some(cid_str.to_string());
+        content.roots = self.roots.clone();
+        content.root = self.root.clone();
+        content.other_root = self.other_root;
+        Ok(content)
+    }
+
+    /// The tile as the MASL in root block `cid_str` describes it, for a CAR
+    /// with several roots: that manifest over this file's blocks. Shares
+    /// this content's block index.
+    pub fn at_root(&self, cid_str: &str) -> Result<TileContent> {
+        if !self.roots.iter().any(|root| root == cid_str) {
+            bail!("{cid_str} is not one of the tile's roots");
+        }
+        let block = self.read_block(cid_str)?;
+        if cid_matches(cid_str, &block) == Some(false) {
+            bail!("root {cid_str} does not match its CID");
+        }
+        let (masl, root_hash) =
+            parse_masl(&block).map_err(|e| match e.downcast::<TileError>() {
+                Ok(TileError::InvalidMasl(e)) => {
+                    TileError::InvalidMasl(format!("root {cid_str} isn't a MASL: {e}")).into()
+                }
+                Ok(e) => e.into(),
+                Err(e) => e,
+            })?;
+        let mut content =
+            TileContent::new(&self.path, masl, root_hash, self.data_start, OnceLock::new())?;
+        content.index = self.index.clone();
+        content.backing = self.shared_backing();
+        content.memory_mapped = self.memory_mapped;
+        content.set_verify_reads(self.verifies_reads());
+        content.roots = self.roots.clone();
+        content.root = Some(cid_str.to_string());
+        content.other_root = true;
+        Ok(content)
+    }
+
+    /// Fail with [`TileError::ReadOnlyView`] if this content shows something
+    /// other than the file's own MASL as it is now, a past revision or
+    /// another root: an edit goes to the file's current MASL, not this one.
+    pub fn ensure_editable(&self) -> Result<(), TileError> {
+        if let Some(cid) = &self.revision {
+            return Err(TileError::ReadOnlyView(format!(
+                "this shows revision {cid} of the tile, which can't be edited"
+            )));
+        }
+        if let Some(root) = self.root.as_ref().filter(|_| self.other_root) {
+            return Err(TileError::ReadOnlyView(format!(
+                "this shows root {root} of the tile rather than its own MASL, and can't be edited"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The backing for another view of this file: the same decompressed
+    /// copy for a compressed tile. Only called on CAR-backed content.
+    fn shared_backing(&self) -> Backing {
+        match &self.backing {
+            Backing::Compressed(copy) => Backing::Compressed(copy.clone()),
+            _ => Backing::Car,
+        }
+    }
+
+    /// Rebuild a tile from previously parsed parts, index included.
+    pub fn from_parts(path: &Path, parts: ParsedParts) -> Result<Self> {
+        let index = OnceLock::from(Ok(parts.index));
+        let mut content =
+            TileContent::new(path, parts.masl, parts.root_hash, parts.data_start, index)?;
+        content.roots = parts.roots;
+        content.root = parts.root;
+        Ok(content)
+    }
+
+    /// Use `index` as the block index rather than building one, for a file
+    /// whose blocks are already known.
+    pub(crate) fn set_index(&mut self, index: BlockIndex) {
+        self.index = Arc::new(OnceLock::from(Ok(index)));
+    }
+
+    /// Read blocks from `copy`, the decompressed CAR these parts were parsed
+    /// from, as [`parse_tile_header`] does for a compressed tile.
+    pub(crate) fn with_decompressed(mut self, copy: Arc<Decompressed>) -> Self {
+        self.backing = Backing::Compressed(copy);
+        self
+    }
+
+    /// Copy out the parsed parts, building the index first if needed.
+    pub fn to_parts(&self) -> Result<ParsedParts> {
+        if self.is_directory() {
+            bail!("a directory tile has no CAR to reuse the parse of");
+        }
+        if self.is_compressed() {
+            bail!("a compressed tile's parse only holds for its decompressed copy");
+        }
+        Ok(ParsedParts {
+            masl: self.masl.clone(),
+            root_hash: self.root_hash.clone(),
+            data_start: self.data_start,
+            index: self.index()?.clone(),
+            roots: self.roots.clone(),
+            root: self.root.clone(),
+        })
+    }
+
+    /// Look up the resource served at `path` (see [`RouteTrie`] for the
+    /// matching rules).
+    pub fn resolve(&self, path: &str) -> Option<&Resource> {
+        self.masl.resources.get(self.routes.lookup(path)?)
+    }
+
+    /// [`resolve`](Self::resolve), with the variants the resource's entry
+    /// lists, for a request to be answered with the one it accepts best.
+    pub fn resolve_with_variants(&self, path: &str) -> Option<(&Resource, &[Resource])> {
+        let key = self.routes.lookup(path)?;
+        let variants = self.masl.variants.get(key).map_or(&[][..], Vec::as_slice);
+        Some((self.masl.resources.get(key)?, variants))
+    }
+
+    /// [`resolve_with_variants`](Self::resolve_with_variants) for a path the
+    /// tile names itself, such as an icon's or an error page's: a redirect
+    /// within the tile is followed once, as a client would.
+    pub fn resolve_linked(&self, path: &str) -> Option<(&Resource, &[Resource])> {
+        let path = match self.redirect(path) {
+            Some(redirect) if redirect.stays_in_tile() => redirect.location.as_str(),
+            Some(_) => return None,
+            None => path,
+        };
+        self.resolve_with_variants(path)
+    }
+
+    /// The redirect `path` resolves to, if it resolves to one rather than
+    /// to a resource.
+    pub fn redirect(&self, path: &str) -> Option<&Redirect> {
+        self.masl.redirects.get(self.routes.lookup(path)?)
+    }
+
+    /// Byte offset of the first block frame.
+    pub(crate) fn data_start(&self) -> u64 {
+        self.data_start
+    }
+
+    /// The block index, building it now if nothing has yet.
+    pub fn index(&self) -> Result<&BlockIndex> {
+        self.index
+            .get_or_init(|| {
+                build_index(self.car_path(), self.data_start).map_err(TileError::kept)
+            })
+            .as_ref()
+            .map_err(|e| e.clone().into())
+    }
+
+    /// Build the block index on a background thread so it is usually ready
+    /// by the time the first resource is requested.
+    pub fn spawn_indexer(&self) {
+        let (cell, start) = (self.index.clone(), self.data_start);
+        let (path, copy) = (self.car_path().to_path_buf(), self.shared_backing());
+        std::thread::spawn(move || {
+            // Holding the backing keeps a decompressed copy until indexed.
+            let _copy = copy;
+            cell.get_or_init(|| build_index(&path, start).map_err(TileError::kept));
+        });
+    }
+
+    /// Hash the whole file on a background thread, so a later
+    /// [`TileContent::modified_on_disk`] can tell a `touch` from new bytes.
+    pub fn spawn_hasher(&self) {
+        let (cell, path) = (self.file_hash.clone(), self.path.clone());
+        std::thread::spawn(move || {
+            if let Ok(hash) = file_sha256(&path) {
+                let _ = cell.set(hash);
+            }
+        });
+    }
+
+    /// Whether the file no longer holds the bytes this content was parsed
+    /// from, so serving more blocks from it would mix two versions. Only
+    /// the size, mtime and inode are compared, so this is cheap enough for
+    /// every request. A new size or inode is a change; a new mtime alone is
+    /// decided by hashing the file on a background thread, when a hash was
+    /// taken at open, once for that mtime, and counts as a change until
+    /// then. For a directory tile, whether any of its files changed size or
+    /// mtime.
+    pub fn modified_on_disk(&self) -> bool {
+        if let Backing::Directory(files) = &self.backing {
+            return files.values().any(|(file, stamp)| FileStamp::of(file).ok() != Some(*stamp));
+        }
+        let Ok(now) = FileStamp::of(&self.path) else { return true };
+        let mut stamp = self.stamp.lock().unwrap();
+        match *stamp {
+            Some((checked, same)) if checked == now => return same != Some(true),
+            Some((checked, _)) if !checked.may_be_same(&now) => {
+                *stamp = Some((now, Some(false)));
+                return true;
+            }
+            _ => {}
+        }
+        let Some(hash) = self.file_hash.get().cloned() else {
+            *stamp = Some((now, Some(false)));
+            return true;
+        };
+        *stamp = Some((now, None));
+        let (cell, path) = (self.stamp.clone(), self.path.clone());
+        std::thread::spawn(move || {
+            let same = file_sha256(&path).is_ok_and(|current| current == hash);
+            let mut stamp = cell.lock().unwrap();
+            if stamp.is_some_and(|(checked, _)| checked == now) {
+                *stamp = Some((now, Some(same)));
+            }
+        });
+        true
+    }
+
+    /// Whether [`TileContent::modified_on_disk`] is still hashing the file
+    /// to tell whether a new mtime came with new bytes.
+    pub fn checking_on_disk(&self) -> bool {
+        self.stamp.lock().unwrap().is_some_and(|(_, same)| same.is_none())
+    }
+
+    /// Read the content of the block identified by `cid_str`: its bytes,
+    /// or for a dag-pb block the file it heads, reassembled from every
+    /// block of its DAG.
     pub fn read_block(&self, cid_str: &str) -> Result<Vec<u8>> {
-        let &(offset, len) = self
-            .index
-            .get(cid_str)
-            .ok_or_else(|| anyhow!("block not found for CID {cid_str}"))?;
-        let mut f = File::open(&self.path)?;
+        self.read_content(cid_str, 0, &DagWalk::default())
+    }
+
+    /// Length in bytes of the content of the block identified by `cid_str`.
+    /// For a dag-pb file, taken from the sizes its nodes record where they
+    /// do, so only nodes that don't are read.
+    pub fn content_len(&self, cid_str: &str) -> Result<u64> {
+        self.content_len_at(cid_str, 0, &DagWalk::default())
+    }
+
+    /// Read `len` bytes starting `start` bytes into a block's content, so
+    /// very large blocks can be served in chunks without loading them whole.
+    /// In a dag-pb file only the blocks overlapping the range are read.
+    /// Inline blocks are read whole first. When reads are verified, each
+    /// block is hashed whole on its first read.
+    pub fn read_block_range(&self, cid_str: &str, start: u64, len: u64) -> Result<Vec<u8>> {
+        self.read_content_range(cid_str, start, len, 0, &DagWalk::default())
+    }
+
+    /// The blocks the content of `cid_str` is stored in: its own, and for a
+    /// dag-pb file every block of its DAG. An inline block is its CID, so
+    /// isn't among them.
+    pub(crate) fn content_blocks(&self, cid_str: &str) -> Result<Vec<String>> {
+        let walk = DagWalk::default();
+        let mut blocks = Vec::new();
+        let mut next = vec![(cid_str.to_string(), 0)];
+        while let Some((cid, depth)) = next.pop() {
+            walk.visit(&cid)?;
+            if is_dag_pb(&cid) {
+                let block = self.raw_block(&cid)?;
+                let node = self.file_node(&cid, &block, depth)?;
+                next.extend(node.links.iter().map(|link| (link.to_string(), depth + 1)));
+            }
+            if !is_inline(&cid) {
+                blocks.push(cid);
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Length of the block `cid_str` as stored, not inline.
+    pub(crate) fn stored_len(&self, cid_str: &str) -> Result<u64> {
+        Ok(self.locate(cid_str)?.1)
+    }
+
+    /// Copy the block `cid_str`, as stored, to `out` straight from the file
+    /// rather than reading it whole first.
+    pub(crate) fn copy_block(&self, cid_str: &str, out: &mut impl std::io::Write) -> Result<()> {
+        let (offset, len) = self.locate(cid_str)?;
+        let mut f = match &self.backing {
+            Backing::Directory(files) => {
+                let (file, _) = files.get(cid_str).ok_or_else(|| anyhow!("no file for {cid_str}"))?;
+                File::open(file)?
+            }
+            _ => File::open(self.car_path())?,
+        };
         f.seek(SeekFrom::Start(offset))?;
-        let mut buf = vec![0u8; len as usize];
-        f.read_exact(&mut buf)?;
+        if std::io::copy(&mut f.take(len), out)? != len {
+            bail!("block {cid_str} extends beyond file");
+        }
+        Ok(())
+    }
+
+    /// The content of `cid_str`: its bytes, or a dag-pb file's, refused as
+    /// soon as it outgrows the size its node declares, and if it falls short
+    /// of it.
+    fn read_content(&self, cid_str: &str, depth: usize, walk: &DagWalk) -> Result<Vec<u8>> {
+        walk.visit(cid_str)?;
+        let block = self.raw_block(cid_str)?;
+        if !is_dag_pb(cid_str) {
+            return Ok(block);
+        }
+        let node = self.file_node(cid_str, &block, depth)?;
+        let declared = node.size();
+        let mut content = node.data.to_vec();
+        for link in &node.links {
+            content.extend(self.read_content(&link.to_string(), depth + 1, walk)?);
+            if declared.is_some_and(|size| content.len() as u64 > size) {
+                break;
+            }
+        }
+        if let Some(size) = declared.filter(|&size| content.len() as u64 != size) {
+            let e = format!("dag-pb file {cid_str} doesn't hold the {size} bytes it declares");
+            bail!(TileError::MalformedDag(e));
+        }
+        Ok(content)
+    }
+
+    fn content_len_at(&self, cid_str: &str, depth: usize, walk: &DagWalk) -> Result<u64> {
+        walk.visit(cid_str)?;
+        if !is_dag_pb(cid_str) {
+            return match inline_block(cid_str) {
+                Some(block) => Ok(block.len() as u64),
+                None => Ok(self.locate(cid_str)?.1),
+            };
+        }
+        let block = self.raw_block(cid_str)?;
+        let node = self.file_node(cid_str, &block, depth)?;
+        if let Some(size) = node.size() {
+            return Ok(size);
+        }
+        let mut len = node.data.len() as u64;
+        for link in &node.links {
+            len += self.content_len_at(&link.to_string(), depth + 1, walk)?;
+        }
+        Ok(len)
+    }
+
+    fn read_content_range(
+        &self,
+        cid_str: &str,
+        start: u64,
+        len: u64,
+        depth: usize,
+        walk: &DagWalk,
+    ) -> Result<Vec<u8>> {
+        walk.visit(cid_str)?;
+        let out_of_range = |block_len: u64| start.checked_add(len).is_none_or(|end| end > block_len);
+        if is_dag_pb(cid_str) {
+            return self.read_file_range(cid_str, start, len, depth, walk);
+        }
+        if is_inline(cid_str) {
+            let content = self.raw_block(cid_str)?;
+            if out_of_range(content.len() as u64) {
+                bail!("range {start}+{len} outside block {cid_str} of {} bytes", content.len());
+            }
+            return Ok(content[start as usize..(start + len) as usize].to_vec());
+        }
+        let (offset, block_len) = self.locate(cid_str)?;
+        if out_of_range(block_len) {
+            bail!("range {start}+{len} outside block {cid_str} of {block_len} bytes");
+        }
+        if self.unverified(cid_str) {
+            self.checked(cid_str, self.verify_block(cid_str)?)?;
+        }
+        self.read_at(cid_str, offset + start, len)
+    }
+
+    /// The range of a dag-pb file: the part of the node's own bytes it
+    /// covers, then the part under each link it overlaps.
+    fn read_file_range(
+        &self,
+        cid_str: &str,
+        start: u64,
+        len: u64,
+        depth: usize,
+        walk: &DagWalk,
+    ) -> Result<Vec<u8>> {
+        let block = self.raw_block(cid_str)?;
+        let node = self.file_node(cid_str, &block, depth)?;
+        let child_lens = match node.child_lens() {
+            Some(lens) => lens.to_vec(),
+            None => node
+                .links
+                .iter()
+                .map(|link| self.content_len_at(&link.to_string(), depth + 1, walk))
+                .collect::<Result<_>>()?,
+        };
+        let total = node.data.len() as u64 + child_lens.iter().sum::<u64>();
+        let end = start.checked_add(len).filter(|&end| end <= total);
+        let Some(end) = end else {
+            bail!("range {start}+{len} outside block {cid_str} of {total} bytes");
+        };
+        let mut content = Vec::with_capacity(len as usize);
+        let data_len = node.data.len() as u64;
+        if start < data_len {
+            content.extend(&node.data[start as usize..end.min(data_len) as usize]);
+        }
+        let mut at = data_len;
+        for (link, child_len) in node.links.iter().zip(child_lens) {
+            let (from, to) = (start.max(at), end.min(at + child_len));
+            if from < to {
+                let child = link.to_string();
+                let range = (from - at, to - from);
+                content.extend(self.read_content_range(&child, range.0, range.1, depth + 1, walk)?);
+            }
+            at += child_len;
+            if at >= end {
+                break;
+            }
+        }
+        Ok(content)
+    }
+
+    /// `block` of `cid_str` parsed as a UnixFS file node, at `depth` links
+    /// below the resource's own block.
+    fn file_node<'a>(
+        &self,
+        cid_str: &str,
+        block: &'a [u8],
+        depth: usize,
+    ) -> Result<FileNode<'a>> {
+        if depth > MAX_DAG_DEPTH {
+            bail!("dag-pb file is linked more than {MAX_DAG_DEPTH} blocks deep at {cid_str}");
+        }
+        dagpb::file_node(block).map_err(|e| anyhow!("{cid_str}: {e}"))
+    }
+
+    /// The bytes of the block `cid_str`, from the file or its CID, hashed
+    /// against the CID if reads are verified.
+    fn raw_block(&self, cid_str: &str) -> Result<Vec<u8>> {
+        let block = match inline_block(cid_str) {
+            Some(block) => block,
+            None => {
+                let (offset, len) = self.locate(cid_str)?;
+                self.read_at(cid_str, offset, len)?
+            }
+        };
+        if self.unverified(cid_str) {
+            self.checked(cid_str, cid_matches(cid_str, &block))?;
+        }
+        Ok(block)
+    }
+
+    /// Hash the block identified by `cid_str` against its CID, reading it
+    /// in bounded chunks. `Ok(None)` if the hash function is unsupported.
+    /// An inline block is its CID, so always matches.
+    pub fn verify_block(&self, cid_str: &str) -> Result<Option<bool>> {
+        const CHUNK: u64 = 8 << 20;
+        let cid = Cid::try_from(cid_str)?;
+        if cid.hash().code() == IDENTITY {
+            return Ok(Some(true));
+        }
+        let (offset, len) = self.locate(cid_str)?;
+        let Some(mut hasher) = MultiHasher::new(cid.hash().code()) else {
+            return Ok(None);
+        };
+        let mut done = 0;
+        while done < len {
+            let n = CHUNK.min(len - done);
+            hasher.update(&self.read_at(cid_str, offset + done, n)?);
+            done += n;
+        }
+        Ok(Some(names_digest(&cid, &hasher.finalize())))
+    }
+
+    /// Whether a read of `cid_str` has to hash it first.
+    fn unverified(&self, cid_str: &str) -> bool {
+        self.verify_reads.load(Ordering::Relaxed)
+            && !self.verified.lock().unwrap().contains(cid_str)
+    }
+
+    /// Record the outcome of hashing `cid_str` for a read. A hash function
+    /// that can't be checked lets the read through, as when verifying at
+    /// open.
+    fn checked(&self, cid_str: &str, matches: Option<bool>) -> Result<()> {
+        match matches {
+            Some(false) => Err(TileError::CorruptBlock(cid_str.to_string()).into()),
+            Some(true) => {
+                self.verified.lock().unwrap().insert(cid_str.to_string());
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn locate(&self, cid_str: &str) -> Result<(u64, u64)> {
+        self.index()?
+            .get(cid_str)
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(TileError::MissingBlock(format!("block not found for CID {cid_str}")))
+            })
+    }
+
+    fn read_at(&self, cid_str: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let len = usize::try_from(len).map_err(|_| anyhow!("{len}-byte read exceeds address space"))?;
+        let mut buf = vec![0u8; len];
+        if let Backing::Directory(files) = &self.backing {
+            let (file, _) = files.get(cid_str).ok_or_else(|| anyhow!("no file for {cid_str}"))?;
+            read_exact_at(&File::open(file)?, &mut buf, offset)?;
+            return Ok(buf);
+        }
+        if let Some(mapping) = self.mapping()? {
+            let bytes = mapping.bytes();
+            let end = offset.checked_add(len as u64).filter(|&end| end <= bytes.len() as u64);
+            let Some(end) = end else { bail!("block {cid_str} extends beyond file") };
+            buf.copy_from_slice(&bytes[offset as usize..end as usize]);
+            return Ok(buf);
+        }
+        if read_exact_at(&*self.file()?, &mut buf, offset).is_err() {
+            // The handle may have gone stale (unmounted volume, replaced
+            // file); retry once on a fresh one before giving up.
+            *self.handle.write().unwrap() = None;
+            read_exact_at(&*self.file()?, &mut buf, offset)?;
+        }
         Ok(buf)
     }
+
+    /// The retained file handle, (re)opened if missing or if the file on
+    /// disk no longer matches the one it was opened against.
+    fn file(&self) -> Result<Arc<File>> {
+        let stamp = FileStamp::of(self.car_path())?;
+        if let Some((f, s)) = self.handle.read().unwrap().as_ref() {
+            if *s == stamp {
+                return Ok(f.clone());
+            }
+        }
+        let f = Arc::new(File::open(self.car_path())?);
+        *self.handle.write().unwrap() = Some((f.clone(), stamp));
+        Ok(f)
+    }
+
+    /// The mapping of the file, (re)made if missing or stale, when blocks
+    /// are read that way. Only a decompressed copy, which nothing else
+    /// writes, is mapped (see `mmap.rs`).
+    fn mapping(&self) -> Result<Option<Arc<Mapping>>> {
+        if !self.memory_mapped || !self.is_compressed() {
+            return Ok(None);
+        }
+        let stamp = FileStamp::of(self.car_path())?;
+        if let Some((m, s)) = self.mapping.read().unwrap().as_ref() {
+            if *s == stamp {
+                return Ok(Some(m.clone()));
+            }
+        }
+        let Some(mapping) = Mapping::of(&*self.file()?)? else { return Ok(None) };
+        let mapping = Arc::new(mapping);
+        *self.mapping.write().unwrap() = Some((mapping.clone(), stamp));
+        Ok(Some(mapping))
+    }
+
+    /// Check that every root and every resource in the manifest, and each
+    /// of its encoded and content-type variants, points at a block present
+    /// in the CAR or inline in its CID.
+    pub fn verify_resources(&self) -> Result<()> {
+        let index = self.index()?;
+        if let Some(root) = self.roots.iter().find(|r| !index.contains_key(*r) && !is_inline(r)) {
+            bail!(TileError::MissingBlock(format!("root {root} is missing from the CAR")));
+        }
+        for (path, resource) in self.masl.served() {
+            for (key, src) in resource.iter().filter(|(k, _)| is_src_key(k)) {
+                if !index.contains_key(src) && !is_inline(src) {
+                    let missing = format!("resource {path} `{key}` points at missing block {src}");
+                    bail!(TileError::MissingBlock(missing));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 // ── CAR parsing ──────────────────────────────────────────────────────────────
 
-/// Parse a `.tile` (CARv1) file. Returns `TileContent` with MASL metadata and
-/// a CID→offset index built from the file's blocks.
+/// Parse a `.tile` (CARv1 or CARv2) file. Returns `TileContent` with MASL
+/// metadata and a CID→offset index built from the file's blocks.
 pub fn parse_tile(path: &Path) -> Result<TileContent> {
-    let mut f = File::open(path)?;
-    let mut data = Vec::new();
-    f.read_to_end(&mut data)?;
+    let content = parse_tile_header(path)?;
+    content.index()?;
+    Ok(content)
+}
+
+/// Fast-open path: read only the CAR header and MASL. The block index is
+/// deferred until [`TileContent::index`] or [`TileContent::spawn_indexer`].
+/// A compressed tile is decompressed in full first.
+pub fn parse_tile_header(path: &Path) -> Result<TileContent> {
+    // A tile that can't be written to keeps its old header, which still
+    // names blocks the file holds.
+    if let Err(e) = crate::edit::replay_journal(path) {
+        tracing::warn!(path = %path.display(), "couldn't finish a header update: {e:#}");
+    }
+    let copy = decompress(path)?;
+    let header = read_masl(copy.as_ref().map_or(path, |copy| &copy.0))?;
+    let (masl, root_hash) = parse_masl(&header.masl)?;
+    tracing::debug!(
+        path = %path.display(),
+        header_bytes = header.masl.len(),
+        resources = masl.resources.len(),
+        roots = header.roots.len(),
+        "parsed tile header"
+    );
+    let mut content = TileContent::new(path, masl, root_hash, header.data_start, OnceLock::new())?;
+    content.roots = header.roots;
+    content.root = header.masl_root.map(|root| root.to_string());
+    if let Some(copy) = copy {
+        content.backing = Backing::Compressed(copy);
+    }
+    Ok(content)
+}
+
+// ── Compressed tiles ─────────────────────────────────────────────────────────
+//
+// A tile may ship as a zstd frame around the whole CAR (`.tile.zst` by
+// convention, though only the frame's magic number is checked). Blocks are
+// read by offset, which a compressed stream can't give, so on open the CAR
+// is decompressed once into a file of its own under the app cache, readable
+// by the user alone, and read from there. Copies are kept by the compressed
+// file's hash, so opening the same file again while a view of it is open
+// reuses its copy; a copy is removed when the last view using it is dropped,
+// and any left by a crash when the app next starts. Staleness is still
+// judged by the compressed file. Compressed tiles are read-only: editing
+// and the parse cache go by offsets into the file itself.
+
+/// The four bytes every zstd frame starts with.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// Largest CAR a compressed tile may decompress to.
+const MAX_DECOMPRESSED_LEN: u64 = 16 << 30;
+
+/// Where copies are decompressed to, once [`set_decompress_dir`] has named
+/// it; the temporary directory until then.
+static DECOMPRESS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Copies in use, by the SHA-256 of the compressed file.
+static COPIES: Mutex<BTreeMap<String, std::sync::Weak<Decompressed>>> =
+    Mutex::new(BTreeMap::new());
+
+/// File each process holds locked in its own directory under the one
+/// [`set_decompress_dir`] names, for as long as it runs.
+const DECOMPRESS_LOCK: &str = ".lock";
+
+/// Decompress tiles into a directory of this process's own under `dir` from
+/// now on, removing what earlier runs left there. Another instance still
+/// running holds its directory's lock, so the copies it serves stay.
+pub(crate) fn set_decompress_dir(dir: PathBuf) -> Result<()> {
+    static LOCK: OnceLock<File> = OnceLock::new();
+    std::fs::create_dir_all(&dir)?;
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        let stale = entry.path();
+        let lock = File::open(stale.join(DECOMPRESS_LOCK));
+        if lock.is_ok_and(|lock| lock.try_lock().is_err()) {
+            continue;
+        }
+        let _ = match entry.file_type() {
+            Ok(kind) if kind.is_dir() => std::fs::remove_dir_all(&stale),
+            _ => std::fs::remove_file(&stale),
+        };
+    }
+    let own = dir.join(std::process::id().to_string());
+    std::fs::create_dir_all(&own)?;
+    let lock = File::create(own.join(DECOMPRESS_LOCK))?;
+    lock.lock()?;
+    let _ = LOCK.set(lock);
+    let _ = DECOMPRESS_DIR.set(own);
+    Ok(())
+}
+
+/// A compressed tile's CAR, decompressed to a file of its own that is
+/// deleted on drop.
+#[derive(Debug)]
+pub(crate) struct Decompressed(PathBuf);
+
+impl Decompressed {
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Decompressed {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Whether `path` is named as a tile: `.tile`, or `.tile.zst` compressed.
+pub fn has_tile_extension(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(".tile") || name.ends_with(".tile.zst")
+}
 
-    let mut pos = 0usize;
+/// Whether the file at `path` starts with a zstd frame.
+pub(crate) fn is_compressed(path: &Path) -> Result<bool> {
+    let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+    File::open(path)?.take(ZSTD_MAGIC.len() as u64).read_to_end(&mut magic)?;
+    Ok(magic == ZSTD_MAGIC)
+}
+
+/// Decompress the tile at `path` to a file of its own, or `None` if it
+/// isn't compressed. A copy of the same bytes still in use is shared.
+pub(crate) fn decompress(path: &Path) -> Result<Option<Arc<Decompressed>>> {
+    use std::sync::atomic::AtomicUsize;
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    if !is_compressed(path)? {
+        return Ok(None);
+    }
+    let hash = file_sha256(path)?;
+    // Held throughout, so the same file opened twice at once is decompressed
+    // once.
+    let mut copies = COPIES.lock().unwrap();
+    if let Some(copy) = copies.get(&hash).and_then(std::sync::Weak::upgrade) {
+        return Ok(Some(copy));
+    }
+    copies.retain(|_, copy| copy.strong_count() > 0);
+    // Named apart from copies of the same file, one of which may be on its
+    // way to being removed.
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let name = format!("tile-documents-{}-{n}-{}.car", std::process::id(), &hash[..16]);
+    let dir = DECOMPRESS_DIR.get().cloned().unwrap_or_else(std::env::temp_dir);
+    let copy = Decompressed(dir.join(name));
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut out = options.open(&copy.0)?;
+    let decoder = zstd::stream::read::Decoder::new(File::open(path)?)?;
+    let written = std::io::copy(&mut decoder.take(MAX_DECOMPRESSED_LEN + 1), &mut out)
+        .map_err(|e| TileError::NotCar(format!("decompressing {}: {e}", path.display())))?;
+    if written > MAX_DECOMPRESSED_LEN {
+        let limit = format!("compressed tile is over {MAX_DECOMPRESSED_LEN} bytes decompressed");
+        bail!(TileError::TooLarge(limit));
+    }
+    tracing::debug!(path = %path.display(), bytes = written, "decompressed tile");
+    let copy = Arc::new(copy);
+    copies.insert(hash, Arc::downgrade(&copy));
+    Ok(Some(copy))
+}
+
+/// What [`read_masl`] finds at the start of a tile.
+pub(crate) struct TileHeader {
+    /// The MASL's bytes.
+    pub masl: Vec<u8>,
+    /// Offset of the first block frame.
+    pub data_start: u64,
+    /// The CAR header's `roots`, as CID strings.
+    pub roots: Vec<String>,
+    /// The root block the MASL is read from, if it isn't the header.
+    pub masl_root: Option<Cid>,
+}
+
+/// Read a tile's MASL. Tiles usually keep the MASL as the CAR header
+/// itself. The standard CAR layout instead has a header of `version` and
+/// `roots` and stores the MASL as the DAG-CBOR block the first root names;
+/// a header with `roots` but no `resources` is read that way. Another root
+/// of such a tile is opened with [`TileContent::at_root`].
+pub(crate) fn read_masl(path: &Path) -> Result<TileHeader> {
+    let (header, data_start) = read_header(&mut File::open(path)?)?;
+    let (roots, masl_root) = header_roots(&header);
+    let masl = match masl_root {
+        Some(root) => root_block(path, data_start, &root)?,
+        None => header,
+    };
+    Ok(TileHeader { masl, data_start, roots, masl_root })
+}
 
-    // ── header ────────────────────────────────────────────────────────────
-    let (header_len, n) = read_uvarint(&data[pos..])
-        .ok_or_else(|| anyhow!("failed to read CAR header varint"))?;
-    pos += n;
+/// The `roots` a CAR header lists, and the first of them if the header is
+/// in the standard CAR layout and so names the MASL by it. A header that
+/// doesn't decode has neither, and parsing it as the MASL reports why.
+fn header_roots(header: &[u8]) -> (Vec<String>, Option<Cid>) {
+    let Ok(CborValue::Map(map)) = ciborium::de::from_reader(header) else {
+        return (Vec::new(), None);
+    };
+    let field = |name: &str| map.iter().find(|(k, _)| k.as_text() == Some(name)).map(|(_, v)| v);
+    let roots: Vec<String> = match field("roots") {
+        Some(CborValue::Array(roots)) => roots.iter().filter_map(cbor_to_cid_string).collect(),
+        _ => Vec::new(),
+    };
+    let masl_root = match field("resources") {
+        Some(_) => None,
+        None => roots.first().and_then(|root| root.parse().ok()),
+    };
+    (roots, masl_root)
+}
 
-    let header_end = pos + header_len as usize;
-    if header_end > data.len() {
-        bail!("CAR header length exceeds file size");
+/// The bytes of the block `root`, found by walking the frames after
+/// `data_start`. Producers write the root first, so the walk is short.
+fn root_block(path: &Path, data_start: u64, root: &Cid) -> Result<Vec<u8>> {
+    let root_str = root.to_string();
+    if let Some(block) = inline_block(&root_str) {
+        return Ok(block);
+    }
+    let frame = BlockScanner::new(path, data_start)?
+        .find(|frame| frame.as_ref().map_or(true, |frame| frame.cid == *root))
+        .transpose()?
+        .ok_or_else(|| {
+            TileError::MissingBlock(format!("root block {root} holding the MASL is missing"))
+        })?;
+    if frame.len > MAX_HEADER_LEN {
+        let limit =
+            format!("MASL block is {} bytes, over the {MAX_HEADER_LEN}-byte limit", frame.len);
+        bail!(TileError::TooLarge(limit));
+    }
+    let mut block = vec![0u8; usize::try_from(frame.len)?];
+    read_exact_at(&File::open(path)?, &mut block, frame.offset)?;
+    if cid_matches(&root_str, &block) == Some(false) {
+        bail!(TileError::CorruptBlock(root_str));
     }
+    Ok(block)
+}
 
-    let masl = parse_masl(&data[pos..header_end])?;
-    pos = header_end;
+/// Read the CAR header from the start of `f` (for CARv2, from the start of
+/// its data payload), returning its bytes and the offset of the first block
+/// frame after it. Leaves `f` at that offset.
+pub(crate) fn read_header(f: &mut File) -> Result<(Vec<u8>, u64)> {
+    let (start, end) = match read_v2_header(f)? {
+        Some(v2) => (v2.data_offset, v2.data_end()),
+        None => (0, f.metadata()?.len()),
+    };
+    // A u64 varint is at most 10 bytes.
+    let mut prefix = Vec::with_capacity(10);
+    f.seek(SeekFrom::Start(start))?;
+    f.by_ref().take(10).read_to_end(&mut prefix)?;
+    let (header_len, n) = read_uvarint(&prefix)
+        .ok_or_else(|| TileError::NotCar("failed to read CAR header varint".into()))?;
 
-    // ── blocks ────────────────────────────────────────────────────────────
-    let mut index: HashMap<String, (u64, u64)> = HashMap::new();
+    let header_start = start + n as u64;
+    let data_start = header_start.saturating_add(header_len);
+    if data_start > end {
+        bail!(TileError::NotCar("CAR header length exceeds file size".into()));
+    }
+    if header_len > MAX_HEADER_LEN {
+        let limit =
+            format!("CAR header is {header_len} bytes, over the {MAX_HEADER_LEN}-byte limit");
+        bail!(TileError::TooLarge(limit));
+    }
 
-    while pos < data.len() {
-        let (block_len, n) = read_uvarint(&data[pos..])
-            .ok_or_else(|| anyhow!("failed to read block varint at pos {pos}"))?;
-        pos += n;
+    f.seek(SeekFrom::Start(header_start))?;
+    let mut header = vec![0u8; usize::try_from(header_len)?];
+    f.read_exact(&mut header)?;
+    Ok((header, data_start))
+}
+
+/// Whether `f` is a CARv2 file.
+pub(crate) fn is_carv2(f: &File) -> Result<bool> {
+    Ok(read_v2_header(f)?.is_some())
+}
+
+/// Offset just past the last block frame: the end of a CARv2's data
+/// payload, or of the file.
+pub(crate) fn data_end(f: &File) -> Result<u64> {
+    match read_v2_header(f)? {
+        Some(v2) => Ok(v2.data_end()),
+        None => Ok(f.metadata()?.len()),
+    }
+}
+
+/// Walk the block frames starting at `data_start`, recording where each
+/// block's data lives. A CARv2 index that covers every block, or else a
+/// current index sidecar (see `sidecar.rs`), is used instead of the walk.
+fn build_index(path: &Path, data_start: u64) -> Result<BlockIndex> {
+    match embedded_index(path) {
+        Ok(Some(index)) => {
+            tracing::debug!(path = %path.display(), blocks = index.len(), "used CARv2 index");
+            return Ok(index);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(path = %path.display(), "CARv2 index unusable: {e:#}"),
+    }
+    match sidecar::read(path, data_start) {
+        Ok(Some(index)) => {
+            tracing::debug!(path = %path.display(), blocks = index.len(), "used index sidecar");
+            return Ok(index);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(path = %path.display(), "index sidecar unusable: {e:#}"),
+    }
+    let index = walk_frames(path, data_start)?;
+    tracing::debug!(path = %path.display(), blocks = index.len(), "indexed tile blocks");
+    Ok(index)
+}
+
+/// The block index of the CAR at `path` found by walking its block frames,
+/// ignoring any index it comes with: the walk [`TileContent::index`] falls
+/// back on, for benchmarks.
+pub fn walk_index(path: &Path) -> Result<BlockIndex> {
+    let (_, data_start) = read_header(&mut File::open(path)?)?;
+    walk_frames(path, data_start)
+}
+
+fn walk_frames(path: &Path, data_start: u64) -> Result<BlockIndex> {
+    let mut index = IndexBuilder::default();
+    for frame in BlockScanner::new(path, data_start)? {
+        index.insert(frame?)?;
+    }
+    Ok(index.0)
+}
+
+/// A [`BlockIndex`] being filled, held to the limits on its size.
+#[derive(Default)]
+pub(crate) struct IndexBuilder(BlockIndex, usize);
+
+impl IndexBuilder {
+    pub(crate) fn insert(&mut self, frame: BlockFrame) -> Result<()> {
+        let cid = frame.cid.to_string();
+        self.1 += cid.len() + INDEX_ENTRY_OVERHEAD;
+        if self.0.len() == MAX_BLOCKS {
+            bail!(TileError::TooLarge(format!("tile has more than {MAX_BLOCKS} blocks")));
+        }
+        if self.1 > MAX_INDEX_BYTES {
+            let limit = format!("block index exceeds {} MiB", MAX_INDEX_BYTES >> 20);
+            bail!(TileError::TooLarge(limit));
+        }
+        self.0.insert(cid, (frame.offset, frame.len));
+        Ok(())
+    }
+
+    pub(crate) fn into_index(self) -> BlockIndex {
+        self.0
+    }
+}
+
+/// Read-ahead size for [`BlockScanner`]. Small blocks are walked out of this
+/// buffer; large ones are skipped with a seek.
+const SCAN_CHUNK: usize = 1 << 20;
+
+/// A block frame found by [`BlockScanner`]: its CID and where its data lives.
+pub(crate) struct BlockFrame {
+    pub cid: Cid,
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Incremental scanner over the block frames of a CAR file. The file is read
+/// through a fixed [`SCAN_CHUNK`] buffer and only each frame's varint and CID
+/// are decoded, so memory use stays flat however large the tile is and all
+/// offsets stay 64-bit.
+pub(crate) struct BlockScanner {
+    reader: BufReader<File>,
+    pos: u64,
+    /// Where the block frames end (see [`data_end`]).
+    end: u64,
+    done: bool,
+}
+
+impl BlockScanner {
+    pub fn new(path: &Path, data_start: u64) -> Result<Self> {
+        let file = File::open(path)?;
+        let end = data_end(&file)?;
+        let mut reader = BufReader::with_capacity(SCAN_CHUNK, file);
+        reader.seek(SeekFrom::Start(data_start))?;
+        Ok(BlockScanner { reader, pos: data_start, end, done: false })
+    }
+
+    fn next_frame(&mut self) -> Result<Option<BlockFrame>> {
+        if self.pos >= self.end {
+            return Ok(None);
+        }
+        let pos = self.pos;
+        let (block_len, n) = read_uvarint_from(&mut self.reader)?
+            .ok_or_else(|| TileError::NotCar(format!("failed to read block varint at pos {pos}")))?;
+        let frame_start = pos + n as u64;
 
         if block_len == 0 {
-            break;
+            return Ok(None);
         }
 
-        let block_end = pos + block_len as usize;
-        if block_end > data.len() {
-            bail!("block extends beyond file at pos {pos}");
+        let block_end = frame_start
+            .checked_add(block_len)
+            .filter(|&end| end <= self.end)
+            .ok_or_else(|| {
+                TileError::NotCar(format!("block extends beyond file at pos {frame_start}"))
+            })?;
+
+        // Enough for any CID with a digest of up to 64 bytes plus its varints.
+        let mut head = [0u8; MAX_CID_LEN];
+        let want = block_len.min(MAX_CID_LEN as u64) as usize;
+        self.reader.read_exact(&mut head[..want])?;
+        let (cid, cid_len) = read_cid(&head[..want])
+            .ok_or_else(|| TileError::NotCar(format!("failed to parse CID at pos {frame_start}")))?;
+
+        self.reader.seek_relative((block_len - want as u64) as i64)?;
+        self.pos = block_end;
+        Ok(Some(BlockFrame {
+            cid,
+            offset: frame_start + cid_len as u64,
+            len: block_len - cid_len as u64,
+        }))
+    }
+}
+
+impl Iterator for BlockScanner {
+    type Item = Result<BlockFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
+        let frame = self.next_frame().transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            self.done = true;
+        }
+        frame
+    }
+}
+
+// ── CARv2 ────────────────────────────────────────────────────────────────────
+//
+// A CARv2 file is an 11-byte pragma (a CARv1 header reading `{version: 2}`),
+// a fixed 40-byte header, then a whole CARv1 as its data payload, optionally
+// followed by an index of the payload's blocks. Everything else reads the
+// payload as it would a CARv1 file; the offsets in the block index stay
+// offsets into the CARv2 file.
+//
+// The index is used in place of walking the payload only when the header
+// marks it as fully indexed. Otherwise it may leave out blocks with identity
+// CIDs, which writers don't index by default, and the payload is walked.
+
+const CARV2_PRAGMA: [u8; 11] = [0x0a, 0xa1, 0x67, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0x02];
+/// The pragma and the fixed header.
+const CARV2_PREFIX_LEN: usize = 51;
+/// Multicodec codes of the index formats read.
+const INDEX_SORTED: u64 = 0x0400;
+const MULTIHASH_INDEX_SORTED: u64 = 0x0401;
+
+/// The fixed header of a CARv2 file.
+#[derive(Debug, Clone, Copy)]
+struct CarV2Header {
+    /// The `fully-indexed` characteristic: the index lists every block.
+    fully_indexed: bool,
+    data_offset: u64,
+    data_size: u64,
+    /// `0` when there is no index.
+    index_offset: u64,
+}
+
+impl CarV2Header {
+    fn data_end(&self) -> u64 {
+        self.data_offset + self.data_size
+    }
+}
 
-        let (cid, cid_len) = read_cid(&data[pos..])
-            .ok_or_else(|| anyhow!("failed to parse CID at pos {pos}"))?;
+/// The CARv2 header of `f`, or `None` if it is not a CARv2 file.
+fn read_v2_header(f: &File) -> Result<Option<CarV2Header>> {
+    let file_len = f.metadata()?.len();
+    if file_len < CARV2_PREFIX_LEN as u64 {
+        return Ok(None);
+    }
+    let mut prefix = [0u8; CARV2_PREFIX_LEN];
+    read_exact_at(f, &mut prefix, 0)?;
+    if prefix[..CARV2_PRAGMA.len()] != CARV2_PRAGMA {
+        return Ok(None);
+    }
+    let u64_at = |i: usize| u64::from_le_bytes(prefix[i..i + 8].try_into().unwrap());
+    let header = CarV2Header {
+        // The characteristics are two little-endian u64s; `fully-indexed`
+        // is the top bit of the first.
+        fully_indexed: u64_at(11) >> 63 == 1,
+        data_offset: u64_at(27),
+        data_size: u64_at(35),
+        index_offset: u64_at(43),
+    };
+    let end = header.data_offset.checked_add(header.data_size);
+    if header.data_offset < CARV2_PREFIX_LEN as u64 || end.is_none_or(|end| end > file_len) {
+        bail!(TileError::NotCar("CARv2 data payload lies outside the file".into()));
+    }
+    if header.index_offset >= file_len {
+        bail!(TileError::NotCar("CARv2 index lies outside the file".into()));
+    }
+    Ok(Some(header))
+}
 
-        let data_offset = (pos + cid_len) as u64;
-        let data_len = (block_len as usize - cid_len) as u64;
-        index.insert(cid.to_string(), (data_offset, data_len));
+/// The block index read from a fully indexed CARv2's own index, or `None`
+/// if the file has none to use.
+fn embedded_index(path: &Path) -> Result<Option<BlockIndex>> {
+    let file = File::open(path)?;
+    let Some(v2) = read_v2_header(&file)? else { return Ok(None) };
+    if v2.index_offset == 0 || !v2.fully_indexed {
+        return Ok(None);
+    }
+    let mut reader = BufReader::new(&file);
+    reader.seek(SeekFrom::Start(v2.index_offset))?;
+    let (codec, _) =
+        read_uvarint_from(&mut reader)?.ok_or_else(|| anyhow!("failed to read index codec"))?;
+    let mut offsets = Vec::new();
+    match codec {
+        INDEX_SORTED => read_sorted_index(&mut reader, &mut offsets)?,
+        MULTIHASH_INDEX_SORTED => {
+            for _ in 0..read_count(&mut reader)? {
+                // The multihash code of the bucket, which the CIDs repeat.
+                read_le::<8>(&mut reader)?;
+                read_sorted_index(&mut reader, &mut offsets)?;
+            }
+        }
+        other => {
+            tracing::debug!(path = %path.display(), "CARv2 index format {other:#x} not read");
+            return Ok(None);
+        }
+    }
+    let mut index = IndexBuilder::default();
+    for offset in offsets {
+        let pos = v2
+            .data_offset
+            .checked_add(offset)
+            .filter(|&pos| pos < v2.data_end())
+            .ok_or_else(|| anyhow!("index offset {offset} outside the data payload"))?;
+        index.insert(frame_at(&file, pos, v2.data_end())?)?;
+    }
+    Ok(Some(index.0))
+}
 
-        pos = block_end;
+/// Append the payload offsets listed by an `IndexSorted` body: buckets of
+/// fixed-width entries, each a digest followed by a little-endian u64.
+fn read_sorted_index(reader: &mut impl Read, offsets: &mut Vec<u64>) -> Result<()> {
+    for _ in 0..read_count(reader)? {
+        let width = u64::from(u32::from_le_bytes(read_le::<4>(reader)?));
+        let bytes = u64::from_le_bytes(read_le::<8>(reader)?);
+        if width < 8 || bytes % width != 0 {
+            bail!("malformed index bucket: width {width}, {bytes} bytes");
+        }
+        if offsets.len() as u64 + bytes / width > MAX_BLOCKS as u64 {
+            bail!(TileError::TooLarge(format!("tile has more than {MAX_BLOCKS} blocks")));
+        }
+        let mut entry = vec![0u8; width as usize];
+        for _ in 0..bytes / width {
+            reader.read_exact(&mut entry)?;
+            let tail = &entry[entry.len() - 8..];
+            offsets.push(u64::from_le_bytes(tail.try_into().unwrap()));
+        }
     }
+    Ok(())
+}
 
-    Ok(TileContent { path: path.to_path_buf(), masl, index })
+/// A little-endian i32 count, which must not be negative.
+fn read_count(reader: &mut impl Read) -> Result<u32> {
+    let count = i32::from_le_bytes(read_le::<4>(reader)?);
+    u32::try_from(count).map_err(|_| anyhow!("negative count {count} in index"))
+}
+
+fn read_le<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The block frame at `pos`, which must end by `end`.
+fn frame_at(f: &File, pos: u64, end: u64) -> Result<BlockFrame> {
+    let mut head = [0u8; 10 + MAX_CID_LEN];
+    let want = (end - pos).min(head.len() as u64) as usize;
+    read_exact_at(f, &mut head[..want], pos)?;
+    let (block_len, n) = read_uvarint(&head[..want.min(10)])
+        .ok_or_else(|| TileError::NotCar(format!("failed to read block varint at pos {pos}")))?;
+    let frame_start = pos + n as u64;
+    if frame_start.checked_add(block_len).is_none_or(|block_end| block_end > end) {
+        bail!(TileError::NotCar(format!("block extends beyond file at pos {frame_start}")));
+    }
+    let cid_end = n + (block_len.min(MAX_CID_LEN as u64) as usize).min(want - n);
+    let (cid, cid_len) = read_cid(&head[n..cid_end])
+        .ok_or_else(|| TileError::NotCar(format!("failed to parse CID at pos {frame_start}")))?;
+    Ok(BlockFrame {
+        cid,
+        offset: frame_start + cid_len as u64,
+        len: block_len - cid_len as u64,
+    })
 }
 
 // ── MASL extraction from CBOR header ─────────────────────────────────────────
 
-fn parse_masl(header_bytes: &[u8]) -> Result<Masl> {
+/// Decode the MASL from the CAR header, along with the header's root hash
+/// (see [`TileContent::root_hash`]).
+/// Failures are [`TileError::InvalidMasl`] unless they are more specific.
+pub(crate) fn parse_masl(header_bytes: &[u8]) -> Result<(Masl, String)> {
+    decode_masl(header_bytes).map_err(|e| match e.downcast::<TileError>() {
+        Ok(e) => e.into(),
+        Err(e) => TileError::InvalidMasl(format!("{e:#}")).into(),
+    })
+}
+
+fn decode_masl(header_bytes: &[u8]) -> Result<(Masl, String)> {
     let value: CborValue = ciborium::de::from_reader(header_bytes)
         .map_err(|e| anyhow!("CBOR decode error: {e}"))?;
 
@@ -132,48 +1639,260 @@ fn parse_masl(header_bytes: &[u8]) -> Result<Masl> {
         _ => bail!("CAR header is not a CBOR map"),
     };
 
+    let unsigned: Vec<(CborValue, CborValue)> = map
+        .iter()
+        .filter(|(k, _)| cbor_to_string(k).as_deref() != Some("signature"))
+        .cloned()
+        .collect();
+    let mut unsigned_bytes = Vec::new();
+    ciborium::ser::into_writer(&CborValue::Map(unsigned), &mut unsigned_bytes)?;
+    let root_hash = hex(&Sha256::digest(&unsigned_bytes));
+
     let mut name: Option<String> = None;
+    let mut names = Localized::new();
     let mut resources: HashMap<String, Resource> = HashMap::new();
+    let mut variants = Variants::new();
+    let mut redirects = HashMap::new();
     let mut icons: Vec<Icon> = Vec::new();
     let mut description: Option<String> = None;
+    let mut descriptions = Localized::new();
     let mut short_name: Option<String> = None;
     let mut theme_color: Option<String> = None;
     let mut background_color: Option<String> = None;
+    let mut update_url: Option<String> = None;
+    let mut permissions: Vec<String> = Vec::new();
+    let mut signature: Option<TileSignature> = None;
+    let mut author_did: Option<String> = None;
+    let mut author_proof: Option<String> = None;
+    let mut authors: Vec<Author> = Vec::new();
+    let mut license: Option<String> = None;
+    let mut published: Option<String> = None;
+    let mut modified: Option<String> = None;
+    let mut dc = Terms::new();
+    let mut error_pages = ErrorPages::new();
+    let mut extra: BTreeMap<String, RawCbor> = BTreeMap::new();
 
     for (k, v) in &map {
         let key = cbor_to_string(k).unwrap_or_default();
         match key.as_str() {
-            "name" => name = cbor_to_string(v),
-            "description" => description = cbor_to_string(v),
+            "name" => (name, names) = parse_localized(v),
+            "description" => (description, descriptions) = parse_localized(v),
             "short_name" => short_name = cbor_to_string(v),
             "theme_color" => theme_color = cbor_to_string(v),
             "background_color" => background_color = cbor_to_string(v),
-            "resources" => resources = parse_resources(v)?,
+            "update_url" => update_url = cbor_to_string(v),
+            "resources" => (resources, variants, redirects) = parse_resources(v)?,
             "icons" => icons = parse_icons(v)?,
-            _ => {} // skip `version`, `roots`, and unknown fields
+            "permissions" => permissions = parse_permissions(v)?,
+            "signature" => signature = Some(parse_signature(v)?),
+            "author_did" => author_did = cbor_to_string(v),
+            "author_proof" => match v {
+                CborValue::Bytes(b) => author_proof = Some(hex(b)),
+                _ => bail!("`author_proof` is not CBOR bytes"),
+            },
+            "author" => authors = parse_authors(v),
+            "license" => license = cbor_to_string(v),
+            "published" => published = cbor_to_string(v),
+            "modified" => modified = cbor_to_string(v),
+            "dc" => dc = parse_terms(v),
+            "error_pages" => error_pages = parse_error_pages(v),
+            "version" | "roots" | revisions::PREV | revisions::REVISED => {}
+            _ => {
+                if let Some(key) = k.as_text() {
+                    extra.insert(key.to_string(), RawCbor(v.clone()));
+                }
+            }
         }
     }
 
-    Ok(Masl {
-        name: name.ok_or_else(|| anyhow!("MASL missing `name` field"))?,
+    let masl = Masl {
+        name: name.ok_or(TileError::MissingName)?,
+        names,
         resources,
+        variants,
+        redirects,
         icons,
         description,
+        descriptions,
         short_name,
         theme_color,
         background_color,
+        update_url,
+        permissions,
+        signature,
+        author_did,
+        author_proof,
+        authors,
+        license,
+        published,
+        modified,
+        dc,
+        error_pages,
+        extra,
+    };
+    Ok((masl, root_hash))
+}
+
+/// A text field that may be given by language: its text, or for a language
+/// map the [`default_text`] and the text by tag. Entries that aren't
+/// strings are skipped.
+fn parse_localized(v: &CborValue) -> (Option<String>, Localized) {
+    let CborValue::Map(entries) = v else { return (cbor_to_string(v), Localized::new()) };
+    let by_language: Localized = entries
+        .iter()
+        .filter_map(|(tag, text)| Some((cbor_to_string(tag)?, cbor_to_string(text)?)))
+        .collect();
+    (default_text(&by_language).map(|(_, text)| text.to_string()), by_language)
+}
+
+/// The entry of a language map shown when no locale picks one: `en`'s, or
+/// else the first by tag. Returns the tag and the text.
+pub fn default_text(by_language: &Localized) -> Option<(&str, &str)> {
+    by_language
+        .get_key_value("en")
+        .or_else(|| by_language.first_key_value())
+        .map(|(tag, text)| (tag.as_str(), text.as_str()))
+}
+
+/// `author`: a name, a map of `name`, `url` and `email`, or an array of
+/// either. Authors without a name are skipped.
+fn parse_authors(v: &CborValue) -> Vec<Author> {
+    let one = |v: &CborValue| match v {
+        CborValue::Text(name) => Some(Author { name: name.clone(), url: None, email: None }),
+        CborValue::Map(fields) => {
+            let text = |key: &str| {
+                let (_, v) = fields.iter().find(|(k, _)| k.as_text() == Some(key))?;
+                cbor_to_string(v)
+            };
+            Some(Author { name: text("name")?, url: text("url"), email: text("email") })
+        }
+        _ => None,
+    };
+    match v {
+        CborValue::Array(items) => items.iter().filter_map(one).collect(),
+        v => one(v).into_iter().collect(),
+    }
+}
+
+/// `error_pages`: status keys, as text, to paths. Entries of any other shape
+/// are skipped.
+fn parse_error_pages(v: &CborValue) -> ErrorPages {
+    let entries = v.as_map().map(Vec::as_slice).unwrap_or_default();
+    entries
+        .iter()
+        .filter_map(|(status, page)| Some((status.as_text()?.parse().ok()?, cbor_to_string(page)?)))
+        .collect()
+}
+
+/// `dc`: terms whose values are a string or an array of strings. Values
+/// that aren't strings are skipped, and terms left without any.
+fn parse_terms(v: &CborValue) -> Terms {
+    let CborValue::Map(terms) = v else { return Terms::new() };
+    let mut out = Terms::new();
+    for (term, value) in terms {
+        let values: Vec<String> = match value {
+            CborValue::Array(items) => items.iter().filter_map(cbor_to_string).collect(),
+            value => cbor_to_string(value).into_iter().collect(),
+        };
+        if let (Some(term), false) = (cbor_to_string(term), values.is_empty()) {
+            out.insert(term, values);
+        }
+    }
+    out
+}
+
+fn parse_permissions(v: &CborValue) -> Result<Vec<String>> {
+    match v {
+        CborValue::Array(a) => Ok(a.iter().filter_map(cbor_to_string).collect()),
+        _ => bail!("`permissions` is not a CBOR array"),
+    }
+}
+
+fn parse_signature(v: &CborValue) -> Result<TileSignature> {
+    let map = match v {
+        CborValue::Map(m) => m,
+        _ => bail!("`signature` is not a CBOR map"),
+    };
+    let (mut alg, mut key, mut sig, mut name) = (None, None, None, None);
+    for (k, v) in map {
+        match (cbor_to_string(k).as_deref(), v) {
+            (Some("alg"), v) => alg = cbor_to_string(v),
+            (Some("key"), CborValue::Bytes(b)) => key = Some(hex(b)),
+            (Some("sig"), CborValue::Bytes(b)) => sig = Some(hex(b)),
+            (Some("name"), v) => name = cbor_to_string(v),
+            _ => {}
+        }
+    }
+    Ok(TileSignature {
+        alg: alg.ok_or_else(|| anyhow!("signature missing `alg`"))?,
+        key: key.ok_or_else(|| anyhow!("signature missing `key` bytes"))?,
+        sig: sig.ok_or_else(|| anyhow!("signature missing `sig` bytes"))?,
+        name,
     })
 }
 
-fn parse_resources(v: &CborValue) -> Result<HashMap<String, Resource>> {
+/// Resources, their variants and redirects, by path.
+type ParsedResources = (HashMap<String, Resource>, Variants, HashMap<String, Redirect>);
+
+fn parse_resources(v: &CborValue) -> Result<ParsedResources> {
     let map = match v {
         CborValue::Map(m) => m,
         _ => bail!("`resources` is not a CBOR map"),
     };
+    if map.len() > MAX_RESOURCES {
+        bail!("MASL declares {} resources, over the limit of {MAX_RESOURCES}", map.len());
+    }
     let mut out = HashMap::new();
+    let mut variants = Variants::new();
+    let mut redirects = HashMap::new();
     for (k, rv) in map {
         let path = cbor_to_string(k).ok_or_else(|| anyhow!("resource key is not a string"))?;
-        out.insert(path, parse_resource(rv)?);
+        if let Some(redirect) = parse_redirect(rv) {
+            let redirect = redirect.map_err(|e| anyhow!("resource {path}: {e}"))?;
+            redirects.insert(path, redirect);
+            continue;
+        }
+        let resource = parse_resource(rv)?;
+        let alternatives = parse_variants(rv, &resource)
+            .map_err(|e| anyhow!("resource {path}: variant {e}"))?;
+        if !alternatives.is_empty() {
+            variants.insert(path.clone(), alternatives);
+        }
+        out.insert(path, resource);
+    }
+    Ok((out, variants, redirects))
+}
+
+/// The resource entry `v` as a redirect, if it has a `location` and no
+/// `src`. A `status` that isn't an integer leaves the default.
+fn parse_redirect(v: &CborValue) -> Option<Result<Redirect>> {
+    let entry = v.as_map()?;
+    let field = |name: &str| entry.iter().find(|(k, _)| k.as_text() == Some(name)).map(|(_, v)| v);
+    let location = field("location")?;
+    if field("src").is_some() {
+        return None;
+    }
+    let Some(location) = cbor_to_string(location) else {
+        return Some(Err(anyhow!("redirect `location` is not a string")));
+    };
+    let status = field("status").and_then(|s| s.as_integer()).and_then(|s| u16::try_from(s).ok());
+    Some(Ok(Redirect { location, status: status.unwrap_or(DEFAULT_REDIRECT_STATUS) }))
+}
+
+/// The `variants` of the resource entry `v`, parsed as `resource` was, and
+/// given `resource`'s headers where they don't set their own. Variants that
+/// aren't maps are skipped.
+fn parse_variants(v: &CborValue, resource: &Resource) -> Result<Vec<Resource>> {
+    let entry = v.as_map().map(Vec::as_slice).unwrap_or_default();
+    let items = entry.iter().find(|(k, _)| k.as_text() == Some("variants"));
+    let Some((_, CborValue::Array(items))) = items else { return Ok(Vec::new()) };
+    let mut out = Vec::new();
+    for item in items.iter().filter(|item| item.is_map()) {
+        let own = parse_resource(item)?;
+        let headers = resource.iter().filter(|(k, _)| !is_src_key(k));
+        let mut variant: Resource = headers.map(|(k, v)| (k.clone(), v.clone())).collect();
+        variant.extend(own);
+        out.push(variant);
     }
     Ok(out)
 }
@@ -190,9 +1909,9 @@ fn parse_resource(v: &CborValue) -> Result<Resource> {
 
     for (k, rv) in map {
         let key = cbor_to_string(k).unwrap_or_default();
-        let value = if key == "src" {
+        let value = if is_src_key(&key) {
             cbor_to_cid_string(rv)
-                .ok_or_else(|| anyhow!("resource `src` is not a CID"))?
+                .ok_or_else(|| anyhow!("resource `{key}` is not a CID"))?
         } else if let Some(s) = cbor_to_string(rv) {
             s
         } else {
@@ -247,7 +1966,7 @@ fn cbor_to_string(v: &CborValue) -> Option<String> {
 
 /// Extract a CID from a DAG-CBOR CID link: `Tag(42, Bytes(0x00 || raw_cid))`.
 /// The leading `0x00` byte is the identity multibase prefix.
-fn cbor_to_cid_string(v: &CborValue) -> Option<String> {
+pub(crate) fn cbor_to_cid_string(v: &CborValue) -> Option<String> {
     match v {
         CborValue::Tag(42, inner) => {
             if let CborValue::Bytes(bytes) = inner.as_ref() {
@@ -263,8 +1982,68 @@ fn cbor_to_cid_string(v: &CborValue) -> Option<String> {
 
 // ── Varint / CID helpers ──────────────────────────────────────────────────────
 
+/// Whether `data` is the block `cid_str` names: hashed with the CID's own
+/// multihash, under its own version and codec, it gives the same CID. A
+/// dag-pb node is hashed as stored, links and all. `None` if the CID is
+/// malformed or uses an unsupported hash function.
+pub fn cid_matches(cid_str: &str, data: &[u8]) -> Option<bool> {
+    let cid = Cid::try_from(cid_str).ok()?;
+    Some(names_digest(&cid, &multihash_digest(cid.hash().code(), data)?))
+}
+
+/// Whether `digest`, of the multihash `cid` uses, rebuilds `cid`.
+fn names_digest(cid: &Cid, digest: &[u8]) -> bool {
+    Multihash::wrap(cid.hash().code(), digest)
+        .ok()
+        .and_then(|hash| Cid::new(cid.version(), cid.codec(), hash).ok())
+        .is_some_and(|rebuilt| rebuilt == *cid)
+}
+
+/// Whether `cid_str` has an identity multihash, so carries its block
+/// inline rather than naming one in the CAR.
+pub fn is_inline(cid_str: &str) -> bool {
+    Cid::try_from(cid_str).is_ok_and(|cid| cid.hash().code() == IDENTITY)
+}
+
+/// The block an identity CID carries, if `cid_str` is one.
+fn inline_block(cid_str: &str) -> Option<Vec<u8>> {
+    let cid = Cid::try_from(cid_str).ok()?;
+    (cid.hash().code() == IDENTITY).then(|| cid.hash().digest().to_vec())
+}
+
+/// Deepest a dag-pb file's links are followed. Real DAGs are a few levels
+/// deep; this stops a malformed one from recursing without end.
+const MAX_DAG_DEPTH: usize = 32;
+
+/// Most blocks one read of a dag-pb file visits. Depth alone doesn't bound
+/// the work: a node may link the same block over and over, at every level.
+const MAX_DAG_NODES: usize = 1 << 20;
+
+/// The blocks one read of a dag-pb file has visited so far.
+#[derive(Default)]
+struct DagWalk {
+    visited: std::cell::Cell<usize>,
+}
+
+impl DagWalk {
+    fn visit(&self, cid_str: &str) -> Result<()> {
+        let visited = self.visited.get() + 1;
+        self.visited.set(visited);
+        if visited > MAX_DAG_NODES {
+            let limit = format!("dag-pb file links more than {MAX_DAG_NODES} blocks at {cid_str}");
+            bail!(TileError::TooLarge(limit));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `cid_str` names a dag-pb block, whose content is wrapped.
+fn is_dag_pb(cid_str: &str) -> bool {
+    Cid::try_from(cid_str).is_ok_and(|cid| cid.codec() == DAG_PB)
+}
+
 /// Decode an unsigned LEB128 varint. Returns `(value, bytes_consumed)`.
-fn read_uvarint(data: &[u8]) -> Option<(u64, usize)> {
+pub(crate) fn read_uvarint(data: &[u8]) -> Option<(u64, usize)> {
     let mut value = 0u64;
     let mut shift = 0u32;
     for (i, &byte) in data.iter().enumerate() {
@@ -280,6 +2059,21 @@ fn read_uvarint(data: &[u8]) -> Option<(u64, usize)> {
     None
 }
 
+/// Decode an unsigned LEB128 varint from a reader. `Ok(None)` means EOF or an
+/// over-long varint.
+fn read_uvarint_from(reader: &mut impl Read) -> Result<Option<(u64, usize)>> {
+    let mut buf = [0u8; 10];
+    for i in 0..buf.len() {
+        if reader.read(&mut buf[i..=i])? == 0 {
+            return Ok(None);
+        }
+        if buf[i] & 0x80 == 0 {
+            return Ok(read_uvarint(&buf[..=i]));
+        }
+    }
+    Ok(None)
+}
+
 /// Parse a CID from the start of a slice. Returns `(cid, bytes_consumed)`.
 fn read_cid(data: &[u8]) -> Option<(Cid, usize)> {
     let mut cursor = std::io::Cursor::new(data);
@@ -287,19 +2081,64 @@ fn read_cid(data: &[u8]) -> Option<(Cid, usize)> {
     Some((cid, cursor.position() as usize))
 }
 
+/// Read exactly `buf.len()` bytes at `offset` without moving a shared cursor,
+/// so concurrent readers can share one handle.
+#[cfg(unix)]
+fn read_exact_at(f: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    f.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(f: &File, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match f.seek_read(buf, offset) {
+            Ok(0) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(mut f: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    f.seek(SeekFrom::Start(offset))?;
+    f.read_exact(buf)
+}
+
 // ── Authority helper ──────────────────────────────────────────────────────────
 
-/// Derive a `tile:` URI authority from the full file name.
-/// e.g. `"My Document.tile"` → `"my-document.tile"`.
+/// Derive a `tile:` URI authority from the full file name plus a digest of
+/// the file's location, e.g. `"/docs/My Document.tile"` →
+/// `"my-document.tile-3f2a91c0"`.
+///
+/// The authority is the document's web origin, and so its storage partition
+/// (localStorage, IndexedDB, caches). The location digest keeps two
+/// documents that happen to share a file name from sharing an origin, while
+/// reopening the same file keeps its storage.
 pub fn authority_from_path(path: &Path) -> String {
     let name = path
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or("tile");
-    name.to_lowercase()
+    let label: String = name
+        .to_lowercase()
         .chars()
         .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '-' })
         .collect::<String>()
         .trim_matches('-')
-        .to_string()
+        .chars()
+        // DNS labels max out at 63 bytes; leave room for the digest.
+        .take(48)
+        .collect();
+    let location = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let digest = Sha256::digest(location.to_string_lossy().as_bytes());
+    format!("{label}-{}", hex(&digest[..4]))
 }
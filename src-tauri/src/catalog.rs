@@ -0,0 +1,127 @@
+use crate::car::parse_tile;
+use crate::library::library_dir;
+use crate::settings::SettingsStore;
+use crate::{load_tile, net, OpenOptions, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+// ── Catalog format ───────────────────────────────────────────────────────────
+//
+// A catalog is a JSON document served over HTTP(S):
+//
+//   { "name": "Team docs",
+//     "tiles": [ { "name": "Handbook", "url": "handbook.tile",
+//                  "icon": "handbook.png", "cid": "bafk…" } ] }
+//
+// `url` and `icon` may be relative to the catalog URL. `cid` is the raw
+// (0x55) sha2-256 CID of the whole `.tile` file and is checked on install.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Catalog {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub tiles: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<String>,
+}
+
+/// Fetch a catalog and resolve its relative URLs against `url`.
+pub fn fetch(url: &str) -> Result<Catalog> {
+    let base = Url::parse(url)?;
+    let mut catalog: Catalog = net::get_json(url)?;
+    for entry in &mut catalog.tiles {
+        entry.url = base.join(&entry.url)?.to_string();
+        if let Some(icon) = &entry.icon {
+            entry.icon = Some(base.join(icon)?.to_string());
+        }
+    }
+    Ok(catalog)
+}
+
+/// Download a catalog entry into the library, checking its CID (if given)
+/// and that it parses as a complete tile before it replaces anything.
+pub fn install(app: &AppHandle, entry: &CatalogEntry) -> Result<std::path::PathBuf> {
+    let dir = library_dir(app)?;
+    let file_name = install_file_name(entry);
+    let dest = dir.join(&file_name);
+    let tmp = dir.join(format!(".{file_name}.download"));
+
+    let response = net::get(&entry.url)
+        .call()
+        .map_err(|e| anyhow!("fetching {}: {e}", entry.url))?;
+    let digest = net::download_to(response, &tmp)?;
+
+    let result = (|| {
+        if let Some(expected) = &entry.cid {
+            let expected = Cid::try_from(expected.as_str())?;
+            // sha2-256 is the only multihash we compute for whole files.
+            if expected.hash().code() != 0x12 || expected.hash().digest() != digest {
+                bail!("downloaded file does not match catalog CID {expected}");
+            }
+        }
+        parse_tile(&tmp)?.verify_resources()?;
+        std::fs::rename(&tmp, &dest)?;
+        Ok(dest.clone())
+    })();
+    let _ = std::fs::remove_file(&tmp);
+    result
+}
+
+/// Prefer the file name from the download URL; fall back to the entry name.
+fn install_file_name(entry: &CatalogEntry) -> String {
+    let from_url = Url::parse(&entry.url).ok().and_then(|u| {
+        u.path_segments()
+            .and_then(|mut s| s.next_back().map(str::to_string))
+            .filter(|s| s.ends_with(".tile"))
+    });
+    from_url.unwrap_or_else(|| {
+        let stem: String = entry
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == ' ' { c } else { '_' })
+            .collect();
+        format!("{}.tile", stem.trim())
+    })
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Fetch the catalog at `url`, or the one configured in settings.
+#[tauri::command]
+pub async fn fetch_catalog(url: Option<String>, app: AppHandle) -> Result<Catalog, String> {
+    let url = url
+        .or_else(|| app.state::<SettingsStore>().get().catalog_url)
+        .ok_or("no catalog configured")?;
+    tauri::async_runtime::spawn_blocking(move || fetch(&url))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Install a catalog entry into the library and open it.
+#[tauri::command]
+pub async fn install_catalog_tile(
+    entry: CatalogEntry,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = install(&app, &entry)?;
+        load_tile(&path, OpenOptions::default(), &app.state::<TileStore>(), &app)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,312 @@
+use crate::builder::MaslFields;
+use crate::car::{parse_tile, parse_tile_header};
+use crate::edit::{self, MaslEdit};
+use crate::merge::{self, ConflictPolicy};
+use crate::pack::PackOptions;
+use crate::signing::{self, Signer};
+use crate::verify::{self, VerifyReport};
+use crate::{diff, directory, markdown, pack, revisions};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+// ── Command line ─────────────────────────────────────────────────────────────
+//
+// `tile`, the authoring features for scripts and builds. Each subcommand
+// calls the function the matching Tauri command calls, on a file by path
+// instead of an open tile, so the app and the command line can't drift
+// apart. Results are printed as JSON on stdout. Errors go to stderr, and a
+// failed `verify` or any error exits with status 1. The app's settings
+// aren't read here: tiles are signed with the keychain key only when
+// `--sign` asks, and `--signer-name` names the publisher.
+
+const USAGE: &str = "\
+usage: tile <command> [arguments] [options]
+
+  pack <dir> <out.tile>             [--name N] [--metadata FILE] [--options FILE] [--sign]
+  import-markdown <dir> <out.tile>  [--name N] [--metadata FILE] [--options FILE] [--sign]
+  unpack <in.tile> <dir>
+  put <tile> <resource> <file>      [--type T] [edit options]
+  remove <tile> <resource>          [edit options]
+  meta <tile>                       [--name N] [--description D] [--short-name S]
+                                    [--theme-color C] [--background-color C] [edit options]
+  compact <tile>                    [--sign]
+  merge <a> <b> <out.tile>          [--on-conflict fail|first|second] [--sign]
+  sign <tile>
+  verify <tile>
+  history <tile>
+  diff <old.tile> <new.tile>
+
+edit options: --revision | --no-revision, --sign
+--metadata and --options take JSON files in the shape the app's pack_folder
+command takes. An empty value, as in --description \"\", removes a field.
+--signer-name N sets the publisher name claimed by --sign and sign.";
+
+/// Options that take a value.
+const VALUED: [&str; 10] = [
+    "--name",
+    "--metadata",
+    "--options",
+    "--type",
+    "--description",
+    "--short-name",
+    "--theme-color",
+    "--background-color",
+    "--signer-name",
+    "--on-conflict",
+];
+/// Options that don't.
+const SWITCHES: [&str; 3] = ["--sign", "--revision", "--no-revision"];
+
+/// Run the command line on this process's arguments, returning the exit
+/// status.
+pub fn main() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok((output, ok)) => {
+            println!("{output}");
+            if ok {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("tile: {e}");
+            1
+        }
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    values: HashMap<&'static str, String>,
+    switches: HashSet<&'static str>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut parsed =
+            Args { positional: Vec::new(), values: HashMap::new(), switches: HashSet::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if let Some(&name) = VALUED.iter().find(|v| **v == arg) {
+                let value = args.next().ok_or_else(|| anyhow!("{name} needs a value"))?;
+                parsed.values.insert(name, value.clone());
+            } else if let Some(&name) = SWITCHES.iter().find(|s| **s == arg) {
+                parsed.switches.insert(name);
+            } else if arg.starts_with("--") {
+                bail!("unknown option {arg}\n\n{USAGE}");
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The positional arguments, which must number exactly `N`.
+    fn positional<const N: usize>(&self) -> Result<[&Path; N]> {
+        let paths: Vec<&Path> = self.positional.iter().map(Path::new).collect();
+        let count = paths.len();
+        paths.try_into().map_err(|_| anyhow!("expected {N} arguments, got {count}\n\n{USAGE}"))
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned()
+    }
+
+    /// The value of an option setting an optional field: `Some(None)` to
+    /// remove the field.
+    fn field(&self, name: &str) -> Option<Option<String>> {
+        self.values.get(name).map(|v| (!v.is_empty()).then(|| v.clone()))
+    }
+
+    fn json<T: serde::de::DeserializeOwned>(&self, name: &str) -> Result<Option<T>> {
+        let Some(file) = self.values.get(name) else { return Ok(None) };
+        let bytes = std::fs::read(file).with_context(|| format!("reading {file}"))?;
+        Ok(Some(serde_json::from_slice(&bytes).with_context(|| format!("{name} {file}"))?))
+    }
+
+    fn signer(&self) -> Result<Signer> {
+        signing::keychain_signer(self.value("--signer-name"))?
+            .ok_or_else(|| anyhow!("no signing key is set up"))
+    }
+
+    /// The keychain signer if `--sign` asks for one.
+    fn export_signer(&self) -> Result<Option<Signer>> {
+        self.switches.contains("--sign").then(|| self.signer()).transpose()
+    }
+
+    fn revision(&self) -> Result<Option<bool>> {
+        match (self.switches.contains("--revision"), self.switches.contains("--no-revision")) {
+            (true, true) => bail!("--revision and --no-revision contradict each other"),
+            (true, false) => Ok(Some(true)),
+            (false, true) => Ok(Some(false)),
+            (false, false) => Ok(None),
+        }
+    }
+
+    /// Metadata for a new tile: `--metadata`, then `--name`.
+    fn metadata(&self) -> Result<Option<MaslFields>> {
+        let mut metadata: Option<MaslFields> = self.json("--metadata")?;
+        if let Some(name) = self.value("--name") {
+            metadata.get_or_insert_with(Default::default).name = name;
+        }
+        Ok(metadata)
+    }
+}
+
+#[derive(Serialize)]
+struct Edited {
+    /// An embedded signature had to be dropped, with none replacing it.
+    signature_dropped: bool,
+}
+
+#[derive(Serialize)]
+struct Verified {
+    ok: bool,
+    blocks: VerifyReport,
+    /// Why the resources don't all have their blocks, if they don't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<SignatureCheck>,
+}
+
+#[derive(Serialize)]
+struct SignatureCheck {
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claimed_name: Option<String>,
+    detached: bool,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Pretty JSON for a command that succeeded.
+fn json(value: &impl Serialize) -> Result<(String, bool)> {
+    Ok((serde_json::to_string_pretty(value)?, true))
+}
+
+/// Run `args`, returning what to print and whether the command succeeded.
+fn run(args: &[String]) -> Result<(String, bool)> {
+    let Some((command, rest)) = args.split_first() else { bail!("{USAGE}") };
+    if matches!(command.as_str(), "help" | "--help" | "-h") {
+        return Ok((USAGE.to_string(), true));
+    }
+    let args = Args::parse(rest)?;
+    match command.as_str() {
+        "pack" => {
+            let [dir, dest] = args.positional()?;
+            let options: PackOptions = args.json("--options")?.unwrap_or_default();
+            let signer = args.export_signer()?;
+            json(&pack::pack(dir, dest, args.metadata()?, options, signer)?)
+        }
+        "import-markdown" => {
+            let [dir, dest] = args.positional()?;
+            let options: PackOptions = args.json("--options")?.unwrap_or_default();
+            let signer = args.export_signer()?;
+            json(&markdown::import(dir, dest, args.metadata()?, options, signer)?)
+        }
+        "unpack" => {
+            let [tile, dest] = args.positional()?;
+            json(&directory::unpack(tile, dest)?)
+        }
+        "put" => {
+            let [tile, resource, file] = args.positional()?;
+            let resource = resource.to_string_lossy();
+            let bytes =
+                std::fs::read(file).with_context(|| format!("reading {}", file.display()))?;
+            let (revision, signer) = (args.revision()?, args.export_signer()?);
+            let content_type = args.value("--type");
+            let signature_dropped = edit::put_resource(
+                tile,
+                &resource,
+                bytes,
+                content_type,
+                revision,
+                signer.as_ref(),
+            )?
+            .dropped_signature;
+            json(&Edited { signature_dropped })
+        }
+        "remove" => {
+            let [tile, resource] = args.positional()?;
+            let resource = resource.to_string_lossy();
+            let (revision, signer) = (args.revision()?, args.export_signer()?);
+            let saved = edit::remove_resource(tile, &resource, revision, signer.as_ref())?;
+            json(&Edited { signature_dropped: saved.dropped_signature })
+        }
+        "meta" => {
+            let [tile] = args.positional()?;
+            let masl = parse_tile_header(tile)?.masl;
+            let edit = MaslEdit {
+                name: args.value("--name").unwrap_or(masl.name),
+                description: args.field("--description").unwrap_or(masl.description),
+                short_name: args.field("--short-name").unwrap_or(masl.short_name),
+                theme_color: args.field("--theme-color").unwrap_or(masl.theme_color),
+                background_color: args
+                    .field("--background-color")
+                    .unwrap_or(masl.background_color),
+                icons: masl.icons,
+            };
+            let (revision, signer) = (args.revision()?, args.export_signer()?);
+            let signature_dropped =
+                edit::edit_metadata(tile, &edit, revision, signer.as_ref())?.dropped_signature;
+            json(&Edited { signature_dropped })
+        }
+        "compact" => {
+            let [tile] = args.positional()?;
+            let saved = edit::compact(tile, args.export_signer()?.as_ref(), |_| {})?;
+            json(&serde_json::json!({ "bytes_saved": saved }))
+        }
+        "merge" => {
+            let [first, second, dest] = args.positional()?;
+            let policy = match args.value("--on-conflict").as_deref() {
+                None | Some("fail") => ConflictPolicy::Fail,
+                Some("first") => ConflictPolicy::First,
+                Some("second") => ConflictPolicy::Second,
+                Some(other) => bail!("--on-conflict takes fail, first or second, not {other}"),
+            };
+            json(&merge::merge(first, second, dest, policy, args.export_signer()?)?)
+        }
+        "sign" => {
+            let [tile] = args.positional()?;
+            let signer = args.signer()?;
+            edit::sign(tile, &signer)?;
+            json(&serde_json::json!({ "key": signer.public_key() }))
+        }
+        "verify" => {
+            let [tile] = args.positional()?;
+            let tile = parse_tile(tile)?;
+            let blocks = verify::verify_blocks(&tile, |_| {})?;
+            let missing = tile.verify_resources().err().map(|e| e.to_string());
+            let signature = signing::check_tile(&tile).map(|(signature, detached, error)| {
+                SignatureCheck {
+                    key: signature.key,
+                    claimed_name: signature.name,
+                    detached,
+                    valid: error.is_none(),
+                    error,
+                }
+            });
+            let ok = blocks.corrupt.is_empty()
+                && blocks.unreadable.is_empty()
+                && missing.is_none()
+                && signature.as_ref().is_none_or(|s| s.valid);
+            let verified = Verified { ok, blocks, missing, signature };
+            Ok((serde_json::to_string_pretty(&verified)?, ok))
+        }
+        "history" => {
+            let [tile] = args.positional()?;
+            json(&revisions::history(&parse_tile(tile)?)?)
+        }
+        "diff" => {
+            let [old, new] = args.positional()?;
+            json(&diff::diff(old, new)?)
+        }
+        other => bail!("unknown command {other}\n\n{USAGE}"),
+    }
+}
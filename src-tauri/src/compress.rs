@@ -0,0 +1,158 @@
+use crate::builder::TileBuilder;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+// ── Precompression ───────────────────────────────────────────────────────────
+//
+// Optional pass over a tile's resources before it is written, storing
+// Brotli and gzip copies of bodies that compress well as `src-br` and
+// `src-gzip` variants. The protocol handler serves one of them to a client
+// whose `Accept-Encoding` allows it, and the plain body to anyone else, so
+// nothing is lost for readers that can't decode them. Whether a resource is
+// compressed is decided by its content type and size. Text, JSON, XML, SVG,
+// JavaScript, WebAssembly and uncompressed fonts qualify. Images, audio,
+// video, archives and WOFF fonts are already compressed and are left alone.
+// A variant that doesn't save at least a tenth of the body is dropped, as
+// not worth the bytes it adds to the file.
+
+/// Smallest fraction of the body a variant has to save to be kept.
+const MIN_SAVING: f64 = 0.1;
+
+/// A content coding a variant can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Coding {
+    Br,
+    Gzip,
+}
+
+impl Coding {
+    fn name(self) -> &'static str {
+        match self {
+            Coding::Br => "br",
+            Coding::Gzip => "gzip",
+        }
+    }
+
+    fn compress(self, body: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self {
+            Coding::Br => {
+                let params = brotli::enc::BrotliEncoderParams { quality: 11, ..Default::default() };
+                brotli::BrotliCompress(&mut &body[..], &mut out, &params)?;
+            }
+            Coding::Gzip => {
+                let level = flate2::Compression::best();
+                let mut encoder = flate2::write::GzEncoder::new(&mut out, level);
+                encoder.write_all(body)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CompressOptions {
+    /// Variants to store, for each resource that qualifies.
+    pub codings: Vec<Coding>,
+    /// Bodies smaller than this many bytes are left alone.
+    pub min_size: u64,
+    /// Content types compressed on top of the built-in list.
+    pub extra_types: Vec<String>,
+    /// Resource paths never compressed.
+    pub exclude: Vec<String>,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressOptions {
+            codings: vec![Coding::Br, Coding::Gzip],
+            min_size: 1024,
+            extra_types: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompressedResource {
+    pub path: String,
+    pub size: u64,
+    /// Variants kept.
+    pub variants: Vec<CompressedVariant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompressedVariant {
+    pub coding: Coding,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompressReport {
+    pub resources: Vec<CompressedResource>,
+    /// Bytes the kept variants add to the file.
+    pub bytes_added: u64,
+}
+
+/// Store compressed variants of the resources in `builder` that `options`
+/// picks out.
+pub fn compress(builder: &mut TileBuilder, options: &CompressOptions) -> Result<CompressReport> {
+    let mut report = CompressReport::default();
+    let paths: Vec<String> = builder.paths().map(str::to_string).collect();
+    for path in paths {
+        let content_type = builder.resource_header(&path, "content-type").unwrap_or_default();
+        let wanted = compressible(content_type)
+            || options.extra_types.iter().any(|t| essence(t) == essence(content_type));
+        let body = builder.body(&path).unwrap_or_default();
+        if !wanted || (body.len() as u64) < options.min_size || options.exclude.contains(&path) {
+            continue;
+        }
+        let mut kept = Vec::new();
+        for &coding in &options.codings {
+            let compressed = coding.compress(body)?;
+            if (compressed.len() as f64) <= body.len() as f64 * (1.0 - MIN_SAVING) {
+                kept.push((coding, compressed));
+            }
+        }
+        if kept.is_empty() {
+            continue;
+        }
+        let size = body.len() as u64;
+        let mut resource = CompressedResource { path: path.clone(), size, variants: Vec::new() };
+        for (coding, compressed) in kept {
+            report.bytes_added += compressed.len() as u64;
+            resource.variants.push(CompressedVariant { coding, size: compressed.len() as u64 });
+            builder.add_variant(&path, coding.name(), compressed)?;
+        }
+        report.resources.push(resource);
+    }
+    Ok(report)
+}
+
+fn essence(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+/// Whether bodies of `content_type` are worth compressing.
+fn compressible(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence,
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/wasm"
+                | "font/ttf"
+                | "font/otf"
+                | "application/vnd.ms-fontobject"
+                | "image/bmp"
+                | "image/x-icon"
+        )
+}
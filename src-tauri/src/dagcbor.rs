@@ -0,0 +1,151 @@
+use crate::hashing::{hex, unhex};
+use anyhow::{bail, Result};
+use ciborium::value::Value as CborValue;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+
+// ── Canonical DAG-CBOR ───────────────────────────────────────────────────────
+//
+// A MASL is a DAG-CBOR block, and DAG-CBOR fixes how every value is encoded:
+// map keys are text, in order of length and then bytewise; integers and
+// lengths take their shortest form; floats are always 64-bit and finite;
+// the only tag is 42, for CID links. [`encode`] writes values that way, so a
+// header the app writes comes out as any other DAG-CBOR encoder would write
+// it, and one read and written again unchanged keeps its bytes and its CID.
+// A value DAG-CBOR can't hold, such as another tag, fails to encode rather
+// than being written some other way.
+//
+// MASL fields the app doesn't read are kept as [`RawCbor`] in `Masl::extra`
+// and written back by `TileBuilder`, so a merge, a repack or an unpacked
+// folder doesn't drop a manifest's extensions.
+
+/// A CBOR value kept as is. It goes to JSON as the hex of its CBOR encoding,
+/// which the parse cache and `masl.json` then hold exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCbor(pub CborValue);
+
+impl Serialize for RawCbor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(&self.0, &mut bytes).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&hex(&bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawCbor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = unhex(&s).ok_or_else(|| de::Error::custom("not hex CBOR"))?;
+        ciborium::de::from_reader(bytes.as_slice()).map(RawCbor).map_err(de::Error::custom)
+    }
+}
+
+/// DAG-CBOR's order of map keys: shorter first, then bytewise.
+fn key_order(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.as_bytes().cmp(b.as_bytes()))
+}
+
+/// Put the keys of `map`, and of every map inside it, in DAG-CBOR order.
+/// Keys that aren't text go last, for [`encode`] to refuse.
+pub fn canonicalize(map: &mut [(CborValue, CborValue)]) {
+    map.sort_by(|(a, _), (b, _)| match (a.as_text(), b.as_text()) {
+        (Some(a), Some(b)) => key_order(a, b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    for (_, value) in map.iter_mut() {
+        sort_nested(value);
+    }
+}
+
+fn sort_nested(value: &mut CborValue) {
+    match value {
+        CborValue::Map(map) => canonicalize(map),
+        CborValue::Array(items) => items.iter_mut().for_each(sort_nested),
+        _ => {}
+    }
+}
+
+/// `value` in canonical DAG-CBOR.
+pub fn encode(value: &CborValue) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    write(&mut out, value)?;
+    Ok(out)
+}
+
+fn write(out: &mut Vec<u8>, value: &CborValue) -> Result<()> {
+    match value {
+        CborValue::Integer(i) => {
+            let i = i128::from(*i);
+            if i >= 0 {
+                head(out, 0, u64::try_from(i)?);
+            } else {
+                head(out, 1, u64::try_from(-1 - i)?);
+            }
+        }
+        CborValue::Bytes(bytes) => {
+            head(out, 2, bytes.len() as u64);
+            out.extend(bytes);
+        }
+        CborValue::Text(s) => {
+            head(out, 3, s.len() as u64);
+            out.extend(s.as_bytes());
+        }
+        CborValue::Array(items) => {
+            head(out, 4, items.len() as u64);
+            for item in items {
+                write(out, item)?;
+            }
+        }
+        CborValue::Map(entries) => {
+            let mut keyed = Vec::with_capacity(entries.len());
+            for (k, v) in entries {
+                let Some(k) = k.as_text() else { bail!("DAG-CBOR map keys are text") };
+                keyed.push((k, v));
+            }
+            keyed.sort_by(|(a, _), (b, _)| key_order(a, b));
+            if let Some(dup) = keyed.windows(2).find(|w| w[0].0 == w[1].0) {
+                bail!("map key {} appears twice", dup[0].0);
+            }
+            head(out, 5, keyed.len() as u64);
+            for (k, v) in keyed {
+                head(out, 3, k.len() as u64);
+                out.extend(k.as_bytes());
+                write(out, v)?;
+            }
+        }
+        CborValue::Tag(42, link) if matches!(**link, CborValue::Bytes(_)) => {
+            head(out, 6, 42);
+            write(out, link)?;
+        }
+        CborValue::Tag(tag, _) => bail!("DAG-CBOR has no tag {tag} (only 42, over bytes)"),
+        CborValue::Float(f) if f.is_finite() => {
+            out.push(0xfb);
+            out.extend(f.to_be_bytes());
+        }
+        CborValue::Float(_) => bail!("DAG-CBOR has no NaN or infinite floats"),
+        CborValue::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        CborValue::Null => out.push(0xf6),
+        _ => bail!("value has no DAG-CBOR encoding"),
+    }
+    Ok(())
+}
+
+/// The head of an item of major type `major` with argument `n`, in its
+/// shortest form.
+fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if let Ok(n) = u8::try_from(n) {
+        out.extend([major | 24, n]);
+    } else if let Ok(n) = u16::try_from(n) {
+        out.push(major | 25);
+        out.extend(n.to_be_bytes());
+    } else if let Ok(n) = u32::try_from(n) {
+        out.push(major | 26);
+        out.extend(n.to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend(n.to_be_bytes());
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{anyhow, bail, Result};
+use cid::Cid;
+
+// ── dag-pb blocks ────────────────────────────────────────────────────────────
+//
+// Some IPFS pipelines want file blocks in dag-pb rather than raw, as a UnixFS
+// `File` node carrying the bytes inline. Only single-block files are written
+// here. Reading also takes a file chunked across blocks: a node whose links
+// lead to the rest of the file, raw leaves or further nodes, in order after
+// its own bytes. `car.rs` walks that DAG; this only parses one node.
+
+/// Multicodec code of dag-pb.
+pub const DAG_PB: u64 = 0x70;
+
+/// UnixFS `Data.Type` values that hold file bytes.
+const UNIXFS_RAW: u64 = 0;
+const UNIXFS_FILE: u64 = 2;
+
+/// Encode `data` as a single-block UnixFS file node.
+pub fn encode_file(data: &[u8]) -> Vec<u8> {
+    let mut unixfs = Vec::with_capacity(data.len() + 16);
+    field_varint(&mut unixfs, 1, UNIXFS_FILE);
+    if !data.is_empty() {
+        field_bytes(&mut unixfs, 2, data);
+    }
+    field_varint(&mut unixfs, 3, data.len() as u64);
+    let mut node = Vec::with_capacity(unixfs.len() + 8);
+    field_bytes(&mut node, 1, &unixfs);
+    node
+}
+
+/// A UnixFS file node: the file's bytes are `data` followed by the content
+/// under each of `links`.
+#[derive(Debug)]
+pub struct FileNode<'a> {
+    pub data: &'a [u8],
+    pub links: Vec<Cid>,
+    /// Length of the content under each link, if the node records them.
+    pub blocksizes: Vec<u64>,
+    /// Length of the whole file, if the node records it.
+    pub filesize: Option<u64>,
+}
+
+impl FileNode<'_> {
+    /// Length of the whole file, as far as this node alone tells.
+    pub fn size(&self) -> Option<u64> {
+        if self.links.is_empty() {
+            return Some(self.data.len() as u64);
+        }
+        self.filesize.or_else(|| {
+            let sized = self.blocksizes.len() == self.links.len();
+            sized.then(|| self.data.len() as u64 + self.blocksizes.iter().sum::<u64>())
+        })
+    }
+
+    /// Length of the content under each link, if the node records them all.
+    pub fn child_lens(&self) -> Option<&[u64]> {
+        (self.blocksizes.len() == self.links.len()).then_some(&self.blocksizes[..])
+    }
+}
+
+/// Parse a UnixFS file node.
+pub fn file_node(block: &[u8]) -> Result<FileNode<'_>> {
+    let mut unixfs: &[u8] = &[];
+    let mut links = Vec::new();
+    for field in Fields(block) {
+        match field? {
+            (1, Value::Bytes(b)) => unixfs = b,
+            (2, Value::Bytes(link)) => links.push(link_cid(link)?),
+            _ => {}
+        }
+    }
+    let mut node = FileNode { data: &[], links, blocksizes: Vec::new(), filesize: None };
+    let mut kind = None;
+    for field in Fields(unixfs) {
+        match field? {
+            (1, Value::Varint(t)) => kind = Some(t),
+            (2, Value::Bytes(b)) => node.data = b,
+            (3, Value::Varint(n)) => node.filesize = Some(n),
+            (4, Value::Varint(n)) => node.blocksizes.push(n),
+            // Packed, as some encoders write repeated varints.
+            (4, Value::Bytes(packed)) => {
+                let mut sizes = Fields(packed);
+                while !sizes.0.is_empty() {
+                    node.blocksizes.push(sizes.varint()?);
+                }
+            }
+            _ => {}
+        }
+    }
+    match kind {
+        Some(UNIXFS_FILE | UNIXFS_RAW) => Ok(node),
+        Some(t) => bail!("dag-pb node is UnixFS type {t}, not a file"),
+        None => bail!("dag-pb node has no UnixFS data"),
+    }
+}
+
+/// The CID a `PBLink` points at.
+fn link_cid(link: &[u8]) -> Result<Cid> {
+    for field in Fields(link) {
+        if let (1, Value::Bytes(hash)) = field? {
+            return Ok(Cid::try_from(hash)?);
+        }
+    }
+    bail!("dag-pb link has no hash")
+}
+
+fn field_varint(out: &mut Vec<u8>, field: u64, value: u64) {
+    varint(out, field << 3);
+    varint(out, value);
+}
+
+fn field_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    varint(out, (field << 3) | 2);
+    varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Protobuf fields of a message, as (field number, value).
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        Some(self.field())
+    }
+}
+
+impl<'a> Fields<'a> {
+    fn field(&mut self) -> Result<(u64, Value<'a>)> {
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.skip(8)?,
+            2 => {
+                let len = usize::try_from(self.varint()?)?;
+                let bytes = self.0.get(..len).ok_or_else(|| anyhow!("truncated dag-pb field"))?;
+                self.0 = &self.0[len..];
+                Value::Bytes(bytes)
+            }
+            5 => self.skip(4)?,
+            wire => bail!("unsupported protobuf wire type {wire} in dag-pb"),
+        };
+        Ok((key >> 3, value))
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for (i, &byte) in self.0.iter().enumerate().take(10) {
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.0 = &self.0[i + 1..];
+                return Ok(value);
+            }
+        }
+        bail!("bad varint in dag-pb")
+    }
+
+    fn skip(&mut self, n: usize) -> Result<Value<'a>> {
+        if self.0.len() < n {
+            bail!("truncated dag-pb field");
+        }
+        self.0 = &self.0[n..];
+        Ok(Value::Fixed)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::builder::{MaslFields, TileBuilder};
+use crate::cache::BlockCache;
+use crate::car::{authority_from_path, parse_tile};
+use crate::pack::{self, FileCache, PackSummary};
+use crate::{reload_tile, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// ── Dev mode ─────────────────────────────────────────────────────────────────
+//
+// Authoring loop for a tile's source folder. `dev_mode` packs the folder
+// into a scratch tile under the app cache directory and opens it. It then
+// watches the folder and repacks after every burst of changes, reading only
+// the files whose size or mtime moved (see `pack::FileCache`). Each build is
+// written to a new file, so requests still being served from the previous
+// build never see it change underneath them. The new build is swapped in
+// with `reload_tile`, which emits `tile:reloaded`. A failed build emits
+// `tile:dev-build-failed` and leaves the last good one showing.
+//
+// Dev tiles are scratch output: they aren't recorded in the trust store or
+// the parse cache, and aren't verified on reload.
+
+/// Quiet period after the last change before rebuilding.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Managed: authority → running dev session.
+#[derive(Default)]
+pub struct DevSessions(Mutex<HashMap<String, DevSession>>);
+
+struct DevSession {
+    /// Dropping it stops the watch.
+    debouncer: Debouncer<RecommendedWatcher>,
+    build: Arc<Mutex<DevBuild>>,
+}
+
+struct DevBuild {
+    src_dir: PathBuf,
+    out_dir: PathBuf,
+    authority: String,
+    metadata: MaslFields,
+    cache: FileCache,
+    generation: u64,
+    /// The tile file currently being served.
+    current: Option<PathBuf>,
+}
+
+/// Sent as `tile:dev-build-failed`.
+#[derive(Debug, Clone, Serialize)]
+struct DevBuildFailed {
+    authority: String,
+    error: String,
+}
+
+impl DevBuild {
+    /// Pack the folder into the next generation's file.
+    fn build(&mut self) -> Result<(PathBuf, PackSummary)> {
+        let mut builder = TileBuilder::new();
+        builder.set_metadata(self.metadata.clone());
+        let summary = pack::add_folder_cached(&mut builder, &self.src_dir, &mut self.cache)?;
+        self.generation += 1;
+        let out = self.out_dir.join(format!("{}-{}.tile", self.authority, self.generation));
+        builder.finish(&out)?;
+        Ok((out, summary))
+    }
+
+    /// Make `out` the served build and delete the one it replaced.
+    fn swap_in(&mut self, app: &AppHandle, out: PathBuf) {
+        if let Some(old) = self.current.replace(out) {
+            app.state::<BlockCache>().evict_path(&old);
+            let _ = std::fs::remove_file(old);
+        }
+    }
+}
+
+/// Rebuild after a change and reload the tab.
+fn rebuild(app: &AppHandle, build: &Mutex<DevBuild>) {
+    let mut build = build.lock().unwrap();
+    let result = build.build().and_then(|(out, _)| {
+        let store = app.state::<TileStore>();
+        match reload_tile(&build.authority, &out, Some(false), true, &store, app) {
+            Ok(_) => Ok(out),
+            Err(e) => {
+                let _ = std::fs::remove_file(&out);
+                Err(e)
+            }
+        }
+    });
+    match result {
+        Ok(out) => build.swap_in(app, out),
+        Err(e) => {
+            let authority = build.authority.clone();
+            let failed = DevBuildFailed { authority, error: e.to_string() };
+            if let Err(e) = app.emit("tile:dev-build-failed", failed) {
+                tracing::warn!("emitting tile:dev-build-failed failed: {e}");
+            }
+        }
+    }
+}
+
+/// Whether `path` is under a dot-directory or is a dotfile, which packing
+/// skips, so a change there needs no rebuild.
+fn ignored(src_dir: &Path, path: &Path) -> bool {
+    path.strip_prefix(src_dir)
+        .map(|rel| rel.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')))
+        .unwrap_or(true)
+}
+
+fn start(
+    app: &AppHandle,
+    src_dir: &Path,
+    authority: Option<String>,
+    metadata: Option<MaslFields>,
+) -> Result<TileOpenedPayload> {
+    let src_dir = std::fs::canonicalize(src_dir)?;
+    let authority = authority.unwrap_or_else(|| authority_from_path(&src_dir));
+    // It names the build files as well as the origin.
+    if authority.is_empty()
+        || !authority.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    {
+        bail!("authority {authority:?} may only use a-z, 0-9 and -");
+    }
+    stop(app, &authority);
+
+    let mut metadata = metadata.unwrap_or_default();
+    if metadata.name.trim().is_empty() {
+        metadata.name = src_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("can't name a tile after {}", src_dir.display()))?;
+    }
+    let out_dir = app.path().app_cache_dir()?.join("dev");
+    std::fs::create_dir_all(&out_dir)?;
+    let mut build = DevBuild {
+        src_dir: src_dir.clone(),
+        out_dir,
+        authority: authority.clone(),
+        metadata,
+        cache: FileCache::default(),
+        generation: 0,
+        current: None,
+    };
+    let (out, _) = build.build()?;
+    let content = Arc::new(parse_tile(&out)?);
+    build.swap_in(app, out);
+    let payload = TileOpenedPayload::new(&authority, &content);
+    app.state::<TileStore>().0.write().unwrap().insert(authority.clone(), content);
+
+    let build = Arc::new(Mutex::new(build));
+    let (handle, watched) = (app.clone(), build.clone());
+    let watch_root = src_dir.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE, move |res: DebounceEventResult| {
+        if let Ok(events) = res {
+            if events.iter().any(|e| !ignored(&watch_root, &e.path)) {
+                rebuild(&handle, &watched);
+            }
+        }
+    })?;
+    debouncer.watcher().watch(&src_dir, RecursiveMode::Recursive)?;
+    let session = DevSession { debouncer, build };
+    app.state::<DevSessions>().0.lock().unwrap().insert(authority, session);
+
+    app.emit("tile:opened", &payload)?;
+    Ok(payload)
+}
+
+/// Whether `authority` is being served from a dev session.
+pub fn is_dev(app: &AppHandle, authority: &str) -> bool {
+    app.try_state::<DevSessions>().is_some_and(|s| s.0.lock().unwrap().contains_key(authority))
+}
+
+/// End the dev session for `authority`, if there is one, and delete its
+/// build.
+pub fn stop(app: &AppHandle, authority: &str) {
+    let Some(sessions) = app.try_state::<DevSessions>() else { return };
+    let Some(session) = sessions.0.lock().unwrap().remove(authority) else { return };
+    // Stop watching first, so no rebuild starts after the cleanup.
+    drop(session.debouncer);
+    let mut build = session.build.lock().unwrap();
+    if let Some(current) = build.current.take() {
+        app.state::<BlockCache>().evict_path(&current);
+        let _ = std::fs::remove_file(current);
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Pack `src_dir`, serve it under `authority` (by default one derived from
+/// the folder), and rebuild it whenever the folder changes.
+#[tauri::command]
+pub async fn dev_mode(
+    src_dir: String,
+    authority: Option<String>,
+    metadata: Option<MaslFields>,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        start(&app, Path::new(&src_dir), authority, metadata)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_dev_mode(authority: String, app: AppHandle) {
+    stop(&app, &authority);
+}
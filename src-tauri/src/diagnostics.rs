@@ -0,0 +1,190 @@
+use crate::car::{decompress, parse_masl, parse_tile_header, read_masl};
+use crate::logging::{LogEntry, Logs};
+use crate::settings::{Settings, SettingsStore};
+use crate::validate::{self, ValidationFailure};
+use anyhow::{anyhow, Result};
+use ciborium::value::Value as CborValue;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Diagnostics bundle ───────────────────────────────────────────────────────
+//
+// `export_diagnostics` writes a `.tar.gz` for the user to attach to a bug
+// report by hand; nothing is sent anywhere. It holds the app and platform
+// versions, the settings, the recent log entries and log files, and, when a
+// tile is named, that tile's CAR header bytes with a report of how far it
+// parses. A tile's blocks, where its content lives, are never included.
+
+/// What the app knows about itself.
+#[derive(Debug, Clone, Serialize)]
+struct AppInfo {
+    name: String,
+    version: String,
+    tauri: &'static str,
+    os: &'static str,
+    arch: &'static str,
+}
+
+/// How far a tile gets through parsing, step by step. A step that failed
+/// has its error; the steps after it don't run.
+#[derive(Debug, Clone, Default, Serialize)]
+struct ParseReport {
+    path: PathBuf,
+    file_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    masl_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blocks: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index_error: Option<String>,
+    /// What `validate` would refuse to write, once the blocks are indexed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    validation: Vec<ValidationFailure>,
+}
+
+/// What went into the archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsSummary {
+    pub dest: String,
+    /// Archive member names.
+    pub files: Vec<String>,
+    pub bytes: u64,
+}
+
+/// Parse the tile at `path` as far as it goes, returning the report and
+/// the header bytes if they could be read.
+fn parse_report(path: &Path) -> Result<(ParseReport, Option<Vec<u8>>)> {
+    let mut report = ParseReport {
+        path: path.to_path_buf(),
+        file_bytes: std::fs::metadata(path)?.len(),
+        ..Default::default()
+    };
+    let copy = match decompress(path) {
+        Ok(copy) => copy,
+        Err(e) => {
+            report.header_error = Some(format!("{e:#}"));
+            return Ok((report, None));
+        }
+    };
+    let header = match read_masl(copy.as_ref().map_or(path, |copy| copy.path())) {
+        Ok(header) => header.masl,
+        Err(e) => {
+            report.header_error = Some(format!("{e:#}"));
+            return Ok((report, None));
+        }
+    };
+    report.header_bytes = Some(header.len());
+    if let Err(e) = parse_masl(&header) {
+        report.masl_error = Some(format!("{e:#}"));
+        return Ok((report, Some(header)));
+    }
+    let tile = parse_tile_header(path)?;
+    report.resources = Some(tile.masl.resources.len());
+    match tile.index() {
+        Ok(index) => {
+            report.blocks = Some(index.len());
+            let blocks = index.keys().filter_map(|cid| cid.parse().ok()).collect();
+            if let Ok(CborValue::Map(map)) = ciborium::de::from_reader(header.as_slice()) {
+                report.validation = validate::check(&map, &blocks);
+            }
+        }
+        Err(e) => report.index_error = Some(format!("{e:#}")),
+    }
+    Ok((report, Some(header)))
+}
+
+/// Write the bundle to `dest`, with the tile at `tile` if given.
+pub fn export<R: Runtime>(
+    app: &AppHandle<R>,
+    tile: Option<&Path>,
+    dest: &Path,
+) -> Result<DiagnosticsSummary> {
+    let package = app.package_info();
+    let info = AppInfo {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        tauri: tauri::VERSION,
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+    };
+    let settings: Settings = app.try_state::<SettingsStore>().map(|s| s.get()).unwrap_or_default();
+    let logs: Vec<LogEntry> = app.try_state::<Logs>().map(|l| l.entries()).unwrap_or_default();
+
+    let gzip = GzEncoder::new(File::create(dest)?, Compression::default());
+    let mut archive = tar::Builder::new(gzip);
+    let mut files = Vec::new();
+    let mut add = |name: &str, bytes: &[u8]| -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        );
+        archive.append_data(&mut header, name, bytes)?;
+        files.push(name.to_string());
+        Ok(())
+    };
+    add("app.json", &serde_json::to_vec_pretty(&info)?)?;
+    add("settings.json", &serde_json::to_vec_pretty(&settings)?)?;
+    add("logs/recent.json", &serde_json::to_vec_pretty(&logs)?)?;
+    if let Ok(dir) = app.path().app_log_dir() {
+        let mut logs: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|p| p.is_file())
+            .collect();
+        logs.sort();
+        for log in logs {
+            let name = log.file_name().and_then(|n| n.to_str()).unwrap_or("log");
+            add(&format!("logs/{name}"), &std::fs::read(&log)?)?;
+        }
+    }
+    if let Some(tile) = tile {
+        let (report, header) = parse_report(tile)?;
+        add("tile/report.json", &serde_json::to_vec_pretty(&report)?)?;
+        if let Some(header) = header {
+            add("tile/header.cbor", &header)?;
+        }
+    }
+    archive.into_inner()?.finish()?;
+    Ok(DiagnosticsSummary {
+        dest: dest.to_string_lossy().into_owned(),
+        files,
+        bytes: std::fs::metadata(dest)?.len(),
+    })
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Write a diagnostics bundle to `dest`, including the tile file at `path`
+/// if given (see the module comment for what goes in it).
+#[tauri::command]
+pub async fn export_diagnostics(
+    path: Option<String>,
+    dest: String,
+    app: AppHandle,
+) -> Result<DiagnosticsSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let tile = path.as_deref().map(Path::new);
+        if tile.is_some_and(|t| !t.is_file()) {
+            return Err(anyhow!("{} is not a tile file", path.unwrap_or_default()));
+        }
+        export(&app, tile, Path::new(&dest))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
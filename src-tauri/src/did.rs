@@ -0,0 +1,202 @@
+use crate::car::TileContent;
+use crate::hashing::{hex, unhex};
+use crate::net;
+use crate::private;
+use crate::settings::SettingsStore;
+use crate::signing::{self, SignatureStatus};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── DID author identity ──────────────────────────────────────────────────────
+//
+// A tile may name its author as a DID (`author_did` in the MASL). The claim
+// holds when the key that validly signed the tile belongs to that DID: either
+// the DID document lists it as a verification method, or one of the keys it
+// lists signed the signing key (`author_proof`), delegating to it. Only
+// Ed25519 keys given as `publicKeyMultibase` are understood.
+//
+// `did:key` resolves locally; `did:web` and `did:plc` documents are fetched
+// over HTTPS and cached for a while. A fetch tells the DID's host the tile
+// is open, so none is made for a private tile or with the `offline` setting,
+// and the tile can't send it to this machine or its network: a `did:web`
+// host that is an IP address, `localhost` or a local name is refused.
+
+/// How long a fetched DID document's keys are reused.
+const DOC_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Multicodec prefix of an Ed25519 public key.
+const ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+/// Result of checking a tile's `author_did`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorStatus {
+    pub did: String,
+    /// The tile's signer is one of the DID's keys, or delegated by one.
+    pub verified: bool,
+    /// Signed through an `author_proof` delegation rather than directly.
+    pub delegated: bool,
+    /// Why `verified` is false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Managed cache of resolved DID keys.
+#[derive(Default)]
+pub struct DidCache(Mutex<HashMap<String, (Instant, Vec<String>)>>);
+
+/// Check `tile`'s `author_did`, if it has one, against its signature. Blocks
+/// on the network for `did:web`/`did:plc`, unless the tile open at
+/// `authority` is private or the app offline.
+pub fn author_status<R: Runtime>(
+    app: &AppHandle<R>,
+    authority: &str,
+    tile: &TileContent,
+    signature: Option<&SignatureStatus>,
+) -> Option<AuthorStatus> {
+    let did = tile.masl.author_did.clone()?;
+    let outcome = match signature {
+        None => Err(anyhow!("tile is not signed")),
+        Some(sig) if !sig.valid => Err(anyhow!("tile signature is invalid")),
+        Some(sig) if sig.revoked.is_some() => Err(anyhow!("signing key has been revoked")),
+        Some(sig) => keys_for(app, authority, &did).and_then(|keys| {
+            authorizes(&keys, &sig.key, tile.masl.author_proof.as_deref())
+        }),
+    };
+    Some(match outcome {
+        Ok(delegated) => AuthorStatus { did, verified: true, delegated, error: None },
+        Err(e) => AuthorStatus { did, verified: false, delegated: false, error: Some(e.to_string()) },
+    })
+}
+
+/// Whether `keys` (the DID's) vouch for `signer`: `Ok(false)` if listed
+/// directly, `Ok(true)` if through `proof`.
+fn authorizes(keys: &[String], signer: &str, proof: Option<&str>) -> Result<bool> {
+    if keys.iter().any(|k| k.eq_ignore_ascii_case(signer)) {
+        return Ok(false);
+    }
+    let proof = proof.ok_or_else(|| anyhow!("signing key is not listed in the DID document"))?;
+    let signer_bytes = unhex(signer).ok_or_else(|| anyhow!("bad signing key"))?;
+    if keys.iter().any(|k| signing::verify_ed25519(k, &signer_bytes, proof).is_ok()) {
+        return Ok(true);
+    }
+    bail!("author_proof is not signed by any key in the DID document")
+}
+
+/// The hex Ed25519 keys `did` lists, from cache when fresh.
+fn keys_for<R: Runtime>(app: &AppHandle<R>, authority: &str, did: &str) -> Result<Vec<String>> {
+    if let Some(key) = did.strip_prefix("did:key:") {
+        return Ok(vec![multibase_ed25519(key)?]);
+    }
+    if app.state::<SettingsStore>().get().offline || private::is_private(app, authority) {
+        bail!("offline");
+    }
+    let cache = app.state::<DidCache>();
+    if let Some((at, keys)) = cache.0.lock().unwrap().get(did) {
+        if at.elapsed() < DOC_TTL {
+            return Ok(keys.clone());
+        }
+    }
+    let doc: DidDocument = net::get_json(&document_url(did)?)?;
+    if doc.id != did {
+        bail!("DID document is for {}, not {did}", doc.id);
+    }
+    let keys: Vec<String> = doc
+        .verification_method
+        .iter()
+        .filter_map(|m| multibase_ed25519(m.public_key_multibase.as_deref()?).ok())
+        .collect();
+    if keys.is_empty() {
+        bail!("DID document lists no Ed25519 keys");
+    }
+    cache.0.lock().unwrap().insert(did.to_string(), (Instant::now(), keys.clone()));
+    Ok(keys)
+}
+
+/// Where the document for a `did:web` or `did:plc` DID lives.
+fn document_url(did: &str) -> Result<String> {
+    if let Some(id) = did.strip_prefix("did:web:") {
+        let mut parts = id.split(':');
+        let host = parts.next().filter(|h| !h.is_empty()).ok_or_else(|| anyhow!("bad did:web"))?;
+        let host = host.replace("%3A", ":").replace("%3a", ":");
+        if is_local_host(&host) {
+            bail!("did:web host {host} is not a public name");
+        }
+        let path: Vec<&str> = parts.collect();
+        return Ok(if path.is_empty() {
+            format!("https://{host}/.well-known/did.json")
+        } else {
+            format!("https://{host}/{}/did.json", path.join("/"))
+        });
+    }
+    if did.starts_with("did:plc:") {
+        return Ok(format!("https://plc.directory/{did}"));
+    }
+    bail!("unsupported DID method in {did}")
+}
+
+/// Whether `host`, with any `:port`, is an IP address, `localhost` or a name
+/// only a local network resolves, rather than a public DNS name.
+fn is_local_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    };
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    let bare = name.trim_start_matches('[').trim_end_matches(']');
+    bare.parse::<IpAddr>().is_ok()
+        || host.parse::<IpAddr>().is_ok()
+        || !name.contains('.')
+        || [".localhost", ".local", ".internal", ".lan", ".home.arpa"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidDocument {
+    id: String,
+    #[serde(default)]
+    verification_method: Vec<VerificationMethod>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMethod {
+    public_key_multibase: Option<String>,
+}
+
+/// Decode a base58btc (`z…`) multibase Ed25519 public key to hex.
+fn multibase_ed25519(value: &str) -> Result<String> {
+    let encoded = value.strip_prefix('z').ok_or_else(|| anyhow!("key is not base58btc multibase"))?;
+    let bytes = base58_decode(encoded).ok_or_else(|| anyhow!("invalid base58 in key"))?;
+    match bytes.strip_prefix(&ED25519_PUB[..]) {
+        Some(key) if key.len() == 32 => Ok(hex(key)),
+        _ => bail!("key is not an Ed25519 public key"),
+    }
+}
+
+fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let mut out: Vec<u8> = Vec::new();
+    for c in s.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in out.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(out);
+    Some(decoded)
+}
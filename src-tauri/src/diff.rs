@@ -0,0 +1,375 @@
+use crate::car::{is_src_key, parse_tile, Redirect, Resource, TileContent};
+use crate::error::CommandError;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+// ── Tile diff ────────────────────────────────────────────────────────────────
+//
+// Backend for the "what changed between these two files" view. Resources
+// are matched by path. A resource whose body or headers differ is reported
+// as modified. Bodies are compared by CID first and by bytes only when the
+// CIDs differ, so the same bytes under a different hash or codec count as
+// unchanged. Text resources also get a line diff, grouped into hunks with a
+// few lines of context as in a unified diff. The diff is Myers' algorithm
+// and gives up past `MAX_EDITS` changed lines, which leaves the view to show
+// the resource as replaced. A resource without a `src` has no body, which
+// differs from any body; one whose `variants` differ is modified too.
+// Redirects are matched by path as well, and reported when their target or
+// status changes or they come or go.
+//
+// Each resource is reported with its body's CID, as authors compare
+// revisions by hash, and sizes come with a signed delta: per resource, and
+// over all bodies for the whole tile, counting added and removed ones.
+
+/// Largest text body, per side, that gets a line diff.
+const MAX_TEXT_BYTES: u64 = 1 << 20;
+/// Most inserted plus deleted lines a line diff looks for.
+const MAX_EDITS: usize = 2000;
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TileDiff {
+    /// Top-level MASL fields (other than `resources` and the variants and
+    /// redirects parsed out of them) that differ.
+    pub metadata_changed: Vec<String>,
+    pub added: Vec<ResourceSummary>,
+    pub removed: Vec<ResourceSummary>,
+    pub modified: Vec<ModifiedResource>,
+    pub unchanged: usize,
+    /// Redirects added, removed or changed, by path.
+    pub redirects: Vec<RedirectChange>,
+    /// Bytes of resource bodies gained (or, negative, lost) from `a` to `b`.
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceSummary {
+    pub path: String,
+    pub content_type: Option<String>,
+    /// `None` for an entry without a `src`, whose size is 0.
+    pub cid: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModifiedResource {
+    pub path: String,
+    pub content_type: Option<String>,
+    pub old_cid: Option<String>,
+    pub new_cid: Option<String>,
+    pub old_size: u64,
+    pub new_size: u64,
+    /// `new_size - old_size`.
+    pub size_delta: i64,
+    pub body_changed: bool,
+    /// The entry's `variants` differ.
+    pub variants_changed: bool,
+    /// Headers added, removed or changed, by name.
+    pub headers_changed: Vec<String>,
+    /// Line diff of a changed text body. `None` for other bodies, text over
+    /// `MAX_TEXT_BYTES`, or text too different to diff line by line.
+    pub hunks: Option<Vec<Hunk>>,
+}
+
+/// A redirect at `path` as it was in `a` and is in `b`, `None` where there's
+/// none.
+#[derive(Debug, Clone, Serialize)]
+pub struct RedirectChange {
+    pub path: String,
+    pub old: Option<Redirect>,
+    pub new: Option<Redirect>,
+}
+
+/// A run of changed lines with their context. Line numbers start at 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub op: LineOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineOp {
+    Context,
+    Added,
+    Removed,
+}
+
+/// What changed from the tile at `a` to the tile at `b`.
+pub fn diff(a: &Path, b: &Path) -> Result<TileDiff> {
+    let (a, b) = (parse_tile(a)?, parse_tile(b)?);
+    let mut diff = TileDiff {
+        metadata_changed: metadata_changed(&a, &b)?,
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+        unchanged: 0,
+        redirects: Vec::new(),
+        size_delta: 0,
+    };
+    let paths: BTreeSet<&String> =
+        a.masl.resources.keys().chain(b.masl.resources.keys()).collect();
+    for path in paths {
+        match (a.masl.resources.get(path), b.masl.resources.get(path)) {
+            (Some(old), Some(new)) => match modified(path, (&a, old), (&b, new))? {
+                Some(modified) => diff.modified.push(modified),
+                None => diff.unchanged += 1,
+            },
+            (None, Some(new)) => diff.added.push(summary(path, &b, new)?),
+            (Some(old), None) => diff.removed.push(summary(path, &a, old)?),
+            (None, None) => unreachable!(),
+        }
+    }
+    let (old, new) = (&a.masl.redirects, &b.masl.redirects);
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    diff.redirects = paths
+        .into_iter()
+        .filter(|path| old.get(*path) != new.get(*path))
+        .map(|path| RedirectChange {
+            path: path.clone(),
+            old: old.get(path).cloned(),
+            new: new.get(path).cloned(),
+        })
+        .collect();
+    diff.size_delta = diff.added.iter().map(|r| delta(0, r.size)).sum::<i64>()
+        + diff.removed.iter().map(|r| delta(r.size, 0)).sum::<i64>()
+        + diff.modified.iter().map(|r| r.size_delta).sum::<i64>();
+    Ok(diff)
+}
+
+fn delta(old: u64, new: u64) -> i64 {
+    new as i64 - old as i64
+}
+
+fn metadata_changed(a: &TileContent, b: &TileContent) -> Result<Vec<String>> {
+    let (serde_json::Value::Object(a), serde_json::Value::Object(b)) =
+        (serde_json::to_value(&a.masl)?, serde_json::to_value(&b.masl)?)
+    else {
+        return Ok(Vec::new());
+    };
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|k| !["resources", "variants", "redirects"].contains(&k.as_str()))
+        .filter(|k| a.get(*k) != b.get(*k))
+        .cloned()
+        .collect())
+}
+
+fn summary(path: &str, tile: &TileContent, resource: &Resource) -> Result<ResourceSummary> {
+    Ok(ResourceSummary {
+        path: path.to_string(),
+        content_type: resource.get("content-type").cloned(),
+        cid: resource.get("src").cloned(),
+        size: body_len(tile, resource)?,
+    })
+}
+
+/// Length of `resource`'s body as served, 0 without a `src`. For a dag-pb
+/// file that is the content its root heads (see `TileContent::content_len`),
+/// not the root block's own length.
+fn body_len(tile: &TileContent, resource: &Resource) -> Result<u64> {
+    resource.get("src").map_or(Ok(0), |src| tile.content_len(src))
+}
+
+fn modified(
+    path: &str,
+    (a, old): (&TileContent, &Resource),
+    (b, new): (&TileContent, &Resource),
+) -> Result<Option<ModifiedResource>> {
+    let headers: BTreeSet<&String> =
+        old.keys().chain(new.keys()).filter(|k| !is_src_key(k)).collect();
+    let headers_changed: Vec<String> =
+        headers.into_iter().filter(|h| old.get(*h) != new.get(*h)).cloned().collect();
+    let (old_size, new_size) = (body_len(a, old)?, body_len(b, new)?);
+    let (old_src, new_src) = (old.get("src"), new.get("src"));
+    let mut bodies = None;
+    let body_changed = match (old_src, new_src) {
+        (Some(old_src), Some(new_src)) if old_src != new_src => {
+            let bytes = (a.read_block(old_src)?, b.read_block(new_src)?);
+            let changed = bytes.0 != bytes.1;
+            bodies = Some(bytes);
+            changed
+        }
+        (old_src, new_src) => old_src.is_some() != new_src.is_some(),
+    };
+    let variants_changed = a.masl.variants.get(path) != b.masl.variants.get(path);
+    if !body_changed && !variants_changed && headers_changed.is_empty() {
+        return Ok(None);
+    }
+    let content_type = new.get("content-type").cloned();
+    let hunks = match bodies {
+        Some((old_body, new_body))
+            if body_changed
+                && is_text(content_type.as_deref().unwrap_or_default())
+                && old_size.max(new_size) <= MAX_TEXT_BYTES =>
+        {
+            match (std::str::from_utf8(&old_body), std::str::from_utf8(&new_body)) {
+                (Ok(old_text), Ok(new_text)) => line_diff(old_text, new_text),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    Ok(Some(ModifiedResource {
+        path: path.to_string(),
+        content_type,
+        old_cid: old_src.cloned(),
+        new_cid: new_src.cloned(),
+        old_size,
+        new_size,
+        size_delta: delta(old_size, new_size),
+        body_changed,
+        variants_changed,
+        headers_changed,
+        hunks,
+    }))
+}
+
+/// Whether a body of `content_type` is worth diffing line by line.
+fn is_text(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(essence, "application/json" | "application/javascript" | "application/xml")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Hunks turning `old` into `new`, or `None` past `MAX_EDITS`.
+fn line_diff(old: &str, new: &str) -> Option<Vec<Hunk>> {
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    let edits = myers(&a, &b)?;
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|&i| !matches!(edits[i], Edit::Same(..)))
+        .collect();
+    // Lines of `a` and `b` before each edit.
+    let mut before = Vec::with_capacity(edits.len());
+    let (mut x, mut y) = (0, 0);
+    for edit in &edits {
+        before.push((x, y));
+        match edit {
+            Edit::Same(..) => (x, y) = (x + 1, y + 1),
+            Edit::Removed(_) => x += 1,
+            Edit::Added(_) => y += 1,
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changes.len() {
+        let start = changes[i].saturating_sub(CONTEXT);
+        let mut end = changes[i] + 1;
+        i += 1;
+        while i < changes.len() && changes[i] <= end + 2 * CONTEXT {
+            end = changes[i] + 1;
+            i += 1;
+        }
+        let end = (end + CONTEXT).min(edits.len());
+        let lines: Vec<DiffLine> = edits[start..end]
+            .iter()
+            .map(|edit| match *edit {
+                Edit::Same(x, _) => DiffLine { op: LineOp::Context, text: a[x].to_string() },
+                Edit::Removed(x) => DiffLine { op: LineOp::Removed, text: a[x].to_string() },
+                Edit::Added(y) => DiffLine { op: LineOp::Added, text: b[y].to_string() },
+            })
+            .collect();
+        let count = |op| lines.iter().filter(|l| l.op == op || l.op == LineOp::Context).count();
+        hunks.push(Hunk {
+            old_start: before[start].0 + 1,
+            old_lines: count(LineOp::Removed),
+            new_start: before[start].1 + 1,
+            new_lines: count(LineOp::Added),
+            lines,
+        });
+    }
+    Some(hunks)
+}
+
+/// Shortest edit script from `a` to `b` (Myers, 1986), or `None` if it is
+/// longer than `MAX_EDITS`.
+fn myers(a: &[&str], b: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    // Furthest x reached on each diagonal k = x - y, indexed from -max - 1.
+    let at = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // `v` as it was before each round d, for diagonals -d..=d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut done = None;
+    'search: for d in 0..=max {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                (x, y) = (x + 1, y + 1);
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                done = Some(d);
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..=done?).rev() {
+        let previous = &trace[d as usize];
+        let prev = |k: isize| previous[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && prev(k - 1) < prev(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = prev(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            (x, y) = (x - 1, y - 1);
+            edits.push(Edit::Same(x as usize, y as usize));
+        }
+        if x == prev_x {
+            edits.push(Edit::Added(prev_y as usize));
+        } else {
+            edits.push(Edit::Removed(prev_x as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    while x > 0 && y > 0 {
+        (x, y) = (x - 1, y - 1);
+        edits.push(Edit::Same(x as usize, y as usize));
+    }
+    edits.reverse();
+    Some(edits)
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn diff_tiles(path_a: String, path_b: String) -> Result<TileDiff, CommandError> {
+    let diffed =
+        tauri::async_runtime::spawn_blocking(move || diff(Path::new(&path_a), Path::new(&path_b)))
+            .await
+            .map_err(anyhow::Error::from)?;
+    Ok(diffed?)
+}
@@ -0,0 +1,229 @@
+use crate::builder::{MaslFields, TileBuilder};
+use crate::car::{authority_from_path, is_src_key, parse_tile, Icon, Redirect, TileContent};
+use crate::error::CommandError;
+use crate::hashing::hex;
+use crate::pack::{self, PackSummary};
+use crate::{permissions, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+// ── Directory tiles ──────────────────────────────────────────────────────────
+//
+// A folder with a `masl.json` at its top can be opened as a virtual tile and
+// previewed through `tile://` before it is packed. Every other file becomes a
+// resource at its relative path, exactly as `pack` would lay it out. Blocks
+// are read straight from those files (see `car::TileContent::from_directory`).
+// `masl.json` holds the MASL fields, icons, optionally extra headers per
+// resource path, and redirects, which replace any file at their path. `src`
+// links are computed, not given. Editing a file makes the next request
+// reload the folder, the same way a rewritten `.tile` is reloaded. Packing
+// such a folder uses its `masl.json` the same way, and unpacking a tile
+// writes one.
+
+/// The manifest file, which is not itself served.
+const MASL_FILE: &str = "masl.json";
+
+#[derive(Debug, Deserialize, Serialize)]
+struct MaslJson {
+    #[serde(flatten)]
+    fields: MaslFields,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    icons: Vec<Icon>,
+    /// Resource path → extra headers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    resources: BTreeMap<String, BTreeMap<String, String>>,
+    /// Resource path → where it redirects, and with what status.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    redirects: BTreeMap<String, Redirect>,
+}
+
+/// Apply the `masl.json` in `dir` to `builder`, which holds the folder's
+/// files as `pack::add_folder` added them and described them in `summary`.
+/// The manifest stops being a resource, and the headers, redirects, icons
+/// and MASL fields it gives are set. Returns whether the folder has one.
+pub(crate) fn apply_manifest(
+    builder: &mut TileBuilder,
+    dir: &Path,
+    summary: &mut PackSummary,
+) -> Result<bool> {
+    let manifest = match std::fs::read(dir.join(MASL_FILE)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => bail!("reading {MASL_FILE} in {}: {e}", dir.display()),
+    };
+    let manifest: MaslJson = serde_json::from_slice(&manifest)
+        .map_err(|e| anyhow!("{MASL_FILE} in {}: {e}", dir.display()))?;
+
+    let path = format!("/{MASL_FILE}");
+    builder.remove_resource(&path);
+    for (path, redirect) in &manifest.redirects {
+        builder.add_redirect(path, &redirect.location, redirect.status)?;
+    }
+    let replaced = |f: &str| f == path || manifest.redirects.contains_key(f);
+    summary.files.retain(|f| !replaced(&f.path));
+    summary.total_bytes = summary.files.iter().map(|f| f.size).sum();
+    for (path, headers) in &manifest.resources {
+        for (name, value) in headers {
+            if name == "src" {
+                bail!("{MASL_FILE}: `src` for {path} is computed, not given");
+            }
+            builder.set_header(path, name, value)?;
+        }
+    }
+    builder.set_metadata(manifest.fields).set_icons(manifest.icons);
+    Ok(true)
+}
+
+/// Read the folder `dir` as a tile.
+pub fn open(dir: &Path) -> Result<TileContent> {
+    let mut builder = TileBuilder::new();
+    let mut summary = pack::add_folder(&mut builder, dir)?;
+    if !apply_manifest(&mut builder, dir, &mut summary)? {
+        bail!("{} has no {MASL_FILE}", dir.display());
+    }
+    let masl = builder.masl()?;
+
+    let mut files = HashMap::new();
+    for (path, resource) in &masl.resources {
+        let source = match (&summary.entry_alias_of, path.as_str()) {
+            (Some(alias), "/index.html") => alias,
+            _ => path,
+        };
+        let file = dir.join(&source[1..]);
+        if let Some(cid) = resource.get("src") {
+            files.insert(cid.clone(), file);
+        }
+    }
+    // Key order in `serde_json::Value` maps is sorted, so this is stable.
+    let root_hash = hex(&Sha256::digest(serde_json::to_vec(&serde_json::to_value(&masl)?)?));
+    TileContent::from_directory(dir, masl, root_hash, files)
+}
+
+// ── Unpacking ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnpackSummary {
+    /// Resource paths written out as files.
+    pub files: Vec<String>,
+    pub total_bytes: u64,
+}
+
+/// Write the tile at `tile` out as the directory tile `dest`: each resource
+/// as a file at its path, and a `masl.json` with the MASL fields, icons, and
+/// any headers packing the folder wouldn't give back. `dest` must be missing
+/// or empty. Precompressed variants, revisions and the signature are left
+/// behind, since packing makes the first afresh and can't keep the others.
+pub fn unpack(tile: &Path, dest: &Path) -> Result<UnpackSummary> {
+    let content = parse_tile(tile)?;
+    if dest.exists() && std::fs::read_dir(dest)?.next().is_some() {
+        bail!("{} is not empty", dest.display());
+    }
+    let masl = &content.masl;
+    if masl.resources.contains_key(&format!("/{MASL_FILE}")) {
+        bail!("the tile has its own /{MASL_FILE}, which unpacking would overwrite");
+    }
+    let mut paths: Vec<&String> = masl.resources.keys().collect();
+    paths.sort();
+    let mut summary = UnpackSummary { files: Vec::new(), total_bytes: 0 };
+    let mut resources = BTreeMap::new();
+    for path in paths {
+        let file = dest.join(relative_file(path)?);
+        let resource = &masl.resources[path];
+        let body = content.read_block(&resource["src"])?;
+        let detected = pack::detect_type(path, &body).0;
+        let headers: BTreeMap<String, String> = resource
+            .iter()
+            .filter(|(k, _)| !is_src_key(k))
+            // The content type is only kept where packing would guess another.
+            .filter(|(k, v)| *k != "content-type" || v.as_str() != detected)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        if !headers.is_empty() {
+            resources.insert(path.clone(), headers);
+        }
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file, &body).map_err(|e| anyhow!("writing {}: {e}", file.display()))?;
+        summary.total_bytes += body.len() as u64;
+        summary.files.push(path.clone());
+    }
+    let manifest = MaslJson {
+        fields: MaslFields {
+            name: masl.name.clone(),
+            names: masl.names.clone(),
+            description: masl.description.clone(),
+            descriptions: masl.descriptions.clone(),
+            short_name: masl.short_name.clone(),
+            theme_color: masl.theme_color.clone(),
+            background_color: masl.background_color.clone(),
+            update_url: masl.update_url.clone(),
+            permissions: masl.permissions.clone(),
+            authors: masl.authors.clone(),
+            license: masl.license.clone(),
+            published: masl.published.clone(),
+            modified: masl.modified.clone(),
+            dc: masl.dc.clone(),
+            error_pages: masl.error_pages.clone(),
+            extra: masl.extra.clone(),
+        },
+        icons: masl.icons.clone(),
+        resources,
+        redirects: masl.redirects.clone().into_iter().collect(),
+    };
+    std::fs::create_dir_all(dest)?;
+    std::fs::write(dest.join(MASL_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(summary)
+}
+
+/// The file a resource at `path` unpacks to, relative to the folder. Paths
+/// that could land outside it are refused.
+fn relative_file(path: &str) -> Result<PathBuf> {
+    let mut file = PathBuf::new();
+    for segment in path.strip_prefix('/').unwrap_or(path).split('/') {
+        if matches!(segment, "" | "." | "..") || segment.contains(['\\', ':']) {
+            bail!("can't unpack {path}: not a plain relative path");
+        }
+        file.push(segment);
+    }
+    Ok(file)
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Open the folder `dir` (holding a `masl.json`) as a virtual tile.
+#[tauri::command]
+pub async fn open_directory_tile(
+    dir: String,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, CommandError> {
+    let opened = tauri::async_runtime::spawn_blocking(move || -> Result<TileOpenedPayload> {
+        let dir = std::fs::canonicalize(&dir)?;
+        let content = open(&dir)?;
+        let authority = authority_from_path(&dir);
+        app.state::<permissions::SafeMode>().set(&authority, false);
+        let payload = TileOpenedPayload::new(&authority, &content);
+        let store: State<'_, TileStore> = app.state();
+        store.0.write().unwrap().insert(authority, Arc::new(content));
+        app.emit("tile:opened", &payload)?;
+        Ok(payload)
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(opened?)
+}
+
+/// Unpack the tile at `path` into the folder `dest` (see [`unpack`]).
+#[tauri::command]
+pub async fn unpack_tile(path: String, dest: String) -> Result<UnpackSummary, CommandError> {
+    let unpacked =
+        tauri::async_runtime::spawn_blocking(move || unpack(Path::new(&path), Path::new(&dest)))
+            .await
+            .map_err(anyhow::Error::from)?;
+    Ok(unpacked?)
+}
@@ -0,0 +1,156 @@
+use crate::i18n::{t, tf};
+use crate::lint;
+use crate::TileStore;
+use anyhow::{anyhow, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tauri::webview::DownloadEvent;
+use tauri::{AppHandle, Manager, Runtime, Url, Webview};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+// ── Downloads ────────────────────────────────────────────────────────────────
+//
+// Downloads of ordinary tile resources go ahead as the webview handles them.
+// Executable or installer content (see `lint::is_dangerous`) is held back: the
+// webview's download is cancelled, the user is asked, and on confirmation the
+// resource is written to the chosen destination from here instead. A file a
+// tile builds itself, as a `blob:` or `data:` URL, has no resource for this
+// to write, so one that is executable by its name or type is refused. The file
+// name comes from the tile, so only a plain name is kept of it (see
+// `file_name`), and a file already there is never overwritten.
+
+/// The main webview's download handler.
+pub fn on_download<R: Runtime>(webview: Webview<R>, event: DownloadEvent<'_>) -> bool {
+    let DownloadEvent::Requested { url, destination } = event else { return true };
+    if url.scheme() != "tile" {
+        let suggested = destination.file_name().map(|n| n.to_string_lossy().into_owned());
+        let name = suggested.unwrap_or_else(|| url.path().to_string());
+        if !lint::is_dangerous_file(&name, data_type(&url)) {
+            return true;
+        }
+        let app = webview.app_handle().clone();
+        // A blob's URL names the origin that made it; a data URL's doesn't,
+        // but a tile's own window does.
+        let inner = (url.scheme() == "blob").then(|| Url::parse(url.path()).ok()).flatten();
+        let own = inner.or_else(|| webview.url().ok()).filter(|own| own.scheme() == "tile");
+        let authority = own.and_then(|own| own.host_str().map(str::to_string));
+        std::thread::spawn(move || refuse(&app, authority.as_deref(), &file_name(&name)));
+        return false;
+    }
+    let authority = url.host_str().unwrap_or("").to_string();
+    let path = url.path().to_string();
+    let app = webview.app_handle().clone();
+    let Some(tile) = app.state::<TileStore>().get(&authority) else { return true };
+    match tile.resolve(&path) {
+        Some(resource) if lint::is_dangerous(&path, resource) => {}
+        _ => return true,
+    }
+
+    let destination = destination.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = confirm_and_save(&app, &authority, &path, &destination) {
+            tracing::warn!("saving {path} from {authority} failed: {e}");
+        }
+    });
+    false
+}
+
+fn confirm_and_save<R: Runtime>(
+    app: &AppHandle<R>,
+    authority: &str,
+    path: &str,
+    destination: &Path,
+) -> Result<()> {
+    let tile = app.state::<TileStore>().get(authority);
+    let tile = tile.ok_or_else(|| anyhow!(t("error.tile-closed")))?;
+    let suggested = destination.file_name().map(|n| n.to_string_lossy());
+    let name = file_name(suggested.as_deref().unwrap_or(path));
+    let message = tf("download.prompt", &[("name", &tile.masl.name), ("file", &name)]);
+    let confirmed = app
+        .dialog()
+        .message(message)
+        .title(t("download.title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(t("download.save"), t("download.cancel")))
+        .blocking_show();
+    if !confirmed {
+        return Ok(());
+    }
+
+    // Some platforms don't suggest a destination; fall back to Downloads.
+    let dir = match destination.parent() {
+        Some(dir) if destination.is_absolute() => dir.to_path_buf(),
+        _ => app.path().download_dir()?,
+    };
+    let resource = tile.resolve(path).ok_or_else(|| anyhow!("no resource at {path}"))?;
+    let src = resource.get("src").ok_or_else(|| anyhow!("resource missing src"))?;
+    let body = tile.read_block(src)?;
+    create_unique(&dir, &name)?.write_all(&body)?;
+    Ok(())
+}
+
+/// The media type a `data:` URL declares, if `url` is one.
+fn data_type(url: &Url) -> Option<&str> {
+    let meta = url.path().split(',').next().filter(|_| url.scheme() == "data")?;
+    meta.split(';').next().filter(|t| !t.is_empty())
+}
+
+/// Tell the user the executable `name` a tile made itself wasn't saved.
+/// `authority` is the tile's when the download came from its own window.
+fn refuse<R: Runtime>(app: &AppHandle<R>, authority: Option<&str>, name: &str) {
+    let tile = authority.and_then(|authority| app.state::<TileStore>().get(authority));
+    let owner = tile.map_or_else(|| t("download.unnamed"), |tile| tile.masl.name.clone());
+    app.dialog()
+        .message(tf("download.refused", &[("name", &owner), ("file", name)]))
+        .title(t("download.refused-title"))
+        .kind(MessageDialogKind::Warning)
+        .blocking_show();
+}
+
+/// Names Windows reserves for devices, whatever the extension.
+const RESERVED: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// A plain file name made from `raw`, a path or name the tile suggests: its
+/// last segment, without control characters or `..`, and without the dots
+/// and spaces some file systems drop from either end. A reserved device
+/// name is prefixed with `_`; nothing left becomes `download`.
+fn file_name(raw: &str) -> String {
+    let base = raw.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = base.chars().filter(|c| !c.is_control()).collect();
+    let name = name.replace("..", "");
+    let name = name.trim_matches(|c| c == '.' || c == ' ');
+    let stem = name.split('.').next().unwrap_or_default().to_ascii_lowercase();
+    if name.is_empty() {
+        "download".into()
+    } else if RESERVED.contains(&stem.as_str()) {
+        format!("_{name}")
+    } else {
+        name.into()
+    }
+}
+
+/// Create `name` in `dir`, or if a file has it already, the first free
+/// `stem (n).ext`, so a download never replaces a file.
+fn create_unique(dir: &Path, name: &str) -> Result<File> {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+        _ => (name, None),
+    };
+    for n in 0..1000 {
+        let candidate = match (n, ext) {
+            (0, _) => name.to_string(),
+            (n, Some(ext)) => format!("{stem} ({n}).{ext}"),
+            (n, None) => format!("{stem} ({n})"),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(dir.join(candidate)) {
+            Ok(file) => return Ok(file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(anyhow!("no free name for {name} in {}", dir.display()))
+}
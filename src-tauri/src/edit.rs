@@ -0,0 +1,817 @@
+use crate::builder::{icons_value, raw_cid, text, write_uvarint};
+use crate::car::{
+    cbor_to_cid_string, data_end, default_text, is_carv2, is_compressed, is_src_key, parse_tile,
+    parse_tile_header, read_header, read_masl, BlockFrame, BlockIndex, BlockScanner, Icon,
+    Localized, TileContent,
+};
+use crate::dagcbor;
+use crate::dagpb::{self, DAG_PB};
+use crate::error::CommandError;
+use crate::events::Coalescer;
+use crate::feed::rfc3339;
+use crate::i18n;
+use crate::pack;
+use crate::revisions;
+use crate::signing::{self, Signer};
+use crate::validate;
+use crate::watch::TileWatcher;
+use crate::{reload_tile, swap_in, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use ciborium::value::Value as CborValue;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+// ── Editing open tiles ───────────────────────────────────────────────────────
+//
+// Changes to an existing tile's MASL are made by rewriting its CAR header.
+// The blocks after the header are copied byte for byte. The new file is
+// written next to the old one and renamed over it, so a crash mid-save
+// leaves the original intact. Header keys the editor doesn't know about are
+// kept as they were, and the header is written as canonical DAG-CBOR. An
+// embedded signature can't survive the edit because it covers the header, so
+// it is removed, and replaced by a new one when signing on export is on. A
+// `.sig` file beside the tile is left alone and will show as invalid until
+// the tile is re-signed.
+//
+// An edit can also keep the header it replaces, as a block the new header
+// links back to, so the tile gains a revision history (see `revisions.rs`).
+// Once a tile has one, every edit extends it unless told not to.
+//
+// Replacing a resource appends the new body as a block and points the
+// resource at its CID. The old block is left in the file, as is the body of
+// a resource that is removed. Compaction copies only the blocks the header
+// still links to, which drops them.
+//
+// A CARv1 in the standard layout, whose header only names the MASL as its
+// root block, is edited without being copied: new bodies and the new MASL
+// are appended as blocks and the header is overwritten where it is to name
+// the new MASL. Its CID is the same kind as the old one's, so the header
+// keeps its size and nothing after it moves; if it wouldn't, the tile is
+// rewritten as usual. A crash before the header is written leaves the old
+// tile with a few blocks nothing references at its end. What was appended
+// is returned, so an open tile's block index can be extended rather than
+// rebuilt.
+
+/// The MASL fields the metadata editor changes. It sends all of them: a
+/// field left unset is removed from the header. A name or description the
+/// tile gives by language keeps its other languages, and the text edited is
+/// that of its default entry (see [`default_text`]).
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaslEdit {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub short_name: Option<String>,
+    #[serde(default)]
+    pub theme_color: Option<String>,
+    #[serde(default)]
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub icons: Vec<Icon>,
+}
+
+/// What [`rewrite`] did.
+#[derive(Debug, Default)]
+pub struct Saved {
+    /// An embedded signature had to be dropped without one replacing it.
+    pub dropped_signature: bool,
+    /// Where each block appended in place went, if the file was added to
+    /// rather than rewritten.
+    pub appended: Option<BlockIndex>,
+}
+
+/// How far [`compact`] has got through the file's blocks.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CompactProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// What [`rewrite`] does with the blocks already in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExistingBlocks {
+    /// Copy them all, byte for byte.
+    Keep,
+    /// Copy only those the new header still references.
+    Compact,
+}
+
+/// Rewrite the tile at `path` with its CAR header passed through `edit`,
+/// carrying over its blocks as `existing` says and adding `append` as new
+/// raw blocks, other than any the file already has. With `revision`, the
+/// current header is first kept as a block the new one links to (see
+/// `revisions.rs`); by default that happens when the tile already has a
+/// history. The new header is signed by `signer`, if
+/// given. A header that fails `validate::check` is refused, and nothing is
+/// written. A tile whose MASL is its root block (see `car::read_masl`) has
+/// its blocks appended in place when `existing` keeps them all, and is
+/// otherwise written back with the MASL as its header. A compressed tile is
+/// refused.
+pub fn rewrite(
+    path: &Path,
+    edit: impl FnOnce(&mut Vec<(CborValue, CborValue)>) -> Result<()>,
+    append: &[Vec<u8>],
+    existing: ExistingBlocks,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+) -> Result<Saved> {
+    rewrite_reporting(path, edit, append, existing, revision, signer, |_| {})
+}
+
+/// [`rewrite`], calling `on_progress` as each block is copied when only
+/// referenced ones are.
+fn rewrite_reporting(
+    path: &Path,
+    edit: impl FnOnce(&mut Vec<(CborValue, CborValue)>) -> Result<()>,
+    append: &[Vec<u8>],
+    existing: ExistingBlocks,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+    mut on_progress: impl FnMut(CompactProgress),
+) -> Result<Saved> {
+    if is_compressed(path)? {
+        bail!("a compressed tile can't be edited; decompress it first");
+    }
+    replay_journal(path)?;
+    let mut f = File::open(path)?;
+    let header = read_masl(path)?;
+    let (header, data_start, masl_root) = (header.masl, header.data_start, header.masl_root);
+    let CborValue::Map(mut map) = ciborium::de::from_reader(header.as_slice())
+        .map_err(|e| anyhow!("CBOR decode error: {e}"))?
+    else {
+        bail!("CAR header is not a CBOR map");
+    };
+
+    let previous = if revision.unwrap_or_else(|| revisions::previous(&map).is_some()) {
+        Some((revisions::header_cid(&header)?, header))
+    } else {
+        None
+    };
+    let original = map.clone();
+    edit(&mut map)?;
+    let signed = map.len();
+    map.retain(|(k, _)| k.as_text() != Some("signature"));
+    let dropped_signature = map.len() != signed && signer.is_none();
+    // A MASL read from a root block may lack the `version` a header needs.
+    if !map.iter().any(|(k, _)| k.as_text() == Some("version")) {
+        map.insert(0, (text("version"), CborValue::Integer(1.into())));
+    }
+    if let Some((cid, _)) = &previous {
+        set_field(&mut map, revisions::PREV, Some(link(cid)));
+        set_field(&mut map, revisions::REVISED, Some(text(&rfc3339(SystemTime::now()))));
+    }
+    if let Some(signer) = signer {
+        signer.sign_header(&mut map)?;
+    }
+    if let Some(root) = masl_root.filter(|_| existing == ExistingBlocks::Keep) {
+        let kept = previous.as_ref().map(|(cid, header)| (cid, header.as_slice()));
+        if let Some(appended) = append_in_place(path, data_start, &root, &map, kept, append)? {
+            return Ok(Saved { dropped_signature, appended: Some(appended) });
+        }
+    }
+
+    let frames = BlockScanner::new(path, data_start)?.collect::<Result<Vec<_>>>()?;
+    let referenced = match existing {
+        ExistingBlocks::Keep => None,
+        // A header kept just now isn't in `frames` yet, so its history is
+        // followed from the header it copies.
+        ExistingBlocks::Compact if previous.is_some() => {
+            let mut cids = referenced_cids(&mut f, &original, &frames)?;
+            cids.extend(resource_cids(&map));
+            Some(cids)
+        }
+        ExistingBlocks::Compact => Some(referenced_cids(&mut f, &map, &frames)?),
+    };
+    let mut written_cids: HashSet<Cid> = frames
+        .iter()
+        .map(|frame| frame.cid)
+        .filter(|cid| referenced.as_ref().is_none_or(|r| r.contains(cid)))
+        .collect();
+    let mut blocks: Vec<(Cid, &[u8])> = Vec::new();
+    // A MASL that was the root block is already one of the file's blocks.
+    if let Some((cid, header)) = previous.as_ref().filter(|(cid, _)| !written_cids.contains(cid)) {
+        blocks.push((*cid, header));
+    }
+    for data in append {
+        let cid = raw_cid(data)?;
+        // A body the file already holds (and keeps) needn't be stored twice.
+        if written_cids.insert(cid) {
+            blocks.push((cid, data));
+        }
+    }
+    written_cids.extend(blocks.iter().map(|(cid, _)| *cid));
+    validate::ensure(&map, &written_cids)?;
+
+    let header_bytes = dagcbor::encode(&CborValue::Map(map))?;
+
+    let tmp = path.with_extension("tile.partial");
+    let written = (|| -> Result<()> {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        write_uvarint(&mut out, header_bytes.len() as u64)?;
+        out.write_all(&header_bytes)?;
+        match &referenced {
+            None => {
+                // Up to the end of the frames: a CARv2's index isn't copied,
+                // as the file is written back as a CARv1.
+                let end = data_end(&f)?;
+                f.seek(SeekFrom::Start(data_start))?;
+                std::io::copy(&mut Read::by_ref(&mut f).take(end - data_start), &mut out)?;
+            }
+            Some(referenced) => {
+                let mut copied = HashSet::new();
+                let total = frames.len();
+                for (i, frame) in frames.iter().enumerate() {
+                    on_progress(CompactProgress { done: i + 1, total });
+                    if !referenced.contains(&frame.cid) || !copied.insert(frame.cid) {
+                        continue;
+                    }
+                    let cid_bytes = frame.cid.to_bytes();
+                    write_uvarint(&mut out, cid_bytes.len() as u64 + frame.len)?;
+                    out.write_all(&cid_bytes)?;
+                    f.seek(SeekFrom::Start(frame.offset))?;
+                    std::io::copy(&mut Read::by_ref(&mut f).take(frame.len), &mut out)?;
+                }
+            }
+        }
+        for (cid, data) in &blocks {
+            let cid_bytes = cid.to_bytes();
+            write_uvarint(&mut out, (cid_bytes.len() + data.len()) as u64)?;
+            out.write_all(&cid_bytes)?;
+            out.write_all(data)?;
+        }
+        let out = out.into_inner()?;
+        out.set_permissions(f.metadata()?.permissions())?;
+        out.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = written.and_then(|()| Ok(std::fs::rename(&tmp, path)?)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(Saved { dropped_signature, appended: None })
+}
+
+/// Append `append` as raw blocks and the MASL `map` as a DAG-CBOR block to
+/// the tile at `path`, whose header names the MASL as root block `root`,
+/// and point the header at the new one. A `previous` MASL kept as a
+/// revision is added too if the file lacks it under that CID. Returns
+/// where the new blocks went; `None`, with nothing written, if the file
+/// isn't a CARv1 or its header would change size.
+///
+/// Only a tile whose MASL is a root block can be added to: a standard
+/// tile's MASL is its CAR header, which grows with any edit and would
+/// have to move every block after it. The header is swapped through a
+/// journal (see [`replay_journal`]), so a crash leaves either header.
+fn append_in_place(
+    path: &Path,
+    data_start: u64,
+    root: &Cid,
+    map: &[(CborValue, CborValue)],
+    previous: Option<(&Cid, &[u8])>,
+    append: &[Vec<u8>],
+) -> Result<Option<BlockIndex>> {
+    let mut f = File::options().read(true).write(true).open(path)?;
+    if is_carv2(&f)? {
+        return Ok(None);
+    }
+    let (header, _) = read_header(&mut f)?;
+    let Ok(CborValue::Map(mut header_map)) = ciborium::de::from_reader(header.as_slice()) else {
+        return Ok(None);
+    };
+    let masl = dagcbor::encode(&CborValue::Map(map.to_vec()))?;
+    let masl_cid = revisions::header_cid(&masl)?;
+    let roots = header_map.iter_mut().find_map(|(k, v)| match v {
+        CborValue::Array(roots) if k.as_text() == Some("roots") => Some(roots),
+        _ => None,
+    });
+    let Some(first) = roots.and_then(|roots| roots.first_mut()) else { return Ok(None) };
+    if cbor_to_cid_string(first) != Some(root.to_string()) {
+        return Ok(None);
+    }
+    *first = link(&masl_cid);
+    let mut new_header = Vec::new();
+    ciborium::ser::into_writer(&CborValue::Map(header_map), &mut new_header)?;
+    if new_header.len() != header.len() {
+        return Ok(None);
+    }
+
+    let frames = BlockScanner::new(path, data_start)?.collect::<Result<Vec<_>>>()?;
+    let mut cids: HashSet<Cid> = frames.iter().map(|frame| frame.cid).collect();
+    let mut blocks: Vec<(Cid, &[u8])> = Vec::new();
+    if let Some((cid, header)) = previous.filter(|(cid, _)| cids.insert(**cid)) {
+        blocks.push((*cid, header));
+    }
+    for data in append {
+        let cid = raw_cid(data)?;
+        if cids.insert(cid) {
+            blocks.push((cid, data));
+        }
+    }
+    if cids.insert(masl_cid) {
+        blocks.push((masl_cid, &masl));
+    }
+    validate::ensure(map, &cids)?;
+
+    let mut at = f.seek(SeekFrom::End(0))?;
+    let mut appended = BlockIndex::new();
+    let mut out = BufWriter::new(&f);
+    for (cid, data) in &blocks {
+        let cid_bytes = cid.to_bytes();
+        let mut prefix = Vec::new();
+        write_uvarint(&mut prefix, (cid_bytes.len() + data.len()) as u64)?;
+        prefix.extend(&cid_bytes);
+        out.write_all(&prefix)?;
+        out.write_all(data)?;
+        appended.insert(cid.to_string(), (at + prefix.len() as u64, data.len() as u64));
+        at += (prefix.len() + data.len()) as u64;
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_data()?;
+    // Only now that the blocks are on disk does the header name them.
+    write_journal(path, &new_header)?;
+    f.seek(SeekFrom::Start(data_start - header.len() as u64))?;
+    f.write_all(&new_header)?;
+    f.sync_data()?;
+    std::fs::remove_file(journal_path(path))?;
+    Ok(Some(appended))
+}
+
+/// Where the header [`append_in_place`] is about to write is kept until
+/// it has been, beside the tile.
+fn journal_path(path: &Path) -> PathBuf {
+    path.with_extension("tile.journal")
+}
+
+/// Durably record `header` as the one the tile at `path` is about to have.
+/// It's written aside and renamed into place, so a journal is never torn.
+pub(crate) fn write_journal(path: &Path, header: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tile.journal.partial");
+    let mut out = File::create(&tmp)?;
+    out.write_all(header)?;
+    out.sync_all()?;
+    std::fs::rename(&tmp, journal_path(path))?;
+    Ok(())
+}
+
+/// Finish a header update a crash interrupted: write the journalled
+/// header over the tile's own and drop the journal. The blocks it names
+/// were synced before the journal was, so replaying it is always safe, and
+/// replaying it twice does no harm. A journal for a header of another size
+/// is left from some other file and is dropped.
+pub fn replay_journal(path: &Path) -> Result<()> {
+    let journal = journal_path(path);
+    let header = match std::fs::read(&journal) {
+        Ok(header) => header,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut f = File::options().read(true).write(true).open(path)?;
+    let (current, data_start) = read_header(&mut f)?;
+    if current.len() == header.len() && !is_carv2(&f)? {
+        tracing::info!(path = %path.display(), "finishing an interrupted header update");
+        f.seek(SeekFrom::Start(data_start - header.len() as u64))?;
+        f.write_all(&header)?;
+        f.sync_data()?;
+    }
+    std::fs::remove_file(&journal)?;
+    Ok(())
+}
+
+/// Every CID the header's roots and resources link to, variants included,
+/// along with the earlier revisions it links back to and everything they link to, and
+/// the blocks of any chunked dag-pb file among them. `frames` are the
+/// file's blocks, for reading those revisions and files from `f`.
+fn referenced_cids(
+    f: &mut File,
+    map: &[(CborValue, CborValue)],
+    frames: &[BlockFrame],
+) -> Result<HashSet<Cid>> {
+    let mut cids = resource_cids(map);
+    cids.extend(validate::roots(map));
+    let mut next = revisions::previous(map);
+    while let Some(cid) = next.filter(|cid| cids.insert(*cid)) {
+        let Some(frame) = frames.iter().find(|frame| frame.cid == cid) else { break };
+        let mut header = vec![0u8; usize::try_from(frame.len)?];
+        f.seek(SeekFrom::Start(frame.offset))?;
+        f.read_exact(&mut header)?;
+        let CborValue::Map(map) = ciborium::de::from_reader(header.as_slice())
+            .map_err(|e| anyhow!("revision {cid}: CBOR decode error: {e}"))?
+        else {
+            bail!("revision {cid} is not a CBOR map");
+        };
+        cids.extend(resource_cids(&map));
+        next = revisions::previous(&map);
+    }
+    let frames: HashMap<Cid, &BlockFrame> =
+        frames.iter().map(|frame| (frame.cid, frame)).collect();
+    let mut nodes: Vec<Cid> = cids.iter().filter(|cid| cid.codec() == DAG_PB).copied().collect();
+    while let Some(cid) = nodes.pop() {
+        let Some(frame) = frames.get(&cid) else { continue };
+        let mut block = vec![0u8; usize::try_from(frame.len)?];
+        f.seek(SeekFrom::Start(frame.offset))?;
+        f.read_exact(&mut block)?;
+        let node = dagpb::file_node(&block).map_err(|e| anyhow!("{cid}: {e}"))?;
+        for link in node.links {
+            if cids.insert(link) && link.codec() == DAG_PB {
+                nodes.push(link);
+            }
+        }
+    }
+    Ok(cids)
+}
+
+/// Every CID a header's resources link to, precompressed and content-type
+/// variants included.
+fn resource_cids(map: &[(CborValue, CborValue)]) -> HashSet<Cid> {
+    fn entry_cids(entry: &[(CborValue, CborValue)], cids: &mut HashSet<Cid>) {
+        for (key, value) in entry {
+            match (key.as_text(), value) {
+                (Some("variants"), CborValue::Array(variants)) => {
+                    for variant in variants.iter().filter_map(CborValue::as_map) {
+                        entry_cids(variant, cids);
+                    }
+                }
+                (Some(key), CborValue::Tag(42, link)) if is_src_key(key) => {
+                    if let CborValue::Bytes(bytes) = link.as_ref() {
+                        if let Some(cid) = bytes.get(1..).and_then(|b| Cid::try_from(b).ok()) {
+                            cids.insert(cid);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut cids = HashSet::new();
+    let resources = map.iter().find(|(k, _)| k.as_text() == Some("resources"));
+    let Some((_, CborValue::Map(resources))) = resources else { return cids };
+    for entry in resources.iter().filter_map(|(_, entry)| entry.as_map()) {
+        entry_cids(entry, &mut cids);
+    }
+    cids
+}
+
+/// The DAG-CBOR link to `cid`, as stored under `src`.
+pub(crate) fn link(cid: &Cid) -> CborValue {
+    let mut bytes = vec![0x00];
+    bytes.extend(cid.to_bytes());
+    CborValue::Tag(42, Box::new(CborValue::Bytes(bytes)))
+}
+
+/// Set `key` to `value` in a header map, or remove it for `None`, keeping
+/// its position if it was already there.
+fn set_field(map: &mut Vec<(CborValue, CborValue)>, key: &str, value: Option<CborValue>) {
+    let existing = map.iter().position(|(k, _)| k.as_text() == Some(key));
+    match (existing, value) {
+        (Some(i), Some(value)) => map[i].1 = value,
+        (Some(i), None) => {
+            map.remove(i);
+        }
+        (None, Some(value)) => map.push((text(key), value)),
+        (None, None) => {}
+    }
+}
+
+/// [`set_field`] for a field that may be a language map. A map keeps its
+/// other languages: `value` replaces the text of its default entry.
+fn set_text_field(map: &mut Vec<(CborValue, CborValue)>, key: &str, value: Option<&str>) {
+    let existing = map.iter_mut().find_map(|(k, v)| match v {
+        CborValue::Map(entries) if k.as_text() == Some(key) => Some(entries),
+        _ => None,
+    });
+    if let (Some(entries), Some(value)) = (existing, value) {
+        let by_language: Localized = entries
+            .iter()
+            .filter_map(|(tag, text)| Some((tag.as_text()?.into(), text.as_text()?.into())))
+            .collect();
+        if let Some((tag, _)) = default_text(&by_language) {
+            if let Some((_, entry)) = entries.iter_mut().find(|(t, _)| t.as_text() == Some(tag)) {
+                *entry = text(value);
+                return;
+            }
+        }
+    }
+    set_field(map, key, value.map(text));
+}
+
+// ── Edits ────────────────────────────────────────────────────────────────────
+//
+// The changes the editor and the `tile` command line make, on a tile file by
+// path. Each returns what [`rewrite`] does: whether a signature was dropped.
+
+/// Save `edit` into the tile at `path`.
+pub fn edit_metadata(
+    path: &Path,
+    edit: &MaslEdit,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+) -> Result<Saved> {
+    if edit.name.trim().is_empty() {
+        bail!("tile needs a name");
+    }
+    let save = |map: &mut Vec<(CborValue, CborValue)>| {
+        let optional = |v: &Option<String>| v.as_deref().map(text);
+        set_text_field(map, "name", Some(&edit.name));
+        set_text_field(map, "description", edit.description.as_deref());
+        set_field(map, "short_name", optional(&edit.short_name));
+        set_field(map, "theme_color", optional(&edit.theme_color));
+        set_field(map, "background_color", optional(&edit.background_color));
+        let icons = (!edit.icons.is_empty()).then(|| icons_value(&edit.icons));
+        set_field(map, "icons", icons);
+        Ok(())
+    };
+    rewrite(path, save, &[], ExistingBlocks::Keep, revision, signer)
+}
+
+/// Serve `bytes` at `resource` in the tile at `path`, adding the resource or
+/// replacing its body. `content_type` defaults to the current one, or for a
+/// new resource to one guessed as when packing. The old body stays in the
+/// file until [`compact`] drops it, or for good if `revision` keeps the
+/// current header as a revision. Bytes the tile already serves, as another
+/// resource's body or variant, are linked to under the CID they have there
+/// rather than stored again.
+pub fn put_resource(
+    path: &Path,
+    resource: &str,
+    bytes: Vec<u8>,
+    content_type: Option<String>,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+) -> Result<Saved> {
+    let held = if is_compressed(path)? { None } else { held_cid(&parse_tile(path)?, &bytes) };
+    put_held(path, resource, bytes, held, content_type, revision, signer)
+}
+
+/// [`put_resource`], with `held` the CID under which the tile already
+/// serves `bytes`, if it does (see [`held_cid`]).
+fn put_held(
+    path: &Path,
+    resource: &str,
+    bytes: Vec<u8>,
+    held: Option<Cid>,
+    content_type: Option<String>,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+) -> Result<Saved> {
+    if !resource.starts_with('/') {
+        bail!("resource path {resource} must start with /");
+    }
+    let cid = match held {
+        Some(cid) => cid,
+        None => raw_cid(&bytes)?,
+    };
+    let save = |map: &mut Vec<(CborValue, CborValue)>| {
+        let resources = resources_mut(map)?;
+        let existing = resources.iter().position(|(k, _)| k.as_text() == Some(resource));
+        let mut entry = match existing {
+            Some(i) => match resources.remove(i).1 {
+                CborValue::Map(entry) => entry,
+                _ => bail!("resource {resource} is not a map"),
+            },
+            None => Vec::new(),
+        };
+        let current = entry.iter().find(|(k, _)| k.as_text() == Some("content-type"));
+        let content_type = content_type
+            .or_else(|| current.and_then(|(_, v)| v.as_text()).map(str::to_string))
+            .unwrap_or_else(|| pack::detect_type(resource, &bytes).0.to_string());
+        // Precompressed and content-type variants were of the old body, and
+        // a redirect's fields are of what was there before it.
+        let stale = |k: &str| is_src_key(k) || matches!(k, "variants" | "location" | "status");
+        entry.retain(|(k, _)| !k.as_text().is_some_and(stale));
+        set_field(&mut entry, "content-type", Some(text(&content_type)));
+        entry.insert(0, (text("src"), link(&cid)));
+        let at = existing.unwrap_or(resources.len());
+        resources.insert(at, (text(resource), CborValue::Map(entry)));
+        Ok(())
+    };
+    let append = if held.is_some() { &[] } else { std::slice::from_ref(&bytes) };
+    rewrite(path, save, append, ExistingBlocks::Keep, revision, signer)
+}
+
+/// A CID under which `tile` already serves `bytes`, if any. Only blocks of
+/// the same length as `bytes` in its index are read.
+fn held_cid(tile: &TileContent, bytes: &[u8]) -> Option<Cid> {
+    let len = bytes.len() as u64;
+    let srcs = tile.masl.served().flat_map(|(_, resource)| {
+        resource.iter().filter(|(k, _)| is_src_key(k)).map(|(_, cid)| cid)
+    });
+    for cid in srcs {
+        // A block the tile lacks can't be linked to; the bytes are stored.
+        let same = tile.content_len(cid).ok() == Some(len)
+            && tile.read_block(cid).ok().as_deref() == Some(bytes);
+        if same {
+            return cid.parse().ok();
+        }
+    }
+    None
+}
+
+/// Stop serving `resource` from the tile at `path`. Its body stays in the
+/// file until [`compact`] drops it.
+pub fn remove_resource(
+    path: &Path,
+    resource: &str,
+    revision: Option<bool>,
+    signer: Option<&Signer>,
+) -> Result<Saved> {
+    let save = |map: &mut Vec<(CborValue, CborValue)>| {
+        let resources = resources_mut(map)?;
+        let Some(i) = resources.iter().position(|(k, _)| k.as_text() == Some(resource)) else {
+            bail!("no resource at {resource}");
+        };
+        resources.remove(i);
+        Ok(())
+    };
+    rewrite(path, save, &[], ExistingBlocks::Keep, revision, signer)
+}
+
+/// Rewrite the tile at `path` without blocks nothing references any more,
+/// such as bodies [`put_resource`] replaced. Revisions and the blocks they
+/// link to are kept. Calls `on_progress` as it goes through the blocks.
+/// Returns the bytes saved.
+pub fn compact(
+    path: &Path,
+    signer: Option<&Signer>,
+    on_progress: impl FnMut(CompactProgress),
+) -> Result<u64> {
+    let before = std::fs::metadata(path)?.len();
+    let existing = ExistingBlocks::Compact;
+    rewrite_reporting(path, |_| Ok(()), &[], existing, Some(false), signer, on_progress)?;
+    Ok(before.saturating_sub(std::fs::metadata(path)?.len()))
+}
+
+/// Sign the tile at `path` with `signer`, replacing any embedded signature.
+pub fn sign(path: &Path, signer: &Signer) -> Result<Saved> {
+    rewrite(path, |_| Ok(()), &[], ExistingBlocks::Keep, Some(false), Some(signer))
+}
+
+fn resources_mut(
+    map: &mut [(CborValue, CborValue)],
+) -> Result<&mut Vec<(CborValue, CborValue)>> {
+    map.iter_mut()
+        .find_map(|(k, v)| match v {
+            CborValue::Map(resources) if k.as_text() == Some("resources") => Some(resources),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("MASL has no resources map"))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// The open tile `authority`, unless it is a view of a past revision or of
+/// another root, which an edit to the file would not be made to.
+fn editable(state: &TileStore, authority: &str) -> Result<Arc<TileContent>> {
+    let tile = state.get(authority).ok_or_else(|| anyhow!(i18n::t("error.tile-not-loaded")))?;
+    tile.ensure_editable()?;
+    Ok(tile)
+}
+
+/// Save `edit` into the open tile `authority` and reload it in place.
+/// `revision` says whether to keep the current metadata as a revision (see
+/// [`rewrite`]).
+#[tauri::command]
+pub async fn edit_tile_metadata(
+    authority: String,
+    edit: MaslEdit,
+    revision: Option<bool>,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, CommandError> {
+    let tile = editable(&app.state(), &authority)?;
+    let signer = signing::export_signer(&app)?;
+    rewrite_off_thread(authority, tile, app, move |path| {
+        edit_metadata(path, &edit, revision, signer.as_ref())
+    })
+    .await
+}
+
+/// [`put_resource`] on the open tile `authority`, reloading it in place.
+#[tauri::command]
+pub async fn replace_resource(
+    authority: String,
+    path: String,
+    bytes: Vec<u8>,
+    content_type: Option<String>,
+    revision: Option<bool>,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, CommandError> {
+    let tile = editable(&app.state(), &authority)?;
+    let signer = signing::export_signer(&app)?;
+    // The open tile's index finds a body it already holds; parsing the file
+    // again for every put would make a run of edits quadratic.
+    let open = tile.clone();
+    rewrite_off_thread(authority, tile, app, move |file| {
+        let held = held_cid(&open, &bytes);
+        put_held(file, &path, bytes, held, content_type, revision, signer.as_ref())
+    })
+    .await
+}
+
+/// [`remove_resource`] on the open tile `authority`, reloading it in place.
+#[tauri::command]
+pub async fn remove_tile_resource(
+    authority: String,
+    path: String,
+    revision: Option<bool>,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, CommandError> {
+    let tile = editable(&app.state(), &authority)?;
+    let signer = signing::export_signer(&app)?;
+    rewrite_off_thread(authority, tile, app, move |file| {
+        remove_resource(file, &path, revision, signer.as_ref())
+    })
+    .await
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CompactProgressPayload {
+    authority: String,
+    #[serde(flatten)]
+    progress: CompactProgress,
+}
+
+/// [`compact`] the open tile `authority`, emitting `tile:compact-progress`
+/// as it goes, and reload it. Returns the bytes saved.
+#[tauri::command]
+pub async fn compact_tile(authority: String, app: AppHandle) -> Result<u64, CommandError> {
+    let tile = editable(&app.state(), &authority)?;
+    let signer = signing::export_signer(&app)?;
+    let compacted = tauri::async_runtime::spawn_blocking(move || -> Result<u64> {
+        let events = Coalescer::new(&app, "tile:compact-progress", |pending, next| {
+            *pending = next;
+        });
+        let saved = compact(&tile.path, signer.as_ref(), |progress| {
+            events.push(CompactProgressPayload { authority: authority.clone(), progress });
+        })?;
+        events.flush();
+        reload(&authority, &tile, None, &app.state(), &app)?;
+        Ok(saved)
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(compacted?)
+}
+
+/// Sign the open tile `authority` with the keychain signing key, whether or
+/// not signing on export is on, replacing any embedded signature.
+#[tauri::command]
+pub async fn sign_tile(authority: String, app: AppHandle) -> Result<TileOpenedPayload, CommandError> {
+    let tile = editable(&app.state(), &authority)?;
+    let signer = signing::stored_signer(&app)?.ok_or_else(|| anyhow!("no signing key is set up"))?;
+    rewrite_off_thread(authority, tile, app, move |path| sign(path, &signer)).await
+}
+
+/// Run `write`, rewriting the file of the open tile `authority`, and reload
+/// the tile, on the blocking pool rather than the main thread sync commands
+/// run on: both go through the whole CAR.
+async fn rewrite_off_thread(
+    authority: String,
+    tile: Arc<TileContent>,
+    app: AppHandle,
+    write: impl FnOnce(&Path) -> Result<Saved> + Send + 'static,
+) -> Result<TileOpenedPayload, CommandError> {
+    let reloaded = tauri::async_runtime::spawn_blocking(move || {
+        let saved = write(&tile.path)?;
+        reload(&authority, &tile, saved.appended, &app.state(), &app)
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(reloaded?)
+}
+
+/// Swap the rewritten file of `tile` in under `authority`, without the
+/// watcher taking it for an outside change. Blocks `appended` in place are
+/// added to `tile`'s block index instead of the file being indexed again.
+fn reload(
+    authority: &str,
+    tile: &TileContent,
+    appended: Option<BlockIndex>,
+    state: &TileStore,
+    app: &AppHandle,
+) -> Result<TileOpenedPayload> {
+    let path = &tile.path;
+    let extended = appended.zip(tile.index().ok()).map(|(appended, index)| {
+        let mut content = parse_tile_header(path)?;
+        let mut index = index.clone();
+        index.extend(appended);
+        content.set_index(index);
+        anyhow::Ok(content)
+    });
+    let payload = match extended {
+        Some(content) => {
+            content.and_then(|content| swap_in(authority, path, content, None, true, state, app))
+        }
+        None => reload_tile(authority, path, None, true, state, app),
+    };
+    let payload = payload?;
+    if let Some(watcher) = app.try_state::<TileWatcher>() {
+        watcher.rebaseline(path);
+    }
+    Ok(payload)
+}
@@ -0,0 +1,110 @@
+use crate::validate::ValidationError;
+use serde::Serialize;
+
+// ── Errors ───────────────────────────────────────────────────────────────────
+//
+// Internally errors are `anyhow`, with context added as they travel. Those a
+// caller may want to act on are raised as a [`TileError`] so they can be
+// told apart wherever they end up: the protocol handler downcasts to pick a
+// status, and commands that open tiles return a [`CommandError`], whose
+// `code` the frontend can switch on while `message` stays the text shown.
+// Commands that edit open tiles return one too, so a view that can't be
+// edited is told apart from a failed write.
+//
+// Codes are stable strings; new failures get a variant of their own rather
+// than a new meaning for an old code.
+
+/// A failure reading a tile that callers tell apart.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TileError {
+    /// The file isn't a CAR: its header or block frames don't decode.
+    #[error("{0}")]
+    NotCar(String),
+    /// The CAR decodes but its header isn't a MASL.
+    #[error("{0}")]
+    InvalidMasl(String),
+    /// The MASL has no `name`.
+    #[error("MASL missing `name` field")]
+    MissingName,
+    /// A block the tile refers to isn't in the CAR.
+    #[error("{0}")]
+    MissingBlock(String),
+    /// A block read with verification on doesn't hash to its CID: the file
+    /// is damaged, rather than missing the block.
+    #[error("block {0} doesn't match its CID; the tile is damaged")]
+    CorruptBlock(String),
+    /// The tile is over one of the limits on what parsing may hold.
+    #[error("{0}")]
+    TooLarge(String),
+    /// A dag-pb file's blocks don't add up to the size its nodes declare.
+    #[error("{0}")]
+    MalformedDag(String),
+    /// Reading the file failed.
+    #[error("{0}")]
+    Io(String),
+    /// The tile fails validation, and the `strict_validation` setting
+    /// refuses to open it.
+    #[error("{0}")]
+    Invalid(String),
+    /// An edit was asked of a view of the tile, a past revision or another
+    /// root, rather than of the file as it is.
+    #[error("{0}")]
+    ReadOnlyView(String),
+}
+
+impl TileError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TileError::NotCar(_) => "not-a-car",
+            TileError::InvalidMasl(_) => "invalid-masl",
+            TileError::MissingName => "missing-name",
+            TileError::MissingBlock(_) => "missing-block",
+            TileError::CorruptBlock(_) => "corrupt-block",
+            TileError::TooLarge(_) => "too-large",
+            TileError::MalformedDag(_) => "malformed-dag",
+            TileError::Io(_) => "io",
+            TileError::Invalid(_) => "invalid-tile",
+            TileError::ReadOnlyView(_) => "read-only-view",
+        }
+    }
+
+    /// `e` as a `TileError` that can be kept and raised again, as a failed
+    /// block index is: the first in its chain, else `Io` for a failed read
+    /// and `NotCar` for anything else, which is the CAR not decoding.
+    pub(crate) fn kept(e: anyhow::Error) -> TileError {
+        if let Some(kept) = e.chain().find_map(|c| c.downcast_ref::<TileError>()) {
+            return kept.clone();
+        }
+        if e.chain().any(|c| c.is::<std::io::Error>()) {
+            TileError::Io(format!("{e:#}"))
+        } else {
+            TileError::NotCar(format!("{e:#}"))
+        }
+    }
+}
+
+/// The code for `e`: that of the first [`TileError`] in its chain, else
+/// `invalid-tile` for a write refused by validation, `io` for a failure to
+/// read or write, or `other`.
+pub fn code(e: &anyhow::Error) -> &'static str {
+    let find = |f: fn(&(dyn std::error::Error + 'static)) -> Option<&'static str>| {
+        e.chain().find_map(f)
+    };
+    find(|c| c.downcast_ref::<TileError>().map(TileError::code))
+        .or_else(|| find(|c| c.is::<ValidationError>().then_some("invalid-tile")))
+        .or_else(|| find(|c| c.is::<std::io::Error>().then_some("io")))
+        .unwrap_or("other")
+}
+
+/// An error as a command that opens tiles returns it to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(e: anyhow::Error) -> Self {
+        CommandError { code: code(&e), message: e.to_string() }
+    }
+}
@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+// ── Coalesced events ─────────────────────────────────────────────────────────
+//
+// Bulk operations (batch opens, verification) can produce progress far faster
+// than the webview can usefully repaint. A `Coalescer` emits its event at
+// most once per `EMIT_INTERVAL`, folding whatever arrived in between into the
+// pending payload with a caller-supplied merge. The last payload is always
+// delivered: on `flush`, or when the coalescer is dropped.
+
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct Coalescer<R: Runtime, T: Serialize + Clone> {
+    app: AppHandle<R>,
+    event: &'static str,
+    merge: fn(&mut T, T),
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    pending: Option<T>,
+    last_emit: Option<Instant>,
+}
+
+impl<R: Runtime, T: Serialize + Clone> Coalescer<R, T> {
+    pub fn new(app: &AppHandle<R>, event: &'static str, merge: fn(&mut T, T)) -> Self {
+        Coalescer {
+            app: app.clone(),
+            event,
+            merge,
+            state: Mutex::new(State { pending: None, last_emit: None }),
+        }
+    }
+
+    /// Queue `payload`, emitting now if the interval has passed.
+    pub fn push(&self, payload: T) {
+        let mut state = self.state.lock().unwrap();
+        match state.pending.as_mut() {
+            Some(pending) => (self.merge)(pending, payload),
+            None => state.pending = Some(payload),
+        }
+        if state.last_emit.is_none_or(|t| t.elapsed() >= EMIT_INTERVAL) {
+            self.emit(&mut state);
+        }
+    }
+
+    /// Emit whatever is pending now.
+    pub fn flush(&self) {
+        self.emit(&mut self.state.lock().unwrap());
+    }
+
+    fn emit(&self, state: &mut State<T>) {
+        if let Some(payload) = state.pending.take() {
+            let _ = self.app.emit(self.event, payload);
+            state.last_emit = Some(Instant::now());
+        }
+    }
+}
+
+impl<R: Runtime, T: Serialize + Clone> Drop for Coalescer<R, T> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
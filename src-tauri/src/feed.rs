@@ -0,0 +1,173 @@
+use crate::library::{library_dir, scan, LibraryEntry};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use url::Url;
+
+// ── Feed export ──────────────────────────────────────────────────────────────
+//
+// Builds a JSON Feed 1.1 or Atom document listing the tiles in a folder. Item
+// links are `file://` URLs unless a `base_url` is given, in which case they
+// point at `<base_url>/<file name>` — the usual case when the folder is synced
+// to a web server.
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    Json,
+    Atom,
+}
+
+struct FeedItem {
+    entry: LibraryEntry,
+    link: String,
+    updated: String,
+}
+
+pub fn build_feed(
+    dir: &Path,
+    title: &str,
+    base_url: Option<&str>,
+    format: FeedFormat,
+) -> Result<String> {
+    let base = base_url.map(Url::parse).transpose()?;
+    let mut items = Vec::new();
+    for entry in scan(dir)? {
+        let path = PathBuf::from(&entry.path);
+        let link = match &base {
+            Some(base) => {
+                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                base.join(file_name)?.to_string()
+            }
+            None => Url::from_file_path(&path)
+                .map_err(|_| anyhow!("cannot make a URL for {}", path.display()))?
+                .to_string(),
+        };
+        let modified = std::fs::metadata(&path)?.modified()?;
+        items.push(FeedItem { entry, link, updated: rfc3339(modified) });
+    }
+
+    Ok(match format {
+        FeedFormat::Json => json_feed(title, &items)?,
+        FeedFormat::Atom => atom_feed(title, base_url, &items),
+    })
+}
+
+fn json_feed(title: &str, items: &[FeedItem]) -> Result<String> {
+    let items: Vec<_> = items
+        .iter()
+        .map(|item| {
+            let mut obj = json!({
+                "id": item.link,
+                "url": item.link,
+                "title": item.entry.name,
+                "date_modified": item.updated,
+                "content_text": item.entry.description.clone().unwrap_or_default(),
+                "attachments": [{ "url": item.link, "mime_type": "application/tile" }],
+            });
+            if let Some(desc) = &item.entry.description {
+                obj["summary"] = json!(desc);
+            }
+            obj
+        })
+        .collect();
+    let feed = json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": title,
+        "items": items,
+    });
+    Ok(serde_json::to_string_pretty(&feed)?)
+}
+
+fn atom_feed(title: &str, base_url: Option<&str>, items: &[FeedItem]) -> String {
+    let updated = items
+        .iter()
+        .map(|i| i.updated.as_str())
+        .max()
+        .map(str::to_string)
+        .unwrap_or_else(|| rfc3339(SystemTime::now()));
+    let id = base_url.unwrap_or("urn:tile-documents:library");
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    out.push_str(&format!("  <title>{}</title>\n", xml_escape(title)));
+    out.push_str(&format!("  <id>{}</id>\n", xml_escape(id)));
+    out.push_str(&format!("  <updated>{updated}</updated>\n"));
+    out.push_str("  <author><name>Tile Documents</name></author>\n");
+    for item in items {
+        let link = xml_escape(&item.link);
+        out.push_str("  <entry>\n");
+        out.push_str(&format!("    <title>{}</title>\n", xml_escape(&item.entry.name)));
+        out.push_str(&format!("    <id>{link}</id>\n"));
+        out.push_str(&format!(
+            "    <link rel=\"enclosure\" type=\"application/tile\" href=\"{link}\"/>\n"
+        ));
+        out.push_str(&format!("    <updated>{}</updated>\n", item.updated));
+        if let Some(desc) = &item.entry.description {
+            out.push_str(&format!("    <summary>{}</summary>\n", xml_escape(desc)));
+        }
+        out.push_str("  </entry>\n");
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format a timestamp as an RFC 3339 UTC date-time (`2024-05-01T12:00:00Z`).
+pub(crate) fn rfc3339(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days (Howard Hinnant), valid for all dates after 1970.
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Generate a feed for `folder` (default: the library) and write it to
+/// `dest`.
+#[tauri::command]
+pub async fn export_library_feed(
+    dest: String,
+    format: FeedFormat,
+    folder: Option<String>,
+    title: Option<String>,
+    base_url: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<()> {
+        let dir = match folder {
+            Some(f) => PathBuf::from(f),
+            None => library_dir(&app)?,
+        };
+        let title = title.as_deref().unwrap_or("Tile Library");
+        let feed = build_feed(&dir, title, base_url.as_deref(), format)?;
+        std::fs::write(dest, feed)?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
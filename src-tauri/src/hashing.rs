@@ -0,0 +1,89 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256, Sha512};
+use std::fs::File;
+use std::path::Path;
+
+// ── Content hashes ───────────────────────────────────────────────────────────
+
+/// Hex SHA-256 of a whole file, streamed rather than read into memory.
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut f = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut f, &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Lowercase hex encoding, used for content hashes in file names and stores.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decode lowercase or uppercase hex. `None` on odd length or a non-hex digit.
+pub fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// ── Multihash ────────────────────────────────────────────────────────────────
+
+/// Multihash code of the identity "hash": the digest is the data itself.
+pub const IDENTITY: u64 = 0x00;
+/// Multihash code of SHA-256.
+pub const SHA2_256: u64 = 0x12;
+/// Multihash code of SHA-512.
+pub const SHA2_512: u64 = 0x13;
+/// Multihash code of BLAKE3 with its default 32-byte output.
+pub const BLAKE3: u64 = 0x1e;
+
+/// Incremental hasher for the multihash functions blocks may use.
+pub enum MultiHasher {
+    Identity(Vec<u8>),
+    Sha2_256(Sha256),
+    Sha2_512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl MultiHasher {
+    /// A hasher for multihash `code`, or `None` if it isn't supported.
+    pub fn new(code: u64) -> Option<Self> {
+        match code {
+            IDENTITY => Some(MultiHasher::Identity(Vec::new())),
+            SHA2_256 => Some(MultiHasher::Sha2_256(Sha256::new())),
+            SHA2_512 => Some(MultiHasher::Sha2_512(Sha512::new())),
+            BLAKE3 => Some(MultiHasher::Blake3(Box::default())),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            MultiHasher::Identity(bytes) => bytes.extend_from_slice(data),
+            MultiHasher::Sha2_256(h) => h.update(data),
+            MultiHasher::Sha2_512(h) => h.update(data),
+            MultiHasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            MultiHasher::Identity(bytes) => bytes,
+            MultiHasher::Sha2_256(h) => h.finalize().to_vec(),
+            MultiHasher::Sha2_512(h) => h.finalize().to_vec(),
+            MultiHasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Digest of `data` under multihash `code`, if supported.
+pub fn multihash_digest(code: u64, data: &[u8]) -> Option<Vec<u8>> {
+    let mut hasher = MultiHasher::new(code)?;
+    hasher.update(data);
+    Some(hasher.finalize())
+}
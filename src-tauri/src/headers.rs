@@ -0,0 +1,108 @@
+use crate::car::{is_src_key, Resource, TileContent};
+use crate::settings::SettingsStore;
+use tauri::http::header::{HeaderName, HeaderValue};
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Forwarded resource headers ───────────────────────────────────────────────
+//
+// MASL resource entries carry HTTP headers as siblings of `src`. The manifest
+// is untrusted input, so only a safelist of representation and caching
+// headers is forwarded, never anything that affects CORS, cookies, framing of
+// the response, or redirects, and only with values that are valid header
+// values (which rules out CR/LF injection).
+//
+// A tile that needs other headers (`x-*` and the like) can declare the
+// `extra-headers` capability in its MASL `permissions`; if the user has also
+// enabled `forward_extra_headers`, anything not on the deny list is passed
+// through. Whatever is dropped shows up in the tile's lint warnings.
+
+/// Headers a MASL resource entry may set, besides `content-type`.
+const SAFE_HEADERS: [&str; 8] = [
+    "cache-control",
+    "content-language",
+    "content-security-policy",
+    "etag",
+    "expires",
+    "last-modified",
+    "permissions-policy",
+    "referrer-policy",
+];
+
+/// MASL capability asking for headers beyond the safelist.
+pub const EXTRA_HEADERS: &str = "extra-headers";
+
+/// Never forwarded, whatever the policy: these are set by the protocol
+/// handler, or control CORS, cookies, connection handling, or redirects.
+const DENIED_HEADERS: [&str; 15] = [
+    "accept-ranges",
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-range",
+    "content-type",
+    "keep-alive",
+    "location",
+    "refresh",
+    "service-worker-allowed",
+    "set-cookie",
+    "set-cookie2",
+    "transfer-encoding",
+    "upgrade",
+    "vary",
+];
+
+/// Fallback when a resource's type is missing or malformed.
+const OCTET_STREAM: HeaderValue = HeaderValue::from_static("application/octet-stream");
+
+/// The resource's `content-type`, if it is a valid header value.
+pub fn content_type(resource: &Resource) -> HeaderValue {
+    resource
+        .get("content-type")
+        .and_then(|v| HeaderValue::from_str(v).ok())
+        .unwrap_or(OCTET_STREAM)
+}
+
+/// Whether `tile` may forward headers beyond the safelist.
+pub fn allow_extra<R: Runtime>(app: &AppHandle<R>, tile: &TileContent) -> bool {
+    let enabled = app.try_state::<SettingsStore>().is_some_and(|s| s.get().forward_extra_headers);
+    enabled && tile.masl.permissions.iter().any(|p| p == EXTRA_HEADERS)
+}
+
+/// The entry's other headers that are safe to send, validated.
+pub fn forwarded(
+    resource: &Resource,
+    allow_extra: bool,
+) -> impl Iterator<Item = (HeaderName, HeaderValue)> + '_ {
+    header_entries(resource).filter_map(move |(k, v)| check(k, v, allow_extra).ok())
+}
+
+/// The entry's headers that won't be sent, with why.
+pub fn dropped(resource: &Resource, allow_extra: bool) -> Vec<(String, &'static str)> {
+    header_entries(resource)
+        .filter_map(|(k, v)| Some((k.clone(), check(k, v, allow_extra).err()?)))
+        .collect()
+}
+
+fn header_entries(resource: &Resource) -> impl Iterator<Item = (&String, &String)> {
+    resource.iter().filter(|(k, _)| !is_src_key(k) && *k != "content-type")
+}
+
+fn check(
+    name: &str,
+    value: &str,
+    allow_extra: bool,
+) -> Result<(HeaderName, HeaderValue), &'static str> {
+    let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| "invalid header name")?;
+    let value = HeaderValue::from_str(value).map_err(|_| "invalid header value")?;
+    let n = name.as_str();
+    if SAFE_HEADERS.contains(&n) {
+        return Ok((name, value));
+    }
+    if DENIED_HEADERS.contains(&n) || n.starts_with("access-control-") {
+        return Err("never forwarded");
+    }
+    if !allow_extra {
+        return Err("not on the safelist; needs the extra-headers capability and setting");
+    }
+    Ok((name, value))
+}
@@ -0,0 +1,107 @@
+use crate::markdown::fill;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+// ── Localisation ─────────────────────────────────────────────────────────────
+//
+// Text the backend shows the user itself (menus, native dialogs, and the
+// errors commands return to the frontend) is looked up by key in the
+// translation tables under `locales/`, one flat JSON object per language
+// with `{{name}}` placeholders. The language is the `language` setting, or
+// the system's when that isn't set. Anything a table lacks falls back to
+// English, and a key missing from English too is shown as itself. Logs and
+// anything written into tiles stay in English.
+//
+// Tiles may give their own text by language (see `car::Localized`), which is
+// matched against the full tag the user asked for rather than the table
+// picked for it, so `fr-CA` text is found even though `fr` serves the app.
+
+const TABLES: [(&str, &str); 4] = [
+    ("en", include_str!("../locales/en.json")),
+    ("fr", include_str!("../locales/fr.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+];
+
+static PARSED: LazyLock<HashMap<&'static str, HashMap<String, String>>> = LazyLock::new(|| {
+    TABLES
+        .iter()
+        .map(|(language, json)| {
+            let table = serde_json::from_str(json).unwrap_or_else(|e| {
+                tracing::warn!(language, "translation table doesn't parse: {e}");
+                HashMap::new()
+            });
+            (*language, table)
+        })
+        .collect()
+});
+
+static CURRENT: RwLock<&str> = RwLock::new("en");
+/// The tag the language setting or the system gives, before it is cut down
+/// to a table.
+static LOCALE: RwLock<String> = RwLock::new(String::new());
+
+/// Switch to `language` (a BCP 47 tag such as `fr-CA`), or to the system's
+/// when `None`. A language without a table means English. Returns whether
+/// anything changed, so menus know to be rebuilt.
+pub fn set_language(language: Option<&str>) -> bool {
+    let wanted = language.map(str::to_string).or_else(sys_locale::get_locale);
+    let primary = wanted
+        .as_deref()
+        .and_then(|tag| tag.split(['-', '_', '.']).next())
+        .map(str::to_ascii_lowercase);
+    let found = TABLES.iter().find(|(code, _)| Some(*code) == primary.as_deref());
+    let code = found.map_or("en", |(code, _)| *code);
+    let locale = wanted.as_deref().and_then(|tag| tag.split('.').next()).unwrap_or(code);
+    *LOCALE.write().unwrap() = locale.to_string();
+    let mut current = CURRENT.write().unwrap();
+    let changed = *current != code;
+    *current = code;
+    changed
+}
+
+/// The text for `key` in the current language.
+pub fn t(key: &str) -> String {
+    tf(key, &[])
+}
+
+/// The text for `key` with its `{{name}}` placeholders filled from `values`.
+pub fn tf(key: &str, values: &[(&str, &str)]) -> String {
+    let language = *CURRENT.read().unwrap();
+    let text = [language, "en"]
+        .iter()
+        .find_map(|code| PARSED.get(code)?.get(key))
+        .map_or(key, String::as_str);
+    fill(text, values)
+}
+
+/// The user's locale as a BCP 47 tag, e.g. `fr-CA`.
+pub fn locale() -> String {
+    let locale = LOCALE.read().unwrap();
+    if locale.is_empty() {
+        "en".to_string()
+    } else {
+        locale.clone()
+    }
+}
+
+/// The tag among `tags` that best matches `locale`: the most specific of
+/// `locale` and the tags it narrows (`de-CH-1996`, `de-CH`, `de`) that is
+/// there, else any of the same language (`de-AT`). Tags compare without
+/// regard to case, and `_` counts as `-`.
+pub fn best_match<'a>(tags: &[&'a str], locale: &str) -> Option<&'a str> {
+    let normal = |tag: &str| tag.replace('_', "-").to_ascii_lowercase();
+    let tags: Vec<(String, &str)> = tags.iter().map(|tag| (normal(tag), *tag)).collect();
+    let locale = normal(locale);
+    let mut wanted = locale.as_str();
+    loop {
+        if let Some((_, tag)) = tags.iter().find(|(normal, _)| normal == wanted) {
+            return Some(tag);
+        }
+        match wanted.rfind('-') {
+            Some(at) => wanted = &wanted[..at],
+            None => break,
+        }
+    }
+    tags.iter().find(|(normal, _)| normal.split('-').next() == Some(wanted)).map(|(_, tag)| *tag)
+}
@@ -0,0 +1,178 @@
+use crate::builder::TileBuilder;
+use crate::car::Icon;
+use anyhow::{anyhow, bail, Result};
+
+// ── Icon generation ──────────────────────────────────────────────────────────
+//
+// Packing can derive a tile's icons from one large image instead of making
+// the author prepare every size. A PNG source is scaled down to each
+// standard size it is at least as big as, centred on a transparent square
+// if it isn't square, and written as PNG resources under `/icons/`. Scaling
+// averages each output pixel's exact footprint in the source, with alpha
+// premultiplied so transparent edges don't darken. It never scales up. An SVG
+// source is used as it is, with `sizes: "any"`. Other formats are refused,
+// since there is no decoder for them here.
+
+/// Square sizes generated: favicons, then the launcher sizes manifests use.
+const ICON_SIZES: [u32; 6] = [16, 32, 48, 128, 192, 512];
+
+/// Largest source accepted, per side, to bound memory.
+const MAX_SOURCE_SIDE: u32 = 8192;
+
+/// Turn `source` into icon resources in `builder` and return the icons, from
+/// largest to smallest. `source_path` is only used to tell an SVG apart and
+/// name it.
+pub fn add_generated_icons(
+    builder: &mut TileBuilder,
+    source: &[u8],
+    source_path: &str,
+) -> Result<Vec<Icon>> {
+    if source_path.to_ascii_lowercase().ends_with(".svg") {
+        let src = "/icons/icon.svg";
+        builder.add_resource(src, source.to_vec(), "image/svg+xml");
+        return Ok(vec![Icon { src: src.into(), sizes: "any".into(), purpose: String::new() }]);
+    }
+    let image = decode_png(source)?;
+    let side = image.width.max(image.height);
+    let mut sizes: Vec<u32> = ICON_SIZES.iter().copied().filter(|&s| s <= side).collect();
+    if sizes.is_empty() {
+        sizes.push(side);
+    }
+    let square = image.centred_square();
+    let mut icons = Vec::new();
+    for &size in sizes.iter().rev() {
+        let scaled = square.resize(size);
+        let src = format!("/icons/icon-{size}.png");
+        builder.add_resource(&src, scaled.encode_png()?, "image/png");
+        icons.push(Icon { src, sizes: format!("{size}x{size}"), purpose: String::new() });
+    }
+    Ok(icons)
+}
+
+/// Premultiplied RGBA, one `f32` per channel.
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Image> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader =
+        decoder.read_info().map_err(|e| anyhow!("icon source must be a PNG or SVG: {e}"))?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    if width > MAX_SOURCE_SIDE || height > MAX_SOURCE_SIDE {
+        bail!("icon source is {width}x{height}, over the {MAX_SOURCE_SIDE}px limit");
+    }
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf)?;
+    let channels = match frame.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => bail!("palette PNG was not expanded"),
+    };
+    let pixels = buf[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|p| {
+            let (rgb, a) = match p {
+                [g] => ([*g; 3], 255),
+                [g, a] => ([*g; 3], *a),
+                [r, g, b] => ([*r, *g, *b], 255),
+                [r, g, b, a] => ([*r, *g, *b], *a),
+                _ => unreachable!(),
+            };
+            let a = a as f32 / 255.0;
+            [rgb[0] as f32 / 255.0 * a, rgb[1] as f32 / 255.0 * a, rgb[2] as f32 / 255.0 * a, a]
+        })
+        .collect();
+    Ok(Image { width, height, pixels })
+}
+
+impl Image {
+    /// The image centred on a transparent square canvas.
+    fn centred_square(&self) -> Image {
+        let side = self.width.max(self.height);
+        let (dx, dy) = ((side - self.width) / 2, (side - self.height) / 2);
+        let mut pixels = vec![[0.0; 4]; (side * side) as usize];
+        for y in 0..self.height {
+            let from = (y * self.width) as usize;
+            let to = ((y + dy) * side + dx) as usize;
+            pixels[to..to + self.width as usize]
+                .copy_from_slice(&self.pixels[from..from + self.width as usize]);
+        }
+        Image { width: side, height: side, pixels }
+    }
+
+    /// A `size`×`size` copy of this square image, averaging each output
+    /// pixel's footprint: rows first, then columns.
+    fn resize(&self, size: u32) -> Image {
+        let (w, h, s) = (self.width as usize, self.height as usize, size as usize);
+        let across = weights(w, s);
+        let mut rows = vec![[0.0; 4]; s * h];
+        for y in 0..h {
+            for (x, taps) in across.iter().enumerate() {
+                rows[y * s + x] = blend(taps.iter().map(|&(i, wt)| (self.pixels[y * w + i], wt)));
+            }
+        }
+        let down = weights(h, s);
+        let mut pixels = vec![[0.0; 4]; s * s];
+        for (y, taps) in down.iter().enumerate() {
+            for x in 0..s {
+                pixels[y * s + x] = blend(taps.iter().map(|&(i, wt)| (rows[i * s + x], wt)));
+            }
+        }
+        Image { width: size, height: size, pixels }
+    }
+
+    fn encode_png(&self) -> Result<Vec<u8>> {
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|&[r, g, b, a]| {
+                let un = |c: f32| if a > 0.0 { c / a } else { 0.0 };
+                [un(r), un(g), un(b), a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect();
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Best);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(out)
+    }
+}
+
+/// For each of `to` output positions, the input positions among `from`
+/// that it covers and how much of each, summing to 1.
+fn weights(from: usize, to: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = from as f64 / to as f64;
+    (0..to)
+        .map(|o| {
+            let (start, end) = (o as f64 * scale, (o + 1) as f64 * scale);
+            let first = start.floor() as usize;
+            let last = (end.ceil() as usize).min(from);
+            (first..last)
+                .map(|i| {
+                    let cover = end.min(i as f64 + 1.0) - start.max(i as f64);
+                    (i, (cover / scale) as f32)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn blend(taps: impl Iterator<Item = ([f32; 4], f32)>) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    for (pixel, wt) in taps {
+        for c in 0..4 {
+            out[c] += pixel[c] * wt;
+        }
+    }
+    out
+}
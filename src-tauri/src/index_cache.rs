@@ -0,0 +1,126 @@
+use crate::car::{FileStamp, ParsedParts, TileContent};
+use crate::hashing::file_sha256;
+use crate::settings::SettingsStore;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Parsed-tile cache ────────────────────────────────────────────────────────
+//
+// Recently opened tiles have their MASL and block index saved under the app
+// cache directory, one `<sha256>.json` per distinct file content, so tiles
+// restored at launch come back without rescanning their CARs. A small
+// `paths.json` maps each file path to the size/mtime it had when hashed, so a
+// lookup only needs a `stat` rather than re-hashing the whole file.
+//
+// Entries live under `parsed/v<SCHEMA>`, so a build that stores parts of
+// another shape never reads those an older one left; those are removed the
+// first time this one stores.
+
+/// Version of the stored [`ParsedParts`]. Bump it whenever they change.
+const SCHEMA: u32 = 1;
+
+/// Serialises read-modify-write cycles of `paths.json`.
+static PATHS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PathEntry {
+    path: PathBuf,
+    stamp: FileStamp,
+    hash: String,
+}
+
+/// `<app cache>/parsed/v<SCHEMA>`, created on first use.
+fn cache_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    let dir = app.path().app_cache_dir()?.join("parsed").join(format!("v{SCHEMA}"));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Remove whatever beside `dir` another schema left.
+fn remove_stale(dir: &Path) {
+    let Some(entries) = dir.parent().and_then(|parent| std::fs::read_dir(parent).ok()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path == dir {
+            continue;
+        }
+        let _ = match entry.file_type() {
+            Ok(kind) if kind.is_dir() => std::fs::remove_dir_all(&path),
+            _ => std::fs::remove_file(&path),
+        };
+    }
+}
+
+/// Path entries, least recently stored first.
+fn read_paths(dir: &Path) -> Vec<PathEntry> {
+    std::fs::read(dir.join("paths.json"))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// The cached parse of `path`, if one exists and the file hasn't changed
+/// since it was stored.
+pub fn load<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Option<TileContent> {
+    let dir = cache_dir(app).ok()?;
+    let canonical = std::fs::canonicalize(path).ok()?;
+    let stamp = FileStamp::of(&canonical).ok()?;
+    let hash = read_paths(&dir)
+        .into_iter()
+        .find(|e| e.path == canonical && e.stamp == stamp)?
+        .hash;
+    let bytes = std::fs::read(dir.join(format!("{hash}.json"))).ok()?;
+    let parts: ParsedParts = serde_json::from_slice(&bytes).ok()?;
+    TileContent::from_parts(path, parts).ok()
+}
+
+/// Save `tile` to the cache in the background, once its index is built.
+pub fn store<R: Runtime>(app: &AppHandle<R>, tile: Arc<TileContent>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = try_store(&app, &tile) {
+            tracing::warn!("caching parse of {} failed: {e}", tile.path.display());
+        }
+    });
+}
+
+fn try_store<R: Runtime>(app: &AppHandle<R>, tile: &TileContent) -> Result<()> {
+    let dir = cache_dir(app)?;
+    let path = std::fs::canonicalize(&tile.path)?;
+    let stamp = FileStamp::of(&path)?;
+    let hash = file_sha256(&path)?;
+    let parts = tile.to_parts()?;
+    if FileStamp::of(&path)? != stamp {
+        // Changed while we were hashing; whatever reloads it will store it.
+        return Ok(());
+    }
+    std::fs::write(dir.join(format!("{hash}.json")), serde_json::to_vec(&parts)?)?;
+
+    let _guard = PATHS_LOCK.lock().unwrap();
+    if !dir.join("paths.json").exists() {
+        remove_stale(&dir);
+    }
+    let (mut dropped, mut paths): (Vec<_>, Vec<_>) =
+        read_paths(&dir).into_iter().partition(|e| e.path == path);
+    paths.push(PathEntry { path, stamp, hash });
+    // The `parse_cache_entries` most recent paths are kept.
+    let keep = app.try_state::<SettingsStore>().map(|s| s.get()).unwrap_or_default();
+    let excess = paths.len().saturating_sub(keep.parse_cache_entries);
+    dropped.extend(paths.drain(..excess));
+
+    // Remove entry files that no remaining path refers to.
+    let live: HashSet<&str> = paths.iter().map(|e| e.hash.as_str()).collect();
+    for entry in &dropped {
+        if !live.contains(entry.hash.as_str()) {
+            let _ = std::fs::remove_file(dir.join(format!("{}.json", entry.hash)));
+        }
+    }
+    std::fs::write(dir.join("paths.json"), serde_json::to_vec(&paths)?)?;
+    Ok(())
+}
@@ -1,61 +1,543 @@
-mod car;
+mod accessibility;
+mod audit;
+mod automation;
+pub mod builder;
+mod cache;
+pub mod car;
+mod catalog;
+pub mod cli;
+mod compress;
+pub mod dagcbor;
+mod dagpb;
+mod dev;
+mod diagnostics;
+mod did;
+mod diff;
+mod directory;
+mod downloads;
+mod edit;
+pub mod error;
+mod events;
+mod feed;
+mod hashing;
+mod headers;
+mod i18n;
+mod icons;
+mod index_cache;
+mod library;
+mod lint;
+mod logging;
+mod manifest;
+mod markdown;
+mod merge;
+mod metadata;
+mod mmap;
+mod navigation;
+mod net;
+mod optimize;
+mod pack;
+mod permissions;
+mod prefetch;
+mod private;
+mod ratelimit;
+mod revisions;
+mod revocation;
+pub mod routes;
+mod sandbox;
+mod session;
+mod settings;
+mod sidecar;
+mod signing;
+mod transform;
+mod trust;
+#[cfg(feature = "test-support")]
+#[doc(hidden)]
+pub mod testing;
+mod update;
+mod validate;
+mod verify;
+mod watch;
+mod wizard;
 
-use car::{authority_from_path, parse_tile, Masl, TileContent};
-use serde::{Deserialize, Serialize};
+use audit::AuditKind;
+use bytes::Bytes;
+use cache::BlockCache;
+use car::{authority_from_path, parse_tile, parse_tile_header, TileContent};
+use error::{CommandError, TileError};
+use events::Coalescer;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use settings::{Settings, SettingsStore, Verification};
+use signing::{PublisherStore, SignatureStatus};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
-use tauri::{AppHandle, Emitter, Listener, Manager, State};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::http::HeaderValue;
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_window_state::{Builder as WindowStateBuilder, StateFlags, WindowExt};
+use update::UpdateStore;
+use verify::{integrity_report, Integrity, IntegrityReport};
 
 // ── Shared state ─────────────────────────────────────────────────────────────
 
-/// Authority string → loaded tile content.
-struct TileStore(Mutex<HashMap<String, TileContent>>);
+/// Opens requested before the frontend is listening (CLI args, early
+/// file-open events). `Some(queue)` until `frontend_ready`, then `None`.
+struct PendingOpens(Mutex<Option<Vec<PathBuf>>>);
+
+/// Authority string → loaded tile content. Protocol requests only read the
+/// map, so an `RwLock` lets a page's parallel asset loads proceed together.
+struct TileStore(RwLock<HashMap<String, Arc<TileContent>>>);
+
+impl TileStore {
+    /// Clone out the tile for `authority` so callers can read blocks without
+    /// holding the store lock.
+    fn get(&self, authority: &str) -> Option<Arc<TileContent>> {
+        self.0.read().unwrap().get(authority).cloned()
+    }
+}
 
 // ── Frontend-facing types ────────────────────────────────────────────────────
 
 /// Sent to the frontend when a tile is opened (via command or file-open event).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The MASL is the pre-serialised copy held by the tile, not a fresh clone.
+#[derive(Debug, Clone, Serialize)]
 pub struct TileOpenedPayload {
     pub authority: String,
-    pub masl: Masl,
+    pub masl: Arc<RawValue>,
+    /// Present when the tile was opened in verified mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<IntegrityReport>,
+    /// Present when the tile is signed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureStatus>,
+    /// Opened in safe mode: served with scripts disabled.
+    pub safe_mode: bool,
+    /// Opened in a private session.
+    pub private: bool,
+    /// Resources holding executable or installer content.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dangerous: Vec<String>,
+    /// Roots of a CAR that lists several, any of which `open_tile` can be
+    /// asked to show by its `root` argument.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<String>,
+    /// The root block the MASL shown was read from, when it wasn't the CAR
+    /// header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+}
+
+impl TileOpenedPayload {
+    fn new(authority: &str, content: &TileContent) -> Self {
+        TileOpenedPayload {
+            authority: authority.to_string(),
+            masl: content.masl_json.clone(),
+            integrity: None,
+            signature: None,
+            safe_mode: false,
+            private: false,
+            dangerous: lint::dangerous_resources(content),
+            roots: if content.roots.len() > 1 { content.roots.clone() } else { Vec::new() },
+            root: content.root.clone(),
+        }
+    }
+}
+
+/// Emitted as `tile:fullscreen-changed` when a window enters or leaves
+/// fullscreen. Every window hears it, so each checks `window` against its
+/// own label.
+#[derive(Debug, Clone, Serialize)]
+struct FullscreenChanged {
+    window: String,
+    fullscreen: bool,
 }
 
 // ── Commands ─────────────────────────────────────────────────────────────────
 
 /// Open a `.tile` file at the given path, load it into the store, and return
 /// the tile info. The frontend should then navigate to `tile://<authority>/`.
+/// `verify` overrides the `verification` setting for this open; `safe`
+/// opens it in safe mode (see [`permissions::SafeMode`]) and `private` in a
+/// private session (see `private.rs`). `root` picks which of a CAR's roots
+/// holds the MASL to show, for a tile that lists several. A failure comes
+/// with a code telling what kind it is (see `error.rs`). Runs on the
+/// blocking pool, as parsing or decompressing a large tile takes a while.
 #[tauri::command]
-fn open_tile(
+async fn open_tile(
     path: String,
-    state: State<'_, TileStore>,
+    verify: Option<bool>,
+    safe: Option<bool>,
+    private: Option<bool>,
+    root: Option<String>,
     app: AppHandle,
-) -> Result<TileOpenedPayload, String> {
-    let p = PathBuf::from(&path);
-    load_tile(&p, &state, &app).map_err(|e| e.to_string())
+) -> Result<TileOpenedPayload, CommandError> {
+    let options = OpenOptions {
+        verify,
+        safe: safe.unwrap_or(false),
+        private: private.unwrap_or(false),
+        root,
+    };
+    let opened = tauri::async_runtime::spawn_blocking(move || {
+        load_tile(Path::new(&path), options, &app.state(), &app).inspect_err(|e| {
+            tracing::warn!(path, "opening tile failed: {e:#}");
+        })
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(opened?)
+}
+
+/// Called by the frontend when a tab closes. Ends private and dev sessions;
+/// other tiles stay loaded so reopening them is instant.
+#[tauri::command]
+fn close_tile(authority: String, app: AppHandle) {
+    tracing::debug!(authority, "tile closed");
+    if let Some(session) = app.try_state::<session::Session>() {
+        session.closed(&authority);
+    }
+    private::close(&app, &authority);
+    dev::stop(&app, &authority);
+}
+
+/// Called by the frontend once its event listeners are registered. Offers
+/// back a session left by a crash and starts loading anything that was
+/// queued at startup.
+#[tauri::command]
+fn frontend_ready(pending: State<'_, PendingOpens>, app: AppHandle) {
+    let session = app.try_state::<session::Session>();
+    if let Some(recoverable) = session.and_then(|s| s.recoverable()) {
+        let _ = app.emit("tile:session-recoverable", recoverable);
+    }
+    if let Some(paths) = pending.0.lock().unwrap().take() {
+        spawn_opens(app, paths);
+    }
 }
 
 // ── Internal helpers ─────────────────────────────────────────────────────────
 
+/// Progress for queued opens, emitted as `tile:open-progress` (coalesced, see
+/// [`events::Coalescer`]) before the first file and as files finish.
+#[derive(Debug, Clone, Serialize)]
+struct OpenProgressPayload {
+    done: usize,
+    total: usize,
+    /// Files that failed since the previous emission.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<OpenError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OpenError {
+    path: String,
+    error: String,
+    /// The error's code (see `error.rs`).
+    code: &'static str,
+}
+
+impl OpenProgressPayload {
+    fn merge(pending: &mut Self, next: Self) {
+        pending.done = pending.done.max(next.done);
+        pending.errors.extend(next.errors);
+    }
+}
+
+/// Open `paths` now if the frontend is ready, otherwise queue them.
+fn open_when_ready(app: &AppHandle, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    {
+        let pending = app.state::<PendingOpens>();
+        let mut guard = pending.0.lock().unwrap();
+        if let Some(queue) = guard.as_mut() {
+            queue.extend(paths);
+            return;
+        }
+    }
+    spawn_opens(app.clone(), paths);
+}
+
+/// Load `paths` one after another off the main thread.
+fn spawn_opens(app: AppHandle, paths: Vec<PathBuf>) {
+    std::thread::spawn(move || {
+        let state = app.state::<TileStore>();
+        let total = paths.len();
+        let progress = Coalescer::new(&app, "tile:open-progress", OpenProgressPayload::merge);
+        progress.push(OpenProgressPayload { done: 0, total, errors: Vec::new() });
+        for (i, path) in paths.iter().enumerate() {
+            let errors = match load_tile(path, OpenOptions::default(), &state, &app) {
+                Ok(_) => Vec::new(),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), "opening tile failed: {e:#}");
+                    vec![OpenError {
+                        path: path.to_string_lossy().into_owned(),
+                        error: e.to_string(),
+                        code: error::code(&e),
+                    }]
+                }
+            };
+            progress.push(OpenProgressPayload { done: i + 1, total, errors });
+        }
+    });
+}
+
+/// How a tile is opened; the default is a plain open.
+#[derive(Debug, Default, Clone)]
+struct OpenOptions {
+    /// Hash every block before showing the tile; `None` follows the
+    /// `verification` setting.
+    verify: Option<bool>,
+    /// Serve with scripts disabled until the tile is next opened normally.
+    safe: bool,
+    /// Open under a fresh authority that leaves nothing behind.
+    private: bool,
+    /// Show the MASL in this root block rather than the tile's own. Another
+    /// root than that opens under `<authority>-root<n>`, `n` counting the
+    /// CAR's roots from 1, and isn't kept in the session or parse cache.
+    root: Option<String>,
+}
+
+/// Open `path` into the store and emit `tile:opened`. When verifying, every
+/// block is hashed before the tile is shown and the result is attached to
+/// the payload.
 fn load_tile(
     path: &Path,
+    options: OpenOptions,
     state: &State<'_, TileStore>,
     app: &AppHandle,
 ) -> anyhow::Result<TileOpenedPayload> {
-    let content = parse_tile(path)?;
-    let authority = authority_from_path(path);
-    let payload = TileOpenedPayload {
-        authority: authority.clone(),
-        masl: content.masl.clone(),
+    let settings = app.state::<SettingsStore>().get();
+    let verify = options.verify.unwrap_or(settings.verification != Verification::Off);
+    let cached = index_cache::load(app, path);
+    let fresh = cached.is_none();
+    let content = match cached {
+        Some(content) => content,
+        None if settings.sandboxed_parsing => sandbox::parse(path)?,
+        None if settings.lazy_open && !verify => {
+            let content = parse_tile_header(path)?;
+            content.spawn_indexer();
+            content
+        }
+        None => parse_tile(path)?,
+    };
+    let other_root = options.root.as_deref();
+    let other_root = other_root.filter(|root| content.root.as_deref() != Some(*root));
+    let mut content = match other_root {
+        Some(root) => content.at_root(root)?,
+        None => content,
     };
-    state.0.lock().unwrap().insert(authority, content);
+    if settings.strict_validation {
+        validate::ensure_valid(&content)?;
+    }
+    content.set_memory_mapped(settings.memory_mapped_reads);
+    content.set_verify_reads(settings.verify_reads);
+    content.spawn_hasher();
+    let authority = if options.private {
+        app.state::<private::PrivateSessions>().open()?
+    } else if let Some(root) = other_root {
+        let n = content.roots.iter().position(|r| r == root);
+        let n = n.ok_or_else(|| anyhow::anyhow!("{root} is not one of the tile's roots"))? + 1;
+        format!("{}-root{n}", authority_from_path(path))
+    } else {
+        authority_from_path(path)
+    };
+    app.state::<permissions::SafeMode>().set(&authority, options.safe);
+    let mut payload = TileOpenedPayload::new(&authority, &content);
+    payload.safe_mode = options.safe;
+    payload.private = options.private;
+    if verify {
+        payload.integrity = Some(checked_integrity(&content, &settings)?);
+    }
+    payload.signature = signing::status(app, &content);
+    if vouched_for(app, &content, payload.signature.as_ref()) {
+        content.set_verify_reads(true);
+        app.state::<BlockCache>().evict_path(path);
+    }
+    // A private session leaves no record behind, so it is neither checked
+    // against nor added to the trust store, the parse cache, or the watcher.
+    let warning = if options.private {
+        None
+    } else {
+        observe_trust(app, &authority, &content, &payload, false)
+    };
+    let content = Arc::new(content);
+    state.0.write().unwrap().insert(authority.clone(), content.clone());
+    if fresh && !options.private && other_root.is_none() && !content.is_compressed() {
+        index_cache::store(app, content.clone());
+    }
+    if !options.private {
+        sidecar::store(app, content.clone());
+        if let Err(e) = watch::watch(app, &authority, path) {
+            tracing::warn!(path = %path.display(), "not watching tile: {e:#}");
+        }
+        let session = app.try_state::<session::Session>().filter(|_| other_root.is_none());
+        if let Some(session) = session {
+            session.opened(&authority, path.to_path_buf());
+        }
+    }
+    // The log outlives a private session, so it doesn't name the file.
+    let shown = if options.private { "<private>".into() } else { path.display().to_string() };
+    tracing::info!(
+        authority,
+        path = %shown,
+        cached = !fresh,
+        verified = verify,
+        private = options.private,
+        "opened tile"
+    );
     app.emit("tile:opened", &payload)?;
+    if let Some(warning) = warning {
+        app.emit("tile:content-changed", warning)?;
+    }
+    prefetch::spawn(app, content);
     Ok(payload)
 }
 
+/// Whether `content` is validly signed or holds permissions. Both are keyed
+/// by its header's root hash, so its blocks are then always hashed as they
+/// are served: bodies swapped under an unchanged header are refused rather
+/// than shown under the signature badge or with the grants.
+pub(crate) fn vouched_for(
+    app: &AppHandle,
+    content: &TileContent,
+    signature: Option<&signing::SignatureStatus>,
+) -> bool {
+    signature.is_some_and(|s| s.valid)
+        || app.try_state::<trust::TrustStore>().is_some_and(|t| t.has_grants(&content.root_hash))
+}
+
+/// Re-parse `path` and swap it in under an already-open `authority`. Emits
+/// `tile:reloaded` so the frontend refreshes the existing tab in place.
+/// `verify` overrides the `verification` setting, as for [`load_tile`].
+/// `expected` is whether new content is no surprise, as after an edit or a
+/// live reload, rather than an update that came from elsewhere (see
+/// `TrustStore::observe`).
+fn reload_tile(
+    authority: &str,
+    path: &Path,
+    verify: Option<bool>,
+    expected: bool,
+    state: &TileStore,
+    app: &AppHandle,
+) -> anyhow::Result<TileOpenedPayload> {
+    let settings = app.state::<SettingsStore>().get();
+    let content = if path.is_dir() {
+        directory::open(path)?
+    } else if settings.sandboxed_parsing {
+        sandbox::parse(path)?
+    } else {
+        parse_tile(path)?
+    };
+    if settings.strict_validation {
+        validate::ensure_valid(&content)?;
+    }
+    swap_in(authority, path, content, verify, expected, state, app)
+}
+
+/// Swap `content`, newly read from `path`, in under an already-open
+/// `authority`, as [`reload_tile`] does once it has parsed the file.
+fn swap_in(
+    authority: &str,
+    path: &Path,
+    content: TileContent,
+    verify: Option<bool>,
+    expected: bool,
+    state: &TileStore,
+    app: &AppHandle,
+) -> anyhow::Result<TileOpenedPayload> {
+    let settings = app.state::<SettingsStore>().get();
+    let previous = state.get(authority);
+    // A view of another root stays on it, as a revision view does.
+    let other_root = previous.as_ref().and_then(|p| p.root.clone());
+    let other_root = other_root.filter(|root| content.root.as_ref() != Some(root));
+    let content = match &other_root {
+        Some(root) => content.at_root(root)?,
+        None => content,
+    };
+    let mut content = revisions::reopen(content, previous.as_deref())?;
+    content.set_memory_mapped(settings.memory_mapped_reads);
+    // Verified reads set for this tile alone outlast the reload.
+    content.set_verify_reads(previous.map_or(settings.verify_reads, |p| p.verifies_reads()));
+    content.spawn_hasher();
+    app.state::<BlockCache>().evict_path(path);
+    let mut payload = TileOpenedPayload::new(authority, &content);
+    payload.safe_mode = app.state::<permissions::SafeMode>().is_on(authority);
+    payload.private = private::is_private(app, authority);
+    if verify.unwrap_or(settings.verification != Verification::Off) {
+        payload.integrity = Some(checked_integrity(&content, &settings)?);
+    }
+    payload.signature = signing::status(app, &content);
+    if vouched_for(app, &content, payload.signature.as_ref()) {
+        content.set_verify_reads(true);
+    }
+    // Directory tiles are previews, dev builds are scratch files and revision
+    // views are of the past, with nothing worth recording; another root's
+    // view isn't the file's own.
+    let record = !payload.private
+        && !dev::is_dev(app, authority)
+        && !content.is_directory()
+        && content.revision.is_none()
+        && other_root.is_none();
+    let warning = if record {
+        observe_trust(app, authority, &content, &payload, expected)
+    } else {
+        None
+    };
+    let content = Arc::new(content);
+    state.0.write().unwrap().insert(authority.to_string(), content.clone());
+    if record && !content.is_compressed() {
+        sidecar::store(app, content.clone());
+        index_cache::store(app, content);
+    }
+    tracing::info!(authority, path = %path.display(), "reloaded tile");
+    app.emit("tile:reloaded", &payload)?;
+    if let Some(warning) = warning {
+        app.emit("tile:content-changed", warning)?;
+    }
+    Ok(payload)
+}
+
+/// Hash every block of `content`. Under strict verification, a tile with a
+/// failing block is an error rather than a report; blocks with a hash we
+/// can't check don't count as failing.
+fn checked_integrity(
+    content: &TileContent,
+    settings: &Settings,
+) -> anyhow::Result<IntegrityReport> {
+    let report = integrity_report(content)?;
+    let failed = report.resources.iter().filter(|(_, status)| **status == Integrity::Fail);
+    let failed: Vec<&str> = failed.map(|(path, _)| path.as_str()).collect();
+    if settings.verification == Verification::Strict && !failed.is_empty() {
+        anyhow::bail!(
+            "{} fails verification ({}); strict verification refuses to open it",
+            content.path.display(),
+            failed.join(", ")
+        );
+    }
+    Ok(report)
+}
+
+/// Record an open in the trust store, returning any unexpected-change
+/// warning. Failures only cost the warning, so they are logged, not raised.
+fn observe_trust(
+    app: &AppHandle,
+    authority: &str,
+    content: &TileContent,
+    payload: &TileOpenedPayload,
+    expected: bool,
+) -> Option<trust::ContentChanged> {
+    let signer = payload.signature.as_ref().filter(|s| s.valid).map(|s| s.key.clone());
+    let trust = app.state::<trust::TrustStore>();
+    trust
+        .observe(app, authority, content, signer, expected)
+        .map_err(|e| {
+            tracing::warn!(path = %content.path.display(), "trust store update failed: {e:#}")
+        })
+        .ok()
+        .flatten()
+}
+
 // ── tile: custom protocol ─────────────────────────────────────────────────────
 
 fn handle_tile_protocol(
@@ -69,182 +551,617 @@ fn handle_tile_protocol(
     let raw_path = uri.path();
     let path = if raw_path.is_empty() { "/" } else { raw_path };
 
-    let store = app.state::<TileStore>();
-    let guard = store.0.lock().unwrap();
-
     let error = |status: u16, msg: &str| {
+        if status >= 500 {
+            tracing::warn!(authority, path, status, "serving resource failed: {msg}");
+        } else {
+            tracing::debug!(authority, path, status, "{msg}");
+        }
         tauri::http::Response::builder()
             .status(status)
             .header("content-type", "text/plain")
             .body(msg.as_bytes().to_vec())
             .unwrap()
     };
-
-    let tile = match guard.get(authority) {
+    let tile = match app.state::<TileStore>().get(authority) {
         Some(t) => t,
-        None => return error(404, "tile not loaded"),
+        None => return error(404, &i18n::t("error.tile-not-loaded")),
     };
+    // From here on a failure is shown on the tile's page for its status, if
+    // `error_pages` gives one.
+    let error = |status: u16, msg: &str| {
+        let plain = error(status, msg);
+        error_page(app, &tile, authority, status, request.headers()).unwrap_or(plain)
+    };
+    // A block that fails its hash is the tile's fault, not the viewer's: 502,
+    // as a gateway answers for a bad upstream, rather than 500.
+    let read_failed = |e: anyhow::Error| {
+        let corrupt = matches!(e.downcast_ref(), Some(TileError::CorruptBlock(_)));
+        let status = if corrupt { 502 } else { 500 };
+        error(status, &e.to_string())
+    };
+    // Never serve blocks of a file that has been rewritten since it was
+    // parsed: the offsets no longer mean anything. A file only touched is
+    // still being hashed; it is reloaded once that finds new bytes.
+    if tile.modified_on_disk() {
+        let watcher = app.try_state::<watch::TileWatcher>();
+        if let Some(watcher) = watcher.filter(|_| !tile.checking_on_disk()) {
+            watcher.reload_modified(authority);
+        }
+        return tauri::http::Response::builder()
+            .status(503)
+            .header("retry-after", "1")
+            .header("content-type", "text/plain")
+            .body(b"tile changed on disk; reloading".to_vec())
+            .unwrap();
+    }
+    let limiter = app.try_state::<ratelimit::RequestLimiter>();
+    if limiter.is_some_and(|l| !l.allow(authority)) {
+        return tauri::http::Response::builder()
+            .status(429)
+            .header("retry-after", "1")
+            .body(Vec::new())
+            .unwrap();
+    }
 
-    // Walk the MASL resource map. Try the exact path first, then with/without
-    // trailing slash, then "/index.html" fallback for the root.
-    let candidates: &[&str] = &[
-        path,
-        if path.ends_with('/') { path.trim_end_matches('/') } else { path },
-        if !path.ends_with('/') { &format!("{path}/") } else { path },
-        if path == "/" { "/index.html" } else { path },
-    ];
+    // Paths the app answers for every tile, ahead of the tile's own.
+    if let Some((body, content_type)) = accessibility::serve(path) {
+        return tauri::http::Response::builder()
+            .header("content-type", content_type)
+            .header("cache-control", "no-store")
+            .header("access-control-allow-origin", "*")
+            .body(body)
+            .unwrap();
+    }
 
-    let resource = candidates.iter().find_map(|p| tile.masl.resources.get(*p));
+    if let Some(redirect) = tile.redirect(path) {
+        let location = HeaderValue::from_str(&redirect.location).ok();
+        let location = location.filter(|_| redirect.stays_in_tile());
+        let status = Some(redirect.status).filter(|s| car::REDIRECT_STATUSES.contains(s));
+        let (Some(location), Some(status)) = (location, status) else {
+            let (to, status) = (&redirect.location, redirect.status);
+            return error(500, &format!("won't redirect to {to:?} with status {status}"));
+        };
+        return tauri::http::Response::builder()
+            .status(status)
+            .header("location", location)
+            .header("access-control-allow-origin", "*")
+            .body(Vec::new())
+            .unwrap();
+    }
 
-    let resource = match resource {
+    let (resource, variants) = match tile.resolve_with_variants(path) {
         Some(r) => r,
-        None => return error(404, &format!("no resource at {path}")),
+        None => return error(404, &i18n::tf("error.no-resource", &[("path", path)])),
     };
+    let accept = request.headers().get("accept").and_then(|v| v.to_str().ok());
+    let resource = pick_variant(resource, variants, accept.unwrap_or(""));
+
+    // Browsers mark the fetch of a service worker's script; refusing it is
+    // what makes `register()` fail.
+    let worker = request.headers().get("service-worker").is_some_and(|v| v == "script");
+    if worker && !permissions::granted(app, authority, permissions::SERVICE_WORKER) {
+        return error(403, "service workers need the `service-worker` permission");
+    }
 
-    let src = match resource.get("src") {
-        Some(s) => s.as_str(),
-        None => return error(500, "resource missing src"),
+    let accept = request.headers().get("accept-encoding").and_then(|v| v.to_str().ok());
+    let Some((src, encoding, transformer)) = pick_body(app, resource, accept.unwrap_or("")) else {
+        return error(500, "resource missing src");
     };
-    let data = match tile.read_block(src) {
-        Ok(d) => d,
-        Err(e) => return error(500, &e.to_string()),
+    let block_len = match tile.content_len(src) {
+        Ok(n) => n,
+        Err(e) => return read_failed(e),
     };
 
-    let content_type = resource
-        .get("content-type")
-        .cloned()
-        .unwrap_or_else(|| "application/octet-stream".to_string());
+    // Range requests (media elements seeking through large blocks) read just
+    // the requested slice, capped per response, and bypass the cache.
+    let range = request.headers().get("range").and_then(|v| v.to_str().ok());
+    let range = range.filter(|_| transformer.is_none());
+    let (status, data, content_range) = match range {
+        Some(range) => {
+            let Some((start, end)) = parse_range(range, block_len) else {
+                return tauri::http::Response::builder()
+                    .status(416)
+                    .header("content-range", format!("bytes */{block_len}"))
+                    .body(Vec::new())
+                    .unwrap();
+            };
+            match tile.read_block_range(src, start, end - start + 1) {
+                Ok(d) => (206, Bytes::from(d), Some(format!("bytes {start}-{end}/{block_len}"))),
+                Err(e) => return read_failed(e),
+            }
+        }
+        None => {
+            let cache = app.state::<BlockCache>();
+            match cache.get_or_load(&tile.path, src, || tile.read_block(src)) {
+                Ok(d) => (200, d, None),
+                Err(e) => return read_failed(e),
+            }
+        }
+    };
 
+    let (data, content_type) = match transformer {
+        Some(transformer) => match transformer.apply(&data, path) {
+            Ok(body) => (Bytes::from(body), HeaderValue::from_static(transformer.content_type)),
+            Err(e) => return error(500, &format!("{} transformer: {e:#}", transformer.name)),
+        },
+        None => (data, headers::content_type(resource)),
+    };
+
+    let mut builder = body_response(app, &tile, authority, resource, variants, encoding)
+        .status(status)
+        .header("content-type", content_type)
+        .header("accept-ranges", if transformer.is_some() { "none" } else { "bytes" });
+    if let Some(content_range) = content_range {
+        builder = builder.header("content-range", content_range);
+    }
+
+    // Tauri takes an owned body, so this is the one copy on the serve path;
+    // `Vec::from` reuses the allocation when the cache isn't holding it.
+    builder.body(Vec::from(data)).unwrap()
+}
+
+/// Which body of `resource` to serve to a client sending
+/// `accept_encoding`: its block's CID, the content coding it is stored in,
+/// and the transformer it goes through. A transformed body is made from the
+/// identity body, whole.
+fn pick_body<'a>(
+    app: &AppHandle<impl tauri::Runtime>,
+    resource: &'a car::Resource,
+    accept_encoding: &str,
+) -> Option<(&'a str, Option<&'static str>, Option<&'static transform::Transformer>)> {
+    let transformer = transform::for_resource(app, resource);
+    let (src, encoding) = match transformer {
+        Some(_) => (resource.get("src")?.as_str(), None),
+        None => pick_encoding(resource, accept_encoding)?,
+    };
+    Some((src, encoding, transformer))
+}
+
+/// A response for a body of `resource`, stored in `encoding`, with the
+/// headers every such response gets: CORS, the CSP, `vary` for what the
+/// choice of body depended on, and the safe subset of the MASL resource
+/// entry's headers. The caller adds the status and content type.
+fn body_response(
+    app: &AppHandle<impl tauri::Runtime>,
+    tile: &TileContent,
+    authority: &str,
+    resource: &car::Resource,
+    variants: &[car::Resource],
+    encoding: Option<&'static str>,
+) -> tauri::http::response::Builder {
     let mut builder = tauri::http::Response::builder()
-        .status(200)
-        .header("content-type", &content_type)
-        .header("access-control-allow-origin", "*");
-
-    // Forward any other headers from the MASL resource entry.
-    for (k, v) in resource {
-        if k != "content-type" && k != "src" {
-            builder = builder.header(k.as_str(), v.as_str());
+        .header("access-control-allow-origin", "*")
+        .header("content-security-policy", permissions::csp_for(app, authority));
+    if let Some(encoding) = encoding {
+        builder = builder.header("content-encoding", encoding);
+    }
+    let mut vary = Vec::new();
+    if !variants.is_empty() {
+        vary.push("accept");
+    }
+    if resource.keys().any(|k| k != "src" && car::is_src_key(k)) {
+        vary.push("accept-encoding");
+    }
+    if !vary.is_empty() {
+        builder = builder.header("vary", vary.join(", "));
+    }
+    for (name, value) in headers::forwarded(resource, headers::allow_extra(app, tile)) {
+        builder = builder.header(name, value);
+    }
+    builder
+}
+
+/// `tile`'s page for `status`, from its `error_pages`, served with that
+/// status as a request for the page itself would be: through a redirect,
+/// the routes, and the choice of variant, encoding and transformer, by the
+/// headers of the request that failed. `None` if the tile gives no page or
+/// it can't be read.
+fn error_page(
+    app: &AppHandle<impl tauri::Runtime>,
+    tile: &TileContent,
+    authority: &str,
+    status: u16,
+    request: &tauri::http::HeaderMap,
+) -> Option<tauri::http::Response<Vec<u8>>> {
+    let page = tile.masl.error_pages.get(&status)?.as_str();
+    let (resource, variants) = tile.resolve_linked(page)?;
+    let header = |name| request.get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+    let resource = pick_variant(resource, variants, header("accept"));
+    let (src, encoding, transformer) = pick_body(app, resource, header("accept-encoding"))?;
+    let cache = app.state::<BlockCache>();
+    let body = cache.get_or_load(&tile.path, src, || tile.read_block(src));
+    let body = body.and_then(|body| match transformer {
+        Some(transformer) => {
+            let body = transformer.apply(&body, page)?;
+            Ok((Bytes::from(body), HeaderValue::from_static(transformer.content_type)))
+        }
+        None => Ok((body, headers::content_type(resource))),
+    });
+    let (body, content_type) = match body {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!(authority, page, status, "error page unreadable: {e:#}");
+            return None;
+        }
+    };
+    let builder = body_response(app, tile, authority, resource, variants, encoding)
+        .status(status)
+        .header("content-type", content_type);
+    Some(builder.body(Vec::from(body)).unwrap())
+}
+
+/// Largest slice returned for a single range request. Clients asking for
+/// more (or for an open-ended range) get a shorter 206 and ask again.
+const MAX_RANGE_CHUNK: u64 = 8 << 20;
+
+/// Choose which of `resource` and its content-type `variants` to serve to a
+/// client sending `accept`. Each is rated by the most specific media range
+/// its type matches, for that range's `q` then its specificity, and the
+/// best wins, `resource` on a tie. So `image/*` alone keeps the resource,
+/// while naming a variant's type outright picks it.
+fn pick_variant<'a>(
+    resource: &'a car::Resource,
+    variants: &'a [car::Resource],
+    accept: &str,
+) -> &'a car::Resource {
+    if variants.is_empty() || accept.trim().is_empty() {
+        return resource;
+    }
+    let ranges: Vec<(&str, &str, f32)> = accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let (kind, subtype) = parts.next()?.split_once('/')?;
+            let q = parts.find_map(|p| p.strip_prefix("q=")?.parse().ok()).unwrap_or(1.0);
+            Some((kind, subtype, q))
+        })
+        .collect();
+    // `(q, specificity)`: 2 for `type/subtype`, 1 for `type/*`, 0 for `*/*`.
+    let rate = |candidate: &car::Resource| -> (f32, u8) {
+        let content_type = candidate.get("content-type").map_or("", String::as_str);
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+        let Some((kind, subtype)) = essence.split_once('/') else { return (0.0, 0) };
+        let matching = ranges.iter().filter_map(|(range_kind, range_subtype, q)| {
+            let specificity = match (*range_kind, *range_subtype) {
+                ("*", "*") => 0,
+                (k, "*") if k.eq_ignore_ascii_case(kind) => 1,
+                (k, s) if k.eq_ignore_ascii_case(kind) && s.eq_ignore_ascii_case(subtype) => 2,
+                _ => return None,
+            };
+            Some((*q, specificity))
+        });
+        matching.max_by_key(|(_, specificity)| *specificity).unwrap_or((0.0, 0))
+    };
+    let mut best = (resource, rate(resource));
+    for variant in variants {
+        let rating = rate(variant);
+        if rating.0 > 0.0 && rating > best.1 {
+            best = (variant, rating);
         }
     }
+    best.0
+}
+
+/// Choose which block to serve for `resource`: the first precompressed
+/// variant the client accepts, else the identity `src`. Returns the CID and
+/// the `content-encoding` to send with it.
+fn pick_encoding<'a>(
+    resource: &'a car::Resource,
+    accept_encoding: &str,
+) -> Option<(&'a str, Option<&'static str>)> {
+    // `(name, q)` per listed coding; an explicit entry overrides `*`.
+    let listed: Vec<(&str, f32)> = accept_encoding
+        .split(',')
+        .map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let name = parts.next().unwrap_or("");
+            let q = parts
+                .find_map(|p| p.strip_prefix("q=")?.parse().ok())
+                .unwrap_or(1.0);
+            (name, q)
+        })
+        .collect();
+    let q_of = |name: &str| listed.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, q)| *q);
+    let accepts = |coding: &str| q_of(coding).or_else(|| q_of("*")).is_some_and(|q| q > 0.0);
+    car::ENCODED_SRCS
+        .iter()
+        .filter(|(coding, _)| accepts(coding))
+        .find_map(|(coding, key)| Some((resource.get(*key)?.as_str(), Some(*coding))))
+        .or_else(|| Some((resource.get("src")?.as_str(), None)))
+}
 
-    builder.body(data).unwrap()
+/// Parse a single-range `Range: bytes=…` header into an inclusive
+/// `(start, end)` within a body of `total` bytes. `None` if unsatisfiable.
+fn parse_range(header: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    let (start, end) = spec.split(',').next()?.trim().split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let n: u64 = suffix.parse().ok()?;
+            (total.checked_sub(n.min(total))?, total.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, total.checked_sub(1)?),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(total.checked_sub(1)?)),
+    };
+    if start > end || start >= total {
+        return None;
+    }
+    Some((start, end.min(start + MAX_RANGE_CHUNK - 1)))
 }
 
 // ── App entry point ───────────────────────────────────────────────────────────
 
+/// The window with focus, else `main` if it is still open.
+fn focused_window(app: &AppHandle) -> Option<tauri::WebviewWindow> {
+    let windows = app.webview_windows();
+    let focused = windows.values().find(|w| w.is_focused().unwrap_or(false)).cloned();
+    focused.or_else(|| windows.get("main").cloned())
+}
+
+/// The app's menu bar, in the current language. Built again when the
+/// language changes (see `settings::apply`).
+pub(crate) fn app_menu<R: tauri::Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+    let accel = if cfg!(target_os = "macos") {
+        "Command+Control+F"
+    } else {
+        "F11"
+    };
+
+    let toggle_fs =
+        MenuItemBuilder::with_id("toggle_fullscreen", i18n::t("menu.toggle-fullscreen"))
+            .accelerator(accel)
+            .build(app)?;
+
+    let view = SubmenuBuilder::new(app, i18n::t("menu.view")).item(&toggle_fs).build()?;
+
+    #[cfg_attr(not(target_os = "macos"), allow(unused_mut))]
+    let mut builder = MenuBuilder::new(app);
+
+    #[cfg(target_os = "macos")]
+    {
+        use tauri::menu::PredefinedMenuItem;
+        let app_menu = SubmenuBuilder::new(app, "Tile Documents")
+            .item(&PredefinedMenuItem::about(app, None, None)?)
+            .separator()
+            .item(&PredefinedMenuItem::services(app, None)?)
+            .separator()
+            .item(&PredefinedMenuItem::hide(app, None)?)
+            .item(&PredefinedMenuItem::hide_others(app, None)?)
+            .item(&PredefinedMenuItem::show_all(app, None)?)
+            .separator()
+            .item(&PredefinedMenuItem::quit(app, None)?)
+            .build()?;
+        builder = builder.item(&app_menu);
+    }
+
+    builder.item(&view).build()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if let Some(code) = sandbox::child_main() {
+        std::process::exit(code);
+    }
+    // The system language until settings are loaded, for the menu bar.
+    i18n::set_language(None);
     tauri::Builder::default()
         .plugin(WindowStateBuilder::new().build())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
-        .manage(TileStore(Mutex::new(HashMap::new())))
-        .register_uri_scheme_protocol("tile", |ctx, request| {
-            handle_tile_protocol(ctx.app_handle(), request)
-        })
-        .invoke_handler(tauri::generate_handler![open_tile])
-        .menu(|app| {
-            let accel = if cfg!(target_os = "macos") {
-                "Command+Control+F"
-            } else {
-                "F11"
-            };
-
-            let toggle_fs =
-                MenuItemBuilder::with_id("toggle_fullscreen", "Toggle Full Screen")
-                    .accelerator(accel)
-                    .build(app)?;
-
-            let view = SubmenuBuilder::new(app, "View").item(&toggle_fs).build()?;
-
-            let mut builder = MenuBuilder::new(app);
-
-            #[cfg(target_os = "macos")]
-            {
-                use tauri::menu::PredefinedMenuItem;
-                let app_menu = SubmenuBuilder::new(app, "Tile Documents")
-                    .item(&PredefinedMenuItem::about(app, None, None)?)
-                    .separator()
-                    .item(&PredefinedMenuItem::services(app, None)?)
-                    .separator()
-                    .item(&PredefinedMenuItem::hide(app, None)?)
-                    .item(&PredefinedMenuItem::hide_others(app, None)?)
-                    .item(&PredefinedMenuItem::show_all(app, None)?)
-                    .separator()
-                    .item(&PredefinedMenuItem::quit(app, None)?)
-                    .build()?;
-                builder = builder.item(&app_menu);
-            }
-
-            builder.item(&view).build()
+        .plugin(navigation::plugin())
+        .manage(TileStore(RwLock::new(HashMap::new())))
+        .manage(PendingOpens(Mutex::new(Some(Vec::new()))))
+        .manage(UpdateStore::default())
+        .manage(audit::AuditLog::default())
+        .manage(ratelimit::RequestLimiter::default())
+        .manage(permissions::SafeMode::default())
+        .manage(private::PrivateSessions::default())
+        .manage(dev::DevSessions::default())
+        .manage(did::DidCache::default())
+        .manage(revocation::RevocationList::default())
+        // Block reads run on the blocking pool so one slow read (spinning
+        // disk, network mount) doesn't hold up the document's other requests.
+        .register_asynchronous_uri_scheme_protocol("tile", |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let authority = request.uri().host().unwrap_or("").to_string();
+                let path = request.uri().path().to_string();
+                let response = handle_tile_protocol(&app, request);
+                audit::record(&app, &authority, AuditKind::Resource, &path, response.status().as_str());
+                responder.respond(response);
+            });
         })
+        .invoke_handler(tauri::generate_handler![
+            open_tile,
+            close_tile,
+            private::show_private_tile,
+            frontend_ready,
+            audit::export_tile_audit_log,
+            audit::get_tile_audit_log,
+            audit::record_bridge_call,
+            catalog::fetch_catalog,
+            catalog::install_catalog_tile,
+            diagnostics::export_diagnostics,
+            feed::export_library_feed,
+            library::list_library,
+            dev::dev_mode,
+            diff::diff_tiles,
+            dev::stop_dev_mode,
+            directory::open_directory_tile,
+            directory::unpack_tile,
+            edit::compact_tile,
+            edit::edit_tile_metadata,
+            edit::remove_tile_resource,
+            edit::replace_resource,
+            edit::sign_tile,
+            lint::lint_tile,
+            logging::get_recent_logs,
+            logging::set_log_level,
+            manifest::export_web_manifest,
+            markdown::import_markdown_folder,
+            merge::merge_tiles,
+            metadata::get_tile_metadata,
+            metadata::get_localized_text,
+            pack::pack_folder,
+            revisions::list_revisions,
+            revisions::open_revision,
+            session::discard_session,
+            session::get_recoverable_session,
+            session::restore_session,
+            session::set_active_tile,
+            session::set_tile_view_state,
+            wizard::create_from_template,
+            wizard::create_new_tile,
+            wizard::list_tile_templates,
+            wizard::preview_new_tile,
+            permissions::get_tile_permissions,
+            permissions::request_permission,
+            permissions::revoke_permission,
+            settings::get_preferences,
+            settings::get_settings,
+            settings::set_settings,
+            signing::delete_signing_key,
+            signing::generate_signing_key,
+            signing::get_signing_key,
+            signing::import_signing_key,
+            signing::list_trusted_publishers,
+            signing::trust_publisher,
+            signing::untrust_publisher,
+            trust::forget_trust_record,
+            trust::list_trust_records,
+            update::check_tile_updates,
+            update::apply_tile_update,
+            validate::validate_tile,
+            verify::set_read_verification,
+            verify::verify_tile,
+        ])
+        .menu(app_menu)
         .on_menu_event(|app, event| {
             if event.id() == "toggle_fullscreen" {
-                if let Some(window) = app.get_webview_window("main") {
+                if let Some(window) = focused_window(app) {
                     let is_fs = window.is_fullscreen().unwrap_or(false);
                     let _ = window.set_fullscreen(!is_fs);
                 }
             }
         })
+        // Track the last known fullscreen state of each window so we emit
+        // only on actual changes. This covers menu/keyboard, the macOS green
+        // button, and ESC-to-exit – anything that causes a Resized event.
+        .on_window_event({
+            let last_fs: Mutex<HashMap<String, bool>> = Mutex::default();
+            move |window, event| match event {
+                tauri::WindowEvent::Resized(_) => {
+                    let is_fs = window.is_fullscreen().unwrap_or(false);
+                    let label = window.label().to_string();
+                    let prev = last_fs.lock().unwrap().insert(label.clone(), is_fs);
+                    if prev.unwrap_or(false) != is_fs {
+                        let changed = FullscreenChanged { window: label, fullscreen: is_fs };
+                        let _ = window.emit("tile:fullscreen-changed", changed);
+                    }
+                }
+                tauri::WindowEvent::Destroyed => {
+                    last_fs.lock().unwrap().remove(window.label());
+                }
+                _ => {}
+            }
+        })
         .setup(|app| {
             let app_handle = app.handle().clone();
+            let settings = SettingsStore::load(&app_handle);
+            app.manage(logging::init(&app_handle, &settings.get().log_level));
+            app.manage(BlockCache::new(settings.get().block_cache_mb << 20));
+            app.manage(settings);
+            app.manage(PublisherStore::load(app.handle()));
+            app.manage(trust::TrustStore::load(app.handle()));
+            app.manage(watch::TileWatcher::new(app.handle().clone()));
+            app.manage(session::Session::load(app.handle()));
+            let decompressed = app.path().app_cache_dir().map(|dir| dir.join("decompressed"));
+            let decompressed = decompressed.map_err(anyhow::Error::from);
+            if let Err(e) = decompressed.and_then(car::set_decompress_dir) {
+                tracing::warn!("decompressing tiles to the temporary directory: {e:#}");
+            }
+            let settings = app.state::<SettingsStore>().get();
+            if let Err(e) = settings::apply(app.handle(), &settings) {
+                tracing::warn!("applying settings failed: {e:#}");
+            }
 
-            // Restore saved window state (position, size, fullscreen) and set
-            // up a listener that notifies the frontend on any fullscreen change.
+            // The main window is built here rather than from the config alone
+            // so it can carry the download handler.
+            let window_config =
+                app.config().app.windows.first().cloned().ok_or("no window configured")?;
+            tauri::WebviewWindowBuilder::from_config(app.handle(), &window_config)?
+                .on_download(downloads::on_download)
+                .build()?;
+
+            // Restore saved window state (position, size, fullscreen).
             if let Some(window) = app_handle.get_webview_window("main") {
                 let _ = window.restore_state(StateFlags::all());
-
-                // Track the last known fullscreen state so we emit only on
-                // actual changes. This covers menu/keyboard, the macOS green
-                // button, and ESC-to-exit – anything that causes a Resized
-                // event.
-                let app_for_event = app_handle.clone();
-                let last_fs = Arc::new(AtomicBool::new(
-                    window.is_fullscreen().unwrap_or(false),
-                ));
-                window.on_window_event(move |event| {
-                    if matches!(event, tauri::WindowEvent::Resized(_)) {
-                        if let Some(win) = app_for_event.get_webview_window("main") {
-                            let is_fs = win.is_fullscreen().unwrap_or(false);
-                            let prev = last_fs.swap(is_fs, Ordering::Relaxed);
-                            if prev != is_fs {
-                                let _ = app_for_event.emit("tile:fullscreen-changed", is_fs);
-                            }
-                        }
-                    }
-                });
             }
 
-            // Handle files passed as CLI arguments (Windows / Linux).
-            let args: Vec<String> = std::env::args().skip(1).collect();
-            let state = app_handle.state::<TileStore>();
-            for arg in &args {
-                let p = PathBuf::from(arg);
-                if p.extension().and_then(|e| e.to_str()) == Some("tile") && p.exists() {
-                    let _ = load_tile(&p, &state, &app_handle);
-                }
-            }
+            // Handle files passed as CLI arguments (Windows / Linux). These
+            // are queued until the frontend reports ready so the window can
+            // paint before any parsing happens.
+            let paths: Vec<PathBuf> = std::env::args()
+                .skip(1)
+                .map(PathBuf::from)
+                .filter(|p| car::has_tile_extension(p) && p.exists())
+                .collect();
+            open_when_ready(&app_handle, paths);
 
-            // Handle macOS / deep-link file-open events.
+            // Handle macOS / deep-link file-open events, and automation
+            // actions sent as `tile-documents:` URLs.
             #[cfg(any(target_os = "macos", target_os = "ios"))]
             {
+                use tauri::Listener;
                 let app_handle2 = app_handle.clone();
                 app.listen("deep-link://new-url", move |event| {
                     if let Ok(urls) = serde_json::from_str::<Vec<String>>(event.payload()) {
-                        let state = app_handle2.state::<TileStore>();
-                        for url in urls {
-                            if let Some(file_path) = url.strip_prefix("file://") {
-                                let p = PathBuf::from(file_path);
-                                let _ = load_tile(&p, &state, &app_handle2);
-                            }
+                        let paths = urls
+                            .iter()
+                            .filter_map(|url| url.strip_prefix("file://"))
+                            .map(PathBuf::from)
+                            .collect();
+                        open_when_ready(&app_handle2, paths);
+                        let actions: Vec<tauri::Url> = urls
+                            .iter()
+                            .filter(|url| url.starts_with("tile-documents:"))
+                            .filter_map(|url| url.parse().ok())
+                            .collect();
+                        if actions.is_empty() {
+                            return;
                         }
+                        let app = app_handle2.clone();
+                        std::thread::spawn(move || {
+                            for url in actions {
+                                let done = automation::Action::from_url(&url)
+                                    .and_then(|action| automation::perform_link(&app, action));
+                                if let Err(e) = done {
+                                    tracing::warn!(%url, "automation failed: {e:#}");
+                                }
+                            }
+                        });
                     }
                 });
             }
+            #[cfg(target_os = "linux")]
+            automation::spawn_dbus(app_handle.clone());
+
+            update::spawn_checker(app_handle.clone());
+            revocation::spawn_fetcher(app_handle.clone());
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error running Tile Documents");
+        .build(tauri::generate_context!())
+        .expect("error running Tile Documents")
+        .run(|app, event| {
+            // Only a clean exit gets here; a crash leaves the session marked
+            // as running, to be offered back next launch.
+            if let tauri::RunEvent::Exit = event {
+                if let Some(session) = app.try_state::<session::Session>() {
+                    session.ended();
+                }
+            }
+        });
 }
@@ -0,0 +1,68 @@
+use crate::car::{has_tile_extension, parse_tile};
+use crate::settings::SettingsStore;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Library ──────────────────────────────────────────────────────────────────
+//
+// The library is a plain folder of `.tile` files in the app data directory.
+// Tiles installed from a catalog land here; nothing else is tracked, so users
+// can also drop files in by hand. Folders named in the `library_folders`
+// setting are listed with it, and a folder that has gone missing is skipped.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryEntry {
+    pub path: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// `<app data>/library`, created on first use.
+pub fn library_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    let dir = app.path().app_data_dir()?.join("library");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// List the `.tile` and `.tile.zst` files directly inside `dir`. Files that
+/// fail to parse are skipped rather than failing the whole listing.
+pub fn scan(dir: &Path) -> Result<Vec<LibraryEntry>> {
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !has_tile_extension(&path) {
+            continue;
+        }
+        if let Ok(content) = parse_tile(&path) {
+            out.push(LibraryEntry {
+                path: path.to_string_lossy().into_owned(),
+                name: content.masl.name,
+                description: content.masl.description,
+            });
+        }
+    }
+    out.sort_by_key(|e| e.name.to_lowercase());
+    Ok(out)
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn list_library(app: AppHandle) -> Result<Vec<LibraryEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<Vec<LibraryEntry>> {
+        let mut entries = scan(&library_dir(&app)?)?;
+        for dir in app.state::<SettingsStore>().get().library_folders {
+            if dir.is_dir() {
+                entries.extend(scan(&dir)?);
+            }
+        }
+        entries.sort_by_key(|e| e.name.to_lowercase());
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
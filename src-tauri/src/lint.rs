@@ -0,0 +1,124 @@
+use crate::accessibility::RESERVED_PREFIX;
+use crate::car::{Resource, TileContent};
+use crate::error::CommandError;
+use crate::headers;
+use crate::i18n;
+use crate::TileStore;
+use anyhow::anyhow;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Tile lint ────────────────────────────────────────────────────────────────
+//
+// Non-fatal problems with an open tile that its author would want to know
+// about: things the viewer silently works around or ignores.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintWarning {
+    /// Resource path the warning is about, if it is about one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    pub message: String,
+}
+
+/// Content types of executables, installers, and scripts. Tiles may carry
+/// them, but getting one out of the tile takes an explicit confirmation.
+const DANGEROUS_TYPES: [&str; 14] = [
+    "application/x-msdownload",
+    "application/x-msdos-program",
+    "application/vnd.microsoft.portable-executable",
+    "application/x-msi",
+    "application/x-ms-installer",
+    "application/x-apple-diskimage",
+    "application/x-sh",
+    "application/x-shellscript",
+    "text/x-shellscript",
+    "application/vnd.android.package-archive",
+    "application/java-archive",
+    "application/x-executable",
+    "application/x-elf",
+    "application/x-bat",
+];
+
+/// File extensions treated the same way, whatever type the entry declares.
+const DANGEROUS_EXTENSIONS: [&str; 18] = [
+    "exe", "msi", "bat", "cmd", "com", "scr", "ps1", "vbs", "dmg", "pkg", "app", "sh",
+    "command", "apk", "jar", "deb", "rpm", "appimage",
+];
+
+/// Whether the resource at `path` is executable or installer content.
+pub fn is_dangerous(path: &str, resource: &Resource) -> bool {
+    is_dangerous_file(path, resource.get("content-type").map(String::as_str))
+}
+
+/// Whether a file at `path`, or named `path`, of `content_type` when known,
+/// is executable or installer content.
+pub fn is_dangerous_file(path: &str, content_type: Option<&str>) -> bool {
+    let content_type = content_type.map(|t| {
+        t.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+    });
+    if content_type.is_some_and(|t| DANGEROUS_TYPES.contains(&t.as_str())) {
+        return true;
+    }
+    let name = path.rsplit('/').next().unwrap_or("");
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| DANGEROUS_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Paths of `tile`'s dangerous resources (see [`is_dangerous`]), sorted.
+pub fn dangerous_resources(tile: &TileContent) -> Vec<String> {
+    let mut paths: Vec<String> = tile
+        .masl
+        .resources
+        .iter()
+        .filter(|(path, resource)| is_dangerous(path, resource))
+        .map(|(path, _)| path.clone())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Lint `tile` as this app would serve it.
+pub fn lint<R: Runtime>(app: &AppHandle<R>, tile: &TileContent) -> Vec<LintWarning> {
+    let allow_extra = headers::allow_extra(app, tile);
+    let mut warnings: Vec<LintWarning> = tile
+        .masl
+        .resources
+        .iter()
+        .flat_map(|(path, resource)| {
+            headers::dropped(resource, allow_extra).into_iter().map(|(name, reason)| LintWarning {
+                resource: Some(path.clone()),
+                message: format!("header `{name}` dropped: {reason}"),
+            })
+        })
+        .collect();
+    warnings.extend(dangerous_resources(tile).into_iter().map(|path| LintWarning {
+        resource: Some(path),
+        message: "executable or installer content; saving it asks for confirmation".into(),
+    }));
+    warnings.extend(
+        tile.masl.resources.keys().filter(|path| path.starts_with(RESERVED_PREFIX)).map(|path| {
+            LintWarning {
+                resource: Some(path.clone()),
+                message: format!("never served: {RESERVED_PREFIX} is reserved for the app"),
+            }
+        }),
+    );
+    warnings.sort_by(|a, b| a.resource.cmp(&b.resource).then_with(|| a.message.cmp(&b.message)));
+    warnings
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn lint_tile(
+    authority: String,
+    app: AppHandle,
+) -> Result<Vec<LintWarning>, CommandError> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| anyhow!(i18n::t("error.tile-not-loaded")))?;
+    let warnings = tauri::async_runtime::spawn_blocking(move || lint(&app, &tile))
+        .await
+        .map_err(anyhow::Error::from)?;
+    Ok(warnings)
+}
@@ -0,0 +1,194 @@
+use crate::settings::SettingsStore;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Layer, Registry};
+
+// ── Logging ──────────────────────────────────────────────────────────────────
+//
+// The app logs through `tracing`. Events go to stderr, to a log file in the
+// app log directory that rotates daily with a week kept, and to a buffer of
+// the latest entries that the in-app viewer reads with `get_recent_logs`, so
+// a bug report about a failing tile can carry what happened while it was
+// opened. The `log_level` setting applies to this crate's own events. Other
+// crates (Tauri, the webview) only log warnings and errors, whatever the
+// level, so raising it to `debug` doesn't bury the tile in their noise.
+
+/// Entries kept for [`get_recent_logs`].
+const RECENT_ENTRIES: usize = 2000;
+/// Daily log files kept.
+const LOG_FILES: usize = 7;
+
+/// One logged event.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub time: u64,
+    pub level: String,
+    /// Module the event was logged from.
+    pub target: String,
+    pub message: String,
+    /// The event's other fields, formatted.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Managed state: the latest entries and the handle that changes the level.
+pub struct Logs {
+    recent: Arc<Mutex<VecDeque<(LevelFilter, LogEntry)>>>,
+    filter: reload::Handle<Targets, Registry>,
+}
+
+impl Logs {
+    /// The entries kept, oldest first.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.recent.lock().unwrap().iter().map(|(_, entry)| entry.clone()).collect()
+    }
+
+    /// Apply `level` to the subscriber.
+    pub fn set_level(&self, level: &str) -> Result<()> {
+        let level = parse_level(level)?;
+        self.filter.reload(targets(level)).map_err(|e| anyhow!("changing log level: {e}"))
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter> {
+    level.parse().map_err(|_| anyhow!("unknown log level {level:?}"))
+}
+
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_default(LevelFilter::WARN.min(level))
+        .with_target(env!("CARGO_CRATE_NAME"), level)
+}
+
+/// Install the subscriber at `level` and return the state managing it. The
+/// file is left out, with a warning, if the log directory can't be used.
+pub fn init<R: Runtime>(app: &AppHandle<R>, level: &str) -> Logs {
+    let level = parse_level(level).unwrap_or(LevelFilter::INFO);
+    let (filter, handle) = reload::Layer::new(targets(level));
+    let recent = Arc::new(Mutex::new(VecDeque::new()));
+    let file = app.path().app_log_dir().map_err(anyhow::Error::from).and_then(|dir| {
+        Ok(RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("tile-documents")
+            .filename_suffix("log")
+            .max_log_files(LOG_FILES)
+            .build(dir)?)
+    });
+    let (file, file_error) = match file {
+        Ok(file) => (Some(fmt::layer().with_ansi(false).with_writer(file)), None),
+        Err(e) => (None, Some(e)),
+    };
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file)
+        .with(Recent(recent.clone()))
+        .try_init();
+    // Only fails if another subscriber is already installed, which then gets
+    // this warning.
+    if let Err(e) = installed {
+        tracing::warn!("logging not set up: {e}");
+    }
+    if let Some(e) = file_error {
+        tracing::warn!("not writing a log file: {e:#}");
+    }
+    Logs { recent, filter: handle }
+}
+
+/// Layer recording events into [`Logs::recent`].
+struct Recent(Arc<Mutex<VecDeque<(LevelFilter, LogEntry)>>>);
+
+impl<S: Subscriber> Layer<S> for Recent {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = Fields::default();
+        event.record(&mut visitor);
+        let entry = LogEntry {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            level: metadata.level().as_str().to_ascii_lowercase(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+        let mut recent = self.0.lock().unwrap();
+        if recent.len() == RECENT_ENTRIES {
+            recent.pop_front();
+        }
+        recent.push_back((LevelFilter::from_level(*metadata.level()), entry));
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// The latest log entries, oldest first: at most `limit` of them (default
+/// all that are kept), at `level` or more severe (default all).
+#[tauri::command]
+pub fn get_recent_logs(
+    limit: Option<usize>,
+    level: Option<String>,
+    logs: State<'_, Logs>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = level.as_deref().map(parse_level).transpose().map_err(|e| e.to_string())?;
+    let recent = logs.recent.lock().unwrap();
+    let matching: Vec<&LogEntry> = recent
+        .iter()
+        .filter(|(l, _)| level.is_none_or(|level| *l <= level))
+        .map(|(_, entry)| entry)
+        .collect();
+    let skip = matching.len().saturating_sub(limit.unwrap_or(usize::MAX));
+    Ok(matching[skip..].iter().map(|entry| (*entry).clone()).collect())
+}
+
+/// Change the log level now and for later launches: `error`, `warn`,
+/// `info`, `debug`, `trace` or `off`.
+#[tauri::command]
+pub fn set_log_level(
+    level: String,
+    logs: State<'_, Logs>,
+    store: State<'_, SettingsStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    logs.set_level(&level).map_err(|e| e.to_string())?;
+    let mut settings = store.get();
+    settings.log_level = level.to_ascii_lowercase();
+    store.set(&app, settings).map_err(|e| e.to_string())
+}
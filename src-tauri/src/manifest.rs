@@ -0,0 +1,118 @@
+use crate::car::{Resource, TileContent};
+use crate::i18n;
+use crate::TileStore;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use tauri::State;
+
+// ── W3C Web App Manifest export ──────────────────────────────────────────────
+//
+// MASL is already close to a web app manifest; the main differences are that
+// icon `src`s point into the tile's resource map and that manifests require a
+// `start_url`. Exported icons are written next to the manifest using their
+// tile paths, so the `src` values stay valid relative URLs.
+
+/// Map `tile`'s MASL fields onto a web app manifest. Icon `src`s are made
+/// relative.
+pub fn to_web_manifest(tile: &TileContent) -> Value {
+    let masl = &tile.masl;
+    let icons: Vec<Value> = masl
+        .icons
+        .iter()
+        .map(|icon| {
+            let mut obj = json!({ "src": icon.src.trim_start_matches('/') });
+            if !icon.sizes.is_empty() {
+                obj["sizes"] = json!(icon.sizes);
+            }
+            if !icon.purpose.is_empty() {
+                obj["purpose"] = json!(icon.purpose);
+            }
+            let content_type = icon_resource(tile, &icon.src).and_then(|r| r.get("content-type"));
+            if let Some(ct) = content_type {
+                obj["type"] = json!(ct);
+            }
+            obj
+        })
+        .collect();
+
+    let mut manifest = json!({
+        "name": masl.name,
+        "start_url": ".",
+        "scope": ".",
+        "display": "standalone",
+        "icons": icons,
+    });
+    let optional = [
+        ("short_name", &masl.short_name),
+        ("description", &masl.description),
+        ("theme_color", &masl.theme_color),
+        ("background_color", &masl.background_color),
+    ];
+    for (key, value) in optional {
+        if let Some(v) = value {
+            manifest[key] = json!(v);
+        }
+    }
+    manifest
+}
+
+/// Write `manifest.webmanifest` plus every icon file into `dir`. Returns the
+/// manifest path.
+pub fn export(tile: &TileContent, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    for icon in &tile.masl.icons {
+        let resource = icon_resource(tile, &icon.src)
+            .ok_or_else(|| anyhow!("icon {} is not in the resource map", icon.src))?;
+        let cid = resource.get("src").ok_or_else(|| anyhow!("icon {} has no src", icon.src))?;
+        let dest = dir.join(safe_relative(&icon.src)?);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(dest, tile.read_block(cid)?)?;
+    }
+
+    let manifest = to_web_manifest(tile);
+    let path = dir.join("manifest.webmanifest");
+    std::fs::write(&path, serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(path)
+}
+
+/// The resource an icon's `src` serves to a plain request for it, looked
+/// up as the protocol looks up a path (see `TileContent::resolve_linked`).
+/// Some producers omit the leading slash.
+fn icon_resource<'a>(tile: &'a TileContent, src: &str) -> Option<&'a Resource> {
+    let (resource, _) = if src.starts_with('/') {
+        tile.resolve_linked(src)
+    } else {
+        tile.resolve_linked(&format!("/{src}"))
+    }?;
+    Some(resource)
+}
+
+/// Turn a tile path into a relative filesystem path, refusing anything that
+/// would escape the export directory.
+fn safe_relative(tile_path: &str) -> Result<PathBuf> {
+    let rel = PathBuf::from(tile_path.trim_start_matches('/'));
+    if rel.as_os_str().is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("refusing to export unsafe path {tile_path}");
+    }
+    Ok(rel)
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Export an open tile's MASL as `manifest.webmanifest` (with icons) into
+/// `dest_dir`.
+#[tauri::command]
+pub fn export_web_manifest(
+    authority: String,
+    dest_dir: String,
+    state: State<'_, TileStore>,
+) -> Result<String, String> {
+    let tile = state.get(&authority).ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    export(&tile, Path::new(&dest_dir))
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,341 @@
+use crate::builder::{MaslFields, TileBuilder};
+use crate::pack::{self, PackOptions, PackSummary, PackedFile, TypeSource};
+use crate::signing::{self, Signer};
+use anyhow::{anyhow, bail, Result};
+use pulldown_cmark::{CowStr, Event, MetadataBlockKind, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tauri::AppHandle;
+
+// ── Markdown import ──────────────────────────────────────────────────────────
+//
+// Packs a folder of Markdown notes as a tile of HTML pages. Each `.md` file
+// is rendered as CommonMark with tables, footnotes, strikethrough and task
+// lists, and stored at its own path with `.html` in place of `.md`. A
+// top-level `index.md`, or failing that `README.md`, becomes the start page.
+// A folder with neither gets a generated contents page. Every page links the
+// bundled stylesheet and carries a navigation list of all the pages, each
+// titled by its front matter `title`, its first heading or its file name.
+// Links between notes (`other.md#part`) are pointed at the rendered pages.
+// Everything else in the folder is packed as it is, so images and other
+// attachments keep working. HTML written into the Markdown is passed
+// through; the tile's CSP applies to it as to any other page.
+
+/// Where the bundled stylesheet is stored.
+const STYLESHEET: &str = "/_markdown/style.css";
+const STYLE: &str = include_str!("../templates/markdown/style.css");
+const PAGE: &str = include_str!("../templates/markdown/page.html");
+const SERVED_PAGE: &str = include_str!("../templates/markdown/served.html");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedPage {
+    /// Resource path of the Markdown file, e.g. `/notes/ideas.md`.
+    pub source: String,
+    /// Resource path of the page it became.
+    pub path: String,
+    pub title: String,
+}
+
+/// What [`import`] put in the tile.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarkdownImport {
+    pub pages: Vec<RenderedPage>,
+    /// Set when no note could be the start page, so a contents page was
+    /// generated at `/index.html`.
+    pub generated_contents: bool,
+    pub summary: PackSummary,
+}
+
+struct Note {
+    page: RenderedPage,
+    text: String,
+}
+
+/// Render the Markdown folder `dir` into `builder`, along with the other
+/// files in it. `name` titles a generated contents page.
+pub fn add_markdown_folder(
+    builder: &mut TileBuilder,
+    dir: &Path,
+    name: &str,
+) -> Result<(PackSummary, Vec<RenderedPage>, bool)> {
+    let mut notes = Vec::new();
+    let mut files = Vec::new();
+    for file in pack::walk(dir)? {
+        let path = pack::resource_path(dir, &file)?;
+        let bytes = std::fs::read(&file)?;
+        if !is_markdown(&path) {
+            let (content_type, type_source) = pack::detect_type(&path, &bytes);
+            files.push(PackedFile {
+                path: path.clone(),
+                content_type: content_type.to_string(),
+                type_source,
+                size: bytes.len() as u64,
+            });
+            builder.add_resource(&path, bytes, content_type);
+            continue;
+        }
+        let text = String::from_utf8(bytes).map_err(|_| anyhow!("{path} is not UTF-8"))?;
+        let page = format!("{}.html", &path[..path.rfind('.').unwrap_or(path.len())]);
+        let title = title(&text).unwrap_or_else(|| file_title(&path));
+        notes.push(Note { page: RenderedPage { source: path, path: page, title }, text });
+    }
+    notes.sort_by(|a, b| a.page.source.cmp(&b.page.source));
+
+    let file_index = files.iter().any(|f| f.path == "/index.html");
+    if !file_index && !notes.iter().any(|n| n.page.path == "/index.html") {
+        let readme = notes.iter_mut().find(|n| n.page.source.eq_ignore_ascii_case("/readme.md"));
+        if let Some(readme) = readme {
+            readme.page.path = "/index.html".into();
+        }
+    }
+    let mut taken: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    for note in &notes {
+        if !taken.insert(&note.page.path) {
+            bail!("{} would be rendered over another file at {}", note.page.source, note.page.path);
+        }
+    }
+    let generated_contents = !taken.contains("/index.html");
+
+    let pages: HashMap<&str, &str> =
+        notes.iter().map(|n| (n.page.source.as_str(), n.page.path.as_str())).collect();
+    let mut entries: Vec<(&str, &str)> =
+        notes.iter().map(|n| (n.page.path.as_str(), n.page.title.as_str())).collect();
+    if generated_contents {
+        entries.insert(0, ("/index.html", name));
+    }
+    entries.sort_by_key(|(path, _)| (*path != "/index.html", *path));
+
+    let mut rendered = Vec::new();
+    for note in &notes {
+        let nav = nav(&entries, &note.page.path);
+        let content = render(&note.text, &note.page.source, &pages);
+        rendered.push((note.page.path.clone(), page(&note.page.title, &nav, &content)));
+    }
+    if generated_contents {
+        let list = nav(&entries[1..], "");
+        let content = format!("<h1>{}</h1>\n{list}", escape_html(name));
+        rendered.push(("/index.html".into(), page(name, &nav(&entries, "/index.html"), &content)));
+    }
+    rendered.push((STYLESHEET.into(), STYLE.to_string()));
+    for (path, body) in rendered {
+        let content_type = if path == STYLESHEET { "text/css" } else { "text/html" };
+        files.push(PackedFile {
+            path: path.clone(),
+            content_type: content_type.into(),
+            type_source: TypeSource::Extension,
+            size: body.len() as u64,
+        });
+        builder.add_resource(&path, body, content_type);
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let summary = PackSummary {
+        entry: "/index.html".into(),
+        entry_alias_of: None,
+        total_bytes: files.iter().map(|f| f.size).sum(),
+        files,
+        optimization: None,
+        dedup: None,
+        compression: None,
+    };
+    Ok((summary, notes.into_iter().map(|n| n.page).collect(), generated_contents))
+}
+
+/// Import the Markdown folder `dir` as a tile at `dest`, packed as
+/// `options` says and signed by `signer` if given. The tile is named after
+/// the folder unless `metadata` names it.
+pub fn import(
+    dir: &Path,
+    dest: &Path,
+    metadata: Option<MaslFields>,
+    options: PackOptions,
+    signer: Option<Signer>,
+) -> Result<MarkdownImport> {
+    let metadata = pack::folder_metadata(dir, metadata)?;
+    let mut builder = TileBuilder::new();
+    builder.set_metadata(metadata.clone()).set_cid_options(options.cid);
+    let (summary, pages, generated_contents) =
+        add_markdown_folder(&mut builder, dir, &metadata.name)?;
+    let summary = pack::write(builder, summary, dest, options, signer)?;
+    Ok(MarkdownImport { pages, generated_contents, summary })
+}
+
+fn is_markdown(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown")
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// A note's title: `title:` in its front matter, else its first heading.
+fn title(text: &str) -> Option<String> {
+    let mut events = Parser::new_ext(text, options());
+    let (mut front, mut heading) = (false, None);
+    for event in events.by_ref() {
+        match event {
+            Event::Start(Tag::MetadataBlock(MetadataBlockKind::YamlStyle)) => front = true,
+            Event::End(TagEnd::MetadataBlock(_)) => front = false,
+            Event::Text(t) if front => {
+                let title = t.lines().find_map(|line| line.strip_prefix("title:"));
+                let title = title.map(|t| t.trim().trim_matches(['"', '\'']));
+                if let Some(title) = title.filter(|t| !t.is_empty()) {
+                    return Some(title.to_string());
+                }
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                heading = Some(String::new());
+                break;
+            }
+            _ => {}
+        }
+    }
+    let mut heading = heading?;
+    for event in events {
+        match event {
+            Event::Text(t) | Event::Code(t) => heading.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => heading.push(' '),
+            Event::End(TagEnd::Heading(_)) => break,
+            _ => {}
+        }
+    }
+    let heading = heading.trim();
+    (!heading.is_empty()).then(|| heading.to_string())
+}
+
+/// `/notes/my-ideas.md` → `my-ideas`.
+fn file_title(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name[..name.rfind('.').unwrap_or(name.len())].to_string()
+}
+
+/// The HTML for the note `text` stored at `source`, with links to other
+/// notes pointed at their pages.
+fn render(text: &str, source: &str, pages: &HashMap<&str, &str>) -> String {
+    let events = Parser::new_ext(text, options()).map(|event| match event {
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+            let dest_url = match note_link(&dest_url, source, pages) {
+                Some(page) => CowStr::from(page),
+                None => dest_url,
+            };
+            Event::Start(Tag::Link { link_type, dest_url, title, id })
+        }
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// The page `href`, written in the note at `source`, should point at if it
+/// links to another note.
+fn note_link(href: &str, source: &str, pages: &HashMap<&str, &str>) -> Option<String> {
+    let (target, fragment) = match href.find(['#', '?']) {
+        Some(i) => href.split_at(i),
+        None => (href, ""),
+    };
+    if target.is_empty() || target.starts_with("//") || target.contains(':') {
+        return None;
+    }
+    let mut segments: Vec<&str> = Vec::new();
+    if !target.starts_with('/') {
+        segments.extend(source.split('/').skip(1));
+        segments.pop();
+    }
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let page = pages.get(format!("/{}", segments.join("/")).as_str())?;
+    Some(format!("{page}{fragment}"))
+}
+
+/// The navigation list over `entries`, marking `current`.
+fn nav(entries: &[(&str, &str)], current: &str) -> String {
+    let mut html = String::from("<ul>\n");
+    for (path, title) in entries {
+        let mark = if *path == current { " aria-current=\"page\"" } else { "" };
+        let (path, title) = (escape_html(path), escape_html(title));
+        html.push_str(&format!("<li><a href=\"{path}\"{mark}>{title}</a></li>\n"));
+    }
+    html.push_str("</ul>");
+    html
+}
+
+fn page(title: &str, nav: &str, content: &str) -> String {
+    let title = escape_html(title);
+    fill(PAGE, &[("title", &title), ("nav", nav), ("content", content)])
+}
+
+/// The note `text` at `source` as a page of its own, for serving a tile's
+/// Markdown as HTML (see `transform.rs`). It has no navigation list, and
+/// the stylesheet is inline since the tile has no copy of it.
+pub(crate) fn render_standalone(text: &str, source: &str) -> String {
+    let title = escape_html(&title(text).unwrap_or_else(|| file_title(source)));
+    let content = render(text, source, &HashMap::new());
+    fill(SERVED_PAGE, &[("title", &title), ("style", STYLE), ("content", &content)])
+}
+
+/// `template` with each `{{key}}` replaced by its value, in one pass so a
+/// value that itself contains `{{…}}` is left alone.
+pub(crate) fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let (_, value) = values.iter().find(|(key, _)| *key == &after[..end])?;
+            Some((end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Import the Markdown folder `src_dir` as a tile at `dest`, signed if
+/// signing on export is on.
+#[tauri::command]
+pub async fn import_markdown_folder(
+    src_dir: String,
+    dest: String,
+    metadata: Option<MaslFields>,
+    options: Option<PackOptions>,
+    app: AppHandle,
+) -> Result<MarkdownImport, String> {
+    let signer = signing::export_signer(&app).map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = options.unwrap_or_default();
+        import(Path::new(&src_dir), Path::new(&dest), metadata, options, signer)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,206 @@
+use crate::builder::{MaslFields, TileBuilder};
+use crate::car::{is_src_key, parse_tile, Resource, TileContent};
+use crate::error::CommandError;
+use crate::signing::{self, Signer};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+// ── Tile merge ───────────────────────────────────────────────────────────────
+//
+// Combine two tiles into a new one, for assembling a collection out of
+// fragments authored apart. The new tile serves every path either tile
+// serves. Its name and other metadata are the first tile's, without its
+// `update_url` or publication dates: the merge is a document of its own, not
+// a version of the first. Its `license` and Dublin Core terms are kept only
+// where both tiles give the same ones, since the first tile's terms needn't
+// cover the second tile's pages. It asks for the permissions either tile asks for,
+// since it carries both tiles' pages, and keeps both tiles' icons, authors
+// and error pages, and their redirects: the first tile's where both redirect
+// a path or give a page for a status, and no redirect where the merge serves
+// a resource.
+//
+// A path both tiles serve is a conflict only if the bodies or headers
+// differ; bodies are compared by CID, and by bytes where the CIDs differ, so
+// the same file under another CID merges cleanly. `ConflictPolicy` says what
+// happens to a conflict. Each resource is copied by `TileBuilder` with the
+// CIDs its tile gives it, its blocks streamed frame by frame out of that
+// tile's file as compaction copies them, so a merge never holds the bodies
+// in memory, and blocks nothing serves, such as earlier revisions, are left
+// behind.
+
+/// What to do with a path both tiles serve differently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Refuse to merge, writing nothing.
+    #[default]
+    Fail,
+    /// Serve the first tile's resource.
+    First,
+    /// Serve the second tile's resource.
+    Second,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeSummary {
+    pub dest: String,
+    pub resources: usize,
+    /// Paths both tiles serve differently, settled by the policy.
+    pub conflicts: Vec<String>,
+}
+
+/// Merge the tiles at `first` and `second` into a new tile at `dest`,
+/// settling conflicts by `policy`, signed by `signer` if given.
+pub fn merge(
+    first: &Path,
+    second: &Path,
+    dest: &Path,
+    policy: ConflictPolicy,
+    signer: Option<Signer>,
+) -> Result<MergeSummary> {
+    let (a, b) = (Arc::new(parse_tile(first)?), Arc::new(parse_tile(second)?));
+    let mut builder = TileBuilder::new();
+    let mut conflicts = Vec::new();
+    let paths: BTreeSet<&String> =
+        a.masl.resources.keys().chain(b.masl.resources.keys()).collect();
+    for path in &paths {
+        let (ours, theirs) = (a.masl.resources.get(*path), b.masl.resources.get(*path));
+        let from = match (ours, theirs) {
+            (Some(ours), Some(theirs)) if !same(path, (&a, ours), (&b, theirs))? => {
+                conflicts.push(path.to_string());
+                match policy {
+                    ConflictPolicy::Fail => continue,
+                    ConflictPolicy::First => &a,
+                    ConflictPolicy::Second => &b,
+                }
+            }
+            (Some(_), _) => &a,
+            (None, Some(_)) => &b,
+            (None, None) => unreachable!(),
+        };
+        builder.copy_resource(path, from)?;
+    }
+    if policy == ConflictPolicy::Fail && !conflicts.is_empty() {
+        bail!("both tiles serve {} differently", conflicts.join(", "));
+    }
+    for (path, redirect) in b.masl.redirects.iter().chain(&a.masl.redirects) {
+        if !paths.contains(path) {
+            builder.add_redirect(path, &redirect.location, redirect.status)?;
+        }
+    }
+
+    let mut permissions = a.masl.permissions.clone();
+    for permission in &b.masl.permissions {
+        if !permissions.contains(permission) {
+            permissions.push(permission.clone());
+        }
+    }
+    let mut icons = a.masl.icons.clone();
+    for icon in &b.masl.icons {
+        if !icons.iter().any(|i| i.src == icon.src) {
+            icons.push(icon.clone());
+        }
+    }
+    let mut authors = a.masl.authors.clone();
+    for author in &b.masl.authors {
+        if !authors.contains(author) {
+            authors.push(author.clone());
+        }
+    }
+    let license = (a.masl.license == b.masl.license).then(|| a.masl.license.clone()).flatten();
+    let dc = if a.masl.dc == b.masl.dc { a.masl.dc.clone() } else { Default::default() };
+    let mut error_pages = b.masl.error_pages.clone();
+    error_pages.extend(a.masl.error_pages.clone());
+    builder
+        .set_metadata(MaslFields {
+            name: a.masl.name.clone(),
+            names: a.masl.names.clone(),
+            description: a.masl.description.clone(),
+            descriptions: a.masl.descriptions.clone(),
+            short_name: a.masl.short_name.clone(),
+            theme_color: a.masl.theme_color.clone(),
+            background_color: a.masl.background_color.clone(),
+            update_url: None,
+            permissions,
+            authors,
+            license,
+            published: None,
+            modified: None,
+            dc,
+            error_pages,
+            extra: a.masl.extra.clone(),
+        })
+        .set_icons(icons);
+    let resources = paths.len();
+    builder.set_signer(signer).finish(dest)?;
+    Ok(MergeSummary { dest: dest.to_string_lossy().into_owned(), resources, conflicts })
+}
+
+/// Whether `ours` in `a` and `theirs` in `b`, both served at `path`, have
+/// the same headers and bodies, variants included. Only bodies whose CIDs
+/// differ are read, a pair at a time.
+fn same(
+    path: &str,
+    (a, ours): (&TileContent, &Resource),
+    (b, theirs): (&TileContent, &Resource),
+) -> Result<bool> {
+    let headers = |resource: &Resource| -> BTreeSet<(String, String)> {
+        let headers = resource.iter().filter(|(k, _)| !is_src_key(k));
+        headers.map(|(k, v)| (k.clone(), v.clone())).collect()
+    };
+    let srcs = |resource: &Resource| -> BTreeSet<String> {
+        resource.keys().filter(|k| is_src_key(k)).cloned().collect()
+    };
+    if headers(ours) != headers(theirs) || srcs(ours) != srcs(theirs) {
+        return Ok(false);
+    }
+    let same_body = |ours: &str, theirs: &str| -> Result<bool> {
+        Ok(ours == theirs || a.read_block(ours)? == b.read_block(theirs)?)
+    };
+    for key in srcs(ours) {
+        if !same_body(&ours[&key], &theirs[&key])? {
+            return Ok(false);
+        }
+    }
+    let typed = |tile: &TileContent| -> BTreeSet<(String, String)> {
+        let variants = tile.masl.variants.get(path).into_iter().flatten();
+        let variants = variants.filter_map(|v| Some((v.get("content-type")?, v.get("src")?)));
+        variants.map(|(ty, src)| (ty.clone(), src.clone())).collect()
+    };
+    let (ours, theirs) = (typed(a), typed(b));
+    if ours.len() != theirs.len() {
+        return Ok(false);
+    }
+    for ((ours_type, ours), (theirs_type, theirs)) in ours.iter().zip(&theirs) {
+        if ours_type != theirs_type || !same_body(ours, theirs)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// [`merge`] the tiles at `path_a` and `path_b` into `dest`. `policy`
+/// defaults to failing on a conflict.
+#[tauri::command]
+pub async fn merge_tiles(
+    path_a: String,
+    path_b: String,
+    dest: String,
+    policy: Option<ConflictPolicy>,
+    app: AppHandle,
+) -> Result<MergeSummary, CommandError> {
+    let signer = signing::export_signer(&app)?;
+    let merged = tauri::async_runtime::spawn_blocking(move || {
+        let policy = policy.unwrap_or_default();
+        merge(Path::new(&path_a), Path::new(&path_b), Path::new(&dest), policy, signer)
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(merged?)
+}
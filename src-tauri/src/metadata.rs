@@ -0,0 +1,135 @@
+use crate::car::{default_text, Author, Localized, Masl, Terms};
+use crate::did::{self, AuthorStatus};
+use crate::error::CommandError;
+use crate::i18n;
+use crate::signing::{self, SignatureStatus};
+use crate::TileStore;
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+// ── Tile metadata ────────────────────────────────────────────────────────────
+
+/// Everything known about an open tile beyond what `tile:opened` carries,
+/// for the frontend's document info view.
+#[derive(Debug, Clone, Serialize)]
+pub struct TileMetadata {
+    pub authority: String,
+    pub path: String,
+    pub masl: Arc<RawValue>,
+    /// Hex root hash publisher signatures are made over.
+    pub root_hash: String,
+    /// Signer identity and validity, if the tile is signed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureStatus>,
+    /// Whether the MASL's `author_did` checks out, if it names one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorStatus>,
+    pub provenance: Provenance,
+}
+
+/// Who made a tile, when, and under what terms, as its MASL claims. Unlike
+/// `author_did`, none of it is checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Author>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dc: Terms,
+}
+
+impl Provenance {
+    pub(crate) fn of(masl: &Masl) -> Self {
+        Provenance {
+            authors: masl.authors.clone(),
+            license: masl.license.clone(),
+            published: masl.published.clone(),
+            modified: masl.modified.clone(),
+            dc: masl.dc.clone(),
+        }
+    }
+}
+
+/// A tile's name and description as shown in one locale.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalizedText {
+    /// The BCP 47 tag matched against.
+    pub locale: String,
+    pub name: String,
+    /// The tag the name shown is given under, if the MASL gives names by
+    /// language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_lang: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_lang: Option<String>,
+}
+
+/// `masl`'s name and description in the language best matching `locale`
+/// (see [`i18n::best_match`]), or their default text where none matches.
+pub(crate) fn localized(masl: &Masl, locale: &str) -> LocalizedText {
+    let pick = |by_language: &Localized| {
+        let tags: Vec<&str> = by_language.keys().map(String::as_str).collect();
+        let tag = i18n::best_match(&tags, locale).or_else(|| Some(default_text(by_language)?.0));
+        tag.map(|tag| (by_language[tag].clone(), tag.to_string()))
+    };
+    let (name, name_lang) = match pick(&masl.names) {
+        Some((name, tag)) => (name, Some(tag)),
+        None => (masl.name.clone(), None),
+    };
+    let (description, description_lang) = match pick(&masl.descriptions) {
+        Some((description, tag)) => (Some(description), Some(tag)),
+        None => (masl.description.clone(), None),
+    };
+    LocalizedText { locale: locale.to_string(), name, name_lang, description, description_lang }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Resolving a `did:web`/`did:plc` author goes to the network, so this runs
+/// off the main thread.
+#[tauri::command]
+pub async fn get_tile_metadata(
+    authority: String,
+    app: AppHandle,
+) -> Result<TileMetadata, CommandError> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| anyhow!(i18n::t("error.tile-not-loaded")))?;
+    let metadata = tauri::async_runtime::spawn_blocking(move || {
+        let signature = signing::status(&app, &tile);
+        TileMetadata {
+            path: tile.path.to_string_lossy().into_owned(),
+            masl: tile.masl_json.clone(),
+            root_hash: tile.root_hash.clone(),
+            provenance: Provenance::of(&tile.masl),
+            author: did::author_status(&app, &authority, &tile, signature.as_ref()),
+            signature,
+            authority,
+        }
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(metadata)
+}
+
+/// The open tile `authority`'s name and description in `locale`, a BCP 47
+/// tag, or in the user's locale when that isn't given.
+#[tauri::command]
+pub fn get_localized_text(
+    authority: String,
+    locale: Option<String>,
+    state: State<'_, TileStore>,
+) -> Result<LocalizedText, String> {
+    let tile = state.get(&authority).ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    Ok(localized(&tile.masl, &locale.unwrap_or_else(i18n::locale)))
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use std::fs::File;
+
+// ── Memory-mapped files ──────────────────────────────────────────────────────
+//
+// A read-only mapping of a whole tile file, so a block is copied straight
+// out of the page cache instead of through a read call per request. Only
+// Unix maps files; elsewhere `Mapping::of` gives `None` and reads go through
+// the file handle as usual.
+//
+// A mapped file that is truncated while it is being read faults the process
+// with SIGBUS rather than failing the read. So only files no other program
+// writes are mapped: the decompressed copies of compressed tiles, which sit
+// in the app's cache readable and writable by the user alone and are never
+// written once made. Reads of a tile file itself, which anything may
+// truncate, are always copied out with a read call, and mapping is off
+// unless the `memory_mapped_reads` setting asks for it.
+
+/// A read-only mapping of a file, unmapped on drop.
+pub struct Mapping {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only (`PROT_READ`), so no thread can write
+// through it, and it is owned by this value alone, which unmaps it only on
+// drop; sharing or sending `&[u8]` views of it is as sound as of any
+// immutable buffer.
+unsafe impl Send for Mapping {}
+// SAFETY: as for `Send`: there is no interior mutability to race on.
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Map all of `file`, or `None` if it is empty or the platform doesn't
+    /// map files.
+    #[cfg(unix)]
+    pub fn of(file: &File) -> Result<Option<Self>> {
+        use std::os::unix::io::AsRawFd;
+        let len = usize::try_from(file.metadata()?.len())?;
+        if len == 0 {
+            return Ok(None);
+        }
+        let prot = libc::PROT_READ;
+        // SAFETY: a fresh mapping at an address the kernel picks, so no
+        // existing memory is affected; `file` is open for reading, and
+        // `len` is its non-zero length. The kernel keeps its own reference
+        // to the file, so the mapping outlives the handle.
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, prot, libc::MAP_SHARED, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Some(Mapping { ptr: ptr as *const u8, len }))
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_file: &File) -> Result<Option<Self>> {
+        Ok(None)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        // SAFETY: `ptr` is a live mapping of `len` readable bytes until
+        // `self` is dropped, which the returned borrow can't outlive. Only
+        // files nothing else truncates are mapped (see above), so every
+        // page stays backed.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl std::fmt::Debug for Mapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mapping").field("len", &self.len).finish()
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: unmaps exactly the mapping `of` made, once, with no
+        // borrow of it outstanding as `bytes` borrows `self`.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
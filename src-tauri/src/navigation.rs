@@ -0,0 +1,120 @@
+use crate::i18n::{t, tf};
+use crate::TileStore;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Manager, Runtime, Url, Webview};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_shell::ShellExt;
+
+// ── Navigation policy ────────────────────────────────────────────────────────
+//
+// Links inside a tile must not turn the document's frame (or the app shell)
+// into a web browser. `tile:` and the app's own pages navigate normally;
+// anything else is cancelled, and an `http(s)` or `mailto` link is handed to
+// the system browser once the user agrees. The answer is kept for the rest
+// of the session per tile and origin, and a tile can't stack prompts, so a
+// script can neither leak data through query strings nor open tabs without
+// end. WebKit reports navigations of every frame to this hook; WebView2 only
+// reports top-level ones, where the iframe sandbox already keeps tiles from
+// navigating the shell.
+
+/// Whether the user let each tile (by authority, empty when the navigation
+/// didn't come from a tile's own window) open each origin elsewhere, or
+/// `None` while they are being asked.
+#[derive(Default)]
+struct ExternalOrigins(Mutex<HashMap<(String, String), Option<bool>>>);
+
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("navigation")
+        .setup(|app, _| {
+            app.manage(ExternalOrigins::default());
+            Ok(())
+        })
+        .on_navigation(|webview, url| {
+            let dev_url = webview.config().build.dev_url.as_ref();
+            if is_internal(url, dev_url) {
+                return true;
+            }
+            if matches!(url.scheme(), "http" | "https" | "mailto") {
+                open_externally(webview, url);
+            }
+            false
+        })
+        .build()
+}
+
+/// Whether `url` may load inside the app's webview.
+fn is_internal(url: &Url, dev_url: Option<&Url>) -> bool {
+    match url.scheme() {
+        "tile" | "tauri" | "about" | "blob" => true,
+        // The app shell itself: the Windows asset origin, or in a debug
+        // build the dev server.
+        "http" | "https" => {
+            let dev_server = dev_url.is_some_and(|dev| dev.origin() == url.origin());
+            url.host_str() == Some("tauri.localhost") || (cfg!(debug_assertions) && dev_server)
+        }
+        _ => false,
+    }
+}
+
+/// Open `url` in the system browser if the user lets the tile navigating to
+/// it do so, asking the first time.
+fn open_externally<R: Runtime>(webview: &Webview<R>, url: &Url) {
+    let authority = webview
+        .url()
+        .ok()
+        .filter(|own| own.scheme() == "tile")
+        .and_then(|own| own.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let origin = match url.scheme() {
+        "mailto" => "mailto:".to_string(),
+        _ => url.origin().ascii_serialization(),
+    };
+    let key = (authority, origin);
+    let app = webview.app_handle().clone();
+    let allowed = {
+        let origins = app.state::<ExternalOrigins>();
+        let mut origins = origins.0.lock().unwrap();
+        match origins.get(&key) {
+            Some(Some(true)) => true,
+            // Refused, or still being asked: this navigation is dropped.
+            Some(_) => return,
+            None => {
+                origins.insert(key.clone(), None);
+                false
+            }
+        }
+    };
+    if allowed {
+        open(&app, url);
+    } else {
+        let url = url.clone();
+        std::thread::spawn(move || ask(&app, key, &url));
+    }
+}
+
+fn ask<R: Runtime>(app: &AppHandle<R>, key: (String, String), url: &Url) {
+    let tile = app.state::<TileStore>().get(&key.0);
+    let name = tile.map_or_else(|| t("navigation.unnamed"), |tile| tile.masl.name.clone());
+    let message = tf("navigation.prompt", &[("name", &name), ("origin", &key.1)]);
+    let (open_it, cancel) = (t("navigation.open"), t("navigation.cancel"));
+    let allowed = app
+        .dialog()
+        .message(message)
+        .title(t("navigation.title"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(open_it, cancel))
+        .blocking_show();
+    app.state::<ExternalOrigins>().0.lock().unwrap().insert(key, Some(allowed));
+    if allowed {
+        open(app, url);
+    }
+}
+
+fn open<R: Runtime>(app: &AppHandle<R>, url: &Url) {
+    #[allow(deprecated)] // shell's opener is what this app ships with
+    if let Err(e) = app.shell().open(url.as_str(), None) {
+        tracing::warn!("opening {url} externally failed: {e}");
+    }
+}
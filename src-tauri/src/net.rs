@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// ── HTTP helpers ─────────────────────────────────────────────────────────────
+
+/// Start a GET with the timeout every outbound request in the app uses.
+pub fn get(url: &str) -> ureq::Request {
+    ureq::get(url).timeout(Duration::from_secs(60))
+}
+
+/// Fetch `url` and decode the body as JSON.
+pub fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
+    get(url)
+        .call()
+        .map_err(|e| anyhow!("fetching {url}: {e}"))?
+        .into_json()
+        .map_err(|e| anyhow!("decoding {url}: {e}"))
+}
+
+/// Stream a response body into `dest`, returning the SHA-256 of the bytes.
+pub fn download_to(response: ureq::Response, dest: &Path) -> Result<[u8; 32]> {
+    let mut reader = response.into_reader();
+    let mut out = File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        out.write_all(&buf[..n])?;
+    }
+    out.flush()?;
+    Ok(hasher.finalize().into())
+}
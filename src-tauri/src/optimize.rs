@@ -0,0 +1,345 @@
+use crate::builder::TileBuilder;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// ── Asset optimisation ───────────────────────────────────────────────────────
+//
+// Optional transforms applied to a tile's resources before it is written.
+// Each one is conservative: if it can't be sure a rewrite is equivalent, it
+// leaves the bytes alone. A result that comes out no smaller is dropped.
+//
+// - Minifying HTML collapses runs of whitespace in text and drops comments.
+//   Tags, attribute values, and `<pre>`, `<textarea>`, `<script>` and
+//   `<style>` bodies are left as they are. CSS loses comments and the
+//   whitespace around `{ } ; ,`. JavaScript only loses indentation and blank
+//   lines, and not even that when it has template literals or line
+//   continuations, where that whitespace can be part of a string.
+// - Recompressing re-encodes PNGs at the best zlib level with adaptive
+//   filtering, losslessly. Animated PNGs are skipped. There is no JPEG codec
+//   here, so JPEGs are only ever stripped.
+// - Stripping metadata removes text, EXIF and timestamp chunks from PNGs, and
+//   EXIF, XMP, IPTC and comment segments from JPEGs. Colour profiles stay.
+//   EXIF that rotates the image is kept, since dropping it would display the
+//   image sideways.
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct OptimizeOptions {
+    #[serde(default)]
+    pub minify: bool,
+    #[serde(default)]
+    pub recompress_images: bool,
+    #[serde(default)]
+    pub strip_metadata: bool,
+}
+
+/// A resource an optimisation made smaller.
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizedResource {
+    pub path: String,
+    pub before: u64,
+    pub after: u64,
+    /// Which transforms took effect: `minify`, `recompress`, `strip`.
+    pub applied: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OptimizeReport {
+    pub resources: Vec<OptimizedResource>,
+    /// Sizes over every resource, changed or not.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// One rewrite of a resource body; `None` when it doesn't apply.
+type Transform = fn(&[u8]) -> Option<Vec<u8>>;
+
+/// Apply `options` to every resource in `builder`.
+pub fn optimize(builder: &mut TileBuilder, options: OptimizeOptions) -> Result<OptimizeReport> {
+    let mut report = OptimizeReport::default();
+    let paths: Vec<String> = builder.paths().map(str::to_string).collect();
+    for path in paths {
+        let Some(body) = builder.body(&path) else { continue };
+        let before = body.len() as u64;
+        report.bytes_before += before;
+        let content_type = builder.resource_header(&path, "content-type").unwrap_or_default();
+        let mime = content_type.split(';').next().unwrap_or_default().trim().to_string();
+
+        let mut steps: Vec<(&'static str, Transform)> = Vec::new();
+        if options.minify {
+            let minifier: Option<Transform> = match mime.as_str() {
+                "text/html" => Some(|b| Some(minify_html(std::str::from_utf8(b).ok()?).into())),
+                "text/css" => Some(|b| Some(minify_css(std::str::from_utf8(b).ok()?).into())),
+                "text/javascript" | "application/javascript" => {
+                    Some(|b| Some(minify_js(std::str::from_utf8(b).ok()?)?.into()))
+                }
+                _ => None,
+            };
+            steps.extend(minifier.map(|m| ("minify", m)));
+        }
+        if options.strip_metadata {
+            let stripper: Option<Transform> = match mime.as_str() {
+                "image/png" => Some(strip_png),
+                "image/jpeg" => Some(strip_jpeg),
+                _ => None,
+            };
+            steps.extend(stripper.map(|s| ("strip", s)));
+        }
+        if options.recompress_images && mime == "image/png" {
+            steps.push(("recompress", recompress_png));
+        }
+
+        let mut bytes = body.to_vec();
+        let mut applied = Vec::new();
+        for (name, transform) in steps {
+            if let Some(out) = transform(&bytes).filter(|out| out.len() < bytes.len()) {
+                bytes = out;
+                applied.push(name);
+            }
+        }
+
+        let after = bytes.len() as u64;
+        report.bytes_after += after;
+        if !applied.is_empty() {
+            builder.set_body(&path, bytes)?;
+            report.resources.push(OptimizedResource { path, before, after, applied });
+        }
+    }
+    Ok(report)
+}
+
+// ── Text ─────────────────────────────────────────────────────────────────────
+
+/// Elements whose content is copied verbatim.
+const RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+fn minify_html(src: &str) -> String {
+    let b = src.as_bytes();
+    let mut out = String::with_capacity(src.len());
+    let mut i = 0;
+    while i < b.len() {
+        if src[i..].starts_with("<!--") && !src[i..].starts_with("<!--[if") {
+            match src[i + 4..].find("-->") {
+                Some(end) => i += 4 + end + 3,
+                None => {
+                    out.push_str(&src[i..]);
+                    break;
+                }
+            }
+        } else if b[i] == b'<' {
+            let end = tag_end(b, i);
+            let tag = &src[i..end];
+            out.push_str(tag);
+            i = end;
+            let name: String = tag[1..]
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .map(|c| c.to_ascii_lowercase())
+                .collect();
+            if RAW_ELEMENTS.contains(&name.as_str()) {
+                let close = format!("</{name}");
+                let rest = src[i..].to_ascii_lowercase();
+                let raw_end = rest.find(&close).map_or(b.len(), |n| i + n);
+                out.push_str(&src[i..raw_end]);
+                i = raw_end;
+            }
+        } else if b[i].is_ascii_whitespace() {
+            let start = i;
+            while i < b.len() && b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            out.push(if src[start..i].contains('\n') { '\n' } else { ' ' });
+        } else {
+            let start = i;
+            while i < b.len() && b[i] != b'<' && !b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            out.push_str(&src[start..i]);
+        }
+    }
+    out
+}
+
+/// Index just past the `>` closing the tag that opens at `start`, skipping
+/// `>` inside quoted attribute values.
+fn tag_end(b: &[u8], start: usize) -> usize {
+    let mut quote = None;
+    for (i, &c) in b.iter().enumerate().skip(start + 1) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if c == b'>' => return i + 1,
+            None => {}
+        }
+    }
+    b.len()
+}
+
+fn minify_css(src: &str) -> String {
+    const TIGHT: &[u8] = b"{};,";
+    let b = src.as_bytes();
+    let mut out = String::with_capacity(src.len());
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                i = src[i + 2..].find("*/").map_or(b.len(), |n| i + 2 + n + 2);
+            }
+            q @ (b'"' | b'\'') => {
+                let start = i;
+                i += 1;
+                while i < b.len() && b[i] != q {
+                    i += if b[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(b.len());
+                out.push_str(&src[start..i]);
+            }
+            c if c.is_ascii_whitespace() => {
+                while i < b.len() && b[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                let prev = out.as_bytes().last();
+                let next = b.get(i);
+                let needed = prev.is_some_and(|p| !TIGHT.contains(p))
+                    && next.is_some_and(|n| !TIGHT.contains(n));
+                if needed {
+                    out.push(' ');
+                }
+            }
+            b'}' if out.ends_with(';') => {
+                out.pop();
+                out.push('}');
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                i += 1;
+                while i < b.len() && !b"/\"' \t\r\n}".contains(&b[i]) {
+                    i += 1;
+                }
+                out.push_str(&src[start..i]);
+            }
+        }
+    }
+    out
+}
+
+/// Indentation and blank lines only; `None` where even that isn't safe.
+fn minify_js(src: &str) -> Option<String> {
+    if src.contains('`') || src.lines().any(|l| l.trim_end().ends_with('\\')) {
+        return None;
+    }
+    let lines: Vec<&str> = src.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    Some(lines.join("\n") + "\n")
+}
+
+// ── Images ───────────────────────────────────────────────────────────────────
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// EXIF orientation value meaning "as stored".
+const UPRIGHT: u16 = 1;
+
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = bytes.strip_prefix(PNG_SIGNATURE)?;
+    let mut out = PNG_SIGNATURE.to_vec();
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let chunk = rest.get(..12usize.checked_add(len)?)?;
+        let kind = &chunk[4..8];
+        let drop = match kind {
+            b"tEXt" | b"zTXt" | b"iTXt" | b"tIME" => true,
+            b"eXIf" => exif_orientation(&chunk[8..8 + len]).is_none_or(|o| o == UPRIGHT),
+            _ => false,
+        };
+        if !drop {
+            out.extend_from_slice(chunk);
+        }
+        rest = &rest[chunk.len()..];
+    }
+    Some(out)
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    const EXIF: &[u8] = b"Exif\0\0";
+    const XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+    let mut rest = bytes.strip_prefix(b"\xff\xd8")?;
+    let mut out = b"\xff\xd8".to_vec();
+    loop {
+        if rest.first() != Some(&0xff) {
+            return None;
+        }
+        let marker = *rest.get(1)?;
+        // Standalone markers carry no length.
+        if marker == 0x01 || (0xd0..=0xd7).contains(&marker) || marker == 0xff {
+            out.extend_from_slice(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        // From the start of scan on it is entropy-coded data: copy as is.
+        if marker == 0xda || marker == 0xd9 {
+            out.extend_from_slice(rest);
+            return Some(out);
+        }
+        let len = u16::from_be_bytes([*rest.get(2)?, *rest.get(3)?]) as usize;
+        let segment = rest.get(..2 + len)?;
+        let payload = segment.get(4..)?;
+        let drop = match marker {
+            0xe1 if payload.starts_with(EXIF) => {
+                exif_orientation(&payload[EXIF.len()..]).is_none_or(|o| o == UPRIGHT)
+            }
+            0xe1 => payload.starts_with(XMP),
+            0xed | 0xfe => true,
+            _ => false,
+        };
+        if !drop {
+            out.extend_from_slice(segment);
+        }
+        rest = &rest[segment.len()..];
+    }
+}
+
+/// The orientation tag from the first IFD of a TIFF-structured EXIF block.
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| -> Option<u16> {
+        let b: [u8; 2] = tiff.get(at..at + 2)?.try_into().ok()?;
+        Some(if big { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    };
+    let u32_at = |at: usize| -> Option<u32> {
+        let b: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if big { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    };
+    let ifd = u32_at(4)? as usize;
+    let count = u16_at(ifd)? as usize;
+    (0..count)
+        .map(|n| ifd + 2 + n * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+fn recompress_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info().ok()?;
+    if reader.info().animation_control.is_some() {
+        return None;
+    }
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).ok()?;
+    let mut info = reader.info().clone();
+    info.interlaced = false;
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::with_info(&mut out, info).ok()?;
+    encoder.set_compression(png::Compression::Best);
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(&pixels[..frame.buffer_size()]).ok()?;
+    writer.finish().ok()?;
+    Some(out)
+}
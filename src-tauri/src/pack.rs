@@ -0,0 +1,418 @@
+use crate::builder::{CidOptions, MaslFields, TileBuilder};
+use crate::car::FileStamp;
+use crate::compress::{self, CompressOptions, CompressReport};
+use crate::directory;
+use crate::icons;
+use crate::optimize::{self, OptimizeOptions, OptimizeReport};
+use crate::signing::{self, Signer};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+// ── Folder packing ───────────────────────────────────────────────────────────
+//
+// Turn a directory into a tile: every regular file below it becomes a
+// resource at its relative path, with a content type from its extension or,
+// failing that, from sniffing its first bytes. Dotfiles and dot-directories
+// (`.git`, `.DS_Store`) are skipped, and symlinked directories aren't
+// followed. The start page is `/index.html`; a folder without one but with a
+// single top-level HTML file gets that file served there too. Files with
+// identical contents are stored as one block, and the summary says which.
+
+/// Bytes looked at when sniffing a file's type.
+const SNIFF_LEN: usize = 512;
+
+/// How a packed file's content type was decided.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TypeSource {
+    Extension,
+    Sniffed,
+    /// Neither worked: served as `application/octet-stream`.
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackedFile {
+    /// Resource path, e.g. `/css/site.css`.
+    pub path: String,
+    pub content_type: String,
+    pub type_source: TypeSource,
+    pub size: u64,
+}
+
+/// What [`pack`] put in the tile.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackSummary {
+    /// Resource path of the start page.
+    pub entry: String,
+    /// Set when `/index.html` is served from another file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_alias_of: Option<String>,
+    pub files: Vec<PackedFile>,
+    pub total_bytes: u64,
+    /// What optimisation saved, when it was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimization: Option<OptimizeReport>,
+    /// Files stored once for several paths, once the tile is assembled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<DedupReport>,
+    /// Precompressed variants stored, when compression was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressReport>,
+}
+
+/// Resources sharing one block because their bodies are identical.
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedBody {
+    pub paths: Vec<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupReport {
+    pub shared: Vec<SharedBody>,
+    /// Bytes not written because each shared body is stored once.
+    pub bytes_saved: u64,
+}
+
+impl DedupReport {
+    pub fn of(builder: &TileBuilder) -> Self {
+        let shared: Vec<SharedBody> = builder
+            .identical_bodies()
+            .into_iter()
+            .map(|paths| {
+                let size = builder.body(&paths[0]).map_or(0, |b| b.len() as u64);
+                SharedBody { paths, size }
+            })
+            .collect();
+        let bytes_saved = shared.iter().map(|s| s.size * (s.paths.len() as u64 - 1)).sum();
+        DedupReport { shared, bytes_saved }
+    }
+}
+
+/// File bodies read by an earlier pack, reused while the file's size and
+/// modification time are unchanged. Lets a folder be repacked after an edit
+/// without reading every file again.
+#[derive(Default)]
+pub struct FileCache(HashMap<PathBuf, (FileStamp, Vec<u8>)>);
+
+impl FileCache {
+    fn read(&mut self, file: &Path) -> Result<Vec<u8>> {
+        let stamp = FileStamp::of(file)?;
+        if let Some((cached, bytes)) = self.0.get(file) {
+            if *cached == stamp {
+                return Ok(bytes.clone());
+            }
+        }
+        let bytes = std::fs::read(file)?;
+        self.0.insert(file.to_path_buf(), (stamp, bytes.clone()));
+        Ok(bytes)
+    }
+}
+
+/// Add every file under `dir` to `builder`, returning what was added.
+pub fn add_folder(builder: &mut TileBuilder, dir: &Path) -> Result<PackSummary> {
+    add_folder_cached(builder, dir, &mut FileCache::default())
+}
+
+/// [`add_folder`], reading unchanged files from `cache`.
+pub fn add_folder_cached(
+    builder: &mut TileBuilder,
+    dir: &Path,
+    cache: &mut FileCache,
+) -> Result<PackSummary> {
+    let found = walk(dir)?;
+    cache.0.retain(|path, _| found.contains(path));
+    let mut files = Vec::new();
+    for file in found {
+        let bytes = cache.read(&file)?;
+        let path = resource_path(dir, &file)?;
+        let (content_type, type_source) = detect_type(&path, &bytes);
+        let size = bytes.len() as u64;
+        builder.add_resource(&path, bytes, content_type);
+        files.push(PackedFile { path, content_type: content_type.to_string(), type_source, size });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut entry_alias_of = None;
+    if !files.iter().any(|f| f.path == "/index.html") {
+        let top_level_html: Vec<&PackedFile> = files
+            .iter()
+            .filter(|f| f.path.matches('/').count() == 1 && f.content_type == "text/html")
+            .collect();
+        let [page] = top_level_html.as_slice() else {
+            bail!("{} has no index.html to use as the start page", dir.display());
+        };
+        let bytes = cache.read(&dir.join(&page.path[1..]))?;
+        builder.add_resource("/index.html", bytes, "text/html");
+        entry_alias_of = Some(page.path.clone());
+    }
+
+    Ok(PackSummary {
+        entry: "/index.html".into(),
+        entry_alias_of,
+        total_bytes: files.iter().map(|f| f.size).sum(),
+        files,
+        optimization: None,
+        dedup: None,
+        compression: None,
+    })
+}
+
+/// Per-run packing choices.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PackOptions {
+    /// Transforms applied to the files on the way in (see `optimize.rs`).
+    #[serde(default)]
+    pub optimize: Option<OptimizeOptions>,
+    /// Which resources get precompressed variants (see `compress.rs`).
+    #[serde(default)]
+    pub compress: Option<CompressOptions>,
+    /// Hash function and codec for every resource's CID.
+    #[serde(default)]
+    pub cid: CidOptions,
+    /// Resource path → CID options for that resource instead.
+    #[serde(default)]
+    pub cid_overrides: HashMap<String, CidOptions>,
+    /// Image to generate the tile's icons from (see `icons.rs`): a resource
+    /// path in the folder, such as `/logo.png`, or a file elsewhere.
+    #[serde(default)]
+    pub icon_source: Option<String>,
+}
+
+/// Pack `dir` into a tile at `dest`, signed by `signer` if given. A folder
+/// with a `masl.json`, as a directory tile or an unpacked tile has, takes
+/// its metadata, icons and headers from it (see `directory.rs`). Otherwise
+/// the tile is named after the folder. `metadata` that names the tile is
+/// used either way.
+pub fn pack(
+    dir: &Path,
+    dest: &Path,
+    metadata: Option<MaslFields>,
+    options: PackOptions,
+    signer: Option<Signer>,
+) -> Result<PackSummary> {
+    let mut builder = TileBuilder::new();
+    builder.set_cid_options(options.cid);
+    let mut summary = add_folder(&mut builder, dir)?;
+    let named = metadata.as_ref().is_some_and(|m| !m.name.trim().is_empty());
+    if !directory::apply_manifest(&mut builder, dir, &mut summary)? || named {
+        builder.set_metadata(folder_metadata(dir, metadata)?);
+    }
+    write(builder, summary, dest, options, signer)
+}
+
+/// `metadata`, named after the folder `dir` if it doesn't name the tile.
+pub(crate) fn folder_metadata(dir: &Path, metadata: Option<MaslFields>) -> Result<MaslFields> {
+    let mut metadata = metadata.unwrap_or_default();
+    if metadata.name.trim().is_empty() {
+        metadata.name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("can't name a tile after {}", dir.display()))?;
+    }
+    Ok(metadata)
+}
+
+/// The rest of [`pack`] once the files are in `builder`: icons, CID
+/// overrides, optimisation and compression as `options` asks, then signing
+/// and writing the tile to `dest`.
+pub(crate) fn write(
+    mut builder: TileBuilder,
+    mut summary: PackSummary,
+    dest: &Path,
+    options: PackOptions,
+    signer: Option<Signer>,
+) -> Result<PackSummary> {
+    if let Some(source) = &options.icon_source {
+        let bytes = match builder.body(source) {
+            Some(body) => body.to_vec(),
+            None => std::fs::read(source)
+                .map_err(|e| anyhow!("can't read icon source {source}: {e}"))?,
+        };
+        let generated = icons::add_generated_icons(&mut builder, &bytes, source)?;
+        for icon in &generated {
+            let body = builder.body(&icon.src).unwrap_or_default();
+            let (content_type, type_source) = detect_type(&icon.src, body);
+            summary.total_bytes += body.len() as u64;
+            summary.files.push(PackedFile {
+                path: icon.src.clone(),
+                content_type: content_type.to_string(),
+                type_source,
+                size: body.len() as u64,
+            });
+        }
+        summary.files.sort_by(|a, b| a.path.cmp(&b.path));
+        builder.set_icons(generated);
+    }
+    for (path, cid) in options.cid_overrides {
+        builder.set_resource_cid_options(&path, cid)?;
+    }
+    if let Some(optimize) = options.optimize {
+        summary.optimization = Some(optimize::optimize(&mut builder, optimize)?);
+    }
+    if let Some(compress) = &options.compress {
+        summary.compression = Some(compress::compress(&mut builder, compress)?);
+    }
+    summary.dedup = Some(DedupReport::of(&builder));
+    builder.set_signer(signer).finish(dest)?;
+    Ok(summary)
+}
+
+/// Regular files under `dir`, skipping dotfiles and symlinked directories.
+pub(crate) fn walk(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let kind = entry.file_type()?;
+            if kind.is_dir() {
+                pending.push(entry.path());
+            } else if kind.is_file() || (kind.is_symlink() && entry.path().is_file()) {
+                out.push(entry.path());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// `/`-separated resource path of `file` relative to `dir`.
+pub(crate) fn resource_path(dir: &Path, file: &Path) -> Result<String> {
+    let relative = file.strip_prefix(dir)?;
+    let mut path = String::new();
+    for part in relative.components() {
+        let part = part
+            .as_os_str()
+            .to_str()
+            .ok_or_else(|| anyhow!("non-UTF-8 file name {}", file.display()))?;
+        path.push('/');
+        path.push_str(part);
+    }
+    Ok(path)
+}
+
+// ── Content types ────────────────────────────────────────────────────────────
+
+const BY_EXTENSION: [(&str, &str); 32] = [
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("json", "application/json"),
+    ("webmanifest", "application/manifest+json"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("pdf", "application/pdf"),
+    ("wasm", "application/wasm"),
+    ("zip", "application/zip"),
+    ("map", "application/json"),
+];
+
+/// Leading bytes of formats recognisable by signature alone.
+const MAGIC: [(&[u8], &str); 9] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"\0asm", "application/wasm"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"PK\x03\x04", "application/zip"),
+];
+
+pub(crate) fn detect_type(path: &str, bytes: &[u8]) -> (&'static str, TypeSource) {
+    let ext = path.rsplit('/').next().and_then(|name| name.rsplit_once('.')).map(|(_, e)| e);
+    if let Some(ext) = ext {
+        let ext = ext.to_ascii_lowercase();
+        if let Some((_, t)) = BY_EXTENSION.iter().find(|(e, _)| *e == ext) {
+            return (t, TypeSource::Extension);
+        }
+    }
+    match sniff(&bytes[..bytes.len().min(SNIFF_LEN)]) {
+        Some(t) => (t, TypeSource::Sniffed),
+        None => ("application/octet-stream", TypeSource::Default),
+    }
+}
+
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    if let Some((_, t)) = MAGIC.iter().find(|(sig, _)| head.starts_with(sig)) {
+        return Some(t);
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    // The cut at `SNIFF_LEN` may split a character; only worry about
+    // invalid UTF-8 before that.
+    let valid = match std::str::from_utf8(head) {
+        Ok(s) => s,
+        Err(e) if head.len() - e.valid_up_to() < 4 => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    if valid.contains('\0') {
+        return None;
+    }
+    let text = valid.trim_start().to_ascii_lowercase();
+    if text.starts_with("<!doctype html") || text.starts_with("<html") {
+        Some("text/html")
+    } else if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+        Some("image/svg+xml")
+    } else if text.starts_with("<?xml") {
+        Some("application/xml")
+    } else if text.starts_with('{') || text.starts_with('[') {
+        Some("application/json")
+    } else {
+        Some("text/plain")
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Pack the folder `src_dir` into a tile at `dest`, signed if signing on
+/// export is on.
+#[tauri::command]
+pub async fn pack_folder(
+    src_dir: String,
+    dest: String,
+    metadata: Option<MaslFields>,
+    options: Option<PackOptions>,
+    app: AppHandle,
+) -> Result<PackSummary, String> {
+    let signer = signing::export_signer(&app).map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = options.unwrap_or_default();
+        pack(Path::new(&src_dir), Path::new(&dest), metadata, options, signer)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
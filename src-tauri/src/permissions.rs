@@ -0,0 +1,191 @@
+use crate::audit::{self, AuditKind};
+use crate::cache::BlockCache;
+use crate::i18n::{t, tf};
+use crate::private::{self, PrivateSessions};
+use crate::trust::TrustStore;
+use crate::TileStore;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+// ── Permissions ──────────────────────────────────────────────────────────────
+//
+// Tiles are offline by default: every `tile:` response carries a CSP that
+// limits fetches, subresources, and sockets to the tile itself. A tile that
+// needs more declares it in its MASL `permissions` and asks for it at runtime
+// through the frontend bridge (`postMessage` to the shell). The backend
+// prompts natively and records the answer in the trust store against the
+// tile's root hash, so a grant covers exactly that content: an edited tile
+// asks again. A tile opened in safe mode gets a CSP with no script at all and
+// can't be granted anything. Whatever is granted, forms only post to the
+// tile and `<base>` can't point relative URLs elsewhere.
+
+/// Permission allowing tile content to reach `http(s)`/`ws(s)` URLs.
+pub const NETWORK: &str = "network";
+
+/// Permission allowing a tile to register a service worker. A worker keeps
+/// running after the document closes, outliving the decision to open it, so
+/// its script is refused without this.
+pub const SERVICE_WORKER: &str = "service-worker";
+
+/// Everything a tile can ask for. The prompt wording for each is
+/// `permission.<name>` in the translation tables.
+const KNOWN: [&str; 5] =
+    [NETWORK, SERVICE_WORKER, "clipboard-write", "notifications", "fullscreen"];
+
+/// CSP sent with tile responses unless [`NETWORK`] has been granted.
+/// Inline and eval'd script stay allowed: tiles are self-contained apps.
+pub const OFFLINE_CSP: &str = "default-src 'self' data: blob: 'unsafe-inline' 'unsafe-eval'; \
+     form-action 'self'; base-uri 'self'";
+
+/// CSP sent once [`NETWORK`] has been granted: loads may go anywhere, but
+/// forms still post to the tile and relative URLs stay relative to it.
+pub const NETWORK_CSP: &str = "form-action 'self'; base-uri 'self'";
+
+/// CSP sent in safe mode, replacing any other: no script runs at all, and
+/// nothing loads from outside the tile.
+pub const SAFE_CSP: &str = "default-src 'self' data: blob:; style-src 'self' data: blob: 'unsafe-inline'; \
+     script-src 'none'; object-src 'none'; form-action 'self'; base-uri 'self'";
+
+/// Authorities of tiles opened in safe mode, for reading documents the user
+/// doesn't trust without executing their code.
+#[derive(Default)]
+pub struct SafeMode(Mutex<HashSet<String>>);
+
+impl SafeMode {
+    pub fn set(&self, authority: &str, on: bool) {
+        let mut safe = self.0.lock().unwrap();
+        if on {
+            safe.insert(authority.to_string());
+        } else {
+            safe.remove(authority);
+        }
+    }
+
+    pub fn is_on(&self, authority: &str) -> bool {
+        self.0.lock().unwrap().contains(authority)
+    }
+}
+
+/// Whether the open tile at `authority` holds `permission`. Tiles in safe
+/// mode hold none.
+pub fn granted<R: Runtime>(app: &AppHandle<R>, authority: &str, permission: &str) -> bool {
+    if is_safe(app, authority) {
+        return false;
+    }
+    if private::is_private(app, authority) {
+        return app.state::<PrivateSessions>().has_permission(authority, permission);
+    }
+    let Some(trust) = app.try_state::<TrustStore>() else { return false };
+    let Some(tile) = app.state::<TileStore>().get(authority) else { return false };
+    trust.has_permission(&tile.root_hash, permission)
+}
+
+/// The CSP to send for `authority`'s responses.
+pub fn csp_for<R: Runtime>(app: &AppHandle<R>, authority: &str) -> &'static str {
+    if is_safe(app, authority) {
+        SAFE_CSP
+    } else if granted(app, authority, NETWORK) {
+        NETWORK_CSP
+    } else {
+        OFFLINE_CSP
+    }
+}
+
+fn is_safe<R: Runtime>(app: &AppHandle<R>, authority: &str) -> bool {
+    app.try_state::<SafeMode>().is_some_and(|s| s.is_on(authority))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Ask the user to grant `permission` to an open tile, unless it already
+/// holds it. Only permissions the tile declares in its MASL can be asked for.
+/// Resolves to whether the permission is now granted.
+#[tauri::command]
+pub async fn request_permission(
+    authority: String,
+    permission: String,
+    app: AppHandle,
+) -> Result<bool, String> {
+    let outcome = prompt_for(&authority, &permission, &app).await;
+    let recorded = match &outcome {
+        Ok(true) => "granted",
+        Ok(false) => "denied",
+        Err(_) => "rejected",
+    };
+    audit::record(&app, &authority, AuditKind::Permission, &permission, recorded);
+    outcome
+}
+
+async fn prompt_for(authority: &str, permission: &str, app: &AppHandle) -> Result<bool, String> {
+    let tile = app.state::<TileStore>().get(authority).ok_or_else(|| t("error.tile-not-loaded"))?;
+    if is_safe(app, authority) {
+        return Err(t("error.safe-mode"));
+    }
+    let named = [("permission", permission)];
+    if !KNOWN.contains(&permission) {
+        return Err(tf("error.unknown-permission", &named));
+    }
+    if !tile.masl.permissions.iter().any(|p| p == permission) {
+        return Err(tf("error.undeclared-permission", &named));
+    }
+    if granted(app, authority, permission) {
+        return Ok(true);
+    }
+
+    let wording = t(&format!("permission.{permission}"));
+    let message = tf("permission.prompt", &[("name", &tile.masl.name), ("action", &wording)]);
+    let (allow, deny) = (t("permission.allow"), t("permission.deny"));
+    let dialog = app.clone();
+    let allowed = tauri::async_runtime::spawn_blocking(move || {
+        dialog
+            .dialog()
+            .message(message)
+            .title(t("permission.title"))
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(allow, deny))
+            .blocking_show()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Grants are keyed by the header alone, so from now on every block is
+    // checked as it is served (see `vouched_for` in lib.rs).
+    if allowed {
+        tile.set_verify_reads(true);
+        if let Some(cache) = app.try_state::<BlockCache>() {
+            cache.evict_path(&tile.path);
+        }
+    }
+    if allowed && private::is_private(app, authority) {
+        app.state::<PrivateSessions>().grant(authority, permission);
+    } else if allowed {
+        app.state::<TrustStore>()
+            .grant(app, &tile, permission)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(allowed)
+}
+
+/// Permissions the open tile at `authority` currently holds.
+#[tauri::command]
+pub fn get_tile_permissions(authority: String, app: AppHandle) -> Vec<String> {
+    KNOWN
+        .into_iter()
+        .filter(|p| granted(&app, &authority, p))
+        .map(String::from)
+        .collect()
+}
+
+/// Revoke `permission` from the tile content with root hash `hash`, or every
+/// permission it holds when `permission` is omitted.
+#[tauri::command]
+pub fn revoke_permission(
+    hash: String,
+    permission: Option<String>,
+    trust: State<'_, TrustStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    trust.revoke(&app, &hash, permission.as_deref()).map_err(|e| e.to_string())
+}
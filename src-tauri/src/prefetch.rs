@@ -0,0 +1,83 @@
+use crate::cache::BlockCache;
+use crate::car::TileContent;
+use crate::pick_encoding;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
+use url::Url;
+
+// ── Start-page prefetch ──────────────────────────────────────────────────────
+//
+// Right after a tile opens, its entry HTML is read and scanned for `src` and
+// `href` attributes pointing at other resources in the same tile. Those
+// blocks are loaded into the block cache on the rayon pool, so the first
+// render finds them warm instead of paying disk latency one request at a
+// time.
+
+/// Assets warmed per tile, in document order.
+const MAX_ASSETS: usize = 64;
+/// Larger blocks (video, big archives) are left to be streamed on demand.
+const MAX_ASSET_BYTES: u64 = 4 << 20;
+/// What a browser normally negotiates, so the variant we warm is the one the
+/// webview will ask for.
+const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// Warm the cache for `tile`'s start page in the background.
+pub fn spawn<R: Runtime>(app: &AppHandle<R>, tile: Arc<TileContent>) {
+    let app = app.clone();
+    std::thread::spawn(move || prefetch(&app, &tile));
+}
+
+fn prefetch<R: Runtime>(app: &AppHandle<R>, tile: &TileContent) {
+    let cache = app.state::<BlockCache>();
+    let load = |src: &str| cache.get_or_load(&tile.path, src, || tile.read_block(src));
+
+    // The entry page is read as identity so it can be scanned.
+    let Some(entry) = tile.resolve("/").and_then(|r| r.get("src")) else { return };
+    let Ok(html) = load(entry) else { return };
+    let html = String::from_utf8_lossy(&html);
+
+    let Ok(base) = Url::parse("tile://prefetch/") else { return };
+    let mut seen = HashSet::new();
+    let srcs: Vec<&str> = asset_refs(&html)
+        .into_iter()
+        .filter_map(|r| base.join(r).ok())
+        .filter(|url| url.scheme() == "tile" && url.host_str() == Some("prefetch"))
+        .filter_map(|url| tile.resolve(url.path()))
+        .filter_map(|resource| Some(pick_encoding(resource, ACCEPT_ENCODING)?.0))
+        .filter(|src| *src != entry.as_str() && seen.insert(*src))
+        .filter(|src| tile.content_len(src).is_ok_and(|n| n <= MAX_ASSET_BYTES))
+        .take(MAX_ASSETS)
+        .collect();
+
+    srcs.par_iter().for_each(|src| {
+        let _ = load(src);
+    });
+}
+
+/// Values of `src=` and `href=` attributes in `html`, quoted or not.
+fn asset_refs(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets aligned with `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut refs = Vec::new();
+    for attr in ["src=", "href="] {
+        for (at, _) in lower.match_indices(attr) {
+            let preceded_by_space =
+                lower[..at].chars().next_back().is_some_and(|c| c.is_ascii_whitespace());
+            if !preceded_by_space {
+                continue;
+            }
+            let rest = &html[at + attr.len()..];
+            let value = match rest.chars().next() {
+                Some(q @ ('"' | '\'')) => rest[1..].split(q).next(),
+                _ => rest.split(|c: char| c.is_ascii_whitespace() || c == '>').next(),
+            };
+            if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+                refs.push((at, value));
+            }
+        }
+    }
+    refs.sort_by_key(|(at, _)| *at);
+    refs.into_iter().map(|(_, v)| v).collect()
+}
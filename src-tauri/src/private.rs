@@ -0,0 +1,98 @@
+use crate::cache::BlockCache;
+use crate::downloads;
+use crate::hashing::hex;
+use crate::i18n::t;
+use crate::ratelimit::RequestLimiter;
+use crate::{audit, TileStore};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, Url, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+// ── Private sessions ─────────────────────────────────────────────────────────
+//
+// A tile opened privately gets a fresh random authority, so its web origin,
+// and with it localStorage, IndexedDB, and cookies, is new and is never
+// served again once the session ends. It is shown in a window of its own
+// whose webview keeps its data in memory (`incognito`), so neither that
+// storage nor the HTTP cache reaches the disk. Nothing else about it is
+// written there either: no trust record, no cached parse, and permissions
+// granted to it last only for the session. Closing the window drops the tile
+// and everything held in memory for its authority.
+
+/// Authority prefix of private sessions.
+const PREFIX: &str = "private-";
+
+/// Open private sessions: authority → permissions granted this session.
+#[derive(Default)]
+pub struct PrivateSessions(Mutex<HashMap<String, BTreeSet<String>>>);
+
+impl PrivateSessions {
+    /// Start a session, returning its new authority.
+    pub fn open(&self) -> anyhow::Result<String> {
+        let mut id = [0u8; 16];
+        getrandom::getrandom(&mut id)?;
+        let authority = format!("{PREFIX}{}", hex(&id));
+        self.0.lock().unwrap().insert(authority.clone(), BTreeSet::new());
+        Ok(authority)
+    }
+
+    pub fn is_private(&self, authority: &str) -> bool {
+        self.0.lock().unwrap().contains_key(authority)
+    }
+
+    pub fn has_permission(&self, authority: &str, permission: &str) -> bool {
+        let sessions = self.0.lock().unwrap();
+        sessions.get(authority).is_some_and(|p| p.contains(permission))
+    }
+
+    pub fn grant(&self, authority: &str, permission: &str) {
+        if let Some(granted) = self.0.lock().unwrap().get_mut(authority) {
+            granted.insert(permission.to_string());
+        }
+    }
+}
+
+/// Whether `authority` is a private session.
+pub fn is_private<R: Runtime>(app: &AppHandle<R>, authority: &str) -> bool {
+    app.try_state::<PrivateSessions>().is_some_and(|s| s.is_private(authority))
+}
+
+/// End the private session at `authority` and forget everything held for it.
+pub fn close<R: Runtime>(app: &AppHandle<R>, authority: &str) {
+    if app.state::<PrivateSessions>().0.lock().unwrap().remove(authority).is_none() {
+        return;
+    }
+    let tile = app.state::<TileStore>().0.write().unwrap().remove(authority);
+    if let Some(tile) = tile {
+        app.state::<BlockCache>().evict_path(&tile.path);
+    }
+    audit::forget(app, authority);
+    app.state::<RequestLimiter>().forget(authority);
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Show the private session at `authority` in its own window. Called by the
+/// frontend once the permissions asked for up front have been answered.
+#[tauri::command]
+pub fn show_private_tile(authority: String, app: AppHandle) -> Result<(), String> {
+    if !is_private(&app, &authority) {
+        return Err(t("error.tile-not-loaded"));
+    }
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| t("error.tile-not-loaded"))?;
+    let url: Url = format!("tile://{authority}/").parse().map_err(|e| format!("{e}"))?;
+    let window = WebviewWindowBuilder::new(&app, &authority, WebviewUrl::CustomProtocol(url))
+        .title(&tile.masl.name)
+        .incognito(true)
+        .on_download(downloads::on_download)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let closing = app.clone();
+    window.on_window_event(move |event| {
+        if matches!(event, WindowEvent::Destroyed) {
+            close(&closing, &authority);
+        }
+    });
+    Ok(())
+}
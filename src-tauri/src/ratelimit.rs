@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+// ── Protocol rate limit ──────────────────────────────────────────────────────
+//
+// A token bucket per tile authority. Page loads burst well under the bucket
+// size; a tile hammering its own origin in a loop is answered with 429s
+// instead of keeping the blocking pool busy.
+
+/// Requests a tile may make at once.
+const BURST: f64 = 2000.0;
+/// Sustained requests per second once the burst is spent.
+const PER_SECOND: f64 = 500.0;
+
+#[derive(Default)]
+pub struct RequestLimiter(Mutex<HashMap<String, Bucket>>);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RequestLimiter {
+    /// Take a token for `authority`. `false` means the request should be
+    /// refused.
+    pub fn allow(&self, authority: &str) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.0.lock().unwrap();
+        let bucket = buckets
+            .entry(authority.to_string())
+            .or_insert(Bucket { tokens: BURST, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * PER_SECOND).min(BURST);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Drop `authority`'s bucket.
+    pub fn forget(&self, authority: &str) {
+        self.0.lock().unwrap().remove(authority);
+    }
+}
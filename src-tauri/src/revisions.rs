@@ -0,0 +1,162 @@
+use crate::car::{cbor_to_cid_string, read_masl, TileContent};
+use crate::hashing::SHA2_256;
+use crate::i18n;
+use crate::{signing, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use ciborium::value::Value as CborValue;
+use cid::multihash::Multihash;
+use cid::Cid;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+// ── Revision history ─────────────────────────────────────────────────────────
+//
+// A tile's history lives in the tile. When an edit keeps a revision (see
+// `edit::rewrite`), the CAR header being replaced is appended unchanged as a
+// DAG-CBOR block, and the new header links to it under `prev` and records
+// when it was written under `revised`. Each kept header links to the one
+// before it in the same way. Blocks are never rewritten, so an old header's
+// resources still point at the bodies it had. Bodies that didn't change are
+// shared with later revisions. An old header keeps its embedded signature,
+// which still verifies against it.
+//
+// Opening a revision serves that header's MASL over the same file, under
+// `<authority>-r<number>`, numbered from 1 for the oldest. Revision views are
+// read-only and aren't recorded in the trust store or the parse cache.
+
+/// Header key linking to the previous revision.
+pub const PREV: &str = "prev";
+/// Header key holding when the header was written, as RFC 3339.
+pub const REVISED: &str = "revised";
+
+/// Multicodec code for DAG-CBOR, the codec of kept headers.
+const DAG_CBOR: u64 = 0x71;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionInfo {
+    /// 1 for the oldest revision.
+    pub number: usize,
+    /// CID of the kept header, or `None` for the current one.
+    pub cid: Option<String>,
+    pub name: String,
+    pub revised: Option<String>,
+    pub resources: usize,
+}
+
+/// The CID a kept copy of `header` is stored under.
+pub(crate) fn header_cid(header: &[u8]) -> Result<Cid> {
+    Ok(Cid::new_v1(DAG_CBOR, Multihash::wrap(SHA2_256, &Sha256::digest(header))?))
+}
+
+/// The revision a header links back to, if any.
+pub(crate) fn previous(map: &[(CborValue, CborValue)]) -> Option<Cid> {
+    let (_, link) = map.iter().find(|(k, _)| k.as_text() == Some(PREV))?;
+    cbor_to_cid_string(link)?.parse().ok()
+}
+
+/// Every revision of `tile`'s file, oldest first, the current header last.
+pub fn history(tile: &TileContent) -> Result<Vec<RevisionInfo>> {
+    if tile.is_directory() {
+        bail!("a directory tile has no revisions");
+    }
+    // Earlier headers are read through this content's block index.
+    if tile.modified_on_disk() {
+        bail!("tile changed on disk; reload it first");
+    }
+    let header = read_masl(tile.car_path())?.masl;
+    let mut revisions = Vec::new();
+    let (mut cid, mut header) = (None, header);
+    let mut seen = HashSet::new();
+    loop {
+        let CborValue::Map(map) = ciborium::de::from_reader(header.as_slice())
+            .map_err(|e| anyhow!("CBOR decode error: {e}"))?
+        else {
+            bail!("revision header is not a CBOR map");
+        };
+        let field = |key: &str| {
+            map.iter().find(|(k, _)| k.as_text() == Some(key)).and_then(|(_, v)| v.as_text())
+        };
+        let resources = map
+            .iter()
+            .find(|(k, _)| k.as_text() == Some("resources"))
+            .and_then(|(_, v)| v.as_map())
+            .map_or(0, Vec::len);
+        revisions.push(RevisionInfo {
+            number: 0,
+            cid: cid.map(|c: Cid| c.to_string()),
+            name: field("name").unwrap_or_default().to_string(),
+            revised: field(REVISED).map(str::to_string),
+            resources,
+        });
+        let Some(prev) = previous(&map).filter(|prev| seen.insert(*prev)) else { break };
+        header = tile.read_block(&prev.to_string())?;
+        cid = Some(prev);
+    }
+    revisions.reverse();
+    for (i, revision) in revisions.iter_mut().enumerate() {
+        revision.number = i + 1;
+    }
+    Ok(revisions)
+}
+
+/// `content`, freshly parsed from the file, as of the revision `previous`
+/// showed, so a revision view stays on its revision across reloads.
+pub(crate) fn reopen(content: TileContent, previous: Option<&TileContent>) -> Result<TileContent> {
+    match previous.and_then(|p| p.revision.as_deref()) {
+        Some(cid) => content.as_of(cid),
+        None => Ok(content),
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Walking the `previous` chain reads every revision's header, so this runs
+/// off the main thread, as the other commands here do.
+#[tauri::command]
+pub async fn list_revisions(
+    authority: String,
+    app: AppHandle,
+) -> Result<Vec<RevisionInfo>, String> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    tauri::async_runtime::spawn_blocking(move || history(&tile).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Open the open tile `authority` as it was at revision `cid`, in a tab of
+/// its own.
+#[tauri::command]
+pub async fn open_revision(
+    authority: String,
+    cid: String,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || show_revision(&authority, &cid, &app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn show_revision(authority: &str, cid: &str, app: &AppHandle) -> Result<TileOpenedPayload, String> {
+    let state = app.state::<TileStore>();
+    let tile = state.get(authority).ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    if tile.revision.is_some() {
+        return Err("already showing a revision".into());
+    }
+    let revisions = history(&tile).map_err(|e| e.to_string())?;
+    let number = revisions
+        .iter()
+        .find(|r| r.cid.as_deref() == Some(cid))
+        .ok_or_else(|| format!("{cid} is not a revision of this tile"))?
+        .number;
+    let revision_authority = format!("{authority}-r{number}");
+    let content = tile.as_of(cid).map_err(|e| e.to_string())?;
+    let mut payload = TileOpenedPayload::new(&revision_authority, &content);
+    payload.signature = signing::status(app, &content);
+    state.0.write().unwrap().insert(revision_authority, Arc::new(content));
+    app.emit("tile:opened", &payload).map_err(|e| e.to_string())?;
+    Ok(payload)
+}
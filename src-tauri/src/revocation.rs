@@ -0,0 +1,65 @@
+use crate::net;
+use crate::settings::SettingsStore;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Key revocation ───────────────────────────────────────────────────────────
+//
+// Publisher keys can be revoked by listing them in `revoked-keys.json` in the
+// app config directory, or in a list served at `Settings::revocation_list_url`
+// in the same format. The remote list is fetched in the background at launch
+// and then hourly, so a signature check at open time never waits on the
+// network; if a fetch fails the last copy fetched stays in force.
+
+/// How often the remote list is refetched.
+const REFRESH: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokedKey {
+    /// Hex Ed25519 public key.
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Managed copy of the remote revocation list.
+#[derive(Default)]
+pub struct RevocationList(Mutex<Vec<RevokedKey>>);
+
+/// The revocation entry for `key`, from the local file or the last fetched
+/// remote list.
+pub fn revoked<R: Runtime>(app: &AppHandle<R>, key: &str) -> Option<RevokedKey> {
+    let matches = |entry: &RevokedKey| entry.key.eq_ignore_ascii_case(key);
+    let local: Vec<RevokedKey> = local_path(app)
+        .ok()
+        .and_then(|p| std::fs::read(p).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    if let Some(entry) = local.into_iter().find(matches) {
+        return Some(entry);
+    }
+    let remote = app.try_state::<RevocationList>()?;
+    let found = remote.0.lock().unwrap().iter().find(|e| matches(e)).cloned();
+    found
+}
+
+fn local_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(app.path().app_config_dir()?.join("revoked-keys.json"))
+}
+
+/// Start the background thread that keeps the remote list current.
+pub fn spawn_fetcher(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Some(url) = app.state::<SettingsStore>().get().revocation_list_url {
+            match net::get_json::<Vec<RevokedKey>>(&url) {
+                Ok(list) => *app.state::<RevocationList>().0.lock().unwrap() = list,
+                Err(e) => tracing::warn!("revocation list fetch failed: {e}"),
+            }
+        }
+        std::thread::sleep(REFRESH);
+    });
+}
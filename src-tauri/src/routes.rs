@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+// ── Resource route trie ──────────────────────────────────────────────────────
+//
+// Resolves request paths against the MASL resource map in a single walk over
+// `/`-separated segments instead of probing the map with several candidate
+// strings. Values are the resource-map keys themselves.
+//
+// Matching, most specific first at each level:
+//   - exact segment, then `*` or a `:name` parameter (any single segment;
+//     the two are the same route, served by whichever key sorts first, so
+//     `*` over any `:name` and `:id` over `:slug`, whatever the order the
+//     keys come in)
+//   - `/dir` also matches a `/dir/` key and vice versa
+//   - `/dir/` and `/` fall back to `index.html` inside them
+//   - a key ending in `/*` matches anything below its prefix; the deepest
+//     such match wins
+
+#[derive(Debug, Default, Clone)]
+pub struct RouteTrie {
+    root: Node,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Node {
+    children: HashMap<String, Node>,
+    /// Key for the path ending at this segment (`/a/b`).
+    exact: Option<String>,
+    /// Key for the path ending at this segment with a slash (`/a/b/`).
+    slash: Option<String>,
+    /// Key of a `/a/b/*` catch-all below this segment.
+    rest: Option<String>,
+}
+
+impl RouteTrie {
+    pub fn new<'a>(keys: impl IntoIterator<Item = &'a String>) -> Self {
+        let mut trie = RouteTrie::default();
+        for key in keys {
+            trie.insert(key);
+        }
+        trie
+    }
+
+    pub fn insert(&mut self, key: &str) {
+        let segs: Vec<&str> = segments(key).collect();
+        let mut node = &mut self.root;
+        for (i, seg) in segs.iter().enumerate() {
+            let last = i == segs.len() - 1;
+            match (last, *seg) {
+                (true, "") => claim(&mut node.slash, key),
+                (true, "*") => claim(&mut node.rest, key),
+                (true, _) => {
+                    node = node.children.entry(child_key(seg)).or_default();
+                    claim(&mut node.exact, key);
+                }
+                (false, _) => node = node.children.entry(child_key(seg)).or_default(),
+            }
+        }
+    }
+
+    /// The resource-map key that serves `path`, if any.
+    pub fn lookup(&self, path: &str) -> Option<&str> {
+        let segs: Vec<&str> = segments(path).collect();
+        find(&self.root, &segs)
+    }
+}
+
+/// Make `key` the one `slot` routes to, unless a key that sorts before it
+/// already is: keys differing only in their parameters' names or a `*`
+/// land in the same slot.
+fn claim(slot: &mut Option<String>, key: &str) {
+    if slot.as_deref().is_none_or(|held| key < held) {
+        *slot = Some(key.to_string());
+    }
+}
+
+/// The child a key's segment is filed under: `*` for a `:name` parameter.
+fn child_key(seg: &str) -> String {
+    if seg.len() > 1 && seg.starts_with(':') {
+        "*".to_string()
+    } else {
+        seg.to_string()
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
+
+fn find<'a>(node: &'a Node, segs: &[&str]) -> Option<&'a str> {
+    let (seg, rest) = segs.split_first()?;
+    let found = if rest.is_empty() {
+        if seg.is_empty() {
+            // Directory request: `/dir/`, else `/dir`, else its index.html.
+            node.slash
+                .as_deref()
+                .or(node.exact.as_deref())
+                .or_else(|| node.children.get("index.html")?.exact.as_deref())
+        } else {
+            [node.children.get(*seg), node.children.get("*")]
+                .into_iter()
+                .flatten()
+                .find_map(|child| child.exact.as_deref().or(child.slash.as_deref()))
+        }
+    } else {
+        [node.children.get(*seg), node.children.get("*")]
+            .into_iter()
+            .flatten()
+            .find_map(|child| find(child, rest))
+    };
+    found.or(node.rest.as_deref())
+}
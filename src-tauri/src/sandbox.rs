@@ -0,0 +1,137 @@
+use crate::car::{decompress, parse_tile, ParsedParts, TileContent};
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// ── Sandboxed parsing ────────────────────────────────────────────────────────
+//
+// With the `sandboxed_parsing` setting on, a file the app hasn't parsed before
+// is parsed by a child copy of this executable (started with
+// [`CHILD_FLAG`]) rather than in-process, so a CAR/CBOR parser bug triggered
+// by a hostile file takes down only the child. The child gets an empty
+// environment, no stdin, and a deadline; on Unix it also runs under memory,
+// CPU, file-size, and descriptor limits, and on Linux in its own network
+// namespace where unprivileged user namespaces allow it. It writes the
+// parsed parts to stdout as JSON, which the parent turns back into a
+// `TileContent` without trusting anything but the data. A compressed tile
+// is decompressed by the parent, which the child's file-size limit rules
+// out, and the child parses the copy.
+
+/// Argument that turns the executable into a parse child.
+pub const CHILD_FLAG: &str = "--parse-tile-sandboxed";
+
+/// Wall-clock time a child may take before it is killed.
+const DEADLINE: Duration = Duration::from_secs(60);
+
+/// Largest reply accepted from a child.
+const MAX_OUTPUT: u64 = 512 << 20;
+
+/// Parse `path` in a sandboxed child process.
+pub fn parse(path: &Path) -> Result<TileContent> {
+    let copy = decompress(path)?;
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .arg(CHILD_FLAG)
+        .arg(copy.as_ref().map_or(path, |copy| copy.path()))
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    restrict(&mut command);
+
+    let mut child = command.spawn().context("starting parse sandbox")?;
+    let mut stdout = child.stdout.take().ok_or_else(|| anyhow!("no sandbox stdout"))?;
+    let reader = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        stdout.by_ref().take(MAX_OUTPUT).read_to_end(&mut out).map(|_| out)
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > DEADLINE {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("parsing {} timed out in the sandbox", path.display());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let out = reader.join().map_err(|_| anyhow!("sandbox reader panicked"))??;
+    if !status.success() {
+        let mut err = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = stderr.by_ref().take(4096).read_to_string(&mut err);
+        }
+        let err = err.lines().last().filter(|l| !l.is_empty()).unwrap_or("parse sandbox crashed");
+        tracing::warn!(path = %path.display(), "sandboxed parse failed: {err}");
+        bail!("{err}");
+    }
+    let parts: ParsedParts = serde_json::from_slice(&out).context("decoding sandbox reply")?;
+    let content = TileContent::from_parts(path, parts)?;
+    Ok(match copy {
+        Some(copy) => content.with_decompressed(copy),
+        None => content,
+    })
+}
+
+/// Child side: if this process was started by [`parse`], parse the file,
+/// reply, and return the exit code.
+pub fn child_main() -> Option<i32> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != CHILD_FLAG {
+        return None;
+    }
+    let path = args.next()?;
+    let reply = parse_tile(Path::new(&path))
+        .and_then(|tile| tile.to_parts())
+        .and_then(|parts| Ok(serde_json::to_vec(&parts)?));
+    Some(match reply {
+        Ok(bytes) => match std::io::stdout().lock().write_all(&bytes) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        },
+        // The child has no logger: its stderr is where the parent reads the
+        // error from, and logs it.
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    })
+}
+
+#[cfg(unix)]
+fn restrict(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    fn limit(resource: libc::c_int, value: libc::rlim_t) -> std::io::Result<()> {
+        let rlim = libc::rlimit { rlim_cur: value, rlim_max: value };
+        // SAFETY: setrlimit only reads the struct passed to it.
+        if unsafe { libc::setrlimit(resource as _, &rlim) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // SAFETY: the closure runs between fork and exec and only makes
+    // async-signal-safe system calls.
+    unsafe {
+        command.pre_exec(|| {
+            #[cfg(target_os = "linux")]
+            {
+                // Best effort: fails where user namespaces are disabled.
+                libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET);
+            }
+            limit(libc::RLIMIT_AS as _, 2 << 30)?;
+            limit(libc::RLIMIT_CPU as _, 60)?;
+            limit(libc::RLIMIT_FSIZE as _, 0)?;
+            limit(libc::RLIMIT_NOFILE as _, 32)?;
+            Ok(())
+        });
+    }
+}
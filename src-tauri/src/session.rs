@@ -0,0 +1,198 @@
+use crate::i18n;
+use crate::{open_when_ready, TileStore};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+// ── Session recovery ─────────────────────────────────────────────────────────
+//
+// The open tiles, in tab order, are written to `session.json` in the app
+// data directory whenever one opens or closes, along with which is active
+// and whatever view state the frontend reports for each. The file is marked
+// as belonging to a running app until a clean exit clears the mark, so
+// finding it still set at launch means the last run crashed or was killed.
+// That session's tiles are then offered back: `tile:session-recoverable` is
+// emitted once the frontend is ready, and `restore_session` reopens them.
+// Private tiles are never recorded.
+
+/// A tab of the recorded session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub authority: String,
+    pub path: PathBuf,
+    /// Whatever the frontend last reported through `set_tile_view_state`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<Value>,
+}
+
+/// A session offered back after an unclean exit.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoverableSession {
+    pub tabs: Vec<SessionTab>,
+    /// Authority of the tab that was active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SessionFile {
+    /// Set while the app that wrote the file is running.
+    running: bool,
+    active: Option<String>,
+    tabs: Vec<SessionTab>,
+}
+
+/// Managed state: the session being recorded and any left by a crash.
+pub struct Session {
+    path: Option<PathBuf>,
+    current: Mutex<SessionFile>,
+    recoverable: Mutex<Option<RecoverableSession>>,
+}
+
+impl Session {
+    /// Read the last run's session, keeping it for recovery if that run
+    /// didn't exit cleanly, and start recording this one.
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let path = app.path().app_data_dir().ok().map(|dir| dir.join("session.json"));
+        let previous: SessionFile = path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        // Tiles that have since been moved or deleted have nothing to offer.
+        let tabs: Vec<SessionTab> =
+            previous.tabs.into_iter().filter(|tab| tab.path.exists()).collect();
+        let recoverable = (previous.running && !tabs.is_empty())
+            .then_some(RecoverableSession { tabs, active: previous.active });
+        if recoverable.is_some() {
+            tracing::info!("last run exited uncleanly; offering its session back");
+        }
+        let session = Session {
+            path,
+            current: Mutex::new(SessionFile { running: true, ..Default::default() }),
+            recoverable: Mutex::new(recoverable),
+        };
+        session.update(|_| {});
+        session
+    }
+
+    /// Record `authority`, opened from `path`, as the active tab.
+    pub fn opened(&self, authority: &str, path: PathBuf) {
+        self.update(|file| {
+            if !file.tabs.iter().any(|tab| tab.authority == authority) {
+                file.tabs.push(SessionTab { authority: authority.into(), path, view: None });
+            }
+            file.active = Some(authority.into());
+        });
+    }
+
+    pub fn closed(&self, authority: &str) {
+        self.update(|file| {
+            file.tabs.retain(|tab| tab.authority != authority);
+            if file.active.as_deref() == Some(authority) {
+                file.active = None;
+            }
+        });
+    }
+
+    /// Mark the session as ended cleanly, so it isn't offered next launch.
+    pub fn ended(&self) {
+        self.update(|file| file.running = false);
+    }
+
+    /// The session left by a crash, if it hasn't been restored or discarded.
+    pub fn recoverable(&self) -> Option<RecoverableSession> {
+        self.recoverable.lock().unwrap().clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut SessionFile)) {
+        let mut file = self.current.lock().unwrap();
+        change(&mut file);
+        if let Err(e) = self.save(&file) {
+            tracing::warn!("recording the session failed: {e:#}");
+        }
+    }
+
+    /// Write through a temporary file, so a crash mid-write leaves the last
+    /// complete session behind rather than half of this one.
+    fn save(&self, file: &SessionFile) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(file)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// The session a crash left behind, if any.
+#[tauri::command]
+pub fn get_recoverable_session(session: State<'_, Session>) -> Option<RecoverableSession> {
+    session.recoverable()
+}
+
+/// Reopen the session a crash left behind. The tiles open in the background
+/// as for files passed at launch; the returned tabs carry their view state.
+#[tauri::command]
+pub fn restore_session(
+    session: State<'_, Session>,
+    app: AppHandle,
+) -> Result<RecoverableSession, String> {
+    let recovered = session.recoverable.lock().unwrap().take().ok_or("no session to restore")?;
+    open_when_ready(&app, recovered.tabs.iter().map(|tab| tab.path.clone()).collect());
+    Ok(recovered)
+}
+
+/// Forget the session a crash left behind.
+#[tauri::command]
+pub fn discard_session(session: State<'_, Session>) {
+    session.recoverable.lock().unwrap().take();
+}
+
+/// Record view state for the open tile `authority`, such as its scroll
+/// position, to hand back if the session is restored. The frontend owns its
+/// shape; the file is rewritten each call, so report changes at a
+/// throttled pace.
+#[tauri::command]
+pub fn set_tile_view_state(
+    authority: String,
+    view: Value,
+    session: State<'_, Session>,
+) -> Result<(), String> {
+    let mut found = false;
+    session.update(|file| {
+        if let Some(tab) = file.tabs.iter_mut().find(|tab| tab.authority == authority) {
+            tab.view = Some(view);
+            found = true;
+        }
+    });
+    if found {
+        Ok(())
+    } else {
+        Err("tile not in the session".into())
+    }
+}
+
+/// Record the open tile `authority` as the active tab.
+#[tauri::command]
+pub fn set_active_tile(
+    authority: String,
+    session: State<'_, Session>,
+    tiles: State<'_, TileStore>,
+) -> Result<(), String> {
+    tiles.get(&authority).ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    session.update(|file| {
+        if file.tabs.iter().any(|tab| tab.authority == authority) {
+            file.active = Some(authority);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use crate::cache::BlockCache;
+use crate::i18n;
+use crate::logging::Logs;
+use crate::signing::{PublisherStore, TrustedPublisher};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+//
+// Every change is written to disk, applied to whatever it configures in the
+// running app, and announced as `settings:changed` with the full
+// [`Preferences`], so an open preferences window and the main window agree
+// without reloading. Trusted publishers live in their own file (see
+// `signing.rs`) but are part of the same snapshot and announcement.
+
+/// Where the frontend opens a tile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenIn {
+    #[default]
+    Tabs,
+    Windows,
+}
+
+/// How much of a tile is hashed against its CIDs when it is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verification {
+    /// Blocks are checked only when `verify_tile` asks.
+    #[default]
+    Off,
+    /// Every block is hashed before the tile is shown, and the result is
+    /// attached to `tile:opened`.
+    OnOpen,
+    /// As `on-open`, and a tile with a failing block isn't opened at all.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Follow the operating system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// User preferences, persisted as `settings.json` in the app config directory.
+/// Missing keys fall back to their defaults so older files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Minutes between background checks of tiles that declare an
+    /// `update_url`. `0` disables the checker.
+    pub update_check_interval_mins: u64,
+    /// Fetch nothing on a tile's behalf: no `update_url` is checked, in the
+    /// background or when asked, and no DID author's document is fetched
+    /// (see `did.rs`), as each fetch tells the tile's author it is open.
+    /// Fetches the user starts, like the catalog, still go out.
+    pub offline: bool,
+    /// Catalog browsed by `fetch_catalog` when no URL is given.
+    pub catalog_url: Option<String>,
+    /// Open tiles from their header alone and build the block index in the
+    /// background, so the tab appears before a large CAR has been scanned.
+    pub lazy_open: bool,
+    /// Memory budget for the decoded-block LRU, in MiB.
+    pub block_cache_mb: u64,
+    /// Re-parse open tiles when their file changes on disk.
+    pub reload_on_change: bool,
+    /// Hashing of blocks at open. Can be forced on or off per open.
+    pub verification: Verification,
+    /// Forward MASL resource headers beyond the safelist for tiles that
+    /// declare the `extra-headers` capability.
+    pub forward_extra_headers: bool,
+    /// Parse files the app hasn't opened before in a restricted child
+    /// process (see `sandbox.rs`).
+    pub sandboxed_parsing: bool,
+    /// JSON list of revoked publisher keys, checked alongside the local
+    /// `revoked-keys.json`.
+    pub revocation_list_url: Option<String>,
+    /// Sign tiles with the keychain signing key whenever packing, the
+    /// new-tile wizard or the editor writes one (see `signing.rs`).
+    pub sign_on_export: bool,
+    /// Publisher name embedded in those signatures: the signer's own claim.
+    pub signer_name: Option<String>,
+    /// Level of the app's own log events: `error`, `warn`, `info`, `debug`,
+    /// `trace` or `off` (see `logging.rs`).
+    pub log_level: String,
+    /// Opening a tile adds a tab, or gives it a window of its own. Applied
+    /// by the frontend.
+    pub open_in: OpenIn,
+    /// Number of tiles whose parse is kept in the app cache directory (see
+    /// `index_cache.rs`).
+    pub parse_cache_entries: usize,
+    /// Write the block index of a large tile beside it as `<file>.idx` once
+    /// it is built, so later opens read that instead of scanning the file
+    /// (see `sidecar.rs`). Off by default, as it writes into the folders
+    /// tiles are opened from.
+    pub index_sidecars: bool,
+    /// Refuse to open a tile that fails the checks made before saving one
+    /// (see `validate.rs`).
+    pub strict_validation: bool,
+    /// Folders listed alongside the app's own library folder. Catalog
+    /// installs still go to the app's folder.
+    pub library_folders: Vec<PathBuf>,
+    pub theme: Theme,
+    /// Language of menus, dialogs and error messages, as a BCP 47 tag.
+    /// `None` follows the system (see `i18n.rs`).
+    pub language: Option<String>,
+    /// Serve-time transformers to run, by name: `markdown` serves Markdown
+    /// resources as HTML, `source` highlights `text/x-*` source files (see
+    /// `transform.rs`).
+    pub transformers: Vec<String>,
+    /// Read compressed tiles' blocks out of a memory mapping of their
+    /// decompressed copy. Applies to tiles opened after it changes (see
+    /// `mmap.rs`).
+    pub memory_mapped_reads: bool,
+    /// Hash each block against its CID as it is first served, answering 502
+    /// for one that doesn't match. Applies to tiles opened after it changes;
+    /// `set_read_verification` sets it for one open tile.
+    pub verify_reads: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            update_check_interval_mins: 60,
+            offline: false,
+            catalog_url: None,
+            lazy_open: true,
+            block_cache_mb: 64,
+            reload_on_change: true,
+            verification: Verification::Off,
+            forward_extra_headers: false,
+            sandboxed_parsing: false,
+            revocation_list_url: None,
+            sign_on_export: false,
+            signer_name: None,
+            log_level: "info".into(),
+            open_in: OpenIn::Tabs,
+            parse_cache_entries: 32,
+            index_sidecars: false,
+            strict_validation: false,
+            library_folders: Vec::new(),
+            theme: Theme::System,
+            language: None,
+            transformers: Vec::new(),
+            memory_mapped_reads: false,
+            verify_reads: false,
+        }
+    }
+}
+
+/// Managed state wrapper around the current settings.
+pub struct SettingsStore(pub Mutex<Settings>);
+
+impl SettingsStore {
+    /// Load settings from disk, falling back to defaults if the file is
+    /// missing or unreadable.
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let settings = settings_path(app)
+            .ok()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .and_then(|mut value| {
+                migrate(&mut value);
+                serde_json::from_value(value).ok()
+            })
+            .unwrap_or_default();
+        SettingsStore(Mutex::new(settings))
+    }
+
+    /// Snapshot of the current settings.
+    pub fn get(&self) -> Settings {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Replace the current settings, write them to disk, apply them and
+    /// announce the change.
+    pub fn set<R: Runtime>(&self, app: &AppHandle<R>, settings: Settings) -> Result<()> {
+        let path = settings_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_vec_pretty(&settings)?)?;
+        apply(app, &settings)?;
+        *self.0.lock().unwrap() = settings;
+        changed(app);
+        Ok(())
+    }
+}
+
+/// Bring the settings files of earlier versions up to date.
+fn migrate(value: &mut Value) {
+    let Some(settings) = value.as_object_mut() else { return };
+    // `verify_on_open: true` became `verification: "on-open"`.
+    if let Some(verify) = settings.remove("verify_on_open") {
+        if verify == Value::Bool(true) && !settings.contains_key("verification") {
+            settings.insert("verification".into(), "on-open".into());
+        }
+    }
+}
+
+/// Put `settings` into effect in the running app, for those it configures
+/// here rather than being read when needed.
+pub fn apply<R: Runtime>(app: &AppHandle<R>, settings: &Settings) -> Result<()> {
+    if let Some(cache) = app.try_state::<BlockCache>() {
+        cache.set_budget(settings.block_cache_mb << 20);
+    }
+    if let Some(logs) = app.try_state::<Logs>() {
+        logs.set_level(&settings.log_level)?;
+    }
+    if i18n::set_language(settings.language.as_deref()) {
+        app.set_menu(crate::app_menu(app)?)?;
+    }
+    app.set_theme(match settings.theme {
+        Theme::System => None,
+        Theme::Light => Some(tauri::Theme::Light),
+        Theme::Dark => Some(tauri::Theme::Dark),
+    });
+    Ok(())
+}
+
+/// Everything the preferences window shows.
+#[derive(Debug, Clone, Serialize)]
+pub struct Preferences {
+    pub settings: Settings,
+    pub trusted_publishers: Vec<TrustedPublisher>,
+}
+
+fn preferences<R: Runtime>(app: &AppHandle<R>) -> Preferences {
+    Preferences {
+        settings: app.try_state::<SettingsStore>().map(|s| s.get()).unwrap_or_default(),
+        trusted_publishers: app.try_state::<PublisherStore>().map(|p| p.list()).unwrap_or_default(),
+    }
+}
+
+/// Emit `settings:changed` with the current preferences.
+pub fn changed<R: Runtime>(app: &AppHandle<R>) {
+    if let Err(e) = app.emit("settings:changed", preferences(app)) {
+        tracing::warn!("emitting settings:changed failed: {e}");
+    }
+}
+
+fn settings_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(app.path().app_config_dir()?.join("settings.json"))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_preferences(app: AppHandle) -> Preferences {
+    preferences(&app)
+}
+
+#[tauri::command]
+pub fn get_settings(store: State<'_, SettingsStore>) -> Settings {
+    store.get()
+}
+
+#[tauri::command]
+pub fn set_settings(
+    settings: Settings,
+    store: State<'_, SettingsStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    store.set(&app, settings).map_err(|e| e.to_string())
+}
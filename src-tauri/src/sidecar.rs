@@ -0,0 +1,206 @@
+use crate::builder::write_uvarint;
+use crate::car::{data_end, BlockFrame, BlockIndex, IndexBuilder, TileContent};
+use crate::settings::SettingsStore;
+use anyhow::{bail, Result};
+use cid::Cid;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Index sidecars ───────────────────────────────────────────────────────────
+//
+// Without an index of its own a CAR is opened by walking every block frame,
+// which for a tile of many blocks takes seconds. Once a tile of at least
+// `MIN_BLOCKS` blocks has been indexed, the index is written beside it as
+// `<file>.idx`, and later opens read that instead of walking the file. The
+// parse cache (`index_cache.rs`) does the same for recently opened tiles,
+// but a sidecar travels with the tile and isn't evicted.
+//
+// A sidecar is bound to the CAR it indexes by the file's length and the
+// SHA-256 of everything before the first block frame, which holds the
+// header and so every resource's CID; every edit the app makes changes one
+// or the other. A sidecar that doesn't match is ignored, and replaced once
+// the file has been indexed again. Since anyone who can put a file beside a
+// tile can write a sidecar, the binding alone doesn't make it the tile's:
+// every entry is checked against the file's bounds and the parser's limits
+// and against the frame it points at, whose length varint and CID must be
+// the entry's. That reads a few bytes per block, where the walk reads every
+// frame's header in turn; the blocks' data isn't read. A sidecar of a file
+// whose frames pad their varints fails the check and the file is walked.
+//
+// Writing sidecars is off unless the `index_sidecars` setting is on, as it
+// leaves files in the user's folders.
+//
+// Layout, integers little-endian: `MAGIC`, the file length (u64), the header
+// digest (32 bytes), the entry count (u64), then for each block its CID's
+// length (u16), the CID, and the offset and length of its data (u64 each).
+
+const MAGIC: [u8; 8] = *b"TILEIDX1";
+/// Fewest blocks a tile has for a sidecar to be written.
+const MIN_BLOCKS: usize = 4096;
+
+/// Where the sidecar of the CAR at `path` goes: `path` with `.idx` added.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".idx");
+    PathBuf::from(name)
+}
+
+/// The file length and header digest a sidecar of `f`, whose block frames
+/// start at `data_start`, is bound to.
+fn binding(f: &File, data_start: u64) -> Result<(u64, [u8; 32])> {
+    let mut header = Vec::new();
+    let mut reader = f;
+    (&mut reader).take(data_start).read_to_end(&mut header)?;
+    Ok((f.metadata()?.len(), Sha256::digest(&header).into()))
+}
+
+/// The binding recorded at the start of the sidecar `reader` reads.
+fn read_binding(reader: &mut impl Read) -> Result<(u64, [u8; 32])> {
+    if read_le::<8>(reader)? != MAGIC {
+        bail!("not an index sidecar");
+    }
+    Ok((u64::from_le_bytes(read_le(reader)?), read_le(reader)?))
+}
+
+/// The block index in the sidecar of the CAR at `path`, whose block frames
+/// start at `data_start`, or `None` if it has none for the file as it is.
+pub(crate) fn read(path: &Path, data_start: u64) -> Result<Option<BlockIndex>> {
+    let Ok(sidecar) = File::open(sidecar_path(path)) else { return Ok(None) };
+    let mut reader = BufReader::new(sidecar);
+    let f = File::open(path)?;
+    if read_binding(&mut reader)? != binding(&f, data_start)? {
+        tracing::debug!(path = %path.display(), "index sidecar is of other bytes");
+        return Ok(None);
+    }
+    let end = data_end(&f)?;
+    let mut frames = Frames::new(&f)?;
+    let mut index = IndexBuilder::default();
+    for _ in 0..u64::from_le_bytes(read_le(&mut reader)?) {
+        let mut cid = vec![0u8; usize::from(u16::from_le_bytes(read_le(&mut reader)?))];
+        reader.read_exact(&mut cid)?;
+        let cid = Cid::try_from(cid.as_slice())?;
+        let offset = u64::from_le_bytes(read_le(&mut reader)?);
+        let len = u64::from_le_bytes(read_le(&mut reader)?);
+        if offset < data_start || offset.checked_add(len).is_none_or(|e| e > end) {
+            bail!("index entry for {cid} lies outside the block frames");
+        }
+        frames.check(&cid, offset, len, data_start)?;
+        index.insert(BlockFrame { cid, offset, len })?;
+    }
+    Ok(Some(index.into_index()))
+}
+
+/// The block frames of a CAR, read to check a sidecar's entries.
+struct Frames<'a> {
+    reader: BufReader<&'a File>,
+    /// Where `reader` is in the file.
+    at: u64,
+}
+
+impl<'a> Frames<'a> {
+    fn new(f: &'a File) -> Result<Self> {
+        let mut reader = BufReader::new(f);
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Frames { reader, at: 0 })
+    }
+
+    /// Fail unless the frame whose data starts at `offset` is one of `len`
+    /// bytes named `cid`: the bytes before it are the length varint and CID
+    /// such a frame starts with.
+    fn check(&mut self, cid: &Cid, offset: u64, len: u64, data_start: u64) -> Result<()> {
+        let cid_bytes = cid.to_bytes();
+        let mut head = Vec::new();
+        write_uvarint(&mut head, cid_bytes.len() as u64 + len)?;
+        head.extend(&cid_bytes);
+        let start = offset.checked_sub(head.len() as u64).filter(|s| *s >= data_start);
+        let Some(start) = start else {
+            bail!("index entry for {cid} leaves no room for its frame's head");
+        };
+        match start.checked_sub(self.at) {
+            Some(ahead) => self.reader.seek_relative(i64::try_from(ahead)?)?,
+            None => {
+                self.reader.seek(SeekFrom::Start(start))?;
+            }
+        }
+        let mut found = vec![0u8; head.len()];
+        self.reader.read_exact(&mut found)?;
+        self.at = start + head.len() as u64;
+        if found != head {
+            bail!("index entry for {cid} points at a frame of another block");
+        }
+        Ok(())
+    }
+}
+
+/// Write the sidecar of `tile`, building its index first if need be,
+/// unless it has too few blocks or its sidecar is current. Returns whether
+/// it wrote one.
+pub(crate) fn write(tile: &TileContent) -> Result<bool> {
+    let index = tile.index()?;
+    if index.len() < MIN_BLOCKS {
+        return Ok(false);
+    }
+    let binding = binding(&File::open(&tile.path)?, tile.data_start())?;
+    // The index is of the file as parsed, which may be gone by now.
+    if tile.modified_on_disk() {
+        return Ok(false);
+    }
+    let sidecar = sidecar_path(&tile.path);
+    let current = File::open(&sidecar)
+        .ok()
+        .and_then(|f| read_binding(&mut BufReader::new(f)).ok())
+        .is_some_and(|recorded| recorded == binding);
+    if current {
+        return Ok(false);
+    }
+
+    let mut entries: Vec<(&String, &(u64, u64))> = index.iter().collect();
+    entries.sort_by_key(|(_, (offset, _))| *offset);
+    let tmp = sidecar.with_extension("idx.partial");
+    let written = (|| -> Result<()> {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(&MAGIC)?;
+        out.write_all(&binding.0.to_le_bytes())?;
+        out.write_all(&binding.1)?;
+        out.write_all(&(entries.len() as u64).to_le_bytes())?;
+        for (cid, (offset, len)) in entries {
+            let cid = cid.parse::<Cid>()?.to_bytes();
+            out.write_all(&u16::try_from(cid.len())?.to_le_bytes())?;
+            out.write_all(&cid)?;
+            out.write_all(&offset.to_le_bytes())?;
+            out.write_all(&len.to_le_bytes())?;
+        }
+        out.into_inner()?.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = written.and_then(|()| Ok(std::fs::rename(&tmp, &sidecar)?)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+/// Write `tile`'s sidecar in the background, if the `index_sidecars`
+/// setting is on and the tile is a CAR of its own file.
+pub fn store<R: Runtime>(app: &AppHandle<R>, tile: Arc<TileContent>) {
+    let on = app.try_state::<SettingsStore>().is_some_and(|s| s.get().index_sidecars);
+    if !on || tile.is_directory() || tile.is_compressed() {
+        return;
+    }
+    std::thread::spawn(move || match write(&tile) {
+        Ok(true) => tracing::debug!(path = %tile.path.display(), "wrote index sidecar"),
+        Ok(false) => {}
+        // Most often a folder the app can't write to, which is fine.
+        Err(e) => tracing::debug!(path = %tile.path.display(), "no index sidecar: {e:#}"),
+    });
+}
+
+fn read_le<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
@@ -0,0 +1,379 @@
+use crate::builder::text;
+use crate::car::{TileContent, TileSignature};
+use crate::dagcbor;
+use crate::hashing::{hex, unhex};
+use crate::revocation;
+use crate::settings::{self, SettingsStore};
+use anyhow::{anyhow, bail, Result};
+use ciborium::value::Value as CborValue;
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+// ── Publisher signatures ─────────────────────────────────────────────────────
+//
+// A publisher signs the 32 raw bytes of a tile's root hash (see
+// `TileContent::root_hash`) with Ed25519. The signature is either embedded in
+// the MASL header or shipped next to the file as `<file>.sig`; an embedded one
+// wins. A valid signature only says "this key produced this tile": the `name`
+// inside it is the signer's own claim. Keys the user has chosen to trust are
+// stored in `publishers.json` with a name the user picked. Tiles the user
+// writes can be signed with a key of their own (see "Signing key" below).
+
+/// Signature check result for an open tile.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureStatus {
+    /// Hex public key the tile claims to be signed by.
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claimed_name: Option<String>,
+    /// Name given to this key when the user trusted it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_name: Option<String>,
+    pub valid: bool,
+    pub trusted: bool,
+    /// The key is on a revocation list; the reason given, if any. A revoked
+    /// key is never trusted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked: Option<String>,
+    /// Came from a `<file>.sig` next to the tile rather than its header.
+    pub detached: bool,
+    /// Why `valid` is false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check `tile`'s signature, if it has one, against its root hash and the
+/// trusted publisher list.
+pub fn status<R: Runtime>(app: &AppHandle<R>, tile: &TileContent) -> Option<SignatureStatus> {
+    let (signature, detached, error) = check_tile(tile)?;
+    let trusted_name = app.state::<PublisherStore>().name_of(&signature.key);
+    let revoked = revocation::revoked(app, &signature.key)
+        .map(|r| r.reason.unwrap_or_else(|| "revoked".into()));
+    Some(SignatureStatus {
+        valid: error.is_none(),
+        trusted: error.is_none() && trusted_name.is_some() && revoked.is_none(),
+        revoked,
+        key: signature.key,
+        claimed_name: signature.name,
+        trusted_name,
+        detached,
+        error,
+    })
+}
+
+/// `tile`'s signature, whether it is detached, and why it doesn't verify
+/// against the root hash, if it doesn't. Says nothing about trust.
+pub fn check_tile(tile: &TileContent) -> Option<(TileSignature, bool, Option<String>)> {
+    let (signature, detached) = match &tile.masl.signature {
+        Some(sig) => (sig.clone(), false),
+        None => (read_detached(&tile.path)?, true),
+    };
+    let error = check(&signature, &tile.root_hash).err().map(|e| e.to_string());
+    Some((signature, detached, error))
+}
+
+fn read_detached(tile_path: &Path) -> Option<TileSignature> {
+    let mut path = tile_path.as_os_str().to_owned();
+    path.push(".sig");
+    let bytes = std::fs::read(PathBuf::from(path)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn check(signature: &TileSignature, root_hash: &str) -> Result<()> {
+    if !signature.alg.eq_ignore_ascii_case("ed25519") {
+        bail!("unsupported signature algorithm {}", signature.alg);
+    }
+    let message = unhex(root_hash).ok_or_else(|| anyhow!("bad root hash"))?;
+    verify_ed25519(&signature.key, &message, &signature.sig)
+}
+
+/// Verify a hex Ed25519 signature `sig` by hex public key `key` over
+/// `message`.
+pub(crate) fn verify_ed25519(key: &str, message: &[u8], sig: &str) -> Result<()> {
+    let key: [u8; 32] = unhex(key)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("public key is not 32 hex-encoded bytes"))?;
+    let sig: [u8; 64] = unhex(sig)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("signature is not 64 hex-encoded bytes"))?;
+    VerifyingKey::from_bytes(&key)?
+        .verify_strict(message, &Signature::from_bytes(&sig))
+        .map_err(|_| anyhow!("signature does not match"))
+}
+
+// ── Signing key ──────────────────────────────────────────────────────────────
+//
+// The user's own Ed25519 key, for signing the tiles they write. Its 32-byte
+// secret is kept in the OS keychain (the Keychain on macOS, Credential
+// Manager on Windows, the Secret Service on Linux) and never written to disk
+// by the app. With `sign_on_export` set, every tile packing, the new-tile
+// wizard or the editor writes is signed with it, the signature embedded in
+// the header.
+
+const KEYCHAIN_SERVICE: &str = "ing.dasl.tile";
+const KEYCHAIN_ACCOUNT: &str = "signing-key";
+
+/// A key to sign written tiles with, and the publisher name to claim.
+#[derive(Clone)]
+pub struct Signer {
+    key: SigningKey,
+    name: Option<String>,
+}
+
+impl std::fmt::Debug for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Signer").field("key", &self.public_key()).field("name", &self.name).finish()
+    }
+}
+
+impl Signer {
+    /// Hex public key.
+    pub fn public_key(&self) -> String {
+        hex(self.key.verifying_key().as_bytes())
+    }
+
+    /// Sign a CAR header map, replacing any signature already in it. The
+    /// map is first put in DAG-CBOR order, the order it is written in, which
+    /// `signature` joining it doesn't disturb. The signature covers the root
+    /// hash of the header as it is then, so it has to be the last change
+    /// made.
+    pub fn sign_header(&self, header: &mut Vec<(CborValue, CborValue)>) -> Result<()> {
+        header.retain(|(k, _)| k.as_text() != Some("signature"));
+        dagcbor::canonicalize(header);
+        let mut unsigned = Vec::new();
+        ciborium::ser::into_writer(&CborValue::Map(header.clone()), &mut unsigned)?;
+        let sig = self.key.sign(&Sha256::digest(&unsigned));
+        let mut fields = vec![
+            (text("alg"), text("ed25519")),
+            (text("key"), CborValue::Bytes(self.key.verifying_key().to_bytes().to_vec())),
+            (text("sig"), CborValue::Bytes(sig.to_bytes().to_vec())),
+        ];
+        if let Some(name) = &self.name {
+            fields.push((text("name"), text(name)));
+        }
+        header.push((text("signature"), CborValue::Map(fields)));
+        Ok(())
+    }
+}
+
+/// The signer for tiles written now: `None` unless `sign_on_export` is set.
+pub fn export_signer<R: Runtime>(app: &AppHandle<R>) -> Result<Option<Signer>> {
+    let on = app.try_state::<SettingsStore>().is_some_and(|s| s.get().sign_on_export);
+    if !on {
+        return Ok(None);
+    }
+    let signer = stored_signer(app)?;
+    signer.ok_or_else(|| anyhow!("signing on export is on, but no signing key is set up")).map(Some)
+}
+
+/// A signer with the keychain key and the configured publisher name, if a
+/// key has been set up.
+pub fn stored_signer<R: Runtime>(app: &AppHandle<R>) -> Result<Option<Signer>> {
+    keychain_signer(app.try_state::<SettingsStore>().and_then(|s| s.get().signer_name))
+}
+
+/// A signer with the keychain key claiming `name`, if a key has been set
+/// up. For callers without the app's settings, such as the command line.
+pub fn keychain_signer(name: Option<String>) -> Result<Option<Signer>> {
+    Ok(stored_key()?.map(|key| Signer { key, name }))
+}
+
+fn keychain() -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)?)
+}
+
+/// The signing key in the keychain, if one has been set up.
+fn stored_key() -> Result<Option<SigningKey>> {
+    let secret = match keychain()?.get_password() {
+        Ok(secret) => secret,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let seed: [u8; 32] = unhex(&secret)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("keychain signing key is not 32 hex-encoded bytes"))?;
+    Ok(Some(SigningKey::from_bytes(&seed)))
+}
+
+/// Put `key` in the keychain, refusing to replace a different one unless
+/// `replace` says to.
+fn store_key(key: &SigningKey, replace: bool) -> Result<String> {
+    let public = hex(key.verifying_key().as_bytes());
+    if let Some(existing) = stored_key()? {
+        let existing = hex(existing.verifying_key().as_bytes());
+        if existing != public && !replace {
+            bail!("a different signing key ({existing}) is already set up");
+        }
+    }
+    keychain()?.set_password(&hex(&key.to_bytes()))?;
+    Ok(public)
+}
+
+/// A secret key as given to `import_signing_key`: the 32-byte seed, or the
+/// 64-byte seed-then-public-key form, hex-encoded.
+fn parse_secret(secret: &str) -> Result<SigningKey> {
+    let bytes = unhex(secret.trim()).ok_or_else(|| anyhow!("secret key is not hex"))?;
+    let seed: [u8; 32] = match bytes.len() {
+        32 => bytes.try_into().unwrap(),
+        64 => {
+            let key = SigningKey::from_bytes(&bytes[..32].try_into().unwrap());
+            if key.verifying_key().as_bytes()[..] != bytes[32..] {
+                bail!("public half of the secret key doesn't match its seed");
+            }
+            return Ok(key);
+        }
+        n => bail!("secret key is {n} bytes; expected 32 or 64"),
+    };
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+// ── Trusted publishers ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedPublisher {
+    /// Hex Ed25519 public key.
+    pub key: String,
+    pub name: String,
+}
+
+/// Managed list of trusted publisher keys, persisted in the app config
+/// directory.
+pub struct PublisherStore(Mutex<Vec<TrustedPublisher>>);
+
+impl PublisherStore {
+    /// Load the list from disk; a missing or unreadable file means no one is
+    /// trusted yet.
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let publishers = publishers_path(app)
+            .ok()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        PublisherStore(Mutex::new(publishers))
+    }
+
+    fn name_of(&self, key: &str) -> Option<String> {
+        let publishers = self.0.lock().unwrap();
+        let found = publishers.iter().find(|p| p.key.eq_ignore_ascii_case(key));
+        found.map(|p| p.name.clone())
+    }
+
+    pub fn list(&self) -> Vec<TrustedPublisher> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Apply `edit` to the list, write it back to disk and announce the
+    /// change as part of the preferences.
+    fn update<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        edit: impl FnOnce(&mut Vec<TrustedPublisher>),
+    ) -> Result<()> {
+        {
+            let mut publishers = self.0.lock().unwrap();
+            edit(&mut publishers);
+            let path = publishers_path(app)?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, serde_json::to_vec_pretty(&*publishers)?)?;
+        }
+        settings::changed(app);
+        Ok(())
+    }
+}
+
+fn publishers_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(app.path().app_config_dir()?.join("publishers.json"))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Hex public key of the signing key in the keychain, if there is one.
+#[tauri::command]
+pub async fn get_signing_key() -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        Ok(stored_key()?.map(|key| hex(key.verifying_key().as_bytes())))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Generate a signing key and keep it in the keychain. Returns its hex
+/// public key.
+#[tauri::command]
+pub async fn generate_signing_key(replace: Option<bool>) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed)?;
+        store_key(&SigningKey::from_bytes(&seed), replace.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Keep the hex secret key `secret` in the keychain. Returns its hex public
+/// key.
+#[tauri::command]
+pub async fn import_signing_key(secret: String, replace: Option<bool>) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        store_key(&parse_secret(&secret)?, replace.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_signing_key() -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(|| match keychain()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_trusted_publishers(store: State<'_, PublisherStore>) -> Vec<TrustedPublisher> {
+    store.list()
+}
+
+/// Trust `key` (hex Ed25519 public key) under `name`, replacing any name it
+/// was trusted under before.
+#[tauri::command]
+pub fn trust_publisher(
+    key: String,
+    name: String,
+    store: State<'_, PublisherStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if unhex(&key).is_none_or(|b| b.len() != 32) {
+        return Err("key must be a hex-encoded 32-byte Ed25519 public key".into());
+    }
+    let key = key.to_ascii_lowercase();
+    store
+        .update(&app, |publishers| {
+            publishers.retain(|p| !p.key.eq_ignore_ascii_case(&key));
+            publishers.push(TrustedPublisher { key, name });
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn untrust_publisher(
+    key: String,
+    store: State<'_, PublisherStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    store
+        .update(&app, |publishers| publishers.retain(|p| !p.key.eq_ignore_ascii_case(&key)))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,749 @@
+//! Test and benchmark support (`test-support` feature). Not part of the app.
+
+use crate::builder::{
+    raw_cid, text, write_uvarint, CidOptions, Codec, HashFunction, MaslFields, TileBuilder,
+};
+use crate::cache::BlockCache;
+use crate::car::{authority_from_path, parse_tile, read_header, TileContent};
+use crate::dagcbor;
+use crate::dagpb::DAG_PB;
+use crate::edit::{self, link};
+use crate::error;
+use crate::hashing::{IDENTITY, SHA2_256};
+use crate::merge::{self, ConflictPolicy};
+use crate::revisions::{self, header_cid};
+use crate::{handle_tile_protocol, TileStore};
+use anyhow::{anyhow, bail, Result};
+use ciborium::value::Value as CborValue;
+use cid::multihash::Multihash;
+use cid::Cid;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Manager};
+
+// ── Mock app ─────────────────────────────────────────────────────────────────
+
+/// A mock-runtime app with the state the `tile:` protocol handler needs.
+pub fn mock_app() -> App<MockRuntime> {
+    mock_builder()
+        .manage(TileStore(RwLock::new(HashMap::new())))
+        .manage(BlockCache::new(64 << 20))
+        .build(mock_context(noop_assets()))
+        .expect("failed to build mock app")
+}
+
+/// A directory of one test's own for the files it writes, removed when
+/// dropped, so tests running side by side never share a fixture.
+pub fn temp_dir() -> tempfile::TempDir {
+    tempfile::Builder::new().prefix("tile-test-").tempdir().expect("failed to make a temp dir")
+}
+
+/// Parse `path` and register it in the app's store. Returns its authority.
+pub fn open(app: &App<MockRuntime>, path: &Path) -> Result<String> {
+    let authority = authority_from_path(path);
+    let content = parse_tile(path)?;
+    app.state::<TileStore>()
+        .0
+        .write()
+        .unwrap()
+        .insert(authority.clone(), Arc::new(content));
+    Ok(authority)
+}
+
+/// The file the blocks of the open tile `authority` are read from: for a
+/// compressed tile, its decompressed copy.
+pub fn car_path(app: &App<MockRuntime>, authority: &str) -> Result<PathBuf> {
+    let store = app.state::<TileStore>();
+    let tiles = store.0.read().unwrap();
+    let tile = tiles.get(authority).ok_or_else(|| anyhow!("no open tile {authority}"))?;
+    Ok(tile.car_path().to_path_buf())
+}
+
+/// Hash blocks of the open tile `authority` as they are served.
+pub fn set_verify_reads(app: &App<MockRuntime>, authority: &str, on: bool) -> Result<()> {
+    let tile = app.state::<TileStore>().get(authority);
+    tile.ok_or_else(|| anyhow!("{authority} not open"))?.set_verify_reads(on);
+    Ok(())
+}
+
+/// Run a `GET` for `uri` through the protocol handler.
+pub fn get(app: &App<MockRuntime>, uri: &str) -> tauri::http::Response<Vec<u8>> {
+    get_with(app, uri, &[])
+}
+
+/// Run a `GET` for `uri` with request `headers` through the protocol
+/// handler.
+pub fn get_with(
+    app: &App<MockRuntime>,
+    uri: &str,
+    headers: &[(&str, &str)],
+) -> tauri::http::Response<Vec<u8>> {
+    let mut request = tauri::http::Request::builder().uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    handle_tile_protocol(app.handle(), request.body(Vec::new()).unwrap())
+}
+
+// ── Synthetic tiles ──────────────────────────────────────────────────────────
+
+/// Write a CARv1 tile with `/index.html` plus `resources` HTML pages of
+/// `size` bytes each at `/r/<n>.html`.
+pub fn write_synthetic_tile(path: &Path, resources: usize, size: usize) -> Result<()> {
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Synthetic".into(), ..Default::default() })
+        .add_resource("/index.html", b"<h1>index</h1>".to_vec(), "text/html");
+    for i in 0..resources {
+        let mut body = format!("<p>resource {i}</p>").into_bytes();
+        body.resize(size.max(body.len()), b' ');
+        builder.add_resource(&format!("/r/{i}.html"), body, "text/html");
+    }
+    builder.finish(path)?;
+    Ok(())
+}
+
+// ── Golden tiles ─────────────────────────────────────────────────────────────
+//
+// Tiles written byte by byte to pin down how the parser treats the edges of
+// the format: what it opens, what each resource then reads back as, and what
+// it refuses and with which error. Most can't come out of `TileBuilder`,
+// which only writes well-formed CARv1. A parser change that alters any of
+// these outcomes shows up as a failing `Golden::check`.
+
+/// A golden tile and what opening it should do.
+pub struct Golden {
+    pub name: &'static str,
+    /// The code (see `error.rs`) and part of the message of the error
+    /// opening the tile fails with, or `None` if it opens.
+    pub error: Option<(&'static str, &'static str)>,
+    /// Write the tile, returning the body each resource path should read as.
+    write: fn(&Path) -> Result<Bodies>,
+}
+
+type Bodies = Vec<(String, Vec<u8>)>;
+
+/// Multicodec code for raw blocks.
+const RAW: u64 = 0x55;
+
+pub const GOLDEN: &[Golden] = &[
+    Golden { name: "minimal", error: None, write: minimal },
+    Golden { name: "empty-resources", error: None, write: empty_resources },
+    Golden { name: "skipped-fields", error: None, write: skipped_fields },
+    Golden { name: "extension-fields", error: None, write: extension_fields },
+    Golden { name: "localized-names", error: None, write: localized_names },
+    Golden { name: "provenance", error: None, write: provenance_fields },
+    Golden { name: "padded-varints", error: None, write: padded_varints },
+    Golden { name: "identity-cid", error: None, write: identity_cid },
+    Golden { name: "inline-identity-cid", error: None, write: inline_identity_cid },
+    Golden { name: "duplicate-blocks", error: None, write: duplicate_blocks },
+    Golden { name: "large-block", error: None, write: large_block },
+    Golden { name: "blake3-dag-pb", error: None, write: blake3_dag_pb },
+    Golden { name: "chunked-dag-pb", error: None, write: chunked_dag_pb },
+    Golden {
+        name: "huge-header-varint",
+        error: Some(("not-a-car", "CAR header length exceeds file size")),
+        write: huge_header_varint,
+    },
+    Golden {
+        name: "overlong-varint",
+        error: Some(("not-a-car", "failed to read CAR header varint")),
+        write: overlong_varint,
+    },
+    Golden {
+        name: "huge-block-varint",
+        error: Some(("not-a-car", "block extends beyond file")),
+        write: huge_block_varint,
+    },
+    Golden {
+        name: "missing-block",
+        error: Some(("missing-block", "points at missing block")),
+        write: missing_block,
+    },
+    Golden { name: "masl-root-block", error: None, write: masl_root_block },
+    Golden {
+        name: "missing-masl-root",
+        error: Some(("missing-block", "holding the MASL is missing")),
+        write: missing_masl_root,
+    },
+    Golden { name: "carv2", error: None, write: carv2 },
+    Golden { name: "carv2-indexed", error: None, write: carv2_indexed },
+    Golden {
+        name: "carv2-index-outside",
+        error: Some(("not-a-car", "CARv2 index lies outside the file")),
+        write: carv2_index_outside,
+    },
+];
+
+impl Golden {
+    /// Write this tile into `dir`, open it, and check the outcome. Returns
+    /// the tile's path and the bodies its resources read back as, so they
+    /// can be checked again through the protocol handler.
+    pub fn check(&self, dir: &Path) -> Result<(PathBuf, Bodies)> {
+        let path = dir.join(format!("{}.tile", self.name));
+        let bodies = (self.write)(&path)?;
+        let opened = parse_tile(&path).and_then(|tile| tile.verify_resources().map(|()| tile));
+        let tile = match (opened, self.error) {
+            (Ok(tile), None) => tile,
+            (Err(e), Some((code, want))) if e.to_string().contains(want) => {
+                if error::code(&e) != code {
+                    bail!("{}: error code {}, expected {code}", self.name, error::code(&e));
+                }
+                return Ok((path, Vec::new()));
+            }
+            (Err(e), _) => bail!("{}: {e}", self.name),
+            (Ok(_), Some((_, want))) => bail!("{} opened; expected {want:?}", self.name),
+        };
+        let count = tile.masl.resources.len();
+        if count != bodies.len() {
+            bail!("{}: {count} resources, expected {}", self.name, bodies.len());
+        }
+        for (resource_path, body) in &bodies {
+            let resource = tile
+                .masl
+                .resources
+                .get(resource_path)
+                .ok_or_else(|| anyhow!("{}: no resource {resource_path}", self.name))?;
+            if tile.read_block(&resource["src"])? != *body {
+                bail!("{}: {resource_path} reads back different bytes", self.name);
+            }
+            if tile.verify_block(&resource["src"])? == Some(false) {
+                bail!("{}: {resource_path} doesn't match its CID", self.name);
+            }
+            if tile.content_len(&resource["src"])? != body.len() as u64 {
+                bail!("{}: {resource_path} has the wrong length", self.name);
+            }
+            if let Some(middle) = body.get(1..body.len().saturating_sub(1)) {
+                let range = tile.read_block_range(&resource["src"], 1, middle.len() as u64)?;
+                if range != middle {
+                    bail!("{}: {resource_path} reads back a different range", self.name);
+                }
+            }
+        }
+        Ok((path, bodies))
+    }
+}
+
+/// A CAR header with the given name and resources, each linked to a CID.
+fn header(name: &str, resources: &[(&str, &Cid)]) -> CborValue {
+    let entries = resources
+        .iter()
+        .map(|(path, cid)| {
+            let entry = vec![(text("src"), link(cid)), (text("content-type"), text("text/html"))];
+            (text(path), CborValue::Map(entry))
+        })
+        .collect();
+    CborValue::Map(vec![
+        (text("version"), CborValue::Integer(1.into())),
+        (text("name"), text(name)),
+        (text("resources"), CborValue::Map(entries)),
+    ])
+}
+
+fn cbor(value: &CborValue) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// `value` as a varint of exactly `width` bytes, padded with continuation
+/// bytes the way a non-minimal encoder would.
+fn padded_uvarint(mut value: u64, width: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(width);
+    for i in 0..width {
+        let last = i + 1 == width;
+        out.push((value & 0x7f) as u8 | if last { 0 } else { 0x80 });
+        value >>= 7;
+    }
+    out
+}
+
+/// A CARv1 file: `header`, then each block framed as length, CID, data.
+fn car(header: &CborValue, blocks: &[(Cid, &[u8])]) -> Result<Vec<u8>> {
+    let header = cbor(header)?;
+    let mut out = Vec::new();
+    write_uvarint(&mut out, header.len() as u64)?;
+    out.extend(&header);
+    for (cid, data) in blocks {
+        out.extend(car_frame(cid, data)?);
+    }
+    Ok(out)
+}
+
+fn minimal(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>minimal</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    std::fs::write(path, car(&header("Minimal", &[("/index.html", &cid)]), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// Fields the parser skips: an icon that isn't a map, one without a `src`,
+/// and a header whose value is a number.
+fn skipped_fields(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>skipped</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let CborValue::Map(mut fields) = header("Skipped", &[("/index.html", &cid)]) else {
+        unreachable!()
+    };
+    let icons = vec![text("/icon.png"), CborValue::Map(vec![(text("sizes"), text("16x16"))])];
+    fields.push((text("icons"), CborValue::Array(icons)));
+    let resources = fields.iter_mut().find_map(|(k, v)| match v {
+        CborValue::Map(resources) if k.as_text() == Some("resources") => Some(resources),
+        _ => None,
+    });
+    if let Some((_, CborValue::Map(entry))) = resources.and_then(|r| r.first_mut()) {
+        entry.push((text("x-count"), CborValue::Integer(3.into())));
+    }
+    std::fs::write(path, car(&CborValue::Map(fields), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// A MASL field the app doesn't read, holding one of each kind of value
+/// DAG-CBOR has, in a header whose top-level keys aren't in DAG-CBOR order.
+fn extension_fields(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>extended</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let CborValue::Map(mut fields) = header("Extended", &[("/index.html", &cid)]) else {
+        unreachable!()
+    };
+    let values = vec![
+        (text("b"), CborValue::Array(vec![CborValue::Bool(true), CborValue::Null])),
+        (text("link"), link(&cid)),
+        (text("zeta"), CborValue::Integer((-300).into())),
+        (text("alpha"), CborValue::Float(2.5)),
+        (text("bytes"), CborValue::Bytes(vec![0, 255])),
+    ];
+    fields.insert(1, (text("x-extension"), CborValue::Map(values)));
+    std::fs::write(path, car(&CborValue::Map(fields), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// A name and a description given by language, the description with an
+/// entry that isn't a string.
+fn localized_names(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>localized</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let CborValue::Map(mut fields) = header("Report", &[("/index.html", &cid)]) else {
+        unreachable!()
+    };
+    let names = [("en", "Report"), ("fr", "Rapport"), ("fr-CA", "Rapport (Canada)")];
+    let names = names.iter().map(|(tag, name)| (text(tag), text(name))).collect();
+    fields[1].1 = CborValue::Map(names);
+    let descriptions =
+        vec![(text("de"), text("Ein Bericht")), (text("fr"), CborValue::Integer(3.into()))];
+    fields.push((text("description"), CborValue::Map(descriptions)));
+    std::fs::write(path, car(&CborValue::Map(fields), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// Authorship and publication fields, with an author `parse_masl` skips.
+fn provenance_fields(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>provenance</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let CborValue::Map(mut fields) = header("Provenance", &[("/index.html", &cid)]) else {
+        unreachable!()
+    };
+    let author = CborValue::Map(vec![
+        (text("name"), text("Ada")),
+        (text("url"), text("https://ada.example/")),
+    ]);
+    let unnamed = CborValue::Map(vec![(text("email"), text("anon@example.com"))]);
+    let dc = vec![
+        (text("subject"), CborValue::Array(vec![text("maps"), text("travel")])),
+        (text("publisher"), text("Example Press")),
+    ];
+    fields.extend([
+        (text("author"), CborValue::Array(vec![author, text("Grace"), unnamed])),
+        (text("license"), text("CC-BY-4.0")),
+        (text("published"), text("2024-05-01T00:00:00Z")),
+        (text("dc"), CborValue::Map(dc)),
+    ]);
+    std::fs::write(path, car(&CborValue::Map(fields), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+fn empty_resources(path: &Path) -> Result<Bodies> {
+    std::fs::write(path, car(&header("Empty", &[]), &[])?)?;
+    Ok(Vec::new())
+}
+
+fn padded_varints(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>padded</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let header = cbor(&header("Padded", &[("/index.html", &cid)]))?;
+    let mut out = padded_uvarint(header.len() as u64, 10);
+    out.extend(&header);
+    let cid_bytes = cid.to_bytes();
+    out.extend(padded_uvarint((cid_bytes.len() + body.len()) as u64, 9));
+    out.extend(&cid_bytes);
+    out.extend(&body);
+    std::fs::write(path, out)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+fn identity_cid(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>inline</h1>".to_vec();
+    let cid = Cid::new_v1(RAW, Multihash::wrap(IDENTITY, &body)?);
+    std::fs::write(path, car(&header("Identity", &[("/index.html", &cid)]), &[(cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// An identity CID with no block frame: the bytes are only in the CID.
+fn inline_identity_cid(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>only inline</h1>".to_vec();
+    let cid = Cid::new_v1(RAW, Multihash::wrap(IDENTITY, &body)?);
+    std::fs::write(path, car(&header("Inline", &[("/index.html", &cid)]), &[])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+fn duplicate_blocks(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>twice</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let header = header("Duplicates", &[("/index.html", &cid), ("/copy.html", &cid)]);
+    std::fs::write(path, car(&header, &[(cid, &body), (cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body.clone()), ("/copy.html".into(), body)])
+}
+
+/// A block bigger than the scanner's read-ahead, between two small ones.
+fn large_block(path: &Path) -> Result<Bodies> {
+    let small = b"<h1>small</h1>".to_vec();
+    let large: Vec<u8> = (0..3 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let (small_cid, large_cid) = (raw_cid(&small)?, raw_cid(&large)?);
+    let tail = b"<p>after</p>".to_vec();
+    let tail_cid = raw_cid(&tail)?;
+    let header = header(
+        "Large",
+        &[("/index.html", &small_cid), ("/large.bin", &large_cid), ("/tail.html", &tail_cid)],
+    );
+    let blocks = [(small_cid, &small[..]), (large_cid, &large[..]), (tail_cid, &tail[..])];
+    std::fs::write(path, car(&header, &blocks)?)?;
+    Ok(vec![
+        ("/index.html".into(), small),
+        ("/large.bin".into(), large),
+        ("/tail.html".into(), tail),
+    ])
+}
+
+fn blake3_dag_pb(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>wrapped</h1>".to_vec();
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Wrapped".into(), ..Default::default() })
+        .set_cid_options(CidOptions { hash: HashFunction::Blake3, codec: Codec::DagPb })
+        .add_resource("/index.html", body.clone(), "text/html");
+    builder.finish(path)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// A UnixFS file node over `links`, each with the length of the content
+/// under it when `sizes` is set.
+fn dag_pb_node(data: &[u8], links: &[(Cid, u64)], sizes: bool) -> Result<Vec<u8>> {
+    let field = |out: &mut Vec<u8>, key: u64, bytes: &[u8]| -> Result<()> {
+        write_uvarint(out, (key << 3) | 2)?;
+        write_uvarint(out, bytes.len() as u64)?;
+        out.extend(bytes);
+        Ok(())
+    };
+    // `Type: File`.
+    let mut unixfs = vec![0x08, 0x02];
+    field(&mut unixfs, 2, data)?;
+    for (_, len) in links.iter().filter(|_| sizes) {
+        write_uvarint(&mut unixfs, 4 << 3)?;
+        write_uvarint(&mut unixfs, *len)?;
+    }
+    let mut node = Vec::new();
+    for (cid, _) in links {
+        let mut link = Vec::new();
+        field(&mut link, 1, &cid.to_bytes())?;
+        field(&mut node, 2, &link)?;
+    }
+    field(&mut node, 1, &unixfs)?;
+    Ok(node)
+}
+
+/// A file split across a dag-pb root with bytes of its own and block sizes,
+/// a raw leaf, and a nested node that records no sizes over two more.
+fn chunked_dag_pb(path: &Path) -> Result<Bodies> {
+    let dag_pb = |node: &[u8]| -> Result<Cid> {
+        Ok(Cid::new_v1(DAG_PB, Multihash::wrap(SHA2_256, &Sha256::digest(node))?))
+    };
+    let chunks: [&[u8]; 4] = [b"<h1>chunked", b"</h1><p>over ", b"several ", b"blocks</p>"];
+    let leaves: Vec<Cid> = chunks[1..].iter().map(|c| raw_cid(c)).collect::<Result<_>>()?;
+    let len = |chunk: &[u8]| chunk.len() as u64;
+    let nested = [(leaves[1], len(chunks[2])), (leaves[2], len(chunks[3]))];
+    let nested = dag_pb_node(b"", &nested, false)?;
+    let nested_cid = dag_pb(&nested)?;
+    let links = [(leaves[0], len(chunks[1])), (nested_cid, len(chunks[2]) + len(chunks[3]))];
+    let root = dag_pb_node(chunks[0], &links, true)?;
+    let root_cid = dag_pb(&root)?;
+    let blocks = [
+        (root_cid, &root[..]),
+        (leaves[0], chunks[1]),
+        (nested_cid, &nested[..]),
+        (leaves[1], chunks[2]),
+        (leaves[2], chunks[3]),
+    ];
+    std::fs::write(path, car(&header("Chunked", &[("/index.html", &root_cid)]), &blocks)?)?;
+    Ok(vec![("/index.html".into(), chunks.concat())])
+}
+
+/// A dag-pb file whose root declares more bytes under its one link than
+/// the raw leaf there holds. Returns the root's CID.
+pub fn write_misdeclared_dag_pb(path: &Path) -> Result<String> {
+    let leaf: &[u8] = b"<h1>short</h1>";
+    let leaf_cid = raw_cid(leaf)?;
+    let root = dag_pb_node(b"", &[(leaf_cid, leaf.len() as u64 + 5)], true)?;
+    let root_cid = Cid::new_v1(DAG_PB, Multihash::wrap(SHA2_256, &Sha256::digest(&root))?);
+    let header = header("Misdeclared", &[("/index.html", &root_cid)]);
+    std::fs::write(path, car(&header, &[(root_cid, &root[..]), (leaf_cid, leaf)])?)?;
+    Ok(root_cid.to_string())
+}
+
+/// The standard CAR layout: a header of only `version` and `roots`, with the
+/// MASL in the DAG-CBOR block the root names.
+fn masl_root_block(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>rooted</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let masl = cbor(&header("Rooted", &[("/index.html", &cid)]))?;
+    let root = header_cid(&masl)?;
+    let car_header = CborValue::Map(vec![
+        (text("version"), CborValue::Integer(1.into())),
+        (text("roots"), CborValue::Array(vec![link(&root)])),
+    ]);
+    std::fs::write(path, car(&car_header, &[(root, &masl), (cid, &body)])?)?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// A tile in the standard CAR layout with two roots, each a MASL with a
+/// start page of its own. Returns the start page bodies, in root order.
+pub fn write_two_root_tile(path: &Path) -> Result<[Vec<u8>; 2]> {
+    let bodies = [b"<h1>first root</h1>".to_vec(), b"<h1>second root</h1>".to_vec()];
+    let cids = [raw_cid(&bodies[0])?, raw_cid(&bodies[1])?];
+    let masls = [
+        cbor(&header("First", &[("/index.html", &cids[0])]))?,
+        cbor(&header("Second", &[("/index.html", &cids[1])]))?,
+    ];
+    let roots = [header_cid(&masls[0])?, header_cid(&masls[1])?];
+    let car_header = CborValue::Map(vec![
+        (text("version"), CborValue::Integer(1.into())),
+        (text("roots"), CborValue::Array(roots.iter().map(link).collect())),
+    ]);
+    let blocks = [
+        (roots[0], &masls[0][..]),
+        (roots[1], &masls[1][..]),
+        (cids[0], &bodies[0][..]),
+        (cids[1], &bodies[1][..]),
+    ];
+    std::fs::write(path, car(&car_header, &blocks)?)?;
+    Ok(bodies)
+}
+
+/// [`crate::edit::put_resource`] without a revision or signer, returning
+/// whether the blocks were appended in place rather than the file rewritten.
+pub fn put_resource(path: &Path, resource: &str, bytes: &[u8]) -> Result<bool> {
+    let saved = edit::put_resource(path, resource, bytes.to_vec(), None, Some(false), None)?;
+    Ok(saved.appended.is_some())
+}
+
+/// Leave the tile at `path` as a crash in [`crate::edit::put_resource`]
+/// would have, blocks appended and header journalled but not yet written:
+/// its header goes back to the one in `before`, the file as it was.
+pub fn interrupt_header_update(path: &Path, before: &[u8]) -> Result<()> {
+    let mut f = File::options().read(true).write(true).open(path)?;
+    let (header, data_start) = read_header(&mut f)?;
+    edit::write_journal(path, &header)?;
+    let start = (data_start as usize) - header.len();
+    f.seek(SeekFrom::Start(start as u64))?;
+    f.write_all(&before[start..data_start as usize])?;
+    Ok(())
+}
+
+/// The tile at `path` as of its earliest revision, once an edit has kept
+/// one: a page is put at `/revised.html`, keeping the header before it.
+pub fn earlier_revision(path: &Path) -> Result<TileContent> {
+    let page = b"<h1>revised</h1>".to_vec();
+    edit::put_resource(path, "/revised.html", page, None, Some(true), None)?;
+    let tile = parse_tile(path)?;
+    let history = revisions::history(&tile)?;
+    let cid = history.iter().find_map(|r| r.cid.clone());
+    tile.as_of(&cid.ok_or_else(|| anyhow!("no revision kept"))?)
+}
+
+/// Parse `path` and write its index sidecar, as opening it in the app does
+/// (see `sidecar.rs`). Returns the sidecar's path, or `None` if the tile
+/// has too few blocks for one.
+pub fn write_sidecar(path: &Path) -> Result<Option<PathBuf>> {
+    let written = crate::sidecar::write(&parse_tile(path)?)?;
+    Ok(written.then(|| crate::sidecar::sidecar_path(path)))
+}
+
+/// [`crate::validate::report_file`] for the tile at `path`, as the
+/// `validate_tile` command returns it.
+pub fn validate(path: &Path) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(crate::validate::report_file(&parse_tile(path)?)?)?)
+}
+
+/// [`crate::diff::diff`] from `old` to `new`, as the command returns it.
+pub fn diff(old: &Path, new: &Path) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(crate::diff::diff(old, new)?)?)
+}
+
+/// The [`crate::metadata::Provenance`] of the tile at `path`.
+pub fn provenance(path: &Path) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(crate::metadata::Provenance::of(&parse_tile(path)?.masl))?)
+}
+
+/// [`crate::metadata::localized`] for the tile at `path` in `locale`.
+pub fn localized(path: &Path, locale: &str) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(crate::metadata::localized(&parse_tile(path)?.masl, locale))?)
+}
+
+/// Whether the CAR header of the tile at `path` is canonical DAG-CBOR.
+pub fn canonical_header(path: &Path) -> Result<bool> {
+    let (header, _) = read_header(&mut std::fs::File::open(path)?)?;
+    let value: CborValue = ciborium::de::from_reader(header.as_slice())?;
+    Ok(dagcbor::encode(&value)? == header)
+}
+
+/// [`crate::merge::merge`] without a signer, with the policy named as the
+/// frontend names it. Returns the conflicting paths.
+pub fn merge(first: &Path, second: &Path, dest: &Path, policy: &str) -> Result<Vec<String>> {
+    let policy: ConflictPolicy = serde_json::from_value(serde_json::json!(policy))?;
+    Ok(merge::merge(first, second, dest, policy, None)?.conflicts)
+}
+
+fn missing_masl_root(path: &Path) -> Result<Bodies> {
+    let root = header_cid(b"\xa0")?;
+    let car_header = CborValue::Map(vec![
+        (text("version"), CborValue::Integer(1.into())),
+        (text("roots"), CborValue::Array(vec![link(&root)])),
+    ]);
+    std::fs::write(path, car(&car_header, &[])?)?;
+    Ok(Vec::new())
+}
+
+fn huge_header_varint(path: &Path) -> Result<Bodies> {
+    let mut out = Vec::new();
+    write_uvarint(&mut out, u64::MAX >> 1)?;
+    out.extend(cbor(&header("Huge", &[]))?);
+    std::fs::write(path, out)?;
+    Ok(Vec::new())
+}
+
+fn overlong_varint(path: &Path) -> Result<Bodies> {
+    let mut out = vec![0x80; 10];
+    out.push(0x01);
+    out.extend(cbor(&header("Overlong", &[]))?);
+    std::fs::write(path, out)?;
+    Ok(Vec::new())
+}
+
+fn huge_block_varint(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>short</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let header = cbor(&header("Short", &[("/index.html", &cid)]))?;
+    let mut out = Vec::new();
+    write_uvarint(&mut out, header.len() as u64)?;
+    out.extend(&header);
+    write_uvarint(&mut out, u64::MAX >> 1)?;
+    out.extend(cid.to_bytes());
+    out.extend(&body);
+    std::fs::write(path, out)?;
+    Ok(Vec::new())
+}
+
+fn missing_block(path: &Path) -> Result<Bodies> {
+    let cid = raw_cid(b"<h1>never written</h1>")?;
+    std::fs::write(path, car(&header("Missing", &[("/index.html", &cid)]), &[])?)?;
+    Ok(Vec::new())
+}
+
+/// A CARv2 file: pragma, fixed header, then a CARv1 payload with no index.
+fn carv2(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>v2</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let payload = car(&header("Version 2", &[("/index.html", &cid)]), &[(cid, &body)])?;
+    std::fs::write(path, carv2_file(false, &payload, None))?;
+    Ok(vec![("/index.html".into(), body)])
+}
+
+/// A fully indexed CARv2. A zero length between its two blocks would end a
+/// walk of the payload after the first, so the second is only found if the
+/// index is read.
+fn carv2_indexed(path: &Path) -> Result<Bodies> {
+    let (first, second) = (b"<h1>first</h1>".to_vec(), b"<p>second</p>".to_vec());
+    let (first_cid, second_cid) = (raw_cid(&first)?, raw_cid(&second)?);
+    let header = header("Indexed", &[("/index.html", &first_cid), ("/second.html", &second_cid)]);
+    let mut payload = car(&header, &[(first_cid, &first)])?;
+    let mut entries = vec![(first_cid, payload.len() - frame_len(&first_cid, &first)?)];
+    payload.push(0);
+    entries.push((second_cid, payload.len()));
+    payload.extend(car_frame(&second_cid, &second)?);
+    entries.sort_by(|(a, _), (b, _)| a.hash().digest().cmp(b.hash().digest()));
+
+    // MultihashIndexSorted: one bucket of sha2-256 entries, each the digest
+    // then the frame's offset into the payload.
+    let width = 32 + 8;
+    let mut index = Vec::new();
+    write_uvarint(&mut index, 0x0401)?;
+    index.extend(1i32.to_le_bytes());
+    index.extend(0x12u64.to_le_bytes());
+    index.extend(1i32.to_le_bytes());
+    index.extend((width as u32).to_le_bytes());
+    index.extend(((width * entries.len()) as u64).to_le_bytes());
+    for (cid, offset) in &entries {
+        index.extend(cid.hash().digest());
+        index.extend((*offset as u64).to_le_bytes());
+    }
+    std::fs::write(path, carv2_file(true, &payload, Some(&index)))?;
+    Ok(vec![("/index.html".into(), first), ("/second.html".into(), second)])
+}
+
+/// A CARv2 whose header puts its index past the end of the file.
+fn carv2_index_outside(path: &Path) -> Result<Bodies> {
+    let body = b"<h1>v2</h1>".to_vec();
+    let cid = raw_cid(&body)?;
+    let payload = car(&header("Version 2", &[("/index.html", &cid)]), &[(cid, &body)])?;
+    let mut file = carv2_file(true, &payload, None);
+    let past_end = file.len() as u64 + 1;
+    file[43..51].copy_from_slice(&past_end.to_le_bytes());
+    std::fs::write(path, file)?;
+    Ok(Vec::new())
+}
+
+/// A block frame: length, CID, data.
+fn car_frame(cid: &Cid, data: &[u8]) -> Result<Vec<u8>> {
+    let cid = cid.to_bytes();
+    let mut out = Vec::new();
+    write_uvarint(&mut out, (cid.len() + data.len()) as u64)?;
+    out.extend(cid);
+    out.extend(data);
+    Ok(out)
+}
+
+fn frame_len(cid: &Cid, data: &[u8]) -> Result<usize> {
+    Ok(car_frame(cid, data)?.len())
+}
+
+/// `payload` wrapped as a CARv2, with `index` after it if given.
+fn carv2_file(fully_indexed: bool, payload: &[u8], index: Option<&[u8]>) -> Vec<u8> {
+    let mut out = vec![0x0a, 0xa1, 0x67];
+    out.extend(b"version");
+    out.push(0x02);
+    let data_offset = (out.len() + 40) as u64;
+    let characteristics: u64 = if fully_indexed { 1 << 63 } else { 0 };
+    out.extend(characteristics.to_le_bytes());
+    out.extend(0u64.to_le_bytes());
+    out.extend(data_offset.to_le_bytes());
+    out.extend((payload.len() as u64).to_le_bytes());
+    let index_offset = if index.is_some() { data_offset + payload.len() as u64 } else { 0 };
+    out.extend(index_offset.to_le_bytes());
+    out.extend(payload);
+    out.extend(index.unwrap_or_default());
+    out
+}
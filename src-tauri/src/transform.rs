@@ -0,0 +1,161 @@
+use crate::car::Resource;
+use crate::markdown;
+use crate::settings::SettingsStore;
+use anyhow::Result;
+use tauri::{AppHandle, Manager, Runtime};
+
+// ── Serve-time transformers ──────────────────────────────────────────────────
+//
+// A transformer rewrites a resource's body as it is served, chosen by the
+// resource's content type. They are off unless named in the `transformers`
+// setting, since they change what the tile's author shipped. A transformed
+// response is always the whole identity body run through the transformer:
+// precompressed variants and byte ranges are skipped for it, as neither
+// applies to the new body.
+//
+// To add one, write its function and give it an entry in [`TRANSFORMERS`].
+
+/// A transformer, as listed in [`TRANSFORMERS`].
+pub struct Transformer {
+    /// What the `transformers` setting calls it.
+    pub name: &'static str,
+    /// Whether it applies to a resource of this content type (its essence,
+    /// lowercased, without parameters).
+    applies: fn(&str) -> bool,
+    /// The new body for a resource at `path`.
+    transform: fn(&[u8], &str) -> Result<Vec<u8>>,
+    /// Content type of what it returns.
+    pub content_type: &'static str,
+}
+
+pub const TRANSFORMERS: &[Transformer] = &[
+    Transformer {
+        name: "markdown",
+        applies: |essence| matches!(essence, "text/markdown" | "text/x-markdown"),
+        transform: render_markdown,
+        content_type: "text/html; charset=utf-8",
+    },
+    Transformer {
+        name: "source",
+        applies: |essence| essence.starts_with("text/x-") && !essence.ends_with("markdown"),
+        transform: highlight_source,
+        content_type: "text/html; charset=utf-8",
+    },
+];
+
+impl Transformer {
+    /// The new body for the resource at `path`.
+    pub fn apply(&self, body: &[u8], path: &str) -> Result<Vec<u8>> {
+        (self.transform)(body, path)
+    }
+}
+
+/// The enabled transformer for `resource`, if any. The first in
+/// [`TRANSFORMERS`] order wins.
+pub fn for_resource<R: Runtime>(
+    app: &AppHandle<R>,
+    resource: &Resource,
+) -> Option<&'static Transformer> {
+    let content_type = resource.get("content-type")?;
+    let essence = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    let store = app.try_state::<SettingsStore>()?;
+    let enabled = &store.0.lock().unwrap().transformers;
+    TRANSFORMERS
+        .iter()
+        .find(|t| enabled.iter().any(|name| name == t.name) && (t.applies)(&essence))
+}
+
+fn render_markdown(body: &[u8], path: &str) -> Result<Vec<u8>> {
+    let text = String::from_utf8_lossy(body);
+    Ok(markdown::render_standalone(&text, path).into_bytes())
+}
+
+// ── Source highlighting ──────────────────────────────────────────────────────
+//
+// Not a parser for any one language: it marks what most languages share
+// (comments, string literals, numbers and a set of common keywords) so a
+// source file reads as code rather than as a wall of text. `#` starts a
+// comment only at the start of a line, as in shell and Python; `#[attr]`
+// and `#include` lines come out marked as comments too.
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "case", "class", "const", "continue", "def", "default",
+    "do", "else", "enum", "export", "extends", "false", "fn", "for", "from", "func", "function",
+    "if", "impl", "import", "in", "interface", "let", "match", "mod", "new", "nil", "None",
+    "null", "package", "pub", "return", "self", "static", "struct", "switch", "this", "trait",
+    "true", "True", "False", "try", "type", "use", "var", "while", "yield",
+];
+
+const SOURCE_STYLE: &str = "body{margin:0;background:#fdfdfd;color:#222}\
+pre{margin:0;padding:1em;font:13px/1.5 ui-monospace,monospace;tab-size:4;overflow:auto}\
+.c{color:#6a737d;font-style:italic}.s{color:#22863a}.n{color:#005cc5}.k{color:#d73a49}";
+
+fn highlight_source(body: &[u8], path: &str) -> Result<Vec<u8>> {
+    let text = String::from_utf8_lossy(body);
+    let title = escape(path.rsplit('/').next().unwrap_or(path));
+    let code = highlight(&text);
+    Ok(format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{SOURCE_STYLE}</style>\n</head>\n<body><pre><code>{code}</code></pre></body>\n\
+         </html>\n"
+    )
+    .into_bytes())
+}
+
+/// `text` as escaped HTML with its tokens wrapped in classed spans.
+fn highlight(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let (mut rest, mut line_start) = (text, true);
+    while let Some(c) = rest.chars().next() {
+        let quoted = matches!(c, '"' | '\'' | '`').then(|| string_len(rest, c)).flatten();
+        let (len, class) = if rest.starts_with("//") || (c == '#' && line_start) {
+            (rest.find('\n').unwrap_or(rest.len()), Some("c"))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            (body.find("*/").map_or(rest.len(), |end| end + 4), Some("c"))
+        } else if let Some(len) = quoted {
+            (len, Some("s"))
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_');
+            (end.unwrap_or(rest.len()), Some("n"))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+            (len, KEYWORDS.contains(&&rest[..len]).then_some("k"))
+        } else {
+            (c.len_utf8(), None)
+        };
+        let (token, after) = rest.split_at(len);
+        line_start = match token.rfind('\n') {
+            Some(i) => token[i..].trim().is_empty(),
+            None => line_start && token.trim().is_empty(),
+        };
+        match class {
+            Some(class) => {
+                out.push_str(&format!("<span class=\"{class}\">{}</span>", escape(token)));
+            }
+            None => out.push_str(&escape(token)),
+        }
+        rest = after;
+    }
+    out
+}
+
+/// Length of the string literal opening `rest` with `quote`, up to and
+/// including its closing quote. Only backquoted strings run over lines; a
+/// quote with no match on its line is left as it is.
+fn string_len(rest: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' if quote != '`' => return None,
+            c if c == quote => return Some(i + c.len_utf8()),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,210 @@
+use crate::car::TileContent;
+use crate::feed::rfc3339;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+// ── Trust store ──────────────────────────────────────────────────────────────
+//
+// One record per tile content (root hash) the user has opened: when it was
+// first seen, who signed it, what it has been granted, and the file it was
+// last opened from. Grants attach to the hash, so unchanged documents never
+// prompt twice. When a file opens with a different hash than that path last
+// had, outside an update or live reload, the frontend is warned: someone may
+// have swapped the document.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustRecord {
+    /// Root hash of the tile content.
+    pub hash: String,
+    /// Tile name when first seen, for the management UI.
+    pub name: String,
+    /// RFC 3339 time the content was first opened.
+    pub first_seen: String,
+    /// File this content was last opened from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Hex key of a valid publisher signature, if it had one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    #[serde(default)]
+    pub permissions: BTreeSet<String>,
+}
+
+/// Sent as `tile:content-changed` when a known file opens with new content.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentChanged {
+    pub authority: String,
+    pub name: String,
+    pub path: String,
+    pub previous_hash: String,
+    pub hash: String,
+    /// The previous content was signed by a key this content isn't.
+    pub signer_changed: bool,
+}
+
+/// Managed trust records, persisted as `trust.json` in the app config
+/// directory.
+pub struct TrustStore(Mutex<Vec<TrustRecord>>);
+
+impl TrustStore {
+    /// Load records from disk; a missing or unreadable file means none.
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let records = trust_path(app)
+            .ok()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        TrustStore(Mutex::new(records))
+    }
+
+    pub fn has_permission(&self, hash: &str, permission: &str) -> bool {
+        let records = self.0.lock().unwrap();
+        records.iter().any(|r| r.hash == hash && r.permissions.contains(permission))
+    }
+
+    /// The key the content with root hash `hash` was validly signed by when
+    /// last opened, if it was.
+    pub fn signer_of(&self, hash: &str) -> Option<String> {
+        let records = self.0.lock().unwrap();
+        records.iter().find(|r| r.hash == hash).and_then(|r| r.signer.clone())
+    }
+
+    /// Whether the content with root hash `hash` holds any permission.
+    pub fn has_grants(&self, hash: &str) -> bool {
+        let records = self.0.lock().unwrap();
+        records.iter().any(|r| r.hash == hash && !r.permissions.is_empty())
+    }
+
+    /// Record that `tile` was opened (or reloaded, when `expected`) from its
+    /// path with signature key `signer`. Returns a warning if that path last
+    /// held different content and the change wasn't `expected`.
+    pub fn observe<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        authority: &str,
+        tile: &TileContent,
+        signer: Option<String>,
+        expected: bool,
+    ) -> Result<Option<ContentChanged>> {
+        let path = std::fs::canonicalize(&tile.path)?.to_string_lossy().into_owned();
+        let mut warning = None;
+        self.update(app, |records| {
+            let previous = records.iter_mut().find(|r| r.path.as_deref() == Some(path.as_str()));
+            if let Some(previous) = previous {
+                if previous.hash != tile.root_hash && !expected {
+                    warning = Some(ContentChanged {
+                        authority: authority.to_string(),
+                        name: tile.masl.name.clone(),
+                        path: path.clone(),
+                        previous_hash: previous.hash.clone(),
+                        hash: tile.root_hash.clone(),
+                        signer_changed: previous.signer.is_some() && previous.signer != signer,
+                    });
+                }
+                previous.path = None;
+            }
+            match records.iter_mut().find(|r| r.hash == tile.root_hash) {
+                Some(record) => {
+                    record.path = Some(path);
+                    record.signer = signer;
+                }
+                None => records.push(TrustRecord {
+                    hash: tile.root_hash.clone(),
+                    name: tile.masl.name.clone(),
+                    first_seen: rfc3339(SystemTime::now()),
+                    path: Some(path),
+                    signer,
+                    permissions: BTreeSet::new(),
+                }),
+            }
+        })?;
+        Ok(warning)
+    }
+
+    /// Grant `permission` to `tile`'s content.
+    pub fn grant<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        tile: &TileContent,
+        permission: &str,
+    ) -> Result<()> {
+        self.update(app, |records| {
+            match records.iter_mut().find(|r| r.hash == tile.root_hash) {
+                Some(record) => {
+                    record.permissions.insert(permission.to_string());
+                }
+                None => records.push(TrustRecord {
+                    hash: tile.root_hash.clone(),
+                    name: tile.masl.name.clone(),
+                    first_seen: rfc3339(SystemTime::now()),
+                    path: None,
+                    signer: None,
+                    permissions: BTreeSet::from([permission.to_string()]),
+                }),
+            }
+        })
+    }
+
+    /// Revoke `permission` from the content with root hash `hash`, or all
+    /// of its permissions when `None`.
+    pub fn revoke<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        hash: &str,
+        permission: Option<&str>,
+    ) -> Result<()> {
+        self.update(app, |records| {
+            for record in records.iter_mut().filter(|r| r.hash == hash) {
+                match permission {
+                    Some(p) => {
+                        record.permissions.remove(p);
+                    }
+                    None => record.permissions.clear(),
+                }
+            }
+        })
+    }
+
+    /// Apply `edit` to the records and write them back to disk.
+    fn update<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        edit: impl FnOnce(&mut Vec<TrustRecord>),
+    ) -> Result<()> {
+        let mut records = self.0.lock().unwrap();
+        edit(&mut records);
+        let path = trust_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_vec_pretty(&*records)?)?;
+        Ok(())
+    }
+}
+
+fn trust_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf> {
+    Ok(app.path().app_config_dir()?.join("trust.json"))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Every known tile content, for reviewing and revoking what was approved.
+#[tauri::command]
+pub fn list_trust_records(store: State<'_, TrustStore>) -> Vec<TrustRecord> {
+    store.0.lock().unwrap().clone()
+}
+
+/// Forget a tile content entirely, along with everything granted to it.
+#[tauri::command]
+pub fn forget_trust_record(
+    hash: String,
+    store: State<'_, TrustStore>,
+    app: AppHandle,
+) -> Result<(), String> {
+    store.update(&app, |records| records.retain(|r| r.hash != hash)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,216 @@
+use crate::car::{parse_tile, TileContent};
+use crate::error::CommandError;
+use crate::hashing::{file_sha256, hex};
+use crate::net;
+use crate::private;
+use crate::settings::SettingsStore;
+use crate::signing;
+use crate::trust::TrustStore;
+use crate::verify::{integrity_report, Integrity};
+use crate::{reload_tile, TileOpenedPayload, TileStore};
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// ── Update state ─────────────────────────────────────────────────────────────
+
+/// Per-authority bookkeeping for tiles that declare an `update_url`.
+#[derive(Default)]
+pub struct UpdateStore(Mutex<HashMap<String, UpdateEntry>>);
+
+#[derive(Default)]
+struct UpdateEntry {
+    /// `ETag` of the last download, sent back as `If-None-Match`.
+    etag: Option<String>,
+    /// Downloaded + verified revision the user hasn't switched to yet.
+    pending: Option<PathBuf>,
+}
+
+/// Sent to the frontend (`tile:update-available`) when a newer revision has
+/// been downloaded next to the original file.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateAvailablePayload {
+    pub authority: String,
+    pub name: String,
+    pub path: String,
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// Check every open tile with an `update_url` right now, instead of waiting
+/// for the next background tick.
+#[tauri::command]
+pub async fn check_tile_updates(app: AppHandle) -> Result<Vec<UpdateAvailablePayload>, String> {
+    tauri::async_runtime::spawn_blocking(move || check_all(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Switch an open tile over to its downloaded revision. The old file is left
+/// in place.
+#[tauri::command]
+pub async fn apply_tile_update(
+    authority: String,
+    app: AppHandle,
+) -> Result<TileOpenedPayload, CommandError> {
+    let path = app
+        .state::<UpdateStore>()
+        .0
+        .lock()
+        .unwrap()
+        .get_mut(&authority)
+        .and_then(|e| e.pending.take())
+        .ok_or_else(|| anyhow!("no pending update for {authority}"))?;
+    // Content from an update is new to the user: the trust store warns of it
+    // as of any change it wasn't told to expect.
+    let reloaded = tauri::async_runtime::spawn_blocking(move || {
+        reload_tile(&authority, &path, None, false, &app.state(), &app)
+    })
+    .await
+    .map_err(anyhow::Error::from)?;
+    Ok(reloaded?)
+}
+
+// ── Background checker ───────────────────────────────────────────────────────
+
+/// Start the background thread that polls `update_url`s at the interval set
+/// in `Settings::update_check_interval_mins`.
+pub fn spawn_checker(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let mins = app.state::<SettingsStore>().get().update_check_interval_mins;
+        // Re-read the setting every minute while disabled so enabling it
+        // takes effect without a restart.
+        std::thread::sleep(Duration::from_secs(mins.max(1) * 60));
+        if mins > 0 {
+            check_all(&app);
+        }
+    });
+}
+
+/// Check the open tiles with an `update_url`, unless the app is offline.
+/// Private tiles are never checked: fetching the URL would tell its author
+/// the tile is being read.
+fn check_all(app: &AppHandle) -> Vec<UpdateAvailablePayload> {
+    if app.state::<SettingsStore>().get().offline {
+        return Vec::new();
+    }
+    // Snapshot what to check so the store isn't locked during downloads.
+    let targets: Vec<(String, PathBuf, String)> = app
+        .state::<TileStore>()
+        .0
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(|(authority, tile)| {
+            let url = tile.masl.update_url.clone()?;
+            Some((authority.clone(), tile.path.clone(), url))
+        })
+        .collect();
+
+    let mut found = Vec::new();
+    for (authority, path, url) in targets {
+        if private::is_private(app, &authority) {
+            continue;
+        }
+        match check_one(app, &authority, &path, &url) {
+            Ok(Some(payload)) => {
+                let _ = app.emit("tile:update-available", &payload);
+                found.push(payload);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("update check for {authority} failed: {e}"),
+        }
+    }
+    found
+}
+
+/// Refuse `update` to the tile open at `authority` unless it is validly
+/// signed by the key the installed content was, when that was signed.
+fn check_signer(app: &AppHandle, authority: &str, update: &TileContent) -> Result<()> {
+    let Some(installed) = app.state::<TileStore>().get(authority) else { return Ok(()) };
+    let recorded = app.try_state::<TrustStore>().and_then(|t| t.signer_of(&installed.root_hash));
+    let recorded = recorded.or_else(|| {
+        signing::check_tile(&installed).filter(|(_, _, error)| error.is_none()).map(|(s, ..)| s.key)
+    });
+    let Some(recorded) = recorded else { return Ok(()) };
+    match signing::check_tile(update) {
+        Some((signature, _, None)) if signature.key.eq_ignore_ascii_case(&recorded) => Ok(()),
+        Some((signature, _, None)) => {
+            let key = signature.key;
+            bail!("update is signed by {key}, not by {recorded} as the installed tile is")
+        }
+        _ => bail!("update isn't validly signed by {recorded} as the installed tile is"),
+    }
+}
+
+/// Download `url` and, if it differs from the newest local revision and
+/// verifies, store it beside `current` and mark it pending.
+fn check_one(
+    app: &AppHandle,
+    authority: &str,
+    current: &Path,
+    url: &str,
+) -> Result<Option<UpdateAvailablePayload>> {
+    let updates = app.state::<UpdateStore>();
+    let (etag, pending) = {
+        let guard = updates.0.lock().unwrap();
+        let entry = guard.get(authority);
+        (
+            entry.and_then(|e| e.etag.clone()),
+            entry.and_then(|e| e.pending.clone()),
+        )
+    };
+
+    let mut request = net::get(url);
+    if let Some(etag) = &etag {
+        request = request.set("If-None-Match", etag);
+    }
+    let response = request.call().map_err(|e| anyhow!("fetching {url}: {e}"))?;
+    if response.status() == 304 {
+        return Ok(None);
+    }
+    let new_etag = response.header("etag").map(str::to_string);
+
+    // Stream to a hidden temp file next to the original, hashing as we go.
+    let dir = current.parent().unwrap_or(Path::new("."));
+    let stem = current.file_stem().and_then(|s| s.to_str()).unwrap_or("tile");
+    let tmp = dir.join(format!(".{stem}.download"));
+    let digest = hex(&net::download_to(response, &tmp)?);
+
+    let result = (|| {
+        let newest = pending.as_deref().unwrap_or(current);
+        if file_sha256(newest)? == digest {
+            return Ok(None);
+        }
+        let content = parse_tile(&tmp)?;
+        content.verify_resources()?;
+        let report = integrity_report(&content)?;
+        let failed = report.resources.iter().filter(|(_, s)| **s == Integrity::Fail);
+        let failed: Vec<&str> = failed.map(|(path, _)| path.as_str()).collect();
+        if !failed.is_empty() {
+            bail!("update from {url} fails verification ({})", failed.join(", "));
+        }
+        check_signer(app, authority, &content)?;
+
+        let dest = dir.join(format!("{stem}.{}.tile", &digest[..8]));
+        std::fs::rename(&tmp, &dest)?;
+        Ok(Some(UpdateAvailablePayload {
+            authority: authority.to_string(),
+            name: content.masl.name,
+            path: dest.to_string_lossy().into_owned(),
+        }))
+    })();
+    let _ = std::fs::remove_file(&tmp);
+
+    let mut guard = updates.0.lock().unwrap();
+    let entry = guard.entry(authority.to_string()).or_default();
+    entry.etag = new_etag;
+    if let Ok(Some(payload)) = &result {
+        entry.pending = Some(PathBuf::from(&payload.path));
+    }
+    result
+}
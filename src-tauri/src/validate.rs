@@ -0,0 +1,497 @@
+use crate::car::{
+    cbor_to_cid_string, is_src_key, parse_masl, read_masl, TileContent, REDIRECT_STATUSES,
+};
+use crate::error::{CommandError, TileError};
+use crate::hashing::IDENTITY;
+use crate::i18n;
+use crate::revisions;
+use crate::routes::RouteTrie;
+use crate::{headers, TileStore};
+use anyhow::{anyhow, bail, Result};
+use ciborium::value::Value as CborValue;
+use cid::Cid;
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::http::HeaderValue;
+use tauri::{AppHandle, Manager};
+
+// ── Validate before save ─────────────────────────────────────────────────────
+//
+// Every authoring path that writes a tile (`TileBuilder::finish` and
+// `edit::rewrite`) checks the header it is about to write first, and
+// refuses to write a file that fails. The header has to parse as this app
+// parses it. Beyond that, the tile has to have a name and a start page at
+// `/index.html`, every resource path must be absolute and free of `.`, `..`
+// and empty segments, and every resource, and each of its content-type
+// variants, needs a content type. Header names and values must be valid
+// HTTP. A redirect needs a redirect status and a `location` that is a path
+// in the tile; the start page may be one. Icons and error pages must be
+// resources, and error pages be given for error statuses. Every block the
+// header links to, roots, variants and earlier revisions included, must be
+// in the file being written, unless a resource's identity CID carries it
+// inline. Unlike `lint.rs`, which flags what the viewer works around, these
+// are problems that would leave a tile broken.
+//
+// The same check runs over tiles already written, for `validate_tile` and,
+// with the `strict_validation` setting on, before a tile is opened, which
+// refuses one that fails. Its report adds warnings for what the parser
+// skips rather than refusing: icons that aren't maps or lack a `src`,
+// header values that aren't strings, headers on redirects, `error_pages`
+// entries that don't map a status to a path, language map entries that
+// aren't, authors without a name, and other fields of the wrong type.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationFailure {
+    /// Resource path the failure is about, if it is about one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    /// Where in the header, or in the resource's entry, the failure is:
+    /// `icons[1].sizes`, or a header name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationFailure {
+    /// The message after the resource and field it is about, so it still
+    /// says where once flattened into a line of text.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.resource, &self.field) {
+            (Some(resource), Some(field)) => write!(f, "{resource} `{field}`: ")?,
+            (Some(resource), None) => write!(f, "{resource}: ")?,
+            (None, Some(field)) => write!(f, "`{field}`: ")?,
+            (None, None) => {}
+        }
+        f.write_str(&self.message)
+    }
+}
+
+/// What [`report`] finds in a header.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    /// What [`check`] fails; any one stops the tile being saved.
+    pub errors: Vec<ValidationFailure>,
+    /// Parts of the header the parser skips.
+    pub warnings: Vec<ValidationFailure>,
+}
+
+/// Why a write was refused: the failures, one per line.
+#[derive(Debug)]
+pub struct ValidationError(pub Vec<ValidationFailure>);
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "refusing to write a tile that fails validation:")?;
+        for failure in &self.0 {
+            write!(f, "\n{failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check a CAR header whose file will hold the blocks `blocks`.
+pub fn check(header: &[(CborValue, CborValue)], blocks: &HashSet<Cid>) -> Vec<ValidationFailure> {
+    let mut failures = Vec::new();
+    let tile = |message: String| ValidationFailure { resource: None, field: None, message };
+    let at = |field: String, message: String| ValidationFailure {
+        resource: None,
+        field: Some(field),
+        message,
+    };
+    let mut bytes = Vec::new();
+    if let Err(e) = ciborium::ser::into_writer(&CborValue::Map(header.to_vec()), &mut bytes) {
+        return vec![tile(format!("header doesn't encode: {e}"))];
+    }
+    let masl = match parse_masl(&bytes) {
+        Ok((masl, _)) => masl,
+        Err(e) => return vec![tile(format!("header doesn't parse: {e}"))],
+    };
+
+    if masl.name.trim().is_empty() {
+        failures.push(at("name".into(), "tile has no name".into()));
+    }
+    let start = "/index.html";
+    if !masl.resources.contains_key(start) && !masl.redirects.contains_key(start) {
+        failures.push(tile("tile has no start page at /index.html".into()));
+    }
+    // Icons and error pages are looked up as requests for them are, through
+    // the routes and a redirect (see `TileContent::resolve_linked`).
+    let routes = RouteTrie::new(masl.resources.keys().chain(masl.redirects.keys()));
+    let served = |path: &str| {
+        let key = routes.lookup(path)?;
+        match masl.redirects.get(key) {
+            Some(redirect) if redirect.stays_in_tile() => routes.lookup(&redirect.location),
+            Some(_) => None,
+            None => Some(key),
+        }
+    };
+    let serves = |path: &str| served(path).is_some_and(|key| masl.resources.contains_key(key));
+    for (i, icon) in masl.icons.iter().enumerate() {
+        if !serves(&icon.src) {
+            let message = format!("icon {} is not one of the tile's resources", icon.src);
+            failures.push(at(format!("icons[{i}].src"), message));
+        }
+    }
+    for (status, page) in &masl.error_pages {
+        let field = || format!("error_pages.{status}");
+        if !(400..600).contains(status) {
+            let message = format!("error page {page} is for {status}, not an error status");
+            failures.push(at(field(), message));
+        }
+        if let Some(problem) = path_problem(page) {
+            failures.push(at(field(), format!("error page {page}: {problem}")));
+        } else if !serves(page) {
+            let message = format!("error page {page} is not one of the tile's resources");
+            failures.push(at(field(), message));
+        }
+    }
+    for root in roots(header).iter().filter(|cid| !blocks.contains(cid)) {
+        failures.push(at("roots".into(), format!("root {root} is missing from the file")));
+    }
+    if let Some(prev) = revisions::previous(header).filter(|cid| !blocks.contains(cid)) {
+        let message = format!("previous revision {prev} is missing from the file");
+        failures.push(at("previous".into(), message));
+    }
+
+    let mut paths: Vec<&String> = masl.resources.keys().collect();
+    paths.sort();
+    for path in paths {
+        let resource = &masl.resources[path];
+        let mut fail = |field: Option<String>, message: String| {
+            let resource = Some(path.clone());
+            failures.push(ValidationFailure { resource, field, message })
+        };
+        if let Some(problem) = path_problem(path) {
+            fail(None, problem.into());
+        }
+        let variants = masl.variants.get(path).map_or(&[][..], Vec::as_slice);
+        let variants = variants.iter().enumerate().map(|(i, v)| (format!("variants[{i}]."), v));
+        let served = std::iter::once((String::new(), resource)).chain(variants);
+        for (within, resource) in served {
+            let field = |key: &str| Some(format!("{within}{key}"));
+            match resource.get("content-type") {
+                None => fail(field("content-type"), "no content type".into()),
+                Some(t) if HeaderValue::from_str(t).is_err() => {
+                    fail(field("content-type"), format!("{t:?} is not a valid header value"))
+                }
+                Some(_) => {}
+            }
+            for (name, reason) in headers::dropped(resource, true) {
+                if reason.starts_with("invalid") {
+                    fail(field(&name), format!("header {reason}"));
+                }
+            }
+            for (key, cid) in resource.iter().filter(|(k, _)| is_src_key(k)) {
+                match cid.parse::<Cid>() {
+                    Ok(cid) if blocks.contains(&cid) || cid.hash().code() == IDENTITY => {}
+                    _ => fail(field(key), format!("block {cid} is missing from the file")),
+                }
+            }
+        }
+    }
+
+    let mut redirects: Vec<_> = masl.redirects.iter().collect();
+    redirects.sort_by_key(|(path, _)| *path);
+    for (path, redirect) in redirects {
+        let mut fail = |field: Option<&str>, message: String| {
+            let (resource, field) = (Some(path.clone()), field.map(str::to_string));
+            failures.push(ValidationFailure { resource, field, message })
+        };
+        if let Some(problem) = path_problem(path) {
+            fail(None, problem.into());
+        }
+        if !REDIRECT_STATUSES.contains(&redirect.status) {
+            let status = redirect.status;
+            let message = format!("redirect status {status} is not one of {REDIRECT_STATUSES:?}");
+            fail(Some("status"), message);
+        }
+        let location = &redirect.location;
+        if !redirect.stays_in_tile() {
+            let message = format!("redirect to {location:?} is not to a path in the tile");
+            fail(Some("location"), message);
+        } else if HeaderValue::from_str(location).is_err() {
+            let message = format!("redirect to {location:?} is not a valid header value");
+            fail(Some("location"), message);
+        }
+    }
+    failures
+}
+
+/// What is wrong with `path` as a resource path, if anything.
+fn path_problem(path: &str) -> Option<&'static str> {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    if !path.starts_with('/') {
+        Some("path is not absolute")
+    } else if segments.iter().any(|s| s.is_empty() || *s == "." || *s == "..") {
+        Some("path has an empty, `.` or `..` segment")
+    } else {
+        None
+    }
+}
+
+/// The CIDs a header lists under `roots`.
+pub(crate) fn roots(header: &[(CborValue, CborValue)]) -> Vec<Cid> {
+    let roots = header.iter().find(|(k, _)| k.as_text() == Some("roots"));
+    let Some((_, CborValue::Array(roots))) = roots else { return Vec::new() };
+    roots.iter().filter_map(|root| cbor_to_cid_string(root)?.parse().ok()).collect()
+}
+
+/// [`check`], as an error when anything fails.
+pub fn ensure(header: &[(CborValue, CborValue)], blocks: &HashSet<Cid>) -> Result<()> {
+    let failures = check(header, blocks);
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError(failures).into())
+    }
+}
+
+/// [`check`] of a header and the warnings for what parsing it skips.
+pub fn report(header: &[(CborValue, CborValue)], blocks: &HashSet<Cid>) -> ValidationReport {
+    ValidationReport { errors: check(header, blocks), warnings: skipped(header) }
+}
+
+/// Optional MASL fields read only when they are strings.
+const STRING_FIELDS: [&str; 8] = [
+    "short_name",
+    "theme_color",
+    "background_color",
+    "update_url",
+    "author_did",
+    "license",
+    "published",
+    "modified",
+];
+
+/// Parts of `header` that `parse_masl` passes over rather than failing on.
+fn skipped(header: &[(CborValue, CborValue)]) -> Vec<ValidationFailure> {
+    let mut warnings = Vec::new();
+    let mut warn = |resource: Option<&str>, field: String, message: &str| {
+        warnings.push(ValidationFailure {
+            resource: resource.map(str::to_string),
+            field: Some(field),
+            message: message.into(),
+        })
+    };
+    for (key, value) in header {
+        let Some(key) = key.as_text() else {
+            warn(None, format!("{key:?}"), "key is not a string; ignored");
+            continue;
+        };
+        match (key, value) {
+            (key, value) if STRING_FIELDS.contains(&key) && !value.is_text() => {
+                warn(None, key.into(), "not a string; ignored")
+            }
+            ("name" | "description", CborValue::Map(by_language)) => {
+                for (tag, text) in by_language {
+                    match tag.as_text() {
+                        None => {
+                            let field = format!("{key}.{tag:?}");
+                            warn(None, field, "language tag is not a string; ignored")
+                        }
+                        Some(tag) if !text.is_text() => {
+                            warn(None, format!("{key}.{tag}"), "not a string; ignored")
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            ("description", value) if !value.is_text() => {
+                warn(None, key.into(), "not a string or language map; ignored")
+            }
+            ("author", CborValue::Array(authors)) => {
+                for (i, author) in authors.iter().enumerate() {
+                    if let Some(message) = skipped_author(author) {
+                        warn(None, format!("author[{i}]"), message);
+                    }
+                }
+            }
+            ("author", author) => {
+                if let Some(message) = skipped_author(author) {
+                    warn(None, key.into(), message);
+                }
+            }
+            ("dc", CborValue::Map(terms)) => {
+                for (term, value) in terms {
+                    let Some(term) = term.as_text() else {
+                        warn(None, format!("dc.{term:?}"), "term is not a string; ignored");
+                        continue;
+                    };
+                    match value {
+                        CborValue::Array(values) => {
+                            for (i, _) in values.iter().enumerate().filter(|(_, v)| !v.is_text()) {
+                                warn(None, format!("dc.{term}[{i}]"), "not a string; ignored");
+                            }
+                        }
+                        value if !value.is_text() => {
+                            warn(None, format!("dc.{term}"), "not a string or array; ignored")
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ("dc", _) => warn(None, key.into(), "not a map; ignored"),
+            ("error_pages", CborValue::Map(pages)) => {
+                for (status, page) in pages {
+                    let field = match status.as_text() {
+                        Some(status) => format!("error_pages.{status}"),
+                        None => format!("error_pages.{status:?}"),
+                    };
+                    if status.as_text().is_none_or(|s| s.parse::<u16>().is_err()) {
+                        warn(None, field, "key is not a status; ignored");
+                    } else if !page.is_text() {
+                        warn(None, field, "not a string; ignored");
+                    }
+                }
+            }
+            ("error_pages", _) => warn(None, key.into(), "not a map; ignored"),
+            ("permissions", CborValue::Array(items)) => {
+                for (i, _) in items.iter().enumerate().filter(|(_, item)| !item.is_text()) {
+                    warn(None, format!("permissions[{i}]"), "not a string; ignored");
+                }
+            }
+            ("icons", CborValue::Array(icons)) => {
+                for (i, icon) in icons.iter().enumerate() {
+                    let Some(fields) = icon.as_map() else {
+                        warn(None, format!("icons[{i}]"), "icon is not a map; ignored");
+                        continue;
+                    };
+                    let field = |name: &str| {
+                        fields.iter().find(|(k, _)| k.as_text() == Some(name)).map(|(_, v)| v)
+                    };
+                    if !field("src").is_some_and(CborValue::is_text) {
+                        warn(None, format!("icons[{i}].src"), "icon has no `src` string; ignored");
+                    }
+                    for name in ["sizes", "purpose"] {
+                        if field(name).is_some_and(|v| !v.is_text()) {
+                            warn(None, format!("icons[{i}].{name}"), "not a string; ignored");
+                        }
+                    }
+                }
+            }
+            ("resources", CborValue::Map(resources)) => {
+                for (path, entry) in resources {
+                    let (Some(path), Some(entry)) = (path.as_text(), entry.as_map()) else {
+                        continue;
+                    };
+                    let has = |key: &str| entry.iter().any(|(k, _)| k.as_text() == Some(key));
+                    if has("location") && !has("src") {
+                        for (name, value) in entry {
+                            match name.as_text() {
+                                Some("location") => {}
+                                Some("status") if value.is_integer() => {}
+                                Some("status") => {
+                                    warn(Some(path), "status".into(), "not an integer; ignored")
+                                }
+                                _ => {
+                                    let field = name.as_text().map(str::to_string);
+                                    let field = field.unwrap_or_else(|| format!("{name:?}"));
+                                    let message = "a redirect sends no headers; ignored";
+                                    warn(Some(path), field, message)
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    for (name, value) in entry {
+                        match name.as_text() {
+                            None => {
+                                let field = format!("{name:?}");
+                                warn(Some(path), field, "header name is not a string; ignored")
+                            }
+                            Some("variants") => {
+                                let Some(variants) = value.as_array() else {
+                                    warn(Some(path), "variants".into(), "not an array; ignored");
+                                    continue;
+                                };
+                                for (i, variant) in variants.iter().enumerate() {
+                                    if !variant.is_map() {
+                                        let field = format!("variants[{i}]");
+                                        warn(Some(path), field, "variant is not a map; ignored")
+                                    }
+                                }
+                            }
+                            Some(name) if !is_src_key(name) && !value.is_text() => {
+                                let message = "header value is not a string; ignored";
+                                warn(Some(path), name.into(), message)
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings.sort_by(|a, b| a.resource.cmp(&b.resource).then_with(|| a.field.cmp(&b.field)));
+    warnings
+}
+
+/// Why `parse_masl` skips an `author` entry, if it does.
+fn skipped_author(author: &CborValue) -> Option<&'static str> {
+    match author {
+        CborValue::Text(_) => None,
+        CborValue::Map(fields) => {
+            let name = fields.iter().find(|(k, _)| k.as_text() == Some("name"));
+            match name {
+                Some((_, name)) if name.is_text() => None,
+                _ => Some("author has no `name` string; ignored"),
+            }
+        }
+        _ => Some("author is not a string or map; ignored"),
+    }
+}
+
+/// [`report`] over the header `tile`'s file has now.
+pub(crate) fn report_file(tile: &TileContent) -> Result<ValidationReport> {
+    if tile.is_directory() {
+        bail!("a directory tile isn't saved as a file");
+    }
+    let header = read_masl(tile.car_path())?.masl;
+    let CborValue::Map(header) = ciborium::de::from_reader(header.as_slice())
+        .map_err(|e| anyhow!("CBOR decode error: {e}"))?
+    else {
+        let failure = ValidationFailure {
+            resource: None,
+            field: None,
+            message: "CAR header is not a CBOR map".into(),
+        };
+        return Ok(ValidationReport { errors: vec![failure], warnings: Vec::new() });
+    };
+    let blocks = tile.index()?.keys().filter_map(|cid| cid.parse().ok()).collect();
+    Ok(report(&header, &blocks))
+}
+
+/// Refuse to open `tile` if its file fails [`check`], as the
+/// `strict_validation` setting asks. Directory tiles aren't checked.
+pub(crate) fn ensure_valid(tile: &TileContent) -> Result<()> {
+    if tile.is_directory() {
+        return Ok(());
+    }
+    let errors = report_file(tile)?.errors;
+    let Some(first) = errors.first() else { return Ok(()) };
+    let more = match errors.len() {
+        1 => String::new(),
+        n => format!(" (and {} more)", n - 1),
+    };
+    bail!(TileError::Invalid(format!("tile fails validation: {first}{more}")))
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+/// What would stop the open tile `authority` from being saved as it is, and
+/// what in its header the parser skips.
+#[tauri::command]
+pub async fn validate_tile(
+    authority: String,
+    app: AppHandle,
+) -> Result<ValidationReport, CommandError> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| anyhow!(i18n::t("error.tile-not-loaded")))?;
+    let report = tauri::async_runtime::spawn_blocking(move || report_file(&tile))
+        .await
+        .map_err(anyhow::Error::from)?;
+    Ok(report?)
+}
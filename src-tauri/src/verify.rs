@@ -0,0 +1,214 @@
+use crate::cache::BlockCache;
+use crate::car::{is_inline, is_src_key, TileContent};
+use crate::events::Coalescer;
+use crate::i18n;
+use crate::signing;
+use crate::TileStore;
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+// ── Block verification ───────────────────────────────────────────────────────
+//
+// Hashes every indexed block against its CID on the rayon pool. Blocks are
+// read through the tile's shared positioned-read handle, so workers don't
+// contend on a file cursor. Checking blocks as they are served instead is
+// `TileContent::set_verify_reads`, on for every tile with the `verify_reads`
+// setting or for one with `set_read_verification`.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub blocks: u64,
+    pub bytes: u64,
+    /// CIDs whose bytes don't match their multihash.
+    pub corrupt: Vec<String>,
+    /// CIDs that couldn't be read from disk.
+    pub unreadable: Vec<String>,
+    /// CIDs using a hash function we can't check.
+    pub unsupported: Vec<String>,
+    pub elapsed_ms: u64,
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyProgress {
+    pub done: u64,
+    pub total: u64,
+    pub bytes: u64,
+}
+
+enum Outcome {
+    Ok,
+    Corrupt,
+    Unreadable,
+    Unsupported,
+}
+
+/// Verify every block in `tile`, calling `on_progress` roughly every 1% of
+/// blocks.
+pub fn verify_blocks(
+    tile: &TileContent,
+    on_progress: impl Fn(VerifyProgress) + Sync,
+) -> Result<VerifyReport> {
+    let index = tile.index()?;
+    let total = index.len() as u64;
+    let step = (total / 100).max(1);
+    let done = AtomicU64::new(0);
+    let bytes = AtomicU64::new(0);
+    let started = Instant::now();
+
+    let failures: Vec<(String, Outcome)> = index
+        .par_iter()
+        .map(|(cid_str, &(_, len))| {
+            let outcome = match tile.verify_block(cid_str) {
+                Ok(Some(true)) => Outcome::Ok,
+                Ok(Some(false)) => Outcome::Corrupt,
+                Ok(None) => Outcome::Unsupported,
+                Err(_) => Outcome::Unreadable,
+            };
+            let b = bytes.fetch_add(len, Ordering::Relaxed) + len;
+            let d = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if d.is_multiple_of(step) || d == total {
+                on_progress(VerifyProgress { done: d, total, bytes: b });
+            }
+            (cid_str.clone(), outcome)
+        })
+        .filter(|(_, o)| !matches!(o, Outcome::Ok))
+        .collect();
+
+    let mut report = VerifyReport {
+        blocks: total,
+        bytes: bytes.into_inner(),
+        corrupt: Vec::new(),
+        unreadable: Vec::new(),
+        unsupported: Vec::new(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        bytes_per_sec: 0,
+    };
+    let secs = started.elapsed().as_secs_f64();
+    if secs > 0.0 {
+        report.bytes_per_sec = (report.bytes as f64 / secs) as u64;
+    }
+    for (cid, outcome) in failures {
+        match outcome {
+            Outcome::Corrupt => report.corrupt.push(cid),
+            Outcome::Unreadable => report.unreadable.push(cid),
+            Outcome::Unsupported => report.unsupported.push(cid),
+            Outcome::Ok => {}
+        }
+    }
+    Ok(report)
+}
+
+// ── Integrity report ─────────────────────────────────────────────────────────
+//
+// Verified open mode summarises block verification per resource, which is
+// what the frontend shows: a resource fails if any block it serves (identity,
+// encoded or content-type variant) is corrupt or unreadable.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Integrity {
+    Pass,
+    /// Uses a hash function we can't check.
+    Unchecked,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    /// Every resource passed.
+    pub verified: bool,
+    /// Resource path → result.
+    pub resources: BTreeMap<String, Integrity>,
+}
+
+/// Verify every block of `tile` and fold the result into per-resource
+/// statuses.
+pub fn integrity_report(tile: &TileContent) -> Result<IntegrityReport> {
+    let report = verify_blocks(tile, |_| {})?;
+    let index = tile.index()?;
+    let failed: HashSet<&str> =
+        report.corrupt.iter().chain(&report.unreadable).map(String::as_str).collect();
+    let unsupported: HashSet<&str> = report.unsupported.iter().map(String::as_str).collect();
+
+    let mut resources: BTreeMap<String, Integrity> = BTreeMap::new();
+    for (path, resource) in tile.masl.served() {
+        let status = resource
+            .iter()
+            .filter(|(k, _)| is_src_key(k))
+            .map(|(_, cid)| match cid.as_str() {
+                cid if failed.contains(cid) => Integrity::Fail,
+                cid if !index.contains_key(cid) && !is_inline(cid) => Integrity::Fail,
+                cid if unsupported.contains(cid) => Integrity::Unchecked,
+                _ => Integrity::Pass,
+            })
+            .max()
+            .unwrap_or(Integrity::Pass);
+        let worst = resources.entry(path.clone()).or_insert(status);
+        *worst = status.max(*worst);
+    }
+    let verified = resources.values().all(|s| *s == Integrity::Pass);
+    Ok(IntegrityReport { verified, resources })
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+struct VerifyProgressPayload {
+    authority: String,
+    #[serde(flatten)]
+    progress: VerifyProgress,
+}
+
+impl VerifyProgressPayload {
+    /// Workers finish out of order; keep the furthest-along report.
+    fn merge(pending: &mut Self, next: Self) {
+        if next.progress.done >= pending.progress.done {
+            *pending = next;
+        }
+    }
+}
+
+/// Turn hashing of blocks as they are served on or off for an open tile,
+/// whatever the `verify_reads` setting was when it was opened. Turning it
+/// on drops the tile's cached blocks that weren't checked when read. It
+/// stays on for a signed tile or one holding permissions (see
+/// `vouched_for` in lib.rs).
+#[tauri::command]
+pub fn set_read_verification(
+    authority: String,
+    enabled: bool,
+    app: AppHandle,
+) -> Result<(), String> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    let signature = signing::status(&app, &tile);
+    let enabled = enabled || crate::vouched_for(&app, &tile, signature.as_ref());
+    tile.set_verify_reads(enabled);
+    if let Some(cache) = app.try_state::<BlockCache>().filter(|_| enabled) {
+        cache.evict_path(&tile.path);
+    }
+    Ok(())
+}
+
+/// Hash every block of an open tile, emitting `tile:verify-progress` as it
+/// goes.
+#[tauri::command]
+pub async fn verify_tile(authority: String, app: AppHandle) -> Result<VerifyReport, String> {
+    let tile = app.state::<TileStore>().get(&authority);
+    let tile = tile.ok_or_else(|| i18n::t("error.tile-not-loaded"))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let events = Coalescer::new(&app, "tile:verify-progress", VerifyProgressPayload::merge);
+        verify_blocks(&tile, |progress| {
+            events.push(VerifyProgressPayload { authority: authority.clone(), progress });
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
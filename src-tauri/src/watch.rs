@@ -0,0 +1,191 @@
+use crate::hashing::file_sha256;
+use crate::settings::SettingsStore;
+use crate::{reload_tile, TileStore};
+use anyhow::Result;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// ── File watching ────────────────────────────────────────────────────────────
+//
+// Open tiles are re-parsed when their file changes on disk. Parent directories
+// are watched rather than the files themselves, since editors and sync tools
+// usually replace a file by renaming a temp file over it. Bursts of events are
+// coalesced by the debouncer, and a reload is skipped when the content hash
+// hasn't changed (e.g. a `touch`, or rsync rewriting identical bytes).
+//
+// Watching can be off, or lag behind a protocol request. When a request finds
+// the file rewritten underneath it (`TileContent::modified_on_disk`), the tile
+// is reloaded and re-verified through [`TileWatcher::reload_modified`] and
+// the user is told, whatever the setting.
+
+/// Quiet period after the last event before a changed file is reloaded.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub struct TileWatcher {
+    app: AppHandle,
+    watched: Mutex<Watched>,
+}
+
+struct Watched {
+    debouncer: Option<Debouncer<RecommendedWatcher>>,
+    dirs: HashSet<PathBuf>,
+    /// Canonical file path → (authority, last known content hash).
+    files: HashMap<PathBuf, (String, Option<String>)>,
+    /// Authorities with a [`TileWatcher::reload_modified`] in flight.
+    reloading: HashSet<String>,
+}
+
+/// Sent as `tile:modified-on-disk` when a request caught the file changing.
+#[derive(Debug, Clone, Serialize)]
+struct ModifiedOnDisk {
+    authority: String,
+    name: String,
+    path: String,
+    /// Reloading failed, e.g. the file is now truncated; the tab is stale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The failure's code (see `error.rs`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+}
+
+impl TileWatcher {
+    pub fn new(app: AppHandle) -> Self {
+        let handle = app.clone();
+        let debouncer = new_debouncer(DEBOUNCE, move |res: DebounceEventResult| {
+            if let Ok(events) = res {
+                let paths: HashSet<PathBuf> = events.into_iter().map(|e| e.path).collect();
+                for path in paths {
+                    reload_if_changed(&handle, &path);
+                }
+            }
+        })
+        .map_err(|e| tracing::warn!("file watching unavailable: {e}"))
+        .ok();
+        TileWatcher {
+            app,
+            watched: Mutex::new(Watched {
+                debouncer,
+                dirs: HashSet::new(),
+                files: HashMap::new(),
+                reloading: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Reload and re-verify the tile at `authority` after its file changed
+    /// underneath a request, then emit `tile:modified-on-disk`. Concurrent
+    /// calls for one tile share a single reload.
+    pub fn reload_modified(&self, authority: &str) {
+        if !self.watched.lock().unwrap().reloading.insert(authority.to_string()) {
+            return;
+        }
+        let (app, authority) = (self.app.clone(), authority.to_string());
+        std::thread::spawn(move || {
+            let store = app.state::<TileStore>();
+            if let Some(tile) = store.get(&authority) {
+                let reloaded = reload_tile(&authority, &tile.path, Some(true), true, &store, &app);
+                let failed = reloaded.err();
+                let code = failed.as_ref().map(crate::error::code);
+                let error = failed.map(|e| e.to_string());
+                if error.is_none() {
+                    app.state::<TileWatcher>().rebaseline(&tile.path);
+                }
+                let notice = ModifiedOnDisk {
+                    authority: authority.clone(),
+                    name: tile.masl.name.clone(),
+                    path: tile.path.to_string_lossy().into_owned(),
+                    error,
+                    code,
+                };
+                if let Err(e) = app.emit("tile:modified-on-disk", notice) {
+                    tracing::warn!("emitting tile:modified-on-disk failed: {e}");
+                }
+            }
+            let watcher = app.state::<TileWatcher>();
+            watcher.watched.lock().unwrap().reloading.remove(&authority);
+        });
+    }
+
+    /// Record the file's current hash as handled, so a change that was just
+    /// reloaded isn't reloaded again when the watcher's event for it arrives.
+    pub(crate) fn rebaseline(&self, path: &Path) {
+        let Ok(path) = std::fs::canonicalize(path) else { return };
+        let Ok(hash) = file_sha256(&path) else { return };
+        if let Some(entry) = self.watched.lock().unwrap().files.get_mut(&path) {
+            entry.1 = Some(hash);
+        }
+    }
+}
+
+/// Start watching the file behind an open tile, if enabled in settings.
+pub fn watch(app: &AppHandle, authority: &str, path: &Path) -> Result<()> {
+    if !app.state::<SettingsStore>().get().reload_on_change {
+        return Ok(());
+    }
+    let path = std::fs::canonicalize(path)?;
+    {
+        let watcher = app.state::<TileWatcher>();
+        let mut w = watcher.watched.lock().unwrap();
+        let Some(dir) = path.parent().map(Path::to_path_buf) else { return Ok(()) };
+        if !w.dirs.contains(&dir) {
+            if let Some(debouncer) = w.debouncer.as_mut() {
+                debouncer.watcher().watch(&dir, RecursiveMode::NonRecursive)?;
+            }
+            w.dirs.insert(dir);
+        }
+        w.files.insert(path.clone(), (authority.to_string(), None));
+    }
+
+    // Baseline hash for the "unchanged content" check, off the calling thread.
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Ok(hash) = file_sha256(&path) {
+            let watcher = app.state::<TileWatcher>();
+            let mut w = watcher.watched.lock().unwrap();
+            if let Some(entry) = w.files.get_mut(&path) {
+                entry.1.get_or_insert(hash);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn reload_if_changed(app: &AppHandle, path: &Path) {
+    let watcher = app.state::<TileWatcher>();
+    let Some((authority, old_hash)) = watcher.watched.lock().unwrap().files.get(path).cloned()
+    else {
+        return;
+    };
+    // Mid-replace the file may briefly be missing; the next event retries.
+    let Ok(hash) = file_sha256(path) else { return };
+    if old_hash.as_deref() == Some(hash.as_str()) {
+        return;
+    }
+
+    // Skip if the authority has since been taken over by a different file.
+    let store = app.state::<TileStore>();
+    let still_open = store
+        .get(&authority)
+        .and_then(|t| std::fs::canonicalize(&t.path).ok())
+        .is_some_and(|p| p == path);
+    if !still_open {
+        return;
+    }
+
+    match reload_tile(&authority, path, None, true, &store, app) {
+        Ok(_) => {
+            let mut w = watcher.watched.lock().unwrap();
+            if let Some(entry) = w.files.get_mut(path) {
+                entry.1 = Some(hash);
+            }
+        }
+        Err(e) => tracing::warn!("reloading {} failed: {e}", path.display()),
+    }
+}
@@ -0,0 +1,271 @@
+use crate::builder::{MaslFields, TileBuilder};
+use crate::car::{Icon, Masl};
+use crate::pack::{self, PackSummary};
+use crate::signing;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+// ── New-tile wizard ──────────────────────────────────────────────────────────
+//
+// Backend for the "New Tile" flow. The frontend keeps the whole draft:
+// where the content comes from (a folder on disk or a built-in template),
+// the MASL metadata, and the icons. It sends the draft back on every step.
+// `preview_new_tile` assembles the draft in memory and returns the MASL it
+// would produce. `create_new_tile` writes the same thing to disk. Nothing is
+// kept between calls.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DraftSource {
+    Folder { path: String },
+    Template { id: String },
+}
+
+/// An icon for the new tile: either a resource already in the source, or
+/// an image file elsewhere on disk, copied in under `/icons/`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DraftIcon {
+    #[serde(default)]
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub sizes: String,
+    #[serde(default)]
+    pub purpose: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TileDraft {
+    pub source: DraftSource,
+    #[serde(default)]
+    pub metadata: MaslFields,
+    #[serde(default)]
+    pub icons: Vec<DraftIcon>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TilePreview {
+    pub masl: Masl,
+    pub summary: PackSummary,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TemplateInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// A built-in starting point, embedded from `templates/<id>/`. `{{name}}` in
+/// a file is replaced with the tile's (HTML-escaped) name.
+struct Template {
+    info: TemplateInfo,
+    /// Resource path, content type, body.
+    files: &'static [(&'static str, &'static str, &'static str)],
+}
+
+macro_rules! template_file {
+    ($id:literal, $file:literal, $path:literal, $content_type:literal) => {
+        ($path, $content_type, include_str!(concat!("../templates/", $id, "/", $file)))
+    };
+}
+
+const TEMPLATES: [Template; 4] = [
+    Template {
+        info: TemplateInfo {
+            id: "blank",
+            name: "Blank page",
+            description: "A single empty HTML page.",
+        },
+        files: &[template_file!("blank", "index.html", "/index.html", "text/html")],
+    },
+    Template {
+        info: TemplateInfo {
+            id: "article",
+            name: "Article",
+            description: "A readable single-column document with a stylesheet.",
+        },
+        files: &[
+            template_file!("article", "index.html", "/index.html", "text/html"),
+            template_file!("article", "style.css", "/style.css", "text/css"),
+        ],
+    },
+    Template {
+        info: TemplateInfo {
+            id: "slideshow",
+            name: "Slideshow",
+            description: "Full-screen slides, moved through with the arrow keys or a click.",
+        },
+        files: &[
+            template_file!("slideshow", "index.html", "/index.html", "text/html"),
+            template_file!("slideshow", "style.css", "/style.css", "text/css"),
+            template_file!("slideshow", "slides.js", "/slides.js", "text/javascript"),
+        ],
+    },
+    Template {
+        info: TemplateInfo {
+            id: "gallery",
+            name: "Photo gallery",
+            description: "A grid of captioned photos, with a placeholder to replace.",
+        },
+        files: &[
+            template_file!("gallery", "index.html", "/index.html", "text/html"),
+            template_file!("gallery", "style.css", "/style.css", "text/css"),
+            template_file!(
+                "gallery",
+                "placeholder.svg",
+                "/photos/placeholder.svg",
+                "image/svg+xml"
+            ),
+        ],
+    },
+];
+
+/// Assemble `draft` into a builder, returning it with what went in.
+fn assemble(draft: &TileDraft) -> Result<(TileBuilder, PackSummary)> {
+    let mut metadata = draft.metadata.clone();
+    let mut builder = TileBuilder::new();
+    let mut summary = match &draft.source {
+        DraftSource::Folder { path } => {
+            let dir = Path::new(path);
+            if metadata.name.trim().is_empty() {
+                metadata.name = dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .ok_or_else(|| anyhow!("can't name a tile after {}", dir.display()))?;
+            }
+            pack::add_folder(&mut builder, dir)?
+        }
+        DraftSource::Template { id } => {
+            if metadata.name.trim().is_empty() {
+                bail!("tile needs a name");
+            }
+            add_template(&mut builder, id, &metadata.name)?
+        }
+    };
+
+    let mut icons = Vec::new();
+    for icon in &draft.icons {
+        let src = match (&icon.resource, &icon.file) {
+            (Some(resource), None) => resource.clone(),
+            (None, Some(file)) => {
+                let file = PathBuf::from(file);
+                let name = file
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .ok_or_else(|| anyhow!("bad icon file name {}", file.display()))?;
+                let src = format!("/icons/{name}");
+                let bytes = std::fs::read(&file)?;
+                let (content_type, type_source) = pack::detect_type(&src, &bytes);
+                if !content_type.starts_with("image/") {
+                    bail!("{} is not an image", file.display());
+                }
+                summary.files.retain(|f| f.path != src);
+                summary.files.push(pack::PackedFile {
+                    path: src.clone(),
+                    content_type: content_type.to_string(),
+                    type_source,
+                    size: bytes.len() as u64,
+                });
+                builder.add_resource(&src, bytes, content_type);
+                src
+            }
+            _ => bail!("an icon needs exactly one of resource or file"),
+        };
+        icons.push(Icon { src, sizes: icon.sizes.clone(), purpose: icon.purpose.clone() });
+    }
+    summary.files.sort_by(|a, b| a.path.cmp(&b.path));
+    summary.total_bytes = summary.files.iter().map(|f| f.size).sum();
+
+    builder.set_metadata(metadata).set_icons(icons);
+    summary.dedup = Some(pack::DedupReport::of(&builder));
+    Ok((builder, summary))
+}
+
+fn add_template(builder: &mut TileBuilder, id: &str, name: &str) -> Result<PackSummary> {
+    let template = TEMPLATES
+        .iter()
+        .find(|t| t.info.id == id)
+        .ok_or_else(|| anyhow!("no template named {id}"))?;
+    let escaped = escape_html(name);
+    let mut files = Vec::new();
+    for (path, content_type, body) in template.files {
+        let body = body.replace("{{name}}", &escaped);
+        files.push(pack::PackedFile {
+            path: path.to_string(),
+            content_type: content_type.to_string(),
+            type_source: pack::TypeSource::Extension,
+            size: body.len() as u64,
+        });
+        builder.add_resource(path, body, content_type);
+    }
+    Ok(PackSummary {
+        entry: "/index.html".into(),
+        entry_alias_of: None,
+        total_bytes: files.iter().map(|f| f.size).sum(),
+        files,
+        optimization: None,
+        dedup: None,
+        compression: None,
+    })
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// ── Commands ─────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn list_tile_templates() -> Vec<TemplateInfo> {
+    TEMPLATES.iter().map(|t| t.info).collect()
+}
+
+/// The MASL and file list `draft` would produce, without writing anything.
+#[tauri::command]
+pub async fn preview_new_tile(draft: TileDraft) -> Result<TilePreview, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (builder, summary) = assemble(&draft)?;
+        Ok(TilePreview { masl: builder.masl()?, summary })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Write the built-in template `template` as a new tile at `dest`, named
+/// and described by `metadata`.
+#[tauri::command]
+pub async fn create_from_template(
+    template: String,
+    metadata: MaslFields,
+    dest: String,
+    app: AppHandle,
+) -> Result<PackSummary, String> {
+    let draft =
+        TileDraft { source: DraftSource::Template { id: template }, metadata, icons: Vec::new() };
+    create_new_tile(draft, dest, app).await
+}
+
+/// Write `draft` as a tile at `dest`. The frontend opens it afterwards with
+/// `open_tile` if the user asked to. It is signed if signing on export is
+/// on.
+#[tauri::command]
+pub async fn create_new_tile(
+    draft: TileDraft,
+    dest: String,
+    app: AppHandle,
+) -> Result<PackSummary, String> {
+    let signer = signing::export_signer(&app).map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut builder, summary) = assemble(&draft)?;
+        builder.set_signer(signer).finish(Path::new(&dest))?;
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,20 @@
+const slides = [...document.querySelectorAll(".slide")];
+let current = Math.max(0, Math.min(slides.length - 1, parseInt(location.hash.slice(1), 10) - 1 || 0));
+
+function show(n) {
+  current = Math.max(0, Math.min(slides.length - 1, n));
+  slides.forEach((slide, i) => slide.classList.toggle("current", i === current));
+  history.replaceState(null, "", "#" + (current + 1));
+}
+
+document.addEventListener("keydown", (e) => {
+  if (["ArrowRight", "ArrowDown", "PageDown", " "].includes(e.key)) show(current + 1);
+  else if (["ArrowLeft", "ArrowUp", "PageUp"].includes(e.key)) show(current - 1);
+  else if (e.key === "Home") show(0);
+  else if (e.key === "End") show(slides.length - 1);
+  else return;
+  e.preventDefault();
+});
+document.addEventListener("click", (e) => show(current + (e.clientX < innerWidth / 3 ? -1 : 1)));
+
+show(current);
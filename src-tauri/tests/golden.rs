@@ -0,0 +1,393 @@
+//! Golden tiles through the parser and the protocol handler:
+//! `cargo test --features test-support`.
+
+use tile_documents_lib::builder::{CidOptions, HashFunction, MaslFields, TileBuilder};
+use tile_documents_lib::car::{parse_tile, walk_index, TileContent};
+use tile_documents_lib::error;
+use tile_documents_lib::testing::{self, GOLDEN};
+
+#[test]
+fn golden_tiles_open_as_pinned() {
+    let dir = testing::temp_dir();
+    for golden in GOLDEN {
+        golden.check(dir.path()).unwrap();
+    }
+}
+
+#[test]
+fn golden_tiles_serve_what_they_read() {
+    let (dir, app) = (testing::temp_dir(), testing::mock_app());
+    for golden in GOLDEN.iter().filter(|g| g.error.is_none()) {
+        let (path, bodies) = golden.check(dir.path()).unwrap();
+        let authority = testing::open(&app, &path).unwrap();
+        for (resource, body) in bodies {
+            let response = testing::get(&app, &format!("tile://{authority}{resource}"));
+            assert_eq!(response.status(), 200, "{}: {resource}", golden.name);
+            assert!(*response.body() == body, "{}: {resource} served different bytes", golden.name);
+        }
+    }
+}
+
+#[test]
+fn each_root_of_a_tile_opens_its_own_manifest() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("two-roots.tile");
+    let bodies = testing::write_two_root_tile(&path).unwrap();
+    let tile = parse_tile(&path).unwrap();
+    tile.verify_resources().unwrap();
+    assert_eq!(tile.roots.len(), 2);
+    assert_eq!(tile.root.as_ref(), Some(&tile.roots[0]));
+    let start_page = |tile: &TileContent| {
+        tile.read_block(&tile.resolve("/index.html").unwrap()["src"]).unwrap()
+    };
+    assert_eq!(start_page(&tile), bodies[0]);
+    let second = tile.at_root(&tile.roots[1]).unwrap();
+    assert_eq!(second.masl.name, "Second");
+    assert_eq!(start_page(&second), bodies[1]);
+    let body_cid = &tile.resolve("/index.html").unwrap()["src"];
+    assert!(tile.at_root(body_cid).is_err(), "only a listed root can be opened");
+}
+
+#[test]
+fn dag_pb_files_short_of_their_declared_size_fail_to_read() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("misdeclared.tile");
+    let root = testing::write_misdeclared_dag_pb(&path).unwrap();
+    let tile = parse_tile(&path).unwrap();
+    let e = tile.read_block(&root).unwrap_err();
+    assert_eq!(error::code(&e), "malformed-dag");
+}
+
+#[test]
+fn views_of_a_past_revision_or_another_root_refuse_edits() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("views.tile");
+    testing::write_two_root_tile(&path).unwrap();
+    let tile = parse_tile(&path).unwrap();
+    tile.ensure_editable().unwrap();
+    let other = tile.at_root(&tile.roots[1]).unwrap();
+    assert_eq!(other.ensure_editable().unwrap_err().code(), "read-only-view");
+    let plain = dir.path().join("views-plain.tile");
+    testing::write_synthetic_tile(&plain, 1, 16).unwrap();
+    let past = testing::earlier_revision(&plain).unwrap();
+    assert_eq!(past.ensure_editable().unwrap_err().code(), "read-only-view");
+    parse_tile(&plain).unwrap().ensure_editable().unwrap();
+}
+
+#[test]
+fn resources_are_appended_in_place_to_a_rooted_tile() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("append.tile");
+    testing::write_two_root_tile(&path).unwrap();
+    let before = std::fs::read(&path).unwrap();
+    let body = b"<h1>appended</h1>";
+    assert!(testing::put_resource(&path, "/index.html", body).unwrap());
+    let after = std::fs::read(&path).unwrap();
+    assert!(after.len() > before.len());
+    assert_eq!(after[before.len() - 32..before.len()], before[before.len() - 32..]);
+    let tile = parse_tile(&path).unwrap();
+    tile.verify_resources().unwrap();
+    assert_eq!(tile.read_block(&tile.resolve("/index.html").unwrap()["src"]).unwrap(), body);
+    assert_eq!(tile.at_root(&tile.roots[1]).unwrap().masl.name, "Second");
+    // A crash between journalling the header and writing it is finished
+    // on the next open.
+    testing::interrupt_header_update(&path, &before).unwrap();
+    assert_ne!(std::fs::read(&path).unwrap(), after);
+    let tile = parse_tile(&path).unwrap();
+    assert_eq!(tile.read_block(&tile.resolve("/index.html").unwrap()["src"]).unwrap(), body);
+    assert_eq!(std::fs::read(&path).unwrap(), after);
+    assert!(!path.with_extension("tile.journal").exists());
+    // A tile whose MASL is its header has to be rewritten.
+    let plain = dir.path().join("append-plain.tile");
+    testing::write_synthetic_tile(&plain, 1, 16).unwrap();
+    assert!(!testing::put_resource(&plain, "/index.html", body).unwrap());
+}
+
+#[test]
+fn merged_tiles_serve_both_and_settle_conflicts_by_policy() {
+    let dir = testing::temp_dir();
+    let (first, second) = (dir.path().join("merge-a.tile"), dir.path().join("merge-b.tile"));
+    testing::write_synthetic_tile(&first, 2, 16).unwrap();
+    testing::write_synthetic_tile(&second, 3, 32).unwrap();
+    let dest = dir.path().join("merged.tile");
+    assert!(testing::merge(&first, &second, &dest, "fail").is_err());
+    assert!(!dest.exists(), "a refused merge writes nothing");
+    // `/index.html` is the same in both, so only the padded pages conflict.
+    let conflicts = testing::merge(&first, &second, &dest, "second").unwrap();
+    assert_eq!(conflicts, ["/r/0.html", "/r/1.html"]);
+    let (merged, theirs) = (parse_tile(&dest).unwrap(), parse_tile(&second).unwrap());
+    merged.verify_resources().unwrap();
+    assert_eq!(merged.masl.resources.len(), 4);
+    let body = |tile: &TileContent, path| tile.read_block(&tile.resolve(path).unwrap()["src"]);
+    for path in ["/r/0.html", "/r/2.html"] {
+        assert_eq!(body(&merged, path).unwrap(), body(&theirs, path).unwrap(), "{path}");
+    }
+}
+
+#[test]
+fn merged_tiles_copy_chunked_files_under_their_cids() {
+    let dir = testing::temp_dir();
+    let sub = dir.path().join("merge-chunked");
+    std::fs::create_dir_all(&sub).unwrap();
+    let golden = GOLDEN.iter().find(|g| g.name == "chunked-dag-pb").unwrap();
+    let (chunked, bodies) = golden.check(&sub).unwrap();
+    let other = sub.join("other.tile");
+    testing::write_synthetic_tile(&other, 1, 16).unwrap();
+    let dest = sub.join("merged.tile");
+    testing::merge(&chunked, &other, &dest, "first").unwrap();
+    let (merged, source) = (parse_tile(&dest).unwrap(), parse_tile(&chunked).unwrap());
+    merged.verify_resources().unwrap();
+    let src = &merged.masl.resources["/index.html"]["src"];
+    assert_eq!(src, &source.masl.resources["/index.html"]["src"]);
+    assert_eq!(merged.read_block(src).unwrap(), bodies[0].1);
+    assert!(merged.masl.resources.contains_key("/r/0.html"));
+}
+
+#[test]
+fn merged_tiles_keep_only_the_licensing_both_agree_on() {
+    let dir = testing::temp_dir();
+    let build = |path: &std::path::Path, license: &str, rights: &str| {
+        let dc = [("rights".to_string(), vec![rights.to_string()])].into();
+        let license = Some(license.to_string());
+        let mut builder = TileBuilder::new();
+        builder
+            .set_metadata(MaslFields { name: "Licensed".into(), license, dc, ..Default::default() })
+            .add_resource("/index.html", rights.as_bytes().to_vec(), "text/html");
+        builder.finish(path).unwrap();
+    };
+    let (first, second) = (dir.path().join("merge-cc.tile"), dir.path().join("merge-mit.tile"));
+    build(&first, "CC-BY-4.0", "Alice");
+    build(&second, "MIT", "Alice");
+    let dest = dir.path().join("merged-licensed.tile");
+    testing::merge(&first, &second, &dest, "first").unwrap();
+    let merged = parse_tile(&dest).unwrap();
+    assert_eq!(merged.masl.license, None);
+    assert_eq!(merged.masl.dc["rights"], ["Alice"]);
+}
+
+#[test]
+fn diffs_report_changed_paths_by_cid_with_size_deltas() {
+    let dir = testing::temp_dir();
+    let (old, new) = (dir.path().join("diff-a.tile"), dir.path().join("diff-b.tile"));
+    testing::write_synthetic_tile(&old, 2, 24).unwrap();
+    testing::write_synthetic_tile(&new, 3, 32).unwrap();
+    let diff = testing::diff(&old, &new).unwrap();
+    assert_eq!(diff["unchanged"], 1);
+    assert_eq!(diff["added"][0]["path"], "/r/2.html");
+    assert_eq!(diff["added"][0]["size"], 32);
+    let modified = &diff["modified"][0];
+    assert_eq!(modified["path"], "/r/0.html");
+    assert_ne!(modified["old_cid"], modified["new_cid"]);
+    assert_eq!(modified["size_delta"], 8);
+    assert_eq!(diff["size_delta"], 2 * 8 + 32);
+}
+
+#[test]
+fn diffs_size_chunked_files_by_their_content() {
+    let dir = testing::temp_dir();
+    let golden = GOLDEN.iter().find(|g| g.name == "chunked-dag-pb").unwrap();
+    let (chunked, bodies) = golden.check(dir.path()).unwrap();
+    let raw = dir.path().join("raw.tile");
+    let body = &bodies[0].1;
+    let mut edited = body.clone();
+    edited.extend_from_slice(b"<p>and one more</p>");
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Chunked".into(), ..Default::default() })
+        .add_resource("/index.html", edited.clone(), "text/html");
+    builder.finish(&raw).unwrap();
+    let diff = testing::diff(&chunked, &raw).unwrap();
+    let modified = &diff["modified"][0];
+    assert_eq!(modified["old_size"], body.len());
+    assert_eq!(modified["new_size"], edited.len());
+    assert_eq!(modified["size_delta"], edited.len() - body.len());
+}
+
+#[test]
+fn diffs_report_changed_variants_and_redirects() {
+    let dir = testing::temp_dir();
+    let build = |path: &std::path::Path, avif: &[u8], location: &str| {
+        let mut builder = TileBuilder::new();
+        builder
+            .set_metadata(MaslFields { name: "Variants".into(), ..Default::default() })
+            .add_resource("/logo.png", b"png".to_vec(), "image/png")
+            .add_type_variant("/logo.png", "image/avif", avif.to_vec())
+            .unwrap()
+            .add_redirect("/old", location, 301)
+            .unwrap();
+        builder.finish(path).unwrap();
+    };
+    let old = dir.path().join("diff-variants-a.tile");
+    let new = dir.path().join("diff-variants-b.tile");
+    build(&old, b"avif", "/a");
+    build(&new, b"avif, smaller", "/b");
+    let diff = testing::diff(&old, &new).unwrap();
+    assert_eq!(diff["metadata_changed"], serde_json::json!([]));
+    let modified = &diff["modified"][0];
+    assert_eq!(modified["path"], "/logo.png");
+    assert_eq!(modified["body_changed"], false);
+    assert_eq!(modified["variants_changed"], true);
+    assert_eq!(diff["redirects"][0]["path"], "/old");
+    assert_eq!(diff["redirects"][0]["old"]["location"], "/a");
+    assert_eq!(diff["redirects"][0]["new"]["location"], "/b");
+}
+
+#[test]
+fn identical_bodies_are_stored_once_under_each_of_their_cid_options() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("dedup.tile");
+    let logo = b"<svg>logo</svg>".to_vec();
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Dedup".into(), ..Default::default() })
+        .add_resource("/a/logo.svg", logo.clone(), "image/svg+xml")
+        .add_resource("/b/logo.svg", logo.clone(), "image/svg+xml")
+        .add_resource("/c/logo.svg", logo.clone(), "image/svg+xml")
+        .set_resource_cid_options(
+            "/c/logo.svg",
+            CidOptions { hash: HashFunction::Blake3, ..Default::default() },
+        )
+        .unwrap();
+    builder.finish(&path).unwrap();
+    let src = |tile: &TileContent, path| tile.resolve(path).unwrap()["src"].clone();
+    let tile = parse_tile(&path).unwrap();
+    assert_eq!(src(&tile, "/a/logo.svg"), src(&tile, "/b/logo.svg"));
+    // Options given for a resource make its CID, even for a body held already.
+    let blake3 = CidOptions { hash: HashFunction::Blake3, ..Default::default() };
+    assert_eq!(src(&tile, "/c/logo.svg"), blake3.block(&logo).unwrap().0.to_string());
+    assert_eq!(tile.index().unwrap().len(), 2);
+    // Putting the same bytes at a new path links to the block already there.
+    let before = std::fs::metadata(&path).unwrap().len();
+    testing::put_resource(&path, "/d/logo.svg", &logo).unwrap();
+    let tile = parse_tile(&path).unwrap();
+    tile.verify_resources().unwrap();
+    assert_eq!(src(&tile, "/d/logo.svg"), src(&tile, "/a/logo.svg"));
+    let grown = std::fs::metadata(&path).unwrap().len() - before;
+    assert!(grown < 100, "the header alone grows, by {grown} bytes");
+}
+
+#[test]
+fn index_sidecars_stand_in_for_the_walk_until_the_tile_changes() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("sidecar.tile");
+    testing::write_synthetic_tile(&path, 5000, 16).unwrap();
+    let walked = parse_tile(&path).unwrap().index().unwrap().clone();
+    let sidecar = testing::write_sidecar(&path).unwrap().expect("a sidecar for 5001 blocks");
+    assert!(sidecar.exists());
+    assert_eq!(*parse_tile(&path).unwrap().index().unwrap(), walked);
+    assert!(testing::write_sidecar(&path).unwrap().is_none(), "a current sidecar is kept");
+    // A sidecar giving one block's frame for another's CID is refused, and
+    // the file walked: swap the offsets and lengths of its first two entries.
+    let good = std::fs::read(&sidecar).unwrap();
+    let first = 8 + 8 + 32 + 8;
+    let cid_len = |at: usize| usize::from(u16::from_le_bytes([good[at], good[at + 1]]));
+    let second = first + 2 + cid_len(first) + 16;
+    assert_eq!(cid_len(first), cid_len(second));
+    let (a, b) = (first + 2 + cid_len(first), second + 2 + cid_len(second));
+    let mut swapped = good.clone();
+    swapped[a..a + 16].copy_from_slice(&good[b..b + 16]);
+    swapped[b..b + 16].copy_from_slice(&good[a..a + 16]);
+    std::fs::write(&sidecar, swapped).unwrap();
+    assert_eq!(*parse_tile(&path).unwrap().index().unwrap(), walked);
+    std::fs::write(&sidecar, good).unwrap();
+    // Once the tile is edited the sidecar is of other bytes and is ignored.
+    testing::put_resource(&path, "/new.html", b"<h1>new</h1>").unwrap();
+    let tile = parse_tile(&path).unwrap();
+    assert_eq!(tile.index().unwrap().len(), walked.len() + 1);
+    tile.verify_resources().unwrap();
+    let small = dir.path().join("sidecar-small.tile");
+    testing::write_synthetic_tile(&small, 10, 16).unwrap();
+    assert!(testing::write_sidecar(&small).unwrap().is_none());
+}
+
+#[test]
+fn walks_index_every_block_frame() {
+    let dir = testing::temp_dir();
+    let path = dir.path().join("walk.tile");
+    testing::write_synthetic_tile(&path, 20_000, 16).unwrap();
+    assert_eq!(walk_index(&path).unwrap().len(), 20_001);
+    // A frame cut short fails the walk.
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+    assert!(walk_index(&path).is_err());
+}
+
+#[test]
+fn validation_reports_what_the_parser_skips() {
+    let dir = testing::temp_dir();
+    let golden = GOLDEN.iter().find(|g| g.name == "skipped-fields").unwrap();
+    let (path, _) = golden.check(dir.path()).unwrap();
+    let report = testing::validate(&path).unwrap();
+    assert_eq!(report["errors"], serde_json::json!([]));
+    let warnings: Vec<(&str, &str)> = report["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| (w["resource"].as_str().unwrap_or(""), w["field"].as_str().unwrap()))
+        .collect();
+    assert_eq!(warnings, [("", "icons[0]"), ("", "icons[1].src"), ("/index.html", "x-count")]);
+}
+
+#[test]
+fn unknown_masl_fields_survive_a_rewrite_in_canonical_order() {
+    let dir = testing::temp_dir();
+    let golden = GOLDEN.iter().find(|g| g.name == "extension-fields").unwrap();
+    let (path, _) = golden.check(dir.path()).unwrap();
+    assert!(!testing::canonical_header(&path).unwrap());
+    let tile = parse_tile(&path).unwrap();
+    assert_eq!(tile.masl.extra.keys().collect::<Vec<_>>(), ["x-extension"]);
+    // Through JSON as the parse cache keeps it, and through a rewrite.
+    let json = serde_json::to_string(&tile.masl).unwrap();
+    let cached: tile_documents_lib::car::Masl = serde_json::from_str(&json).unwrap();
+    assert_eq!(cached.extra, tile.masl.extra);
+    let dest = dir.path().join("extension-rewritten.tile");
+    assert!(testing::merge(&path, &path, &dest, "fail").unwrap().is_empty());
+    assert!(testing::canonical_header(&dest).unwrap());
+    assert_eq!(parse_tile(&dest).unwrap().masl.extra, tile.masl.extra);
+}
+
+#[test]
+fn localized_names_match_the_locale_and_survive_a_rewrite() {
+    let dir = testing::temp_dir();
+    let golden = GOLDEN.iter().find(|g| g.name == "localized-names").unwrap();
+    let (path, _) = golden.check(dir.path()).unwrap();
+    let name = |locale| testing::localized(&path, locale).unwrap()["name"].clone();
+    assert_eq!(name("fr-CA"), "Rapport (Canada)");
+    assert_eq!(name("FR_fr"), "Rapport");
+    assert_eq!(name("pt-BR"), "Report");
+    let french = testing::localized(&path, "fr").unwrap();
+    assert_eq!(french["name_lang"], "fr");
+    // The description has no French, so its only entry stands in.
+    assert_eq!(french["description"], "Ein Bericht");
+    assert_eq!(french["description_lang"], "de");
+    let report = testing::validate(&path).unwrap();
+    assert_eq!(report["warnings"][0]["field"], "description.fr");
+    let dest = dir.path().join("localized-rewritten.tile");
+    testing::merge(&path, &path, &dest, "fail").unwrap();
+    let (before, after) = (parse_tile(&path).unwrap(), parse_tile(&dest).unwrap());
+    assert_eq!(after.masl.names, before.masl.names);
+    assert_eq!(after.masl.name, "Report");
+}
+
+#[test]
+fn provenance_fields_are_typed_and_survive_a_rewrite() {
+    let dir = testing::temp_dir();
+    let golden = GOLDEN.iter().find(|g| g.name == "provenance").unwrap();
+    let (path, _) = golden.check(dir.path()).unwrap();
+    let provenance = testing::provenance(&path).unwrap();
+    let expected = serde_json::json!({
+        "authors": [{ "name": "Ada", "url": "https://ada.example/" }, { "name": "Grace" }],
+        "license": "CC-BY-4.0",
+        "published": "2024-05-01T00:00:00Z",
+        "dc": { "publisher": ["Example Press"], "subject": ["maps", "travel"] },
+    });
+    assert_eq!(provenance, expected);
+    let report = testing::validate(&path).unwrap();
+    assert_eq!(report["warnings"][0]["field"], "author[2]");
+    let dest = dir.path().join("provenance-rewritten.tile");
+    testing::merge(&path, &path, &dest, "fail").unwrap();
+    let mut rewritten = testing::provenance(&dest).unwrap();
+    // A merge is a document of its own, with no publication date.
+    rewritten["published"] = expected["published"].clone();
+    assert_eq!(rewritten, expected);
+}
@@ -0,0 +1,465 @@
+//! Requests through the `tile:` protocol handler, checked for status,
+//! headers and body: `cargo test --features test-support`.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::http::Response;
+use tauri::test::MockRuntime;
+use tauri::App;
+use tile_documents_lib::builder::{MaslFields, TileBuilder};
+use tile_documents_lib::testing::{self, GOLDEN};
+
+const INDEX: &[u8] = b"<!doctype html><h1>Protocol</h1>";
+const SCRIPT: &[u8] = b"console.log('a script long enough to be worth compressing');";
+const PNG: &[u8] = b"\x89PNG not really";
+const AVIF: &[u8] = b"\0\0\0\x1cftypavif not really";
+/// Over the 8 MiB a single range response is capped at.
+const MEDIA_LEN: usize = 9 << 20;
+
+fn media() -> Vec<u8> {
+    (0..MEDIA_LEN).map(|i| (i % 251) as u8).collect()
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Write the tile every test reads into `dir`, the test's own (see
+/// `testing::temp_dir`), as `name.tile`.
+fn write_tile(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(format!("{name}.tile"));
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Protocol".into(), ..Default::default() })
+        .add_resource("/index.html", INDEX, "text/html; charset=utf-8")
+        .add_resource("/app.js", SCRIPT, "text/javascript")
+        .add_resource("/media.bin", media(), "application/octet-stream")
+        .add_resource("/logo.png", PNG, "image/png");
+    builder
+        .set_header("/index.html", "cache-control", "max-age=60")
+        .unwrap()
+        .set_header("/index.html", "etag", "\"v1\"")
+        .unwrap()
+        .set_header("/index.html", "set-cookie", "dropped=1")
+        .unwrap()
+        .add_variant("/app.js", "gzip", gzip(SCRIPT))
+        .unwrap()
+        .add_type_variant("/logo.png", "image/avif", AVIF)
+        .unwrap();
+    builder.finish(&path).unwrap();
+    path
+}
+
+fn setup(dir: &Path, name: &str) -> (App<MockRuntime>, PathBuf, String) {
+    let app = testing::mock_app();
+    let path = write_tile(dir, name);
+    let authority = testing::open(&app, &path).unwrap();
+    (app, path, authority)
+}
+
+fn header<'a>(response: &'a Response<Vec<u8>>, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|v| v.to_str().unwrap())
+}
+
+fn range(app: &App<MockRuntime>, authority: &str, spec: &str) -> Response<Vec<u8>> {
+    testing::get_with(app, &format!("tile://{authority}/media.bin"), &[("range", spec)])
+}
+
+// ── Status ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn serves_resources_with_their_content_type() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "content-type");
+    for uri in [format!("tile://{authority}/index.html"), format!("tile://{authority}/")] {
+        let response = testing::get(&app, &uri);
+        assert_eq!(response.status(), 200, "{uri}");
+        assert_eq!(header(&response, "content-type"), Some("text/html; charset=utf-8"));
+        assert_eq!(response.body(), INDEX, "{uri}");
+    }
+}
+
+#[test]
+fn unknown_paths_and_tiles_are_not_found() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "not-found");
+    let missing = testing::get(&app, &format!("tile://{authority}/missing.html"));
+    assert_eq!(missing.status(), 404);
+    assert_eq!(header(&missing, "content-type"), Some("text/plain"));
+    let unloaded = testing::get(&app, "tile://not-a-loaded-tile/index.html");
+    assert_eq!(unloaded.status(), 404);
+}
+
+#[test]
+fn service_worker_scripts_need_the_permission() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "service-worker");
+    let uri = format!("tile://{authority}/app.js");
+    let response = testing::get_with(&app, &uri, &[("service-worker", "script")]);
+    assert_eq!(response.status(), 403);
+    assert_eq!(testing::get(&app, &uri).status(), 200);
+}
+
+#[test]
+fn a_tile_rewritten_on_disk_is_not_served() {
+    let dir = testing::temp_dir();
+    let (app, path, authority) = setup(dir.path(), "rewritten");
+    let uri = format!("tile://{authority}/index.html");
+    assert_eq!(testing::get(&app, &uri).status(), 200);
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.extend(b"trailing");
+    std::fs::write(&path, bytes).unwrap();
+    let response = testing::get(&app, &uri);
+    assert_eq!(response.status(), 503);
+    assert_eq!(header(&response, "retry-after"), Some("1"));
+}
+
+#[test]
+fn corrupt_blocks_fail_when_reads_are_verified() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let path = write_tile(dir.path(), "corrupt");
+    let mut bytes = std::fs::read(&path).unwrap();
+    let at = bytes.windows(INDEX.len()).position(|w| w == INDEX).unwrap();
+    bytes[at + INDEX.len() - 1] ^= 1;
+    std::fs::write(&path, bytes).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+    let uri = format!("tile://{authority}/app.js");
+    let index = format!("tile://{authority}/index.html");
+    testing::set_verify_reads(&app, &authority, true).unwrap();
+    assert_eq!(testing::get(&app, &uri).status(), 200);
+    let response = testing::get(&app, &index);
+    assert_eq!(response.status(), 502);
+    let ranged = testing::get_with(&app, &index, &[("range", "bytes=0-3")]);
+    assert_eq!(ranged.status(), 502);
+}
+
+#[test]
+fn corrupt_chunks_of_dag_pb_files_fail_when_reads_are_verified() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let golden = GOLDEN.iter().find(|g| g.name == "chunked-dag-pb").unwrap();
+    let (path, _) = golden.check(dir.path()).unwrap();
+    let mut bytes = std::fs::read(&path).unwrap();
+    let at = bytes.windows(8).position(|w| w == b"several ").unwrap();
+    bytes[at] ^= 1;
+    std::fs::write(&path, bytes).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+    let index = format!("tile://{authority}/index.html");
+    testing::set_verify_reads(&app, &authority, true).unwrap();
+    assert_eq!(testing::get(&app, &index).status(), 502);
+    let ranged = testing::get_with(&app, &index, &[("range", "bytes=22-25")]);
+    assert_eq!(ranged.status(), 502);
+}
+
+#[test]
+fn compressed_tiles_are_served_like_plain_ones() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let plain = write_tile(dir.path(), "compressed");
+    let path = plain.with_extension("tile.zst");
+    let compressed = zstd::encode_all(std::fs::read(&plain).unwrap().as_slice(), 3).unwrap();
+    std::fs::write(&path, compressed).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+    let response = testing::get(&app, &format!("tile://{authority}/index.html"));
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.body(), INDEX);
+    let ranged = range(&app, &authority, "bytes=1000-1999");
+    assert_eq!(ranged.status(), 206);
+    assert_eq!(ranged.body(), &media()[1000..2000]);
+    // Opened again while open, it's read from the same copy.
+    let copy = testing::car_path(&app, &authority).unwrap();
+    assert_ne!(copy, path);
+    testing::open(&app, &path).unwrap();
+    assert_eq!(testing::car_path(&app, &authority).unwrap(), copy);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&copy).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "only the user reads a decompressed copy");
+    }
+}
+
+#[test]
+fn reserved_paths_are_answered_by_the_app() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "reserved");
+    let response =
+        testing::get(&app, &format!("tile://{authority}/.well-known/tile/accessibility.json"));
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    assert_eq!(header(&response, "cache-control"), Some("no-store"));
+    let prefs: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert!(prefs["font_scale"].is_number());
+}
+
+#[test]
+fn pattern_routes_serve_the_most_specific_match() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let path = write_tile(dir.path(), "routes").with_file_name("routes-patterns.tile");
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Routes".into(), ..Default::default() })
+        .add_resource("/index.html", INDEX, "text/html")
+        .add_resource("/posts/:slug", b"slug".to_vec(), "text/html")
+        .add_resource("/posts/:id", b"post".to_vec(), "text/html")
+        .add_resource("/posts/new", b"new post".to_vec(), "text/html")
+        .add_resource("/assets/*", b"asset".to_vec(), "text/plain")
+        .add_resource("/assets/fonts/*", b"font".to_vec(), "font/woff2");
+    builder.finish(&path).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+    // `/posts/:id` sorts before `/posts/:slug`, so it serves the segment.
+    let cases: [(&str, &[u8]); 5] = [
+        ("/posts/42", b"post"),
+        ("/posts/new", b"new post"),
+        ("/assets/css/site.css", b"asset"),
+        ("/assets/fonts/a/b.woff2", b"font"),
+        ("/", INDEX),
+    ];
+    for (uri, body) in cases {
+        let response = testing::get(&app, &format!("tile://{authority}{uri}"));
+        assert_eq!(response.status(), 200, "{uri}");
+        assert_eq!(response.body(), body, "{uri}");
+    }
+    // A parameter stands for one segment only.
+    let deeper = testing::get(&app, &format!("tile://{authority}/posts/42/comments"));
+    assert_eq!(deeper.status(), 404);
+}
+
+#[test]
+fn redirect_entries_answer_with_their_location() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let path = write_tile(dir.path(), "routes").with_file_name("routes-redirects.tile");
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Redirects".into(), ..Default::default() })
+        .add_resource("/index.html", INDEX, "text/html")
+        .add_redirect("/old.html", "/index.html", 301)
+        .unwrap()
+        .add_redirect("/start", "/index.html#intro", 302)
+        .unwrap()
+        .add_redirect("/legacy/*", "/", 308)
+        .unwrap();
+    builder.finish(&path).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+    let cases = [
+        ("/old.html", 301, "/index.html"),
+        ("/start", 302, "/index.html#intro"),
+        ("/legacy/a/b.html", 308, "/"),
+    ];
+    for (uri, status, location) in cases {
+        let response = testing::get(&app, &format!("tile://{authority}{uri}"));
+        assert_eq!(response.status(), status, "{uri}");
+        assert_eq!(response.headers()["location"], location, "{uri}");
+        assert!(response.body().is_empty(), "{uri}");
+    }
+
+    // Redirects stay within the tile.
+    builder.add_redirect("/away", "//example.com/", 302).unwrap();
+    let refused = builder.finish(&path).unwrap_err().to_string();
+    assert!(refused.contains("not to a path in the tile"), "{refused}");
+    // Each refusal says which resource, and which of its fields, it is about.
+    assert!(refused.contains("\n/away `location`: redirect to"), "{refused}");
+    assert!(builder.add_redirect("/away", "/", 200).is_err());
+}
+
+#[test]
+fn error_pages_replace_the_plain_text_errors() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let path = write_tile(dir.path(), "routes").with_file_name("routes-error-pages.tile");
+    let mut builder = TileBuilder::new();
+    let fields = MaslFields {
+        name: "Error pages".into(),
+        error_pages: [(404, "/not-found.html".to_string())].into(),
+        ..Default::default()
+    };
+    builder
+        .set_metadata(fields.clone())
+        .add_resource("/index.html", INDEX, "text/html")
+        .add_resource("/not-found.html", b"<h1>lost</h1>".to_vec(), "text/html");
+    builder.finish(&path).unwrap();
+    let authority = testing::open(&app, &path).unwrap();
+
+    let response = testing::get(&app, &format!("tile://{authority}/missing.html"));
+    assert_eq!(response.status(), 404);
+    assert_eq!(response.headers()["content-type"], "text/html");
+    assert_eq!(response.body(), b"<h1>lost</h1>");
+    let page = testing::get(&app, &format!("tile://{authority}/not-found.html"));
+    assert_eq!(page.status(), 200);
+
+    // The page is negotiated as a request for it would be.
+    let lost = b"<h1>lost</h1>";
+    builder.add_variant("/not-found.html", "gzip", gzip(lost)).unwrap();
+    let gzip_path = path.with_file_name("routes-error-pages-gzip.tile");
+    builder.finish(&gzip_path).unwrap();
+    let authority = testing::open(&app, &gzip_path).unwrap();
+    let uri = format!("tile://{authority}/missing.html");
+    let gzipped = testing::get_with(&app, &uri, &[("accept-encoding", "gzip")]);
+    assert_eq!(gzipped.status(), 404);
+    assert_eq!(header(&gzipped, "content-encoding"), Some("gzip"));
+    assert_eq!(*gzipped.body(), gzip(lost));
+
+    // The page has to be one of the tile's resources.
+    builder.remove_resource("/not-found.html");
+    let refused = builder.finish(&path).unwrap_err().to_string();
+    assert!(refused.contains("not one of the tile's resources"), "{refused}");
+}
+
+// ── Headers ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn responses_carry_cors_csp_and_range_support() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "headers");
+    let response = testing::get(&app, &format!("tile://{authority}/index.html"));
+    assert_eq!(header(&response, "access-control-allow-origin"), Some("*"));
+    assert_eq!(header(&response, "accept-ranges"), Some("bytes"));
+    // Without the `network` permission the tile is kept offline.
+    let csp = header(&response, "content-security-policy").unwrap();
+    assert!(csp.starts_with("default-src 'self'"), "{csp}");
+    // Forms post, and relative URLs resolve, within the tile whatever else.
+    assert!(csp.contains("form-action 'self'; base-uri 'self'"), "{csp}");
+}
+
+#[test]
+fn safe_masl_headers_are_forwarded_and_others_dropped() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "forwarded");
+    let response = testing::get(&app, &format!("tile://{authority}/index.html"));
+    assert_eq!(header(&response, "cache-control"), Some("max-age=60"));
+    assert_eq!(header(&response, "etag"), Some("\"v1\""));
+    assert_eq!(header(&response, "set-cookie"), None);
+}
+
+#[test]
+fn precompressed_variants_follow_accept_encoding() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "encoding");
+    let uri = format!("tile://{authority}/app.js");
+    let gzipped = testing::get_with(&app, &uri, &[("accept-encoding", "br;q=0, gzip")]);
+    assert_eq!(gzipped.status(), 200);
+    assert_eq!(header(&gzipped, "content-encoding"), Some("gzip"));
+    assert_eq!(header(&gzipped, "vary"), Some("accept-encoding"));
+    assert_eq!(*gzipped.body(), gzip(SCRIPT));
+    for accept in [None, Some("identity"), Some("gzip;q=0")] {
+        let headers: Vec<_> = accept.map(|a| ("accept-encoding", a)).into_iter().collect();
+        let plain = testing::get_with(&app, &uri, &headers);
+        assert_eq!(header(&plain, "content-encoding"), None, "{accept:?}");
+        assert_eq!(header(&plain, "vary"), Some("accept-encoding"), "{accept:?}");
+        assert_eq!(plain.body(), SCRIPT, "{accept:?}");
+    }
+}
+
+#[test]
+fn content_type_variants_follow_accept() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "variants");
+    let uri = format!("tile://{authority}/logo.png");
+    let cases = [
+        (Some("image/avif,image/webp,image/*,*/*;q=0.8"), "image/avif", AVIF),
+        // A wildcard that covers both keeps the resource itself.
+        (Some("image/webp,image/*,*/*;q=0.8"), "image/png", PNG),
+        (Some("image/avif;q=0, image/*"), "image/png", PNG),
+        (None, "image/png", PNG),
+    ];
+    for (accept, content_type, body) in cases {
+        let headers: Vec<_> = accept.map(|a| ("accept", a)).into_iter().collect();
+        let response = testing::get_with(&app, &uri, &headers);
+        assert_eq!(response.status(), 200, "{accept:?}");
+        assert_eq!(header(&response, "content-type"), Some(content_type), "{accept:?}");
+        assert_eq!(header(&response, "vary"), Some("accept"), "{accept:?}");
+        assert_eq!(response.body(), body, "{accept:?}");
+    }
+}
+
+// ── Ranges ───────────────────────────────────────────────────────────────────
+
+#[test]
+fn byte_ranges_return_the_slice_asked_for() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "ranges");
+    let media = media();
+    let total = media.len();
+    let cases = [
+        ("bytes=0-99", 0, 99),
+        ("bytes=1000-1999", 1000, 1999),
+        ("bytes=-10", total - 10, total - 1),
+        (&*format!("bytes={}-", total - 5), total - 5, total - 1),
+        // An end past the body is clamped to it.
+        (&*format!("bytes={}-{}", total - 3, total + 100), total - 3, total - 1),
+    ];
+    for (spec, start, end) in cases {
+        let response = range(&app, &authority, spec);
+        assert_eq!(response.status(), 206, "{spec}");
+        let content_range = format!("bytes {start}-{end}/{total}");
+        assert_eq!(header(&response, "content-range"), Some(&*content_range), "{spec}");
+        assert_eq!(response.body(), &media[start..=end], "{spec}");
+    }
+}
+
+#[test]
+fn long_ranges_are_cut_to_the_chunk_cap() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "range-cap");
+    let response = range(&app, &authority, "bytes=0-");
+    assert_eq!(response.status(), 206);
+    let cap = 8 << 20;
+    let content_range = format!("bytes 0-{}/{MEDIA_LEN}", cap - 1);
+    assert_eq!(header(&response, "content-range"), Some(&*content_range));
+    assert_eq!(response.body().len(), cap);
+}
+
+#[test]
+fn unsatisfiable_ranges_are_refused() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "range-refused");
+    for spec in [format!("bytes={MEDIA_LEN}-"), "bytes=50-10".into(), "bytes=-0".into()] {
+        let response = range(&app, &authority, &spec);
+        assert_eq!(response.status(), 416, "{spec}");
+        let content_range = format!("bytes */{MEDIA_LEN}");
+        assert_eq!(header(&response, "content-range"), Some(&*content_range));
+        assert!(response.body().is_empty(), "{spec}");
+    }
+}
+
+// ── Caching ──────────────────────────────────────────────────────────────────
+
+#[test]
+fn repeated_requests_serve_the_same_bytes() {
+    let dir = testing::temp_dir();
+    let (app, _, authority) = setup(dir.path(), "cached");
+    let uri = format!("tile://{authority}/media.bin");
+    let first = testing::get(&app, &uri);
+    let second = testing::get(&app, &uri);
+    assert_eq!(first.status(), 200);
+    assert_eq!(second.status(), 200);
+    assert!(first.body() == second.body() && *first.body() == media());
+}
+
+#[test]
+fn two_tiles_do_not_share_cached_blocks() {
+    let dir = testing::temp_dir();
+    let app = testing::mock_app();
+    let first = write_tile(dir.path(), "shared-a");
+    let other = first.with_file_name("shared-b.tile");
+    let mut builder = TileBuilder::new();
+    builder
+        .set_metadata(MaslFields { name: "Other".into(), ..Default::default() })
+        .add_resource("/index.html", b"<h1>other</h1>".to_vec(), "text/html");
+    builder.finish(&other).unwrap();
+    let bodies: Vec<Vec<u8>> = [first.as_path(), other.as_path()]
+        .into_iter()
+        .map(|tile: &Path| {
+            let authority = testing::open(&app, tile).unwrap();
+            testing::get(&app, &format!("tile://{authority}/index.html")).into_body()
+        })
+        .collect();
+    assert_eq!(bodies, [INDEX.to_vec(), b"<h1>other</h1>".to_vec()]);
+}
@@ -73,16 +73,89 @@ export class TabBar extends SignalWatcher(LitElement) {
       padding: 0;
     }
     .close:hover { background: #555; }
+    .integrity {
+      flex-shrink: 0;
+      font-size: 11px;
+    }
+    .integrity.pass { color: #6c6; }
+    .integrity.fail { color: #e66; }
+    .integrity.unchecked { color: #cc6; }
+    .signer {
+      flex-shrink: 0;
+      font-size: 11px;
+      color: #888;
+    }
+    .signer.trusted { color: #6c6; }
+    .signer.invalid { color: #e66; }
+    .safe {
+      flex-shrink: 0;
+      font-size: 11px;
+      color: #6ad;
+    }
+    .dangerous {
+      flex-shrink: 0;
+      font-size: 11px;
+      color: #e96;
+    }
+    .opening {
+      flex-shrink: 0;
+      padding: 0 10px;
+      color: #888;
+      font-size: 12px;
+      white-space: nowrap;
+    }
   `;
 
   render() {
-    const { tabs, activeIndex } = appStore.get();
+    const { tabs, activeIndex, opening } = appStore.get();
     return html`
-      <button class="open-btn" @click=${this._openFile}>+ Open</button>
+      <button class="open-btn" @click=${() => this._openFile({})}>+ Open</button>
+      <button
+        class="open-btn"
+        title="Open without running any of the document's scripts"
+        @click=${() => this._openFile({ safe: true })}
+      >Open in Safe Mode</button>
+      <button
+        class="open-btn"
+        title="Open in a session that leaves no storage or history behind"
+        @click=${() => this._openFile({ private: true })}
+      >Open Privately</button>
       ${tabs.map((tab, i) => this._renderTab(tab, i, activeIndex))}
+      ${opening
+        ? html`<span class="opening">Opening ${opening.done + 1} of ${opening.total}…</span>`
+        : nothing}
     `;
   }
 
+  _renderIntegrity(integrity) {
+    if (!integrity) return nothing;
+    const statuses = Object.values(integrity.resources);
+    const failed = statuses.filter((s) => s === 'fail').length;
+    const unchecked = statuses.filter((s) => s === 'unchecked').length;
+    if (failed) {
+      return html`<span class="integrity fail" title="${failed} resource(s) failed verification">⚠</span>`;
+    }
+    if (unchecked) {
+      return html`<span class="integrity unchecked" title="${unchecked} resource(s) could not be checked">?</span>`;
+    }
+    return html`<span class="integrity pass" title="All resources verified">✓</span>`;
+  }
+
+  _renderSigner(signature) {
+    if (!signature) return nothing;
+    if (signature.revoked) {
+      return html`<span class="signer invalid" title="Signed with a revoked key: ${signature.revoked}">⚠ revoked</span>`;
+    }
+    if (!signature.valid) {
+      return html`<span class="signer invalid" title="Invalid signature: ${signature.error}">✗ signed</span>`;
+    }
+    if (signature.trusted) {
+      return html`<span class="signer trusted" title="Signed by ${signature.trusted_name}">${signature.trusted_name}</span>`;
+    }
+    const claimed = signature.claimed_name ? ` (claims to be ${signature.claimed_name})` : '';
+    return html`<span class="signer" title="Signed by untrusted key ${signature.key}${claimed}">signed</span>`;
+  }
+
   _renderTab(tab, index, activeIndex) {
     const iconSrc = tab.masl.icons?.[0]?.src;
     const iconUrl = iconSrc ? `tile://${tab.authority}${iconSrc}` : nothing;
@@ -93,24 +166,52 @@ export class TabBar extends SignalWatcher(LitElement) {
       >
         ${iconSrc ? html`<img src=${iconUrl} alt="" />` : nothing}
         <span class="tab-label">${tab.masl.name}</span>
+        ${this._renderIntegrity(tab.integrity)}
+        ${this._renderSigner(tab.signature)}
+        ${tab.safeMode ? html`<span class="safe" title="Scripts are disabled">safe mode</span>` : nothing}
+        ${tab.private ? html`<span class="safe" title="Nothing from this session is kept">private</span>` : nothing}
+        ${tab.dangerous?.length
+          ? html`<span class="dangerous" title="Contains executable files: ${tab.dangerous.join(', ')}">⚠</span>`
+          : nothing}
         <button
           class="close"
           title="Close"
-          @click=${(e) => { e.stopPropagation(); closeTab(index); }}
+          @click=${(e) => { e.stopPropagation(); this._closeTab(tab, index); }}
         >×</button>
       </div>
     `;
   }
 
-  async _openFile() {
-    const { open } = await import('@tauri-apps/plugin-dialog');
+  async _closeTab(tab, index) {
+    closeTab(index);
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('close_tile', { authority: tab.authority });
+  }
+
+  async _openFile({ safe = false, private: isPrivate = false }) {
+    const { open, message: show } = await import('@tauri-apps/plugin-dialog');
     const { invoke } = await import('@tauri-apps/api/core');
     const filePath = await open({
       multiple: false,
-      filters: [{ name: 'Tile Documents', extensions: ['tile'] }],
+      filters: [{ name: 'Tile Documents', extensions: ['tile', 'zst'] }],
     });
-    if (filePath) {
-      await invoke('open_tile', { path: filePath });
+    if (!filePath) return;
+    // A filter only sees the last extension, so any `.zst` file gets past it.
+    if (!/\.tile(\.zst)?$/.test(filePath)) {
+      await show(`${filePath} is not a .tile or .tile.zst file.`, {
+        title: 'Not a tile',
+        kind: 'error',
+      });
+      return;
+    }
+    try {
+      await invoke('open_tile', { path: filePath, safe, private: isPrivate });
+    } catch ({ code, message }) {
+      console.error(`Failed to open ${filePath} (${code}): ${message}`);
+      await show(`${message}\n\nError code: ${code}`, {
+        title: `Couldn't open ${filePath}`,
+        kind: 'error',
+      });
     }
   }
 }
@@ -1,6 +1,27 @@
 import { LitElement, html, css } from 'lit';
 import { SignalWatcher } from '@lit-labs/signals';
-import { appStore } from '../state.js';
+import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { appStore, recordView } from '../state.js';
+
+// ── Tile bridge ───────────────────────────────────────────────────────────────
+//
+// Tiles talk to the shell with `parent.postMessage({ type, id, ... }, '*')`.
+// `tile:request-permission` asks the backend to prompt for a permission;
+// the other messages perform an action the tile's sandbox doesn't allow
+// directly, provided the permission has been granted. Every message gets a
+// reply posted back to the tile carrying the same `id`.
+//
+// `tile:view-state`, with `path`, `scrollX` and `scrollY`, needs no
+// permission: a tile sends it as it navigates and scrolls, and it is
+// recorded for session recovery. When a restored tab loads, the shell
+// posts `{ type: 'tile:restore-view', scrollX, scrollY }` to it in turn.
+
+const ACTIONS = {
+  'tile:clipboard-write': ['clipboard-write', (msg) => navigator.clipboard.writeText(String(msg.text ?? ''))],
+  'tile:notify': ['notifications', (msg) => new Notification(String(msg.title ?? ''), { body: msg.body })],
+  'tile:fullscreen': ['fullscreen', (msg) => getCurrentWindow().setFullscreen(msg.fullscreen !== false)],
+};
 
 export class TileTab extends SignalWatcher(LitElement) {
   static styles = css`
@@ -30,6 +51,73 @@ export class TileTab extends SignalWatcher(LitElement) {
     }
   `;
 
+  connectedCallback() {
+    super.connectedCallback();
+    this._onMessage = this._onMessage.bind(this);
+    window.addEventListener('message', this._onMessage);
+  }
+
+  disconnectedCallback() {
+    window.removeEventListener('message', this._onMessage);
+    super.disconnectedCallback();
+  }
+
+  async _onMessage(event) {
+    // Identify the sender by its frame, not by anything it claims.
+    const frame = [...this.renderRoot.querySelectorAll('iframe')]
+      .find((f) => f.contentWindow === event.source);
+    const msg = event.data;
+    if (!frame || typeof msg?.type !== 'string' || !msg.type.startsWith('tile:')) return;
+    const { authority } = frame.dataset;
+    const reply = (payload) => {
+      // Permission requests are logged by the backend itself, and view
+      // reports are too frequent to be worth logging.
+      if (msg.type !== 'tile:request-permission' && msg.type !== 'tile:view-state') {
+        const outcome = payload.type === 'tile:error' ? payload.error : 'done';
+        invoke('record_bridge_call', { authority, action: msg.type, outcome }).catch(() => {});
+      }
+      event.source.postMessage({ id: msg.id, ...payload }, '*');
+    };
+
+    try {
+      if (msg.type === 'tile:view-state') {
+        const path = typeof msg.path === 'string' && msg.path.startsWith('/') ? msg.path : '/';
+        recordView(authority, { path, scroll: [Number(msg.scrollX) || 0, Number(msg.scrollY) || 0] });
+        reply({ type: 'tile:done' });
+        return;
+      }
+      if (msg.type === 'tile:request-permission') {
+        const granted = await invoke('request_permission', { authority, permission: msg.permission });
+        reply({ type: 'tile:permission-result', permission: msg.permission, granted });
+        return;
+      }
+      const action = ACTIONS[msg.type];
+      if (!action) {
+        reply({ type: 'tile:error', error: `unknown action "${msg.type}"` });
+        return;
+      }
+      const [permission, run] = action;
+      const granted = await invoke('get_tile_permissions', { authority });
+      if (!granted.includes(permission)) {
+        reply({ type: 'tile:error', error: `permission "${permission}" has not been granted` });
+        return;
+      }
+      await run(msg);
+      reply({ type: 'tile:done' });
+    } catch (e) {
+      reply({ type: 'tile:error', error: String(e) });
+    }
+  }
+
+  // Once a restored tab first loads, hand it back the scroll it reported.
+  _onLoad(event, tab) {
+    this._restored ??= new Set();
+    if (!tab.restoreScroll || this._restored.has(tab.authority)) return;
+    this._restored.add(tab.authority);
+    const [scrollX, scrollY] = tab.restoreScroll;
+    event.target.contentWindow?.postMessage({ type: 'tile:restore-view', scrollX, scrollY }, '*');
+  }
+
   render() {
     const { tabs, activeIndex } = appStore.get();
     if (!tabs.length || activeIndex < 0) {
@@ -41,8 +129,10 @@ export class TileTab extends SignalWatcher(LitElement) {
       ${tabs.map((tab, i) => html`
         <iframe
           style="display: ${i === activeIndex ? 'block' : 'none'}"
-          src=${`tile://${tab.authority}/`}
-          sandbox="allow-forms allow-scripts allow-modals allow-same-origin"
+          src=${`tile://${tab.authority}${tab.path ?? '/'}${tab.revision ? `?rev=${tab.revision}` : ''}`}
+          data-authority=${tab.authority}
+          @load=${(e) => this._onLoad(e, tab)}
+          sandbox=${tab.safeMode ? 'allow-same-origin' : 'allow-forms allow-scripts allow-modals allow-same-origin'}
           referrerpolicy="no-referrer"
           title=${tab.masl.name}
         ></iframe>
@@ -2,12 +2,26 @@ import { LitElement, html, css, nothing } from 'lit';
 import { SignalWatcher } from '@lit-labs/signals';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
-import { addTab, appStore, setFullscreen } from './state.js';
+import {
+  activateTab,
+  addTab,
+  appStore,
+  closeTab,
+  navigateTab,
+  reloadTab,
+  setFullscreen,
+  setOpening,
+} from './state.js';
 import './components/tab-bar.js';
 import './components/tile-tab.js';
 
 // ── Root app shell ────────────────────────────────────────────────────────────
 
+// Authority of the tab to bring forward once a restored session reopens it.
+let restoringActive = null;
+// View state of each restored tab, by authority, applied as it reopens.
+const restoringViews = new Map();
+
 class TileApp extends SignalWatcher(LitElement) {
   static styles = css`
     :host {
@@ -26,13 +40,145 @@ class TileApp extends SignalWatcher(LitElement) {
       .isFullscreen()
       .then((isFs) => { if (isFs) setFullscreen(true); });
 
-    listen('tile:opened', (event) => {
-      const { authority, masl } = event.payload;
-      addTab(authority, masl);
-    });
+    const listeners = [];
+
+    listeners.push(listen('tile:opened', async (event) => {
+      const { authority, masl, integrity, signature, safe_mode, dangerous = [] } = event.payload;
+      const isPrivate = event.payload.private;
+      // Network access and service workers are enforced per response, so
+      // ask before the tab's first load rather than through the bridge.
+      const upfront = ['network', 'service-worker'].filter((p) => masl.permissions?.includes(p));
+      if (upfront.length && !safe_mode) {
+        const { invoke } = await import('@tauri-apps/api/core');
+        for (const permission of upfront) {
+          await invoke('request_permission', { authority, permission }).catch(console.error);
+        }
+      }
+      const view = restoringViews.get(authority);
+      restoringViews.delete(authority);
+      const path = typeof view?.path === 'string' && view.path.startsWith('/') ? view.path : undefined;
+      if (isPrivate) {
+        // A private tile has a window of its own, whose storage isn't kept.
+        const { invoke } = await import('@tauri-apps/api/core');
+        await invoke('show_private_tile', { authority }).catch(console.error);
+      } else {
+        addTab({
+          authority,
+          masl,
+          integrity,
+          signature,
+          safeMode: safe_mode,
+          dangerous,
+          path,
+          restoreScroll: Array.isArray(view?.scroll) ? view.scroll : undefined,
+        });
+      }
+      // Once a restored session's active tab is back, bring it forward.
+      if (authority === restoringActive) {
+        activateTab(appStore.get().tabs.findIndex((tab) => tab.authority === authority));
+        restoringActive = null;
+      }
+      if (signature?.revoked) {
+        const { message } = await import('@tauri-apps/plugin-dialog');
+        await message(
+          `“${masl.name}” is signed with a key its publisher has revoked (${signature.revoked}). ` +
+            'It may have been signed by someone else using a stolen key.',
+          { title: 'Revoked signature', kind: 'error' },
+        );
+      }
+    }));
+
+    listeners.push(listen('tile:open-progress', (event) => {
+      const { done, total, errors = [] } = event.payload;
+      for (const { path, error } of errors) console.error(`Failed to open ${path}: ${error}`);
+      setOpening(done < total ? { done, total } : null);
+    }));
+
+    listeners.push(listen('tile:reloaded', (event) => {
+      const { authority, masl, integrity, signature } = event.payload;
+      reloadTab(authority, masl, integrity, signature);
+    }));
+
+    listeners.push(listen('tile:update-available', async (event) => {
+      const { authority, name } = event.payload;
+      const { ask } = await import('@tauri-apps/plugin-dialog');
+      const { invoke } = await import('@tauri-apps/api/core');
+      const yes = await ask(`A newer version of “${name}” has been downloaded. Switch to it now?`, {
+        title: 'Update available',
+        kind: 'info',
+      });
+      if (yes) await invoke('apply_tile_update', { authority });
+    }));
+
+    listeners.push(listen('tile:content-changed', async (event) => {
+      const { name, path, signer_changed } = event.payload;
+      const { message } = await import('@tauri-apps/plugin-dialog');
+      const detail = signer_changed
+        ? 'It is no longer signed by the publisher who signed the version you opened before.'
+        : 'Its contents differ from the version you opened before. Permissions you granted earlier do not carry over.';
+      await message(`“${name}” (${path}) has changed since you last opened it. ${detail}`, {
+        title: 'Document changed',
+        kind: 'warning',
+      });
+    }));
+
+    listeners.push(listen('tile:modified-on-disk', async (event) => {
+      const { name, path, error } = event.payload;
+      const { message } = await import('@tauri-apps/plugin-dialog');
+      const detail = error
+        ? `It could not be reloaded (${error}), so the open tab may be out of date.`
+        : 'It has been reloaded and verified again.';
+      await message(`“${name}” (${path}) was modified on disk while open. ${detail}`, {
+        title: 'Document modified',
+        kind: 'warning',
+      });
+    }));
+
+    listeners.push(listen('tile:session-recoverable', async (event) => {
+      const { tabs, active } = event.payload;
+      const { ask } = await import('@tauri-apps/plugin-dialog');
+      const { invoke } = await import('@tauri-apps/api/core');
+      const count = tabs.length === 1 ? '1 document was' : `${tabs.length} documents were`;
+      const yes = await ask(`Tile Documents didn't shut down properly. ${count} open. Reopen them?`, {
+        title: 'Restore session',
+        kind: 'info',
+      });
+      if (yes) {
+        // Set before restoring: the first tiles can open before it returns.
+        restoringActive = active ?? null;
+        for (const { authority, view } of tabs) if (view) restoringViews.set(authority, view);
+        await invoke('restore_session');
+      } else {
+        await invoke('discard_session');
+      }
+    }));
+
+    // Automation: a script asked the backend to move or close a tab.
+    listeners.push(listen('tile:navigate', (event) => {
+      const { authority, path } = event.payload;
+      navigateTab(authority, path);
+      activateTab(appStore.get().tabs.findIndex((tab) => tab.authority === authority));
+    }));
+
+    listeners.push(listen('tile:close-requested', async (event) => {
+      const { authority } = event.payload;
+      const index = appStore.get().tabs.findIndex((tab) => tab.authority === authority);
+      if (index < 0) return;
+      closeTab(index);
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('close_tile', { authority });
+    }));
+
+    // Every window hears every window's changes; keep only this one's.
+    listeners.push(listen('tile:fullscreen-changed', (event) => {
+      const { window, fullscreen } = event.payload;
+      if (window === getCurrentWindow().label) setFullscreen(fullscreen);
+    }));
 
-    listen('tile:fullscreen-changed', (event) => {
-      setFullscreen(event.payload);
+    // Files passed at launch are queued in the backend until we're listening.
+    Promise.all(listeners).then(async () => {
+      const { invoke } = await import('@tauri-apps/api/core');
+      await invoke('frontend_ready');
     });
   }
 
@@ -1,11 +1,15 @@
 import { store } from 'refrakt';
+import { invoke } from '@tauri-apps/api/core';
 
 // ── Actions ──────────────────────────────────────────────────────────────────
 
 export const ADD_TAB = 'ADD_TAB';
 export const CLOSE_TAB = 'CLOSE_TAB';
 export const ACTIVATE_TAB = 'ACTIVATE_TAB';
+export const RELOAD_TAB = 'RELOAD_TAB';
+export const NAVIGATE_TAB = 'NAVIGATE_TAB';
 export const SET_FULLSCREEN = 'SET_FULLSCREEN';
+export const SET_OPENING = 'SET_OPENING';
 
 // ── Reducer ───────────────────────────────────────────────────────────────────
 
@@ -26,9 +30,36 @@ function reducer(state, action) {
     case ACTIVATE_TAB: {
       return { ...state, activeIndex: action.index };
     }
+    case RELOAD_TAB: {
+      // Bump the revision so the tab's iframe URL changes and it reloads.
+      const tabs = state.tabs.map((tab) =>
+        tab.authority === action.authority
+          ? {
+              ...tab,
+              masl: action.masl,
+              integrity: action.integrity,
+              signature: action.signature,
+              revision: (tab.revision ?? 0) + 1,
+            }
+          : tab,
+      );
+      return { ...state, tabs };
+    }
+    case NAVIGATE_TAB: {
+      // The revision bump reloads the frame even if it is already at `path`.
+      const tabs = state.tabs.map((tab) =>
+        tab.authority === action.authority
+          ? { ...tab, path: action.path, revision: (tab.revision ?? 0) + 1 }
+          : tab,
+      );
+      return { ...state, tabs };
+    }
     case SET_FULLSCREEN: {
       return { ...state, fullscreen: action.fullscreen };
     }
+    case SET_OPENING: {
+      return { ...state, opening: action.opening };
+    }
     default:
       return state;
   }
@@ -36,22 +67,68 @@ function reducer(state, action) {
 
 // ── Store ─────────────────────────────────────────────────────────────────────
 
-export const appStore = store(reducer, { tabs: [], activeIndex: -1, fullscreen: false });
+export const appStore = store(reducer, {
+  tabs: [],
+  activeIndex: -1,
+  fullscreen: false,
+  opening: null,
+});
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-export function addTab(authority, masl) {
-  appStore.send({ type: ADD_TAB, tab: { authority, masl } });
+export function addTab(tab) {
+  appStore.send({ type: ADD_TAB, tab });
 }
 
 export function closeTab(index) {
   appStore.send({ type: CLOSE_TAB, index });
+  recordActiveTab();
 }
 
 export function activateTab(index) {
   appStore.send({ type: ACTIVATE_TAB, index });
+  recordActiveTab();
+}
+
+// The backend records the active tab for session recovery; tabs opening
+// are recorded as active on its side.
+function recordActiveTab() {
+  const { tabs, activeIndex } = appStore.get();
+  const tab = tabs[activeIndex];
+  if (tab && !tab.private) invoke('set_active_tile', { authority: tab.authority }).catch(() => {});
+}
+
+export function reloadTab(authority, masl, integrity, signature) {
+  appStore.send({ type: RELOAD_TAB, authority, masl, integrity, signature });
+}
+
+export function navigateTab(authority, path) {
+  appStore.send({ type: NAVIGATE_TAB, authority, path });
+  recordView(authority, { path, scroll: [0, 0] });
+}
+
+// View state (`{ path, scroll: [x, y] }`) is recorded for session recovery
+// at most once a second per tab, the latest winning: the backend rewrites
+// the session file on every call.
+const pendingViews = new Map();
+
+export function recordView(authority, view) {
+  const tab = appStore.get().tabs.find((t) => t.authority === authority);
+  if (!tab || tab.private) return;
+  const scheduled = pendingViews.has(authority);
+  pendingViews.set(authority, view);
+  if (scheduled) return;
+  setTimeout(() => {
+    const latest = pendingViews.get(authority);
+    pendingViews.delete(authority);
+    invoke('set_tile_view_state', { authority, view: latest }).catch(() => {});
+  }, 1000);
 }
 
 export function setFullscreen(fullscreen) {
   appStore.send({ type: SET_FULLSCREEN, fullscreen });
 }
+
+export function setOpening(opening) {
+  appStore.send({ type: SET_OPENING, opening });
+}